@@ -0,0 +1,23 @@
+/// Benchmarks comparing the Chinese remainder theorem against sieving through the departures, for
+/// lining up the Day 13 buses.
+use aoc2020::buses::{congruences, parse_schedule};
+use aoc2020::modular::{crt, sieve};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn bench_buses(c: &mut Criterion) {
+    let schedule = parse_schedule(include_str!("../input/13.txt")).expect("Invalid day 13 input");
+    let congruences = congruences(&schedule);
+    let mut largest_first = congruences.clone();
+    largest_first.sort_unstable_by_key(|&(_, id)| std::cmp::Reverse(id));
+
+    let mut group = c.benchmark_group("buses");
+    group.bench_function("crt", |b| b.iter(|| crt(&congruences).unwrap()));
+    group.bench_function("sieve", |b| b.iter(|| sieve(&congruences).unwrap()));
+    group.bench_function("sieve_largest_first", |b| {
+        b.iter(|| sieve(&largest_first).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_buses);
+criterion_main!(benches);
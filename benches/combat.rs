@@ -0,0 +1,88 @@
+/// Benchmarks comparing the Recursive Combat deck implementations, and remembering seen rounds by
+/// copies of the decks against by their fingerprints.
+use aoc2020::combat::{
+    parse_decks, play_combat, play_recursive_combat_with, score_recursive_combat, ArrayDeck, Card,
+    Deck, Rules,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::{HashSet, VecDeque};
+
+/// Play Recursive Combat as `play_recursive_combat` does, but remembering each round seen by a
+/// copy of both decks.
+fn play_with_deck_copies<D: Deck>(deck1: &mut D, deck2: &mut D) -> bool {
+    let mut rounds_seen: HashSet<(D, D)> = HashSet::new();
+    while !deck1.is_empty() && !deck2.is_empty() {
+        if !rounds_seen.insert((deck1.clone(), deck2.clone())) {
+            return true;
+        }
+        let card1 = deck1.draw().unwrap();
+        let card2 = deck2.draw().unwrap();
+        let player1_wins = if deck1.len() >= card1 as usize && deck2.len() >= card2 as usize {
+            let mut subdeck1 = deck1.sub_deck(card1 as usize);
+            let mut subdeck2 = deck2.sub_deck(card2 as usize);
+            play_with_deck_copies(&mut subdeck1, &mut subdeck2)
+        } else {
+            card1 > card2
+        };
+        if player1_wins {
+            deck1.push(card1);
+            deck1.push(card2);
+        } else {
+            deck2.push(card2);
+            deck2.push(card1);
+        }
+    }
+    !deck1.is_empty()
+}
+
+fn recursive_combat_with_copies<D: Deck>(decks: &[VecDeque<Card>; 2]) -> u32 {
+    let mut deck1: D = decks[0].iter().cloned().collect();
+    let mut deck2: D = decks[1].iter().cloned().collect();
+    match play_with_deck_copies(&mut deck1, &mut deck2) {
+        true => deck1.score(),
+        false => deck2.score(),
+    }
+}
+
+fn bench_decks(c: &mut Criterion) {
+    let decks = parse_decks(include_str!("../input/22.txt")).expect("Invalid day 22 input");
+
+    let mut group = c.benchmark_group("combat");
+    group.bench_function("vec_deque", |b| {
+        b.iter(|| {
+            let mut deck1 = decks[0].sub_deck(decks[0].len());
+            let mut deck2 = decks[1].sub_deck(decks[1].len());
+            play_combat(&mut deck1, &mut deck2)
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("recursive_combat");
+    group.sample_size(10);
+    group.bench_function("vec_deque", |b| {
+        b.iter(|| score_recursive_combat::<VecDeque<Card>, _>(&decks, Rules::default(), &mut ()))
+    });
+    group.bench_function("array_deck", |b| {
+        b.iter(|| score_recursive_combat::<ArrayDeck, _>(&decks, Rules::default(), &mut ()))
+    });
+    group.bench_function("array_deck_shortcut", |b| {
+        b.iter(|| {
+            let mut deck1: ArrayDeck = decks[0].iter().cloned().collect();
+            let mut deck2: ArrayDeck = decks[1].iter().cloned().collect();
+            let rules = Rules {
+                shortcut_sub_games: true,
+            };
+            play_recursive_combat_with(&mut deck1, &mut deck2, rules, &mut ())
+        })
+    });
+    group.bench_function("vec_deque_copies", |b| {
+        b.iter(|| recursive_combat_with_copies::<VecDeque<Card>>(&decks))
+    });
+    group.bench_function("array_deck_copies", |b| {
+        b.iter(|| recursive_combat_with_copies::<ArrayDeck>(&decks))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_decks);
+criterion_main!(benches);
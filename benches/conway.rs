@@ -0,0 +1,85 @@
+/// Benchmarks comparing heap-allocated and fixed-size positions, counting neighbours serially and
+/// across threads, and a dense array of every position, for the Day 17 Conway cubes.
+use aoc2020::conway::{parse_initial, run, Engine};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::{HashMap, HashSet};
+
+type Position = Vec<i32>;
+
+/// Every offset to a neighbouring position, built up a dimension at a time.
+fn neighbour_directions(dimensions: usize) -> Vec<Position> {
+    let mut directions = vec![vec![0; dimensions]];
+    for dimension in 0..dimensions {
+        let mut new_directions = Vec::new();
+        for direction in &directions {
+            for &delta in &[-1, 1] {
+                let mut new_direction = direction.clone();
+                new_direction[dimension] = delta;
+                new_directions.push(new_direction);
+            }
+        }
+        directions.extend(new_directions);
+    }
+    directions.remove(0);
+    directions
+}
+
+/// Run the cycles with each position a `Vec` of coordinates.
+fn simulate_vec(active: &[(i32, i32)], dimensions: usize, cycles: usize) -> usize {
+    let directions = neighbour_directions(dimensions);
+    let mut cubes: HashSet<Position> = active
+        .iter()
+        .map(|&(x, y)| {
+            let mut position = vec![0; dimensions];
+            position[0] = x;
+            position[1] = y;
+            position
+        })
+        .collect();
+    for _ in 0..cycles {
+        let mut active_neighbours: HashMap<Position, u32> = HashMap::new();
+        for cube in &cubes {
+            for direction in &directions {
+                let neighbour = cube.iter().zip(direction).map(|(a, b)| a + b).collect();
+                *active_neighbours.entry(neighbour).or_insert(0) += 1;
+            }
+        }
+        cubes = active_neighbours
+            .into_iter()
+            .filter(|(position, n)| *n == 3 || (*n == 2 && cubes.contains(position)))
+            .map(|(position, _)| position)
+            .collect();
+    }
+    cubes.len()
+}
+
+fn bench_conway(c: &mut Criterion) {
+    let active = parse_initial(include_str!("../input/17.txt")).expect("Invalid day 17 input");
+
+    let mut group = c.benchmark_group("conway");
+    group.sample_size(10);
+    group.bench_function("3d_vec", |b| b.iter(|| simulate_vec(&active, 3, 6)));
+    group.bench_function("3d_point", |b| {
+        b.iter(|| run::<3>(&active, 6, Engine::Sparse, false))
+    });
+    group.bench_function("3d_point_parallel", |b| {
+        b.iter(|| run::<3>(&active, 6, Engine::Sparse, true))
+    });
+    group.bench_function("3d_dense", |b| {
+        b.iter(|| run::<3>(&active, 6, Engine::Dense, false))
+    });
+    group.bench_function("4d_vec", |b| b.iter(|| simulate_vec(&active, 4, 6)));
+    group.bench_function("4d_point", |b| {
+        b.iter(|| run::<4>(&active, 6, Engine::Sparse, false))
+    });
+    group.bench_function("4d_point_parallel", |b| {
+        b.iter(|| run::<4>(&active, 6, Engine::Sparse, true))
+    });
+    group.bench_function("4d_dense", |b| {
+        b.iter(|| run::<4>(&active, 6, Engine::Dense, false))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_conway);
+criterion_main!(benches);
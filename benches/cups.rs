@@ -0,0 +1,37 @@
+/// Benchmarks comparing the successor array of labels of the original Day 23 solution against
+/// the 0-based one of `CrabCups`, over part two's ten million moves. Run with
+/// `--features unchecked` to compare against `CrabCups` without bounds checks.
+use aoc2020::cups::{label_layout, label_moves, CrabCups};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const CUPS: u32 = 1_000_000;
+const MOVES: usize = 10_000_000;
+
+fn bench_cups(c: &mut Criterion) {
+    let labels: Vec<u32> = include_str!("../input/23.txt")
+        .trim()
+        .chars()
+        .map(|ch| ch.to_digit(10).expect("Invalid day 23 input"))
+        .collect();
+
+    let mut group = c.benchmark_group("crab_cups");
+    group.sample_size(10);
+    group.bench_function("labels", |b| {
+        b.iter(|| {
+            let mut cups = label_layout(&labels, CUPS);
+            label_moves(&mut cups, labels[0], MOVES);
+            cups[0]
+        })
+    });
+    group.bench_function("indices", |b| {
+        b.iter(|| {
+            let mut cups = CrabCups::new(&labels, CUPS).unwrap();
+            cups.play(MOVES);
+            cups.next(1)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_cups);
+criterion_main!(benches);
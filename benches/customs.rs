@@ -0,0 +1,62 @@
+/// Benchmarks comparing bitmask and HashSet representations of the Day 6 group answers.
+use aoc2020::customs::{count_answers, parse_groups, Threshold};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::HashSet;
+
+/// The answers of each person in each group as a set of the questions answered.
+type SetGroups = Vec<Vec<HashSet<char>>>;
+
+fn parse_set_groups(input: &str) -> SetGroups {
+    input
+        .split("\n\n")
+        .map(|group| {
+            group
+                .lines()
+                .map(|person| person.chars().filter(|c| c.is_ascii_lowercase()).collect())
+                .collect()
+        })
+        .collect()
+}
+
+fn count_any_sets(groups: &SetGroups) -> usize {
+    groups
+        .iter()
+        .map(|group| group.iter().flatten().collect::<HashSet<_>>().len())
+        .sum()
+}
+
+fn count_all_sets(groups: &SetGroups) -> usize {
+    groups
+        .iter()
+        .map(|group| match group.split_first() {
+            Some((first, rest)) => rest
+                .iter()
+                .fold(first.clone(), |acc, person| &acc & person)
+                .len(),
+            None => 0,
+        })
+        .sum()
+}
+
+fn bench_answers(c: &mut Criterion) {
+    let input = include_str!("../input/06.txt");
+
+    let mut group = c.benchmark_group("customs");
+    group.bench_function("parse_hash_set", |b| b.iter(|| parse_set_groups(input)));
+    group.bench_function("parse_bitmask", |b| b.iter(|| parse_groups(input)));
+
+    let sets = parse_set_groups(input);
+    let masks = parse_groups(input);
+    group.bench_function("any_hash_set", |b| b.iter(|| count_any_sets(&sets)));
+    group.bench_function("any_bitmask", |b| {
+        b.iter(|| count_answers(&masks, Threshold::Any))
+    });
+    group.bench_function("all_hash_set", |b| b.iter(|| count_all_sets(&sets)));
+    group.bench_function("all_bitmask", |b| {
+        b.iter(|| count_answers(&masks, Threshold::All))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_answers);
+criterion_main!(benches);
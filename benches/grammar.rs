@@ -0,0 +1,186 @@
+/// Benchmarks comparing the original Day 19 solution, which expands rules into every string they
+/// match, against matching each message with an automaton and from the top down.
+use aoc2020::grammar::{matches, parse_rules, Match, Nfa, Rules};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Expand a rule into every string it matches, as bytes.
+fn expand_rule(
+    rules: &Rules,
+    key: u32,
+    cache: &mut HashMap<u32, Rc<HashSet<Vec<u8>>>>,
+) -> Rc<HashSet<Vec<u8>>> {
+    if let Some(strings) = cache.get(&key) {
+        return Rc::clone(strings);
+    }
+    let mut strings = HashSet::new();
+    for series in &rules[&key].alternatives {
+        let mut series_strings: HashSet<Vec<u8>> = Some(Vec::new()).into_iter().collect();
+        for item in series {
+            series_strings = match *item {
+                Match::Char(ch) => {
+                    let mut buf = [0; 4];
+                    let ch = ch.encode_utf8(&mut buf).as_bytes();
+                    series_strings
+                        .into_iter()
+                        .map(|mut string| {
+                            string.extend_from_slice(ch);
+                            string
+                        })
+                        .collect()
+                }
+                Match::Rule(key) => {
+                    let particles = expand_rule(rules, key, cache);
+                    let mut joined = HashSet::with_capacity(series_strings.len() * particles.len());
+                    for string in &series_strings {
+                        for particle in particles.iter() {
+                            let mut both = Vec::with_capacity(string.len() + particle.len());
+                            both.extend_from_slice(string);
+                            both.extend_from_slice(particle);
+                            joined.insert(both);
+                        }
+                    }
+                    joined
+                }
+            };
+        }
+        strings.extend(series_strings);
+    }
+    let strings = Rc::new(strings);
+    cache.insert(key, Rc::clone(&strings));
+    strings
+}
+
+/// The strings a rule matches grouped by length, so whether a message starts with any of them
+/// takes a lookup per length rather than a comparison per string.
+struct Candidates(Vec<(usize, HashSet<Vec<u8>>)>);
+
+impl Candidates {
+    fn new(strings: &HashSet<Vec<u8>>) -> Self {
+        let mut by_length: HashMap<usize, HashSet<Vec<u8>>> = HashMap::new();
+        for string in strings {
+            by_length
+                .entry(string.len())
+                .or_default()
+                .insert(string.clone());
+        }
+        let mut by_length: Vec<_> = by_length.into_iter().collect();
+        by_length.sort_unstable_by_key(|&(len, _)| len);
+        Self(by_length)
+    }
+
+    /// The rest of the message after each candidate it starts with.
+    fn strip_prefixes<'a>(&'a self, message: &'a [u8]) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.0
+            .iter()
+            .filter(move |(len, strings)| {
+                message.len() >= *len && strings.contains(&message[..*len])
+            })
+            .map(move |(len, _)| &message[*len..])
+    }
+}
+
+/// Whether the message is a string from each set in turn, by stripping each prefix which is one.
+fn matches_series(message: &[u8], series: &[Candidates]) -> bool {
+    series[0]
+        .strip_prefixes(message)
+        .any(|rest| match series.len() {
+            1 => rest.is_empty(),
+            _ => matches_series(rest, &series[1..]),
+        })
+}
+
+/// Count the messages rule 0 matches as the original part one did, as a series of expanded rules.
+fn expansion_part_one(rules: &Rules, messages: &[&str]) -> usize {
+    let mut cache = HashMap::new();
+    let series: Vec<Candidates> = rules[&0].alternatives[0]
+        .iter()
+        .filter_map(|item| match *item {
+            Match::Rule(key) => Some(Candidates::new(&expand_rule(rules, key, &mut cache))),
+            Match::Char(_) => None,
+        })
+        .collect();
+    messages
+        .iter()
+        .filter(|message| matches_series(message.as_bytes(), &series))
+        .count()
+}
+
+/// Strip candidates off the front of the message for as long as any match, counting them.
+fn strip_all<'a>(message: &mut &'a [u8], prefixes: &'a Candidates) -> u32 {
+    let mut count = 0;
+    while let Some(rest) = prefixes.strip_prefixes(message).next() {
+        *message = rest;
+        count += 1;
+    }
+    count
+}
+
+/// Count the messages matching the looping rules as the original part two did, by stripping
+/// prefixes matching rule 42 and then rule 31 and comparing how many of each there were.
+fn expansion_part_two(rules: &Rules, messages: &[&str]) -> usize {
+    let mut cache = HashMap::new();
+    let rule_42 = Candidates::new(&expand_rule(rules, 42, &mut cache));
+    let rule_31 = Candidates::new(&expand_rule(rules, 31, &mut cache));
+    messages
+        .iter()
+        .filter(|message| {
+            let mut message = message.as_bytes();
+            let num_42 = strip_all(&mut message, &rule_42);
+            let num_31 = strip_all(&mut message, &rule_31);
+            message.is_empty() && num_42 > num_31 && num_31 > 0
+        })
+        .count()
+}
+
+fn bench_matching(c: &mut Criterion) {
+    let input = include_str!("../input/19.txt");
+    let (rules, messages) = input.split_once("\n\n").expect("Invalid day 19 input");
+    let rules = parse_rules(rules).expect("Invalid day 19 rules");
+    let messages: Vec<&str> = messages.lines().collect();
+    let mut looping = rules.clone();
+    looping.extend(parse_rules("8: 42 | 42 8\n11: 42 31 | 42 11 31").unwrap());
+
+    let mut group = c.benchmark_group("part_one");
+    group.sample_size(10);
+    group.bench_function("expansion", |b| {
+        b.iter(|| expansion_part_one(&rules, &messages))
+    });
+    group.bench_function("nfa", |b| {
+        b.iter(|| {
+            let nfa = Nfa::compile(&rules, 0).unwrap();
+            messages
+                .iter()
+                .filter(|message| nfa.is_match(message))
+                .count()
+        })
+    });
+    group.bench_function("top_down", |b| {
+        b.iter(|| {
+            messages
+                .iter()
+                .filter(|message| matches(&rules, 0, message).unwrap())
+                .count()
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("part_two");
+    group.sample_size(10);
+    group.bench_function("expansion", |b| {
+        b.iter(|| expansion_part_two(&rules, &messages))
+    });
+    group.bench_function("top_down", |b| {
+        b.iter(|| {
+            messages
+                .iter()
+                .filter(|message| matches(&looping, 0, message).unwrap())
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_matching);
+criterion_main!(benches);
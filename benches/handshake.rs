@@ -0,0 +1,22 @@
+/// Benchmarks comparing the naive loop-size search of Day 25 against baby-step giant-step, for
+/// public keys with a small loop size and loop sizes in the millions as given in puzzle inputs.
+use aoc2020::handshake::{loop_size, transform, Search, SUBJECT};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn bench_loop_size(c: &mut Criterion) {
+    let mut group = c.benchmark_group("loop_size");
+    group.sample_size(10);
+    for &given in &[1_000u64, 1_000_000, 15_000_000] {
+        let public_key = transform(SUBJECT, given);
+        group.bench_function(format!("naive_{}", given), |b| {
+            b.iter(|| loop_size(public_key, Search::Naive))
+        });
+        group.bench_function(format!("bsgs_{}", given), |b| {
+            b.iter(|| loop_size(public_key, Search::BabyStepGiantStep))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_loop_size);
+criterion_main!(benches);
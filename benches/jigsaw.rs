@@ -0,0 +1,58 @@
+/// Benchmarks comparing reading tile edges from the pixels on every comparison against deriving
+/// them from edges cached on each tile, for assembling the Day 20 jigsaw.
+use aoc2020::jigsaw::{
+    assemble, flip_pixels, pixel_edges, rotate_pixels, Puzzle, Tile, NEIGHBOUR_OFFSETS,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::HashMap;
+
+/// Fit the tiles together as `assemble` does, but reading the edges from the pixels every time
+/// they are compared, and turning the pixels a step at a time until an edge is in position.
+fn assemble_from_pixels(puzzle: &Puzzle) -> HashMap<(i64, i64), Tile> {
+    let mut tiles = puzzle.tiles.clone();
+    let mut positions: Vec<Option<(i64, i64)>> = vec![None; tiles.len()];
+    positions[0] = Some((0, 0));
+    let mut processing_stack = vec![0];
+
+    while let Some(index) = processing_stack.pop() {
+        let (row, col) = positions[index].unwrap();
+        let edges = pixel_edges(&tiles[index].rows);
+        for (i, edge) in edges.iter().map(|e| e.flip()).enumerate() {
+            let other = match puzzle.fitting(index, edge) {
+                Some(other) if positions[other].is_none() => other,
+                _ => continue,
+            };
+            let rows = &mut tiles[other].rows;
+            if !pixel_edges(rows).contains(&edge) {
+                flip_pixels(rows);
+            }
+            while pixel_edges(rows)[(i + 2) % 4] != edge {
+                rotate_pixels(rows);
+            }
+            positions[other] = Some((row + NEIGHBOUR_OFFSETS[i].0, col + NEIGHBOUR_OFFSETS[i].1));
+            processing_stack.push(other);
+        }
+    }
+    positions
+        .into_iter()
+        .map(Option::unwrap)
+        .zip(tiles)
+        .collect()
+}
+
+fn bench_jigsaw(c: &mut Criterion) {
+    let tiles = include_str!("../input/20.txt")
+        .split("\n\n")
+        .map(|tile| tile.parse())
+        .collect::<Result<_, _>>()
+        .expect("Invalid day 20 input");
+    let puzzle = Puzzle::new(tiles);
+
+    let mut group = c.benchmark_group("jigsaw");
+    group.bench_function("pixel_edges", |b| b.iter(|| assemble_from_pixels(&puzzle)));
+    group.bench_function("cached_edges", |b| b.iter(|| assemble(&puzzle).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_jigsaw);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+/// Benchmarks comparing a set of the black tiles against a dense array of every tile they can
+/// reach, for the Day 24 lobby exhibit run for the puzzle's 100 days and for 1000. The sparse run
+/// of 1000 days takes about a minute an iteration, so the whole group takes a while.
+use aoc2020::lobby::{get_initial_tiles, run, Engine, FlipRules, Instruction};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn bench_lobby(c: &mut Criterion) {
+    let instructions: Vec<Instruction> = include_str!("../input/24.txt")
+        .lines()
+        .map(|line| line.parse().expect("Invalid day 24 input"))
+        .collect();
+    let tiles = get_initial_tiles(&instructions);
+
+    let mut group = c.benchmark_group("lobby");
+    group.sample_size(10);
+    for &days in &[100, 1000] {
+        group.bench_function(format!("sparse_{}", days), |b| {
+            b.iter(|| run(tiles.clone(), days, FlipRules::EXHIBIT, Engine::Sparse))
+        });
+        group.bench_function(format!("dense_{}", days), |b| {
+            b.iter(|| run(tiles.clone(), days, FlipRules::EXHIBIT, Engine::Dense))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lobby);
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+/// Benchmarks comparing sequential and parallel validation of the Day 2 password policies.
+use aoc2020::password::{count_valid_parallel, PasswordPolicy};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn count_valid_sequential(policies: &[PasswordPolicy]) -> (u32, u32) {
+    policies.iter().fold((0, 0), |acc, policy| {
+        (
+            acc.0 + policy.is_valid_sled_policy() as u32,
+            acc.1 + policy.is_valid_toboggan_policy() as u32,
+        )
+    })
+}
+
+/// Count using the structured checks, which is how validation was done before the byte-level scan.
+fn count_valid_checked(policies: &[PasswordPolicy]) -> (u32, u32) {
+    policies.iter().fold((0, 0), |acc, policy| {
+        (
+            acc.0 + policy.check_sled_policy().is_ok() as u32,
+            acc.1 + policy.check_toboggan_policy().is_ok() as u32,
+        )
+    })
+}
+
+fn bench_validation(c: &mut Criterion) {
+    let policies: Vec<PasswordPolicy> = include_str!("../input/02.txt")
+        .lines()
+        .map(|s| s.parse().expect("Invalid day 2 input"))
+        .collect();
+
+    let mut group = c.benchmark_group("password_validation");
+    group.bench_function("checked", |b| b.iter(|| count_valid_checked(&policies)));
+    group.bench_function("sequential", |b| {
+        b.iter(|| count_valid_sequential(&policies))
+    });
+    group.bench_function("parallel", |b| b.iter(|| count_valid_parallel(&policies)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_validation);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+/// Benchmarks comparing cloning the Day 11 floorplan each generation with double-buffering it.
+use aoc2020::grid::{Adjacent8, Grid, Neighborhood};
+use aoc2020::seating::{SeatingRules, Sightline, Simulation, Tile};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Apply the seat change rules to produce a new floorplan cloned from the old one.
+fn iterate_cloning<N: Neighborhood<Tile>>(
+    from: &Grid<Tile>,
+    neighbourhood: &N,
+    threshold: usize,
+) -> Grid<Tile> {
+    let mut to = from.clone();
+    for (pos, tile) in from.enumerate() {
+        match tile {
+            Tile::Floor => continue,
+            Tile::Seat(false) => {
+                if neighbourhood.count(from, pos, Tile::occupied) == 0 {
+                    to[pos] = Tile::Seat(true);
+                }
+            }
+            Tile::Seat(true) => {
+                if neighbourhood.count(from, pos, Tile::occupied) >= threshold {
+                    to[pos] = Tile::Seat(false);
+                }
+            }
+        }
+    }
+    to
+}
+
+fn run_cloning<N: Neighborhood<Tile>>(
+    data: &Grid<Tile>,
+    neighbourhood: &N,
+    threshold: usize,
+) -> usize {
+    let mut from = data.clone();
+    let mut to = iterate_cloning(&from, neighbourhood, threshold);
+    while to != from {
+        from = to;
+        to = iterate_cloning(&from, neighbourhood, threshold);
+    }
+    to.iter().filter(|tile| tile.occupied()).count()
+}
+
+fn bench_seating(c: &mut Criterion) {
+    let floorplan = Grid::from_chars(include_str!("../input/11.txt"), Tile::from_ch)
+        .expect("Invalid day 11 input");
+
+    let mut group = c.benchmark_group("seating");
+    group.sample_size(10);
+    group.bench_function("adjacent_cloning", |b| {
+        b.iter(|| run_cloning(&floorplan, &Adjacent8, 4))
+    });
+    group.bench_function("adjacent_double_buffered", |b| {
+        b.iter(|| Simulation::new(&floorplan, SeatingRules::PART_ONE).run())
+    });
+    group.bench_function("sightline_cloning", |b| {
+        b.iter(|| run_cloning(&floorplan, &Sightline { limit: None }, 5))
+    });
+    group.bench_function("sightline_double_buffered", |b| {
+        b.iter(|| Simulation::new(&floorplan, SeatingRules::PART_TWO).run())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_seating);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+/// Benchmarks comparing counting the trees on the Day 3 slopes a tile at a time on the grid
+/// against testing a bit per step in a bitset of each row of the map.
+use aoc2020::grid::Grid;
+use aoc2020::toboggan::{trees_hit, Slope, Tile, TreeMap};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const SLOPES: [Slope; 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+
+fn bench_slopes(c: &mut Criterion) {
+    let grid = Grid::from_chars(include_str!("../input/03.txt"), Tile::from_ch)
+        .expect("Invalid day 3 input");
+
+    let mut group = c.benchmark_group("trees_hit");
+    group.bench_function("grid", |b| {
+        b.iter(|| {
+            SLOPES
+                .iter()
+                .map(|&slope| trees_hit(&grid, slope))
+                .product::<u64>()
+        })
+    });
+    group.bench_function("bitset", |b| {
+        b.iter(|| {
+            let map = TreeMap::new(&grid);
+            SLOPES
+                .iter()
+                .map(|&slope| map.trees_hit(slope))
+                .product::<u64>()
+        })
+    });
+    let map = TreeMap::new(&grid);
+    group.bench_function("bitset_prebuilt", |b| {
+        b.iter(|| {
+            SLOPES
+                .iter()
+                .map(|&slope| map.trees_hit(slope))
+                .product::<u64>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_slopes);
+criterion_main!(benches);
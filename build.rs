@@ -0,0 +1,41 @@
+/// Generate a test for each answer recorded in `answers.toml`, for `tests/answers.rs` to include,
+/// each running the day on its real input and checking it gives the recorded answer.
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=answers.toml");
+    let mut tests = String::new();
+    // Without any recorded answers there's nothing to check.
+    if let Ok(manifest) = fs::read_to_string("answers.toml") {
+        let days: toml::Table = manifest
+            .parse()
+            .unwrap_or_else(|err| panic!("Invalid answers.toml: {}", err));
+        for (day, parts) in &days {
+            let num: u32 = day
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid day {:?} in answers.toml", day));
+            for part in 1..=2 {
+                let answer = match parts.get(format!("part{}", part)) {
+                    Some(toml::Value::String(answer)) => answer.clone(),
+                    Some(toml::Value::Integer(answer)) => answer.to_string(),
+                    Some(other) => panic!(
+                        "Invalid answer {} to day {} part {} in answers.toml",
+                        other, day, part
+                    ),
+                    None => continue,
+                };
+                writeln!(
+                    tests,
+                    "#[test]\nfn day_{:02}_part_{}() {{\n    check({}, {}, env!(\"CARGO_BIN_EXE_{:02}\"), {:?});\n}}\n",
+                    num, part, num, part, num, answer
+                )
+                .unwrap();
+            }
+        }
+    }
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("answers.rs");
+    fs::write(out, tests).unwrap();
+}
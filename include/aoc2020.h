@@ -0,0 +1,25 @@
+/* C API to the Advent of Code 2020 solutions, exported by the shared library built with
+ * `cargo rustc --release --lib --features cdylib --crate-type cdylib`. */
+#ifndef AOC2020_H
+#define AOC2020_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* There's no such day or part to solve. */
+#define AOC_UNSUPPORTED (-1)
+/* The input isn't UTF-8 or isn't a puzzle input for the day. */
+#define AOC_INVALID_INPUT (-2)
+/* The input has no answer. */
+#define AOC_NO_ANSWER (-3)
+/* The output buffer is too small for the answer and its terminating NUL. */
+#define AOC_BUFFER_TOO_SMALL (-4)
+
+/* Solve part 1 or 2 of a day from the `len` bytes of puzzle input at `input`, writing the answer
+ * to `out_buf` as a NUL-terminated string. Returns the length of the answer, or else one of the
+ * negative codes above. Every day is supported, solved with the defaults its binary uses when
+ * given no flags; day 25 has only part 1. */
+int32_t aoc_solve(uint32_t day, uint32_t part, const uint8_t *input, size_t len, char *out_buf,
+                  size_t out_len);
+
+#endif
@@ -0,0 +1,354 @@
+/// Joltage adapters of Day 10, and the chains of them which connect the charging port to the
+/// device.
+use crate::parse_lines;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::num::ParseIntError;
+
+/// The number of 1V differences times the number of 3V differences in the chain using every
+/// adapter, for part one.
+pub fn joltage_differences(data: &[u64]) -> Option<u64> {
+    let mut data = data.to_vec();
+    data.sort();
+
+    let mut num_1v_diffs: u64 = 0;
+    // There's always 1 3V difference between the biggest adapter since it is 3V more than the
+    // maximum in the dataset.
+    let mut num_3v_diffs: u64 = 1;
+    let mut last: u64 = 0;
+
+    for number in data {
+        match number - last {
+            1 => num_1v_diffs += 1,
+            2 => (),
+            3 => num_3v_diffs += 1,
+            _ => return None,
+        }
+        last = number;
+    }
+
+    Some(num_1v_diffs * num_3v_diffs)
+}
+
+/// Use sorted data to work out the number of ways to reach an adapter from the available compatible
+/// adapters.
+/// Use some basic caching to try and beat performance issues.
+fn num_ways(data: &[u64], idx: usize, known: &mut HashMap<usize, u64>) -> u64 {
+    // The base case is that the first adapter has only one way to get to it (from the charging
+    // port).
+    if let Some(&ans) = known.get(&idx) {
+        return ans;
+    }
+    match idx {
+        0 => 1,
+        _ => {
+            let mut ways = 0;
+            let mut idx_diff = 1;
+            // Can reach this adapter if the num volts different is 3 or less. If can't reach
+            // this adapter, then we've calculated all the varied ways from reachable adapters
+            // to this one.
+            while idx_diff <= idx && data[idx] - data[idx - idx_diff] <= 3 {
+                ways += num_ways(data, idx - idx_diff, known);
+                idx_diff += 1;
+            }
+            known.insert(idx, ways);
+            ways
+        }
+    }
+}
+
+/// The number of distinct arrangements of adapters which connect the charging port to the device,
+/// for part two.
+pub fn count_arrangements(data: &[u64]) -> Option<u64> {
+    // Use a recursive function to calculate the number of ways to get to a given (final) adapter.
+    // The "last" (device) adapter doesn't count (because there is only one way to get to it as it
+    // is 3V above the highest adapter in the data set), but we need to consider way the ingress
+    // voltage (0V relative charging port) can reach a number of adapters.
+    let mut data = data.to_vec();
+    // Add in the "first" voltage, the 0V represented by the charging power.
+    data.push(0);
+    data.sort();
+    let mut cache: HashMap<usize, u64> = HashMap::new();
+    Some(num_ways(&data, data.len() - 1, &mut cache))
+}
+
+/// The number of ways to cross a run of `length` consecutive 1V differences, which is the
+/// tribonacci number counting the ways to sum to `length` in steps of at most 3.
+fn tribonacci(length: usize) -> u64 {
+    let mut ways: [u64; 3] = [1, 0, 0];
+    for _ in 0..length {
+        ways = [ways.iter().sum(), ways[0], ways[1]];
+    }
+    ways[0]
+}
+
+/// Count the arrangements for part two in closed form, from the runs of consecutive 1V differences.
+///
+/// A 3V difference must always be taken, so the chain splits at each into independent runs of 1V
+/// differences, and the arrangements are the product of the ways to cross each run. This only
+/// holds while every difference is 1V or 3V, so there's no answer if any are 2V.
+pub fn count_arrangements_by_runs(data: &[u64]) -> Option<u64> {
+    let mut data = data.to_vec();
+    data.push(0);
+    data.sort_unstable();
+
+    let mut ways = 1;
+    let mut run = 0;
+    for pair in data.windows(2) {
+        match pair[1] - pair[0] {
+            1 => run += 1,
+            3 => {
+                ways *= tribonacci(run);
+                run = 0;
+            }
+            _ => return None,
+        }
+    }
+    Some(ways * tribonacci(run))
+}
+
+/// Lazily enumerates the valid chains of adapters, as found by a depth-first search.
+struct Arrangements {
+    /// The charging port's 0V followed by the sorted adapters.
+    joltages: Vec<u64>,
+    /// Whether the highest adapter can be reached from each joltage, to prune dead ends.
+    can_finish: Vec<bool>,
+    /// Partial chains still to extend, as indices into the joltages, with the next to try on top.
+    stack: Vec<Vec<usize>>,
+}
+
+impl Arrangements {
+    fn new(data: &[u64]) -> Self {
+        let mut joltages = data.to_vec();
+        joltages.push(0);
+        joltages.sort_unstable();
+
+        let last = joltages.len() - 1;
+        let mut can_finish = vec![false; joltages.len()];
+        can_finish[last] = true;
+        for idx in (0..last).rev() {
+            can_finish[idx] = (idx + 1..=last)
+                .take_while(|&next| joltages[next] - joltages[idx] <= 3)
+                .any(|next| can_finish[next]);
+        }
+
+        let stack = if can_finish[0] {
+            vec![vec![0]]
+        } else {
+            Vec::new()
+        };
+        Self {
+            joltages,
+            can_finish,
+            stack,
+        }
+    }
+}
+
+impl Iterator for Arrangements {
+    /// The joltages of the adapters in the chain, from the charging port to the device.
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let last = self.joltages.len() - 1;
+        while let Some(chain) = self.stack.pop() {
+            let end = *chain.last().unwrap();
+            if end == last {
+                return Some(chain[1..].iter().map(|&idx| self.joltages[idx]).collect());
+            }
+            // Push the furthest adapters first so the chains come out using the nearest first.
+            let reachable: Vec<usize> = (end + 1..=last)
+                .take_while(|&next| self.joltages[next] - self.joltages[end] <= 3)
+                .filter(|&next| self.can_finish[next])
+                .collect();
+            for &next in reachable.iter().rev() {
+                let mut extended = chain.clone();
+                extended.push(next);
+                self.stack.push(extended);
+            }
+        }
+        None
+    }
+}
+
+/// Enumerate up to `limit` of the valid chains of adapters, each a list of the joltages of the
+/// adapters used in order.
+pub fn arrangements(data: &[u64], limit: usize) -> impl Iterator<Item = Vec<u64>> {
+    Arrangements::new(data).take(limit)
+}
+
+/// The highest joltage an adapter can have when streaming, which bounds the memory the table of
+/// joltages takes.
+pub const MAX_STREAMED_JOLTAGE: u64 = 1 << 20;
+
+/// Solve both parts while streaming the adapters from a reader.
+///
+/// The adapters come in any order and there's no sorting them without holding them all, so
+/// rather than collecting them, mark each joltage present in a table indexed by joltage: walking
+/// that table visits the adapters in order, and both parts only ever need to look back over the
+/// last three joltages. The table only grows as far as the highest joltage, which can be at most
+/// `MAX_STREAMED_JOLTAGE`, so an adapter out of all proportion is refused rather than allocated
+/// for.
+pub fn solve_streaming<R: BufRead>(reader: R) -> io::Result<(u64, u64)> {
+    let mut present: Vec<bool> = Vec::new();
+    for joltage in parse_lines::<_, u64>(reader) {
+        let joltage = joltage?;
+        if joltage > MAX_STREAMED_JOLTAGE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Adapter of {}V is too high to stream: expected at most {}V",
+                    joltage, MAX_STREAMED_JOLTAGE
+                ),
+            ));
+        }
+        let joltage = joltage as usize;
+        if joltage >= present.len() {
+            present.resize(joltage + 1, false);
+        }
+        present[joltage] = true;
+    }
+
+    // Start from the charging port at 0V, which can be reached exactly one way. Track the ways to
+    // reach the previous three joltages, most recent first.
+    let mut num_1v_diffs: u64 = 0;
+    let mut num_3v_diffs: u64 = 1;
+    let mut last: usize = 0;
+    let mut ways: [u64; 3] = [1, 0, 0];
+
+    for (joltage, &is_adapter) in present.iter().enumerate().skip(1) {
+        if is_adapter {
+            match joltage - last {
+                1 => num_1v_diffs += 1,
+                2 => (),
+                3 => num_3v_diffs += 1,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("No adapter can reach {}V", joltage),
+                    ))
+                }
+            }
+            last = joltage;
+        }
+        let here = if is_adapter { ways.iter().sum() } else { 0 };
+        ways = [here, ways[0], ways[1]];
+    }
+
+    Ok((num_1v_diffs * num_3v_diffs, ways[0]))
+}
+
+/// Parse the joltage of each adapter, one per line.
+pub fn parse_adapters(input: &str) -> Result<Vec<u64>, ParseIntError> {
+    input.lines().map(|s| s.parse()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_given_example() {
+        let input = "16
+10
+15
+5
+1
+11
+7
+19
+6
+12
+4";
+        let data = parse_adapters(input).expect("Couldn't convert test input");
+
+        // Assert get the right number.
+        assert_eq!(joltage_differences(&data), Some(7 * 5));
+        assert_eq!(count_arrangements(&data), Some(8));
+        assert_eq!(count_arrangements_by_runs(&data), Some(8));
+
+        // Every arrangement counted is enumerated, each only once, starting with using them all.
+        let chains: Vec<Vec<u64>> = arrangements(&data, 100).collect();
+        assert_eq!(chains.len(), 8);
+        assert_eq!(chains.iter().unique().count(), 8);
+        assert_eq!(chains[0], vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]);
+        assert_eq!(chains[7], vec![1, 4, 7, 10, 12, 15, 16, 19]);
+        assert_eq!(arrangements(&data, 3).count(), 3);
+    }
+
+    #[test]
+    fn test_other_given_example() {
+        let input = "28
+33
+18
+42
+31
+14
+46
+20
+48
+47
+24
+23
+49
+45
+19
+38
+39
+11
+1
+32
+25
+35
+8
+17
+7
+9
+4
+2
+34
+10
+3";
+        let data = parse_adapters(input).expect("Couldn't convert test input");
+
+        // Assert get the right number.
+        assert_eq!(joltage_differences(&data), Some(22 * 10));
+        assert_eq!(count_arrangements(&data), Some(19208));
+        assert_eq!(count_arrangements_by_runs(&data), Some(19208));
+        assert_eq!(arrangements(&data, 20000).count(), 19208);
+    }
+
+    #[test]
+    fn test_runs_match() {
+        // Runs of every length up to 10, cut up by 3V differences.
+        let mut data = Vec::new();
+        let mut joltage = 0;
+        for run in 0..=10 {
+            joltage += 3;
+            data.push(joltage);
+            for _ in 0..run {
+                joltage += 1;
+                data.push(joltage);
+            }
+        }
+        assert_eq!(count_arrangements_by_runs(&data), count_arrangements(&data));
+        assert_eq!(tribonacci(4), 7);
+
+        // The closed form doesn't cover 2V differences.
+        let data = vec![1, 3, 4];
+        assert_eq!(count_arrangements(&data), Some(3));
+        assert_eq!(count_arrangements_by_runs(&data), None);
+    }
+
+    #[test]
+    fn test_streaming_matches() {
+        let input = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4";
+        assert_eq!(solve_streaming(input.as_bytes()).unwrap(), (7 * 5, 8));
+
+        // A gap of more than 3V can't be bridged.
+        assert!(solve_streaming("1\n5".as_bytes()).is_err());
+        // Nor is the table of joltages grown to fit an absurd adapter.
+        assert!(solve_streaming("1\n9999999999".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+/// The foods of Day 21, and which of their ingredients contain which allergens.
+use crate::fasthash::{FastMap, FastSet};
+use crate::matching::{assign_unique, AssignError};
+use itertools::Itertools;
+use std::fmt;
+use std::io::{self, BufRead};
+
+/// A small integer standing for an ingredient or allergen name, so sets of them are cheap to
+/// copy, intersect and compare.
+pub type Id = u32;
+
+/// Names given an ID each the first time they're seen.
+#[derive(Clone, Debug, Default)]
+pub struct Names {
+    ids: FastMap<String, Id>,
+    names: Vec<String>,
+}
+
+impl Names {
+    fn intern(&mut self, name: &str) -> Id {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len() as Id;
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        id
+    }
+
+    /// The name given the ID.
+    pub fn name(&self, id: Id) -> &str {
+        &self.names[id as usize]
+    }
+}
+
+/// The ingredients of a food and the allergens it is known to contain.
+#[derive(Clone, Debug)]
+pub struct Food {
+    pub ingreds: FastSet<Id>,
+    pub allergens: Vec<Id>,
+}
+
+impl Food {
+    /// Parse a food, interning the names of its ingredients and allergens.
+    fn parse(s: &str, ingred_names: &mut Names, allergen_names: &mut Names) -> Self {
+        let mut parts = s.split(" (contains ");
+        let ingred_list = parts.next().unwrap();
+        let allergens = match parts.next() {
+            Some(s) => s
+                .trim_matches(')')
+                .split(", ")
+                .map(|s| allergen_names.intern(s))
+                .collect(),
+            None => Vec::new(),
+        };
+        Self {
+            ingreds: ingred_list
+                .split(' ')
+                .map(|s| ingred_names.intern(s))
+                .collect(),
+            allergens,
+        }
+    }
+}
+
+/// The foods, along with the names of every ingredient and allergen in them.
+#[derive(Clone, Debug, Default)]
+pub struct Menu {
+    pub foods: Vec<Food>,
+    pub ingred_names: Names,
+    pub allergen_names: Names,
+}
+
+impl Menu {
+    fn add_food(&mut self, line: &str) {
+        let food = Food::parse(line, &mut self.ingred_names, &mut self.allergen_names);
+        self.foods.push(food);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Allergen(pub String);
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ingredient(pub String);
+
+/// Every allergen along with the ingredient it is, sorted by allergen. Displays as the canonical
+/// dangerous ingredient list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DangerousList(pub Vec<(Allergen, Ingredient)>);
+
+impl fmt::Display for DangerousList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ingreds = self.0.iter().map(|(_, Ingredient(name))| name).join(",");
+        write!(f, "{}", ingreds)
+    }
+}
+
+/// Narrow down the ingredients each of a food's allergens could be: an allergen must be one of
+/// the ingredients common to every food which lists it.
+fn narrow_allergen_possibles(food: &Food, allergen_possibles: &mut FastMap<Id, FastSet<Id>>) {
+    for &allergen in &food.allergens {
+        allergen_possibles
+            .entry(allergen)
+            .and_modify(|possibles| possibles.retain(|ingred| food.ingreds.contains(ingred)))
+            .or_insert_with(|| food.ingreds.clone());
+    }
+}
+
+/// Pin down which ingredient each allergen is, each a different one, sorted by allergen name.
+/// Errors if the foods don't narrow it down to exactly one way.
+fn assign_allergens(
+    allergen_possibles: FastMap<Id, FastSet<Id>>,
+    ingred_names: &Names,
+    allergen_names: &Names,
+) -> io::Result<DangerousList> {
+    let (allergens, candidates): (Vec<Id>, Vec<FastSet<Id>>) =
+        allergen_possibles.into_iter().unzip();
+    let ingreds = assign_unique(&candidates).map_err(|err| match err {
+        AssignError::Ambiguous(slot) => io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Allergen {} could be more than one ingredient",
+                allergen_names.name(allergens[slot])
+            ),
+        ),
+        AssignError::Unsatisfiable => io::Error::new(
+            io::ErrorKind::InvalidData,
+            "No ingredients can be given to the allergens, one each",
+        ),
+    })?;
+    let mut dangerous: Vec<(Allergen, Ingredient)> = allergens
+        .into_iter()
+        .zip(ingreds)
+        .map(|(allergen, ingred)| {
+            (
+                Allergen(allergen_names.name(allergen).to_string()),
+                Ingredient(ingred_names.name(ingred).to_string()),
+            )
+        })
+        .collect();
+    dangerous.sort();
+    Ok(DangerousList(dangerous))
+}
+
+/// Count the appearances of ingredients which can't possibly be any allergen, given how many
+/// times each ingredient appears.
+fn count_impossibles(ingred_counts: &[u32], allergen_possibles: &FastMap<Id, FastSet<Id>>) -> u32 {
+    let mut possible = vec![false; ingred_counts.len()];
+    for &ingred in allergen_possibles.values().flatten() {
+        possible[ingred as usize] = true;
+    }
+    ingred_counts
+        .iter()
+        .zip(possible)
+        .filter(|(_, possible)| !possible)
+        .map(|(count, _)| count)
+        .sum()
+}
+
+/// Count the appearances of ingredients which can't possibly be any allergen, for part one.
+pub fn count_safe_appearances(data: &Menu) -> Option<u32> {
+    // - For each allergen, keep a set of which ingredients it could be
+    // - For each food listed:
+    //     - For each allergen listed:
+    //         - If already have a set of possible ingreds, take the intersection with the new
+    //           possible ingreds to see which ingreds are always the same
+    //         - Else take the new possible ingreds as current best guess
+    // Then, combine all the sets for all allergens with their possibilities. Any ingredient not
+    // in any of them can't possibly be an allergen.
+    let mut allergen_possibles: FastMap<Id, FastSet<Id>> = FastMap::default();
+    let mut ingred_counts = vec![0; data.ingred_names.names.len()];
+
+    for food in &data.foods {
+        for &ingred in &food.ingreds {
+            ingred_counts[ingred as usize] += 1;
+        }
+        narrow_allergen_possibles(food, &mut allergen_possibles);
+    }
+
+    Some(count_impossibles(&ingred_counts, &allergen_possibles))
+}
+
+/// Work out which ingredient each allergen is, for part two.
+pub fn dangerous_list(data: &Menu) -> io::Result<DangerousList> {
+    // Use the same strategy as part one to get the possible matches for each allergen.
+    let mut allergen_possibles: FastMap<Id, FastSet<Id>> = FastMap::default();
+    for food in &data.foods {
+        narrow_allergen_possibles(food, &mut allergen_possibles);
+    }
+
+    // Then go a step further and pin down exactly which ingredient is which allergen.
+    assign_allergens(allergen_possibles, &data.ingred_names, &data.allergen_names)
+}
+
+/// Solve both parts while streaming the foods from a reader, only keeping per-ingredient counts
+/// and the per-allergen possibilities rather than the whole list of foods.
+pub fn solve_streaming<R: BufRead>(reader: R) -> io::Result<(u32, DangerousList)> {
+    let mut allergen_possibles: FastMap<Id, FastSet<Id>> = FastMap::default();
+    let mut ingred_counts: Vec<u32> = Vec::new();
+    let mut ingred_names = Names::default();
+    let mut allergen_names = Names::default();
+
+    for line in reader.lines() {
+        let food = Food::parse(&line?, &mut ingred_names, &mut allergen_names);
+        ingred_counts.resize(ingred_names.names.len(), 0);
+        for &ingred in &food.ingreds {
+            ingred_counts[ingred as usize] += 1;
+        }
+        narrow_allergen_possibles(&food, &mut allergen_possibles);
+    }
+
+    let number_impossibles = count_impossibles(&ingred_counts, &allergen_possibles);
+    Ok((
+        number_impossibles,
+        assign_allergens(allergen_possibles, &ingred_names, &allergen_names)?,
+    ))
+}
+
+/// Parse each food on the menu, one per line.
+pub fn parse_menu(input: &str) -> io::Result<Menu> {
+    let mut menu = Menu::default();
+    for line in input.trim().split('\n') {
+        menu.add_food(line);
+    }
+    Ok(menu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_example() {
+        let input = "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)
+trh fvjkl sbzzf mxmxvkd (contains dairy)
+sqjhc fvjkl (contains soy)
+sqjhc mxmxvkd sbzzf (contains fish)";
+
+        let data = parse_menu(input).expect("Couldn't convert test input");
+
+        // Assert get the right number.
+        assert_eq!(count_safe_appearances(&data), Some(5));
+        let dangerous = dangerous_list(&data).unwrap();
+        assert_eq!(dangerous.to_string(), "mxmxvkd,sqjhc,fvjkl");
+        assert_eq!(
+            dangerous.0[0],
+            (
+                Allergen("dairy".to_string()),
+                Ingredient("mxmxvkd".to_string())
+            )
+        );
+
+        // Streaming the same foods gives the same answers.
+        let (ans1, ans2) = solve_streaming(input.as_bytes()).expect("Couldn't stream test input");
+        assert_eq!(ans1, 5);
+        assert_eq!(ans2, dangerous);
+    }
+
+    #[test]
+    fn test_assignment_errors() {
+        // Either ingredient could be either allergen.
+        let data = parse_menu("a b (contains x, y)").unwrap();
+        let err = dangerous_list(&data).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("could be more than one ingredient"));
+
+        // Both allergens can only be the same ingredient.
+        let data = parse_menu("a b (contains x)\na c (contains y)\na (contains x, y)");
+        assert!(dangerous_list(&data.unwrap()).is_err());
+        assert!(solve_streaming("a (contains x, y)".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,49 @@
+/// Counting heap allocations, to see how much each part leans on the allocator.
+///
+/// With the "count-allocations" feature every allocation made through the global allocator is
+/// counted. Without it, there is nothing to count.
+#[cfg(feature = "count-allocations")]
+use std::alloc::{GlobalAlloc, Layout, System};
+#[cfg(feature = "count-allocations")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "count-allocations")]
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting each allocation (including growing an allocation in place).
+#[cfg(feature = "count-allocations")]
+struct Counting;
+
+#[cfg(feature = "count-allocations")]
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// The number of allocations made so far, if they are being counted.
+pub fn allocations() -> Option<usize> {
+    #[cfg(feature = "count-allocations")]
+    return Some(ALLOCATIONS.load(Ordering::Relaxed));
+    #[cfg(not(feature = "count-allocations"))]
+    return None;
+}
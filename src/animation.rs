@@ -0,0 +1,182 @@
+/// Animations of the cellular automaton days as GIFs, a frame per generation.
+///
+/// Frames are grids of cells drawn as characters, coloured from the same palettes as the SVG
+/// drawings, although only colours given as "#rrggbb" or "#rgb" can be used. Encoding needs the
+/// `animation` feature.
+use crate::get_option;
+use crate::grid::Grid;
+use crate::svg::Palette;
+use std::io;
+use std::time::Duration;
+
+/// How many pixels across each cell is, unless overridden with "--scale <px>".
+pub const SCALE: usize = 4;
+
+/// The pause between frames, unless overridden with "--delay <ms>".
+pub const FRAME_DELAY_MS: u64 = 100;
+
+/// The colour of cells drawn as a character missing from the palette.
+const BACKGROUND: [u8; 3] = [0xff, 0xff, 0xff];
+
+/// Parse a colour given as "#rrggbb" or "#rgb" into its red, green and blue.
+fn rgb(colour: &str) -> io::Result<[u8; 3]> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid colour {:?}: expected #rrggbb or #rgb", colour),
+        )
+    };
+    let hex = colour.strip_prefix('#').ok_or_else(invalid)?;
+    let digits = hex
+        .chars()
+        .map(|ch| ch.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+    match digits[..] {
+        [r, g, b] => Ok([r * 17, g * 17, b * 17]),
+        [r1, r2, g1, g2, b1, b2] => Ok([r1 << 4 | r2, g1 << 4 | g2, b1 << 4 | b2]),
+        _ => Err(invalid()),
+    }
+}
+
+/// The frames as indices into a colour table, all padded with the background to the size of the
+/// largest, along with that size and the table as red, green and blue bytes.
+pub struct Indexed {
+    pub width: usize,
+    pub height: usize,
+    pub colours: Vec<u8>,
+    pub frames: Vec<Vec<u8>>,
+}
+
+/// Turn frames of cells drawn as characters into frames of pixels `scale` across, each an index
+/// into a table of the colours the palette gives the characters.
+pub fn index_frames(frames: &[Grid<char>], scale: usize, palette: &Palette) -> io::Result<Indexed> {
+    let mut keys: Vec<char> = Vec::new();
+    let mut colours = BACKGROUND.to_vec();
+    let width = frames.iter().map(Grid::width).max().unwrap_or(0) * scale;
+    let height = frames.iter().map(Grid::height).max().unwrap_or(0) * scale;
+    let mut indexed = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let mut pixels = vec![0; width * height];
+        for ((row, col), &ch) in frame.enumerate() {
+            let index = match keys.iter().position(|&key| key == ch) {
+                Some(index) => index + 1,
+                None => match palette.colour(ch) {
+                    Some(colour) => {
+                        if keys.len() == 255 {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "Too many colours for a GIF",
+                            ));
+                        }
+                        colours.extend_from_slice(&rgb(colour)?);
+                        keys.push(ch);
+                        keys.len()
+                    }
+                    None => continue,
+                },
+            };
+            for y in row * scale..(row + 1) * scale {
+                pixels[y * width + col * scale..y * width + (col + 1) * scale].fill(index as u8);
+            }
+        }
+        indexed.push(pixels);
+    }
+    Ok(Indexed {
+        width,
+        height,
+        colours,
+        frames: indexed,
+    })
+}
+
+/// Encode indexed frames as a GIF which loops forever, pausing between each frame.
+#[cfg(feature = "animation")]
+fn encode(path: &str, indexed: &Indexed, delay: Duration) -> io::Result<()> {
+    use gif::{Encoder, Frame, Repeat};
+    use std::borrow::Cow;
+    use std::convert::TryFrom;
+    use std::fs::File;
+    use std::io::BufWriter;
+
+    let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "Too large for a GIF");
+    let width = u16::try_from(indexed.width).map_err(|_| too_large())?;
+    let height = u16::try_from(indexed.height).map_err(|_| too_large())?;
+    let out = BufWriter::new(File::create(path)?);
+    let mut encoder =
+        Encoder::new(out, width, height, &indexed.colours).map_err(io::Error::other)?;
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(io::Error::other)?;
+    // GIFs count delays in hundredths of a second.
+    let delay = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+    for pixels in &indexed.frames {
+        let frame = Frame {
+            width,
+            height,
+            delay,
+            buffer: Cow::Borrowed(pixels),
+            ..Default::default()
+        };
+        encoder.write_frame(&frame).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "animation"))]
+fn encode(path: &str, _: &Indexed, _: Duration) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "Can't write {}: build with --features animation to save GIFs",
+            path
+        ),
+    ))
+}
+
+/// Save frames of cells drawn as characters to a GIF file, with each cell the number of pixels
+/// across given by "--scale <px>", the pause between frames given by "--delay <ms>" and the
+/// colours given by "--palette <char>=<colour>,...".
+pub fn save_gif(path: &str, frames: &[Grid<char>]) -> io::Result<()> {
+    let scale = get_option("--scale")?.unwrap_or(SCALE);
+    let delay = Duration::from_millis(get_option("--delay")?.unwrap_or(FRAME_DELAY_MS));
+    let palette = get_option("--palette")?.unwrap_or_default();
+    encode(path, &index_frames(frames, scale, &palette)?, delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb() {
+        assert_eq!(rgb("#4caf50").unwrap(), [0x4c, 0xaf, 0x50]);
+        assert_eq!(rgb("#fff").unwrap(), [0xff, 0xff, 0xff]);
+        assert!(rgb("black").is_err());
+        assert!(rgb("#12345").is_err());
+    }
+
+    #[test]
+    fn test_index_frames() {
+        let frames = [
+            Grid::from_chars("#.\n.#", Some).unwrap(),
+            Grid::from_chars("# \n", Some).unwrap(),
+        ];
+        let indexed = index_frames(&frames, 2, &Palette::default()).unwrap();
+        assert_eq!((indexed.width, indexed.height), (4, 4));
+        // The background comes first, then each colour in the order it's first seen.
+        assert_eq!(
+            indexed.colours,
+            [0xff, 0xff, 0xff, 0x33, 0x33, 0x33, 0xee, 0xee, 0xee]
+        );
+        assert_eq!(
+            indexed.frames[0],
+            [1, 1, 2, 2, 1, 1, 2, 2, 2, 2, 1, 1, 2, 2, 1, 1]
+        );
+        // A smaller frame is padded with the background, as are cells with no colour.
+        assert_eq!(
+            indexed.frames[1],
+            [1, 1, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+}
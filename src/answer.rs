@@ -0,0 +1,217 @@
+/// Answers to each part of a day, as they pass between the days, the runner and the reports
+/// written about them.
+///
+/// With the `serde` feature, answers and results can be serialised and deserialised, with an
+/// answer as a JSON number or string as suits it.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The answer to a part of a puzzle: a number for most days, or text for the days whose answer is
+/// a list of labels or ingredients.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{}", number),
+            Self::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl FromStr for Answer {
+    type Err = io::Error;
+
+    /// Parse an answer as a number if it is one, and as text otherwise. Surrounding whitespace is
+    /// ignored, but an answer can't be blank.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Expected an answer, not a blank",
+            ));
+        }
+        Ok(match s.parse() {
+            Ok(number) => Self::Number(number),
+            Err(_) => Self::Text(s.to_string()),
+        })
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(number: i64) -> Self {
+        Self::Number(number)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+/// An answer is equal to an answer stored as text if they'd be written the same, ignoring any
+/// whitespace around the stored one (e.g. the newline ending a file).
+impl PartialEq<str> for Answer {
+    fn eq(&self, stored: &str) -> bool {
+        match self {
+            Self::Number(number) => stored.trim().parse() == Ok(*number),
+            Self::Text(text) => text == stored.trim(),
+        }
+    }
+}
+
+impl PartialEq<&str> for Answer {
+    fn eq(&self, stored: &&str) -> bool {
+        self == *stored
+    }
+}
+
+/// The answer a day gave to one of its parts, and how long it took to solve if that's known.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DayResult {
+    pub day: u32,
+    /// The part answered, 1 or 2.
+    pub part: u32,
+    pub answer: Answer,
+    pub duration: Option<Duration>,
+}
+
+impl fmt::Display for DayResult {
+    /// Write the result as e.g. "Day 7 part 1: 355 (375µs)".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Day {} part {}: {}", self.day, self.part, self.answer)?;
+        if let Some(duration) = self.duration {
+            write!(f, " ({}µs)", duration.as_micros())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DayResult {
+    type Err = io::Error;
+
+    /// Parse a result as it's displayed, e.g. "Day 7 part 1: 355 (375µs)", with or without the time
+    /// taken.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Invalid result {:?}: expected e.g. \"Day 7 part 1: 355 (375µs)\"",
+                    s
+                ),
+            )
+        };
+        let (heading, answer) = s.trim().split_once(": ").ok_or_else(invalid)?;
+        let (day, part) = heading
+            .strip_prefix("Day ")
+            .and_then(|heading| heading.split_once(" part "))
+            .ok_or_else(invalid)?;
+        let (answer, duration) = match answer
+            .strip_suffix("µs)")
+            .and_then(|answer| answer.rsplit_once(" ("))
+        {
+            Some((answer, micros)) => {
+                let micros = micros.parse().map_err(|_| invalid())?;
+                (answer, Some(Duration::from_micros(micros)))
+            }
+            None => (answer, None),
+        };
+        Ok(Self {
+            day: day.parse().map_err(|_| invalid())?,
+            part: part.parse().map_err(|_| invalid())?,
+            answer: answer.parse()?,
+            duration,
+        })
+    }
+}
+
+/// A result is equal to an answer stored as text if its answer is.
+impl PartialEq<str> for DayResult {
+    fn eq(&self, stored: &str) -> bool {
+        self.answer == *stored
+    }
+}
+
+impl PartialEq<&str> for DayResult {
+    fn eq(&self, stored: &&str) -> bool {
+        self.answer == **stored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answer() {
+        assert_eq!("355".parse::<Answer>().unwrap(), Answer::Number(355));
+        assert_eq!(
+            " dhfng,pgblcd\n".parse::<Answer>().unwrap(),
+            Answer::Text("dhfng,pgblcd".to_string())
+        );
+        assert!("  ".parse::<Answer>().is_err());
+
+        assert_eq!(Answer::Number(-7).to_string(), "-7");
+        assert_eq!(Answer::Number(355), "355\n");
+        assert_ne!(Answer::Number(355), "0355x");
+        assert_eq!(Answer::Text("67384529".to_string()), "67384529");
+    }
+
+    #[test]
+    fn test_day_result() {
+        let result = DayResult {
+            day: 7,
+            part: 1,
+            answer: Answer::Number(355),
+            duration: Some(Duration::from_micros(375)),
+        };
+        assert_eq!(result.to_string(), "Day 7 part 1: 355 (375µs)");
+        assert_eq!(result, "355");
+        let result = DayResult {
+            duration: None,
+            ..result
+        };
+        assert_eq!(result.to_string(), "Day 7 part 1: 355");
+
+        // Results are read back as they're displayed.
+        assert_eq!(result.to_string().parse::<DayResult>().unwrap(), result);
+        assert_eq!(
+            "Day 21 part 2: dhfng,pgblcd (1404µs)"
+                .parse::<DayResult>()
+                .unwrap(),
+            DayResult {
+                day: 21,
+                part: 2,
+                answer: Answer::Text("dhfng,pgblcd".to_string()),
+                duration: Some(Duration::from_micros(1404)),
+            }
+        );
+        assert!("Day 07:".parse::<DayResult>().is_err());
+        assert!("Day 7 part 1: ".parse::<DayResult>().is_err());
+        assert!("Day 100: 2208".parse::<DayResult>().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let answers: Vec<Answer> = serde_json::from_str(r#"[355, "abc"]"#).unwrap();
+        assert_eq!(
+            answers,
+            [Answer::Number(355), Answer::Text("abc".to_string())]
+        );
+        assert_eq!(serde_json::to_string(&answers).unwrap(), r#"[355,"abc"]"#);
+    }
+}
@@ -0,0 +1,96 @@
+/// Scratch space for structures built and thrown away on every generation of a simulation.
+///
+/// With the "arena" feature the scratch space is a bump arena per thread, which is reset before
+/// each use so its memory is reused rather than going back to the allocator every generation.
+/// Without it, scratch structures are ordinary heap allocations.
+#[cfg(feature = "arena")]
+use std::cell::RefCell;
+use std::hash::Hash;
+
+#[cfg(feature = "arena")]
+pub struct Scratch {
+    bump: bumpalo::Bump,
+}
+
+#[cfg(not(feature = "arena"))]
+pub struct Scratch;
+
+/// A hash map allocated in the scratch space.
+#[cfg(feature = "arena")]
+pub type ScratchMap<'a, K, V> =
+    hashbrown::HashMap<K, V, hashbrown::DefaultHashBuilder, &'a bumpalo::Bump>;
+#[cfg(not(feature = "arena"))]
+pub type ScratchMap<'a, K, V> = crate::fasthash::FastMap<K, V>;
+
+/// A vector allocated in the scratch space.
+#[cfg(feature = "arena")]
+pub type ScratchVec<'a, T> = bumpalo::collections::Vec<'a, T>;
+#[cfg(not(feature = "arena"))]
+pub type ScratchVec<'a, T> = Vec<T>;
+
+#[cfg(feature = "arena")]
+thread_local! {
+    static SCRATCH: RefCell<Scratch> = RefCell::new(Scratch {
+        bump: bumpalo::Bump::new(),
+    });
+}
+
+impl Scratch {
+    pub fn map<K: Eq + Hash, V>(&self, capacity: usize) -> ScratchMap<'_, K, V> {
+        #[cfg(feature = "arena")]
+        return ScratchMap::with_capacity_in(capacity, &self.bump);
+        #[cfg(not(feature = "arena"))]
+        return ScratchMap::with_capacity_and_hasher(capacity, Default::default());
+    }
+
+    pub fn vec<T>(&self, capacity: usize) -> ScratchVec<'_, T> {
+        #[cfg(feature = "arena")]
+        return ScratchVec::with_capacity_in(capacity, &self.bump);
+        #[cfg(not(feature = "arena"))]
+        return ScratchVec::with_capacity(capacity);
+    }
+}
+
+/// Run a closure with the scratch space, emptied of anything left from before. Nothing allocated
+/// in it can outlive the closure.
+///
+/// A closure already using the scratch space which asks for it again is given a fresh arena of
+/// its own, rather than having the space it is using reset underneath it.
+pub fn with_scratch<R>(f: impl FnOnce(&Scratch) -> R) -> R {
+    #[cfg(feature = "arena")]
+    return SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+        Ok(mut scratch) => {
+            scratch.bump.reset();
+            f(&scratch)
+        }
+        Err(_) => f(&Scratch {
+            bump: bumpalo::Bump::new(),
+        }),
+    });
+    #[cfg(not(feature = "arena"))]
+    return f(&Scratch);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch() {
+        let total = with_scratch(|scratch| {
+            let mut counts = scratch.map(4);
+            for word in "a b a c a b".split(' ') {
+                *counts.entry(word).or_insert(0) += 1;
+            }
+            // Asking again while in use gets separate space.
+            let inner = with_scratch(|inner| {
+                let mut squares = inner.vec(3);
+                squares.extend(counts.values().map(|count| count * count));
+                squares.iter().sum::<i32>()
+            });
+            assert_eq!(counts["a"], 3);
+            inner
+        });
+        assert_eq!(total, 9 + 4 + 1);
+    }
+}
@@ -0,0 +1,122 @@
+/// A cellular automaton over any space, where cells are live or dead and live or die by how many
+/// live neighbours they have, as for the Conway cubes of Day 17 and the hexagonal tiles of Day 24.
+use crate::arena::{self, ScratchMap};
+use crate::fasthash::{FastMap, FastSet};
+use rayon::prelude::*;
+use std::hash::Hash;
+use std::mem;
+
+/// Step an automaton from its live cells to the next generation's.
+///
+/// Every live cell's neighbours are counted in a single pass over the live cells, by adding 1 to
+/// each neighbour of each live cell, so only cells with a live neighbour or which are live
+/// themselves are considered. `rule` is then given whether each of those is live and its number of
+/// live neighbours, and says whether it is live in the next generation. Any other cell is dead
+/// with no live neighbours, and stays dead: rules can't bring these to life, as there would be no
+/// end to them.
+pub fn step<C, I>(
+    live: &FastSet<C>,
+    neighbours: impl Fn(C) -> I,
+    rule: impl Fn(bool, usize) -> bool,
+) -> FastSet<C>
+where
+    C: Copy + Eq + Hash,
+    I: IntoIterator<Item = C>,
+{
+    // The counts are thrown away once the generation is worked out, so live in the scratch space.
+    arena::with_scratch(|scratch| {
+        let mut live_neighbours: ScratchMap<C, usize> = scratch.map(live.len() * 2);
+        for &cell in live {
+            // Live cells are counted even with no live neighbours, in case they survive that.
+            live_neighbours.entry(cell).or_insert(0);
+            for neighbour in neighbours(cell) {
+                *live_neighbours.entry(neighbour).or_insert(0) += 1;
+            }
+        }
+        live_neighbours
+            .into_iter()
+            .filter(|&(cell, count)| rule(live.contains(&cell), count))
+            .map(|(cell, _)| cell)
+            .collect()
+    })
+}
+
+/// Step an automaton as `step` does, but counting the live neighbours across threads.
+///
+/// The live cells are split into a shard per rayon thread, and each thread counts the neighbours
+/// of its shard into a map of its own. The maps are then merged pairwise, adding the smaller
+/// into the larger, and the rule is applied to the merged counts in parallel too. As the maps
+/// cross threads, they come from the heap rather than the thread's scratch space.
+pub fn step_parallel<C, I>(
+    live: &FastSet<C>,
+    neighbours: impl Fn(C) -> I + Sync,
+    rule: impl Fn(bool, usize) -> bool + Sync,
+) -> FastSet<C>
+where
+    C: Copy + Eq + Hash + Send + Sync,
+    I: IntoIterator<Item = C>,
+{
+    let cells: Vec<C> = live.iter().copied().collect();
+    let shard_size = cells.len().div_ceil(rayon::current_num_threads()).max(1);
+    let live_neighbours = cells
+        .par_chunks(shard_size)
+        .map(|shard| {
+            let mut counts: FastMap<C, usize> = FastMap::default();
+            counts.reserve(shard.len() * 2);
+            for &cell in shard {
+                counts.entry(cell).or_insert(0);
+                for neighbour in neighbours(cell) {
+                    *counts.entry(neighbour).or_insert(0) += 1;
+                }
+            }
+            counts
+        })
+        .reduce(FastMap::default, |mut counts, mut other| {
+            if counts.len() < other.len() {
+                mem::swap(&mut counts, &mut other);
+            }
+            for (cell, count) in other {
+                *counts.entry(cell).or_insert(0) += count;
+            }
+            counts
+        });
+    live_neighbours
+        .into_par_iter()
+        .filter(|&(cell, count)| rule(live.contains(&cell), count))
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        // Cells on a line, which live with exactly 1 live neighbour.
+        let line = |cell: i32| vec![cell - 1, cell + 1];
+        let rule = |_, count| count == 1;
+        let live: FastSet<i32> = [0, 1].iter().copied().collect();
+        let next = step(&live, line, rule);
+        assert_eq!(next, [-1, 0, 1, 2].iter().copied().collect());
+        assert_eq!(
+            step(&next, line, rule),
+            [-2, -1, 2, 3].iter().copied().collect()
+        );
+
+        // A live cell with no live neighbours is still given to the rule.
+        let lonely: FastSet<i32> = [0].iter().copied().collect();
+        assert_eq!(
+            step(&lonely, line, |live, count| live && count == 0),
+            lonely
+        );
+
+        // Counting across threads gives the same generations.
+        let mut live: FastSet<i32> = (0..200).filter(|cell| cell % 3 != 0).collect();
+        for _ in 0..10 {
+            let next = step_parallel(&live, line, rule);
+            assert_eq!(next, step(&live, line, rule));
+            live = next;
+        }
+    }
+}
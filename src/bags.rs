@@ -0,0 +1,152 @@
+/// The graph of bag containment rules of Day 7.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::io;
+use std::str::FromStr;
+
+/// The bag colour both parts ask about.
+pub const TARGET: &str = "shiny gold";
+
+/// Bag rules as a directed graph, with an edge from each bag colour to each colour it must
+/// directly contain, weighted by how many bags of that colour.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BagGraph {
+    rules: HashMap<String, HashMap<String, usize>>,
+}
+
+impl BagGraph {
+    /// The colours and numbers of bags directly inside a bag of the given colour, if there is a
+    /// rule for it.
+    pub fn contents(&self, color: &str) -> Option<&HashMap<String, usize>> {
+        self.rules.get(color)
+    }
+
+    /// Find every bag colour which can (eventually) contain at least one bag of the given colour.
+    ///
+    /// Builds an index of which bags each colour is directly contained by, then searches up
+    /// through it from the given colour, so each rule is only visited once.
+    pub fn containers_of(&self, color: &str) -> HashSet<String> {
+        let mut contained_by: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (outer, contents) in &self.rules {
+            for inner in contents.keys() {
+                contained_by.entry(inner).or_default().push(outer);
+            }
+        }
+
+        let mut containers = HashSet::new();
+        let mut to_visit = vec![color];
+        while let Some(inner) = to_visit.pop() {
+            for &outer in contained_by.get(inner).into_iter().flatten() {
+                if containers.insert(outer.to_string()) {
+                    to_visit.push(outer);
+                }
+            }
+        }
+        containers
+    }
+
+    /// Find how many bags of each colour must be inside a bag of the given colour, counting bags
+    /// nested at every level, or `None` if there is no rule for a colour found along the way.
+    pub fn contents_of(&self, color: &str) -> Option<BTreeMap<String, usize>> {
+        let mut contents = BTreeMap::new();
+        for (inner, &count) in self.contents(color)? {
+            *contents.entry(inner.clone()).or_default() += count;
+            for (nested, nested_count) in self.contents_of(inner)? {
+                *contents.entry(nested).or_default() += count * nested_count;
+            }
+        }
+        Some(contents)
+    }
+
+    /// Write the rules as a Graphviz DOT graph, with each edge labelled by the number of bags and
+    /// the given bag colour highlighted.
+    pub fn to_dot(&self, highlight: &str) -> String {
+        let mut dot = String::from("digraph bags {\n");
+        writeln!(dot, "    {:?} [style=filled, fillcolor=gold];", highlight).unwrap();
+
+        // Sort the rules so the output is stable between runs.
+        let sorted: BTreeMap<_, BTreeMap<_, _>> = self
+            .rules
+            .iter()
+            .map(|(outer, contents)| (outer, contents.iter().collect()))
+            .collect();
+        for (outer, contents) in sorted {
+            if contents.is_empty() {
+                writeln!(dot, "    {:?};", outer).unwrap();
+            }
+            for (inner, count) in contents {
+                writeln!(dot, "    {:?} -> {:?} [label={}];", outer, inner, count).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl FromStr for BagGraph {
+    type Err = io::Error;
+
+    /// Parse rules of the form "<colour> bags contain (no other bags | {<num> <colour> bag[s]})."
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid bag rule {:?}", line),
+            )
+        };
+        let rules = s
+            .lines()
+            .map(|line| {
+                let (outer, contents) = line
+                    .trim_end_matches('.')
+                    .split_once(" bags contain ")
+                    .ok_or_else(|| invalid(line))?;
+                let contents = contents
+                    .split(", ")
+                    .filter(|s| *s != "no other bags")
+                    .map(|s| {
+                        // {<num> <colour>}
+                        let (count, inner) = s
+                            .strip_suffix(" bags")
+                            .or_else(|| s.strip_suffix(" bag"))
+                            .and_then(|s| s.split_once(' '))
+                            .ok_or_else(|| invalid(line))?;
+                        let count = count.parse().map_err(|_| invalid(line))?;
+                        Ok((inner.to_string(), count))
+                    })
+                    .collect::<io::Result<_>>()?;
+                Ok((outer.to_string(), contents))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { rules })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let graph: BagGraph = "bright white bags contain 1 shiny gold bag, 2 dark olive bags.
+shiny gold bags contain no other bags.
+dark olive bags contain no other bags."
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            graph.to_dot("shiny gold"),
+            "digraph bags {
+    \"shiny gold\" [style=filled, fillcolor=gold];
+    \"bright white\" -> \"dark olive\" [label=2];
+    \"bright white\" -> \"shiny gold\" [label=1];
+    \"dark olive\";
+    \"shiny gold\";
+}
+"
+        );
+        assert!("shiny gold bags contain a dark olive bag."
+            .parse::<BagGraph>()
+            .is_err());
+    }
+}
@@ -1,61 +1,7 @@
 /// Solution to Advent of Code Challenge Day 02.
-use aoc2020::{get_day_input, print_elapsed_time};
-use std::num::ParseIntError;
-use std::str::FromStr;
-
-/// Structure specifying a policy and the password to validate against it.
-#[derive(Debug)]
-struct PasswordPolicy {
-    range: (usize, usize),
-    ch: char,
-    password: String,
-}
-
-impl FromStr for PasswordPolicy {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let fields: Vec<&str> = s.split(' ').collect();
-
-        let range_nums: Vec<usize> = fields[0]
-            .split('-')
-            .map(|s| {
-                s.parse()
-                    .expect("Range values are cannot be parsed to numbers")
-            })
-            .collect();
-        let ch: char = fields[1]
-            .chars()
-            .nth(0)
-            .expect("The character specified is invalid");
-        let password: &str = fields[2];
-
-        Ok(PasswordPolicy {
-            ch,
-            range: (range_nums[0], range_nums[1]),
-            password: password.to_string(),
-        })
-    }
-}
-
-impl PasswordPolicy {
-    /// Check if the password is valid given the sled shop policy.
-    fn is_valid_sled_policy(&self) -> bool {
-        let num_chars = self.password.matches(self.ch).count();
-        let range = self.range.0..=self.range.1;
-        range.contains(&num_chars)
-    }
-
-    /// Check if the password is valid given the toboggan store policy.
-    fn is_valid_toboggan_policy(&self) -> bool {
-        let match_idxs: Vec<usize> = self
-            .password
-            .match_indices(self.ch)
-            .map(|(idx, _)| idx)
-            .collect();
-        match_idxs.contains(&(self.range.0 - 1)) ^ match_idxs.contains(&(self.range.1 - 1))
-    }
-}
+use aoc2020::password::{count_valid_parallel, count_valid_streaming, PasswordPolicy};
+use aoc2020::{elapsed_time, get_day_input, get_day_reader, has_flag, print_result};
+use std::io;
 
 /// Parse the challenge input into the list of decoded data structures.
 fn get_password_policies_list(input: String) -> Vec<PasswordPolicy> {
@@ -85,24 +31,67 @@ fn part_two(data: &[PasswordPolicy]) -> Option<u32> {
     }))
 }
 
-fn main() {
+/// Report on every line whether it passes each policy, and why not if it fails.
+fn diagnose(data: &[PasswordPolicy]) -> Vec<String> {
+    data.iter()
+        .enumerate()
+        .map(|(i, policy)| {
+            format!(
+                "Line {} ({}): sled: {}; toboggan: {}",
+                i + 1,
+                policy,
+                policy.describe(policy.check_sled_policy()),
+                policy.describe(policy.check_toboggan_policy())
+            )
+        })
+        .collect()
+}
+
+fn main() -> io::Result<()> {
+    if has_flag("--diagnose") {
+        let data = get_password_policies_list(get_day_input("02"));
+        for line in diagnose(&data) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    if has_flag("--parallel") {
+        let data = get_password_policies_list(get_day_input("02"));
+        println!("Day 02 (parallel):");
+        println!("==========");
+        // Both parts are counted at once, so each is given the time taken for both.
+        let ((ans1, ans2), took) = elapsed_time(|| count_valid_parallel(&data));
+        print_result("02", 1, ans1, took);
+        print_result("02", 2, ans2, took);
+        return Ok(());
+    }
+
+    if has_flag("--stream") {
+        println!("Day 02 (streaming):");
+        println!("==========");
+        let (counts, took) = elapsed_time(|| count_valid_streaming(get_day_reader("02")?));
+        let (ans1, ans2) = counts?;
+        print_result("02", 1, ans1, took);
+        print_result("02", 2, ans2, took);
+        return Ok(());
+    }
+
     let input = get_day_input("02");
     let data = get_password_policies_list(input);
     println!("Day 02:");
     println!("==========");
-    println!(
-        "Part one: {}",
-        print_elapsed_time(|| part_one(&data)).expect("No solution found for part one"),
-    );
-    println!(
-        "Part two: {}",
-        print_elapsed_time(|| part_two(&data)).expect("No solution found for part two"),
-    );
+    let (ans1, took) = elapsed_time(|| part_one(&data));
+    print_result("02", 1, ans1.expect("No solution found for part one"), took);
+    let (ans2, took) = elapsed_time(|| part_two(&data));
+    print_result("02", 2, ans2.expect("No solution found for part two"), took);
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc2020::password::Failure;
 
     #[test]
     fn test_given_example() {
@@ -135,4 +124,24 @@ mod tests {
         assert_eq!(part_one(&data), Some(2));
         assert_eq!(part_two(&data), Some(3));
     }
+
+    #[test]
+    fn test_failure_reasons() {
+        let data = get_password_policies_list("1-3 b: cdefg\n2-9 c: ccccccccc\n".to_string());
+        assert_eq!(
+            data[0].check_sled_policy(),
+            Err(Failure::Count { found: 0 })
+        );
+        assert_eq!(
+            data[1].check_toboggan_policy(),
+            Err(Failure::Positions {
+                found: (Some('c'), Some('c'))
+            })
+        );
+        assert_eq!(
+            diagnose(&data)[0],
+            "Line 1 (1-3 b: cdefg): sled: expected 'b' 1-3 times, found 0; \
+             toboggan: expected 'b' at exactly one of positions 1 and 3, found 'c' and 'e'"
+        );
+    }
 }
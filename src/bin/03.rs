@@ -1,101 +1,87 @@
 /// Solution to Advent of Code Challenge Day 03.
-use aoc2020::{get_day_input, print_elapsed_time};
+use aoc2020::grid::Grid;
+use aoc2020::toboggan::{
+    parse_slopes, render_path, trees_hit, trees_hit_streaming, Engine, Slope, Tile, TreeMap, SLOPES,
+};
+use aoc2020::{elapsed_time, get_day_input, get_day_reader, get_option, has_flag, print_result};
 use std::io;
-use std::str::FromStr;
 
-/// Each tile is either a tree or open space.
-#[derive(PartialEq)]
-enum Tile {
-    Open,
-    Tree,
+/// Parse the map of tiles from an input string.
+fn get_tile_grid(input: String) -> Result<Grid<Tile>, io::Error> {
+    Grid::from_chars(&input, Tile::from_ch)
 }
 
-impl Tile {
-    fn from_ch(ch: char) -> Option<Self> {
-        match ch {
-            '.' => Some(Self::Open),
-            '#' => Some(Self::Tree),
-            _ => None,
+/// Count the trees hit at each slope with the map stored as the engine does, converting it once
+/// up front if need be.
+fn tree_counter(data: &Grid<Tile>, engine: Engine) -> Box<dyn Fn(Slope) -> u64 + '_> {
+    match engine {
+        Engine::Grid => Box::new(move |slope| trees_hit(data, slope)),
+        Engine::Bitset => {
+            let map = TreeMap::new(data);
+            Box::new(move |slope| map.trees_hit(slope))
         }
     }
 }
 
-struct TileRow {
-    row: Vec<Tile>,
-}
-
-impl FromStr for TileRow {
-    type Err = io::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            row: s
-                .chars()
-                .map(|c| {
-                    Tile::from_ch(c).ok_or(Self::Err::new(
-                        io::ErrorKind::Other,
-                        "Invalid character hit reading input",
-                    ))
-                })
-                .collect::<Result<_, _>>()?,
-        })
-    }
-}
-
-/// Parse a list of TileRows from an input string.
-fn get_tile_rows(input: String) -> Result<Vec<TileRow>, io::Error> {
-    input.lines().map(|s| s.parse()).collect::<Result<_, _>>()
-}
-
 /// O(N) in the length of the data to perform the slope calculations and hit the
 /// right tiles to check if tree.
-fn part_one(data: &Vec<TileRow>, right: usize, down: usize) -> Option<u64> {
-    if data.is_empty() {
+fn part_one(data: &Grid<Tile>, right: usize, down: usize, engine: Engine) -> Option<u64> {
+    if data.height() == 0 {
         return None;
     }
-
-    // All lines are guaranteed to be the same length.
-    let length = data[0].row.len();
-
-    let mut row_idx: usize = 0;
-    let mut tree_count: u64 = 0;
-    let mut col_idx: usize = 0;
-
-    // While there is enough space for the next hop.
-    while row_idx + down < data.len() {
-        col_idx = (col_idx + right) % length;
-        row_idx += down;
-        if data[row_idx].row[col_idx] != Tile::Tree {
-            continue;
-        }
-        tree_count += 1;
-    }
-
-    Some(tree_count)
+    Some(tree_counter(data, engine)((right, down)))
 }
 
 /// O(NM) in the length of the data to the number of slopes given to perform the
 /// slope calculations and hit the right tiles to check if tree.
-fn part_two(data: &Vec<TileRow>, slopes: &[(usize, usize)]) -> Option<u64> {
-    slopes
-        .iter()
-        .map(|(right, down)| part_one(data, *right, *down))
-        .product::<Option<_>>()
+fn part_two(data: &Grid<Tile>, slopes: &[Slope], engine: Engine) -> Option<u64> {
+    if data.height() == 0 {
+        return None;
+    }
+    let trees_hit = tree_counter(data, engine);
+    Some(slopes.iter().map(|&slope| trees_hit(slope)).product())
 }
 
 fn main() -> Result<(), io::Error> {
+    // Check other slopes in part two with "--slopes right,down;...".
+    let slopes = match get_option::<String>("--slopes")? {
+        Some(slopes) => parse_slopes(&slopes)?,
+        None => SLOPES.to_vec(),
+    };
+
+    if has_flag("--render") {
+        let data = get_tile_grid(get_day_input("03"))?;
+        for &slope in &slopes {
+            println!("Right {}, down {}:", slope.0, slope.1);
+            println!("{}", render_path(&data, slope));
+        }
+        return Ok(());
+    }
+
+    if has_flag("--stream") {
+        // Part one's slope goes first, then the part two slopes.
+        let slopes: Vec<Slope> = Some((3, 1)).into_iter().chain(slopes).collect();
+        println!("Day 03 (streaming):");
+        println!("==========");
+        // Every slope is counted in the one pass, so each part is given the time taken for both.
+        let (counts, took) = elapsed_time(|| trees_hit_streaming(get_day_reader("03")?, &slopes));
+        let counts = counts?;
+        print_result("03", 1, counts[0], took);
+        print_result("03", 2, counts[1..].iter().product::<u64>(), took);
+        return Ok(());
+    }
+
     let input = get_day_input("03");
-    let data = get_tile_rows(input)?;
+    let data = get_tile_grid(input)?;
+    // Count trees on a bitset of each row of the map rather than the grid of tiles with
+    // "--engine bitset".
+    let engine = get_option("--engine")?.unwrap_or(Engine::Grid);
     println!("Day 03:");
     println!("==========");
-    println!(
-        "Part one: {}",
-        print_elapsed_time(|| part_one(&data, 3, 1)).expect("No solution found for part one"),
-    );
-    println!(
-        "Part two: {}",
-        print_elapsed_time(|| part_two(&data, &[(1, 1), (3, 1), (5, 1), (7, 1), (1, 2),]))
-            .expect("No solution found for part two"),
-    );
+    let (ans1, took) = elapsed_time(|| part_one(&data, 3, 1, engine));
+    print_result("03", 1, ans1.expect("No solution found for part one"), took);
+    let (ans2, took) = elapsed_time(|| part_two(&data, &slopes, engine));
+    print_result("03", 2, ans2.expect("No solution found for part two"), took);
     Ok(())
 }
 
@@ -117,13 +103,12 @@ mod tests {
 #...##....#
 .#..#...#.#"
             .to_string();
-        let data = get_tile_rows(input).expect("Couldn't convert test input");
+        let data = get_tile_grid(input).expect("Couldn't convert test input");
 
         // Check each gives the right answer.
-        assert_eq!(part_one(&data, 3, 1), Some(7));
-        assert_eq!(
-            part_two(&data, &[(1, 1), (3, 1), (5, 1), (7, 1), (1, 2),]),
-            Some(2 * 7 * 3 * 4 * 2)
-        );
+        for &engine in &[Engine::Grid, Engine::Bitset] {
+            assert_eq!(part_one(&data, 3, 1, engine), Some(7));
+            assert_eq!(part_two(&data, &SLOPES, engine), Some(2 * 7 * 3 * 4 * 2));
+        }
     }
 }
@@ -1,80 +1,65 @@
 /// Solution to Advent of Code Challenge Day 05.
-use aoc2020::{get_day_input, print_elapsed_time};
-use std::collections::HashSet;
+use aoc2020::boarding::{
+    decode_seats, find_gaps, find_my_seat, highest_id, Seat, COL_BITS, ROW_BITS,
+};
+use aoc2020::grid::Grid;
+use aoc2020::{elapsed_time, get_day_input, get_option, has_flag, print_result};
 use std::io;
-use std::str::FromStr;
 
-const DAYNUM: &'static str = "05";
-type ChallengeData = Vec<BoardingPass>;
-type ChallengeOut = usize;
+const DAYNUM: &str = "05";
 
-#[derive(Debug)]
-struct BoardingPass {
-    row: usize,
-    col: usize,
-    seat_id: usize,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SeatState {
+    Free,
+    Taken,
+    Mine,
 }
 
-impl FromStr for BoardingPass {
-    type Err = io::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let row_ch: Vec<_> = s.chars().take(7).collect();
-        let col_ch: Vec<_> = s.chars().skip(7).take(3).collect();
-        let row: usize = row_ch
-            .iter()
-            .map(|ch| match ch {
-                'F' => 0,
-                'B' => 1,
-                _ => panic!("Invalid boarding pass data"),
-            })
-            .fold(0, |acc, x| 2 * acc + x);
-        let col: usize = col_ch
-            .iter()
-            .map(|ch| match ch {
-                'L' => 0,
-                'R' => 1,
-                _ => panic!("Invalid boarding pass data"),
-            })
-            .fold(0, |acc, x| 2 * acc + x);
-        let seat_id: usize = (row * 8) + col;
-        Ok(Self { row, col, seat_id })
+/// Lay out every seat on the plane, marking those which could be ours.
+fn seat_map(data: &[Seat], row_bits: u32, col_bits: u32) -> Grid<SeatState> {
+    let (rows, cols) = (1 << row_bits, 1 << col_bits);
+    let mut map = Grid::from_rows(vec![vec![SeatState::Free; cols]; rows])
+        .expect("Rows are all the same length");
+    for seat in data {
+        map[(seat.row, seat.col)] = SeatState::Taken;
     }
+    for id in find_gaps(data) {
+        map[(id >> col_bits, id % cols)] = SeatState::Mine;
+    }
+    map
 }
 
-/// Solution to part one.
-fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
-    // Find the max seat ID.
-    data.iter().map(|p| p.seat_id).max()
-}
-
-/// Solution to part two.
-fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
-    let ids: HashSet<_> = data.iter().map(|p| p.seat_id).collect();
-    // Find our seat: for each taken seat, check if the seat two seats over is taken, but the seat
-    // one over is not. This would be our seat.
-    ids.iter()
-        .find(|id| !ids.contains(&(*id + 1)) && ids.contains(&(*id + 2)))
-        .map(|id| *id + 1)
-}
-
-fn get_data(input: String) -> Result<ChallengeData, io::Error> {
-    input.lines().map(|s| s.parse()).collect()
+/// Render the seat map, with occupied seats as `#`, free seats as `.` and ours as `X`.
+fn render_seat_map(map: &Grid<SeatState>) -> String {
+    map.render(|state| match state {
+        SeatState::Free => '.',
+        SeatState::Taken => '#',
+        SeatState::Mine => 'X',
+    })
 }
 
 fn main() -> Result<(), io::Error> {
+    // Planes of other sizes can be given with "--row-bits <n>" and "--col-bits <n>".
+    let row_bits = get_option("--row-bits")?.unwrap_or(ROW_BITS);
+    let col_bits = get_option("--col-bits")?.unwrap_or(COL_BITS);
     let input = get_day_input(DAYNUM);
-    let data = get_data(input)?;
+    let data = decode_seats(&input, row_bits, col_bits)?;
+    if has_flag("--map") {
+        println!("{}", render_seat_map(&seat_map(&data, row_bits, col_bits)));
+        let gaps = find_gaps(&data);
+        if gaps.len() != 1 {
+            println!("Expected one free seat to be ours, found {}", gaps.len());
+        }
+        return Ok(());
+    }
     println!("Day {}:", DAYNUM);
     println!("==========");
-    println!(
-        "Part one: {}",
-        print_elapsed_time(|| part_one(&data)).expect("No solution found for part one"),
-    );
-    println!(
-        "Part two: {}",
-        print_elapsed_time(|| part_two(&data)).expect("No solution found for part two"),
-    );
+    let (ans1, took) = elapsed_time(|| highest_id(&data));
+    let ans1 = ans1.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
+    let (ans2, took) = elapsed_time(|| find_my_seat(&data));
+    let ans2 = ans2.expect("No solution found for part two");
+    print_result(DAYNUM, 2, ans2, took);
     Ok(())
 }
 
@@ -83,31 +68,17 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_given_example() {
-        let input: String = "FBFBBFFRLR
-BFFFBBFRRR
-FFFBBBFRRR
-BBFFBBFRLL"
-            .to_string();
-        let data = get_data(input).expect("Couldn't convert test input");
-
-        // Check all of the boarding pass data calculated correctly.
-        assert_eq!(data[0].row, 44);
-        assert_eq!(data[0].col, 5);
-        assert_eq!(data[0].seat_id, 357);
-
-        assert_eq!(data[1].row, 70);
-        assert_eq!(data[1].col, 7);
-        assert_eq!(data[1].seat_id, 567);
-
-        assert_eq!(data[2].row, 14);
-        assert_eq!(data[2].col, 7);
-        assert_eq!(data[2].seat_id, 119);
-
-        assert_eq!(data[3].row, 102);
-        assert_eq!(data[3].col, 4);
-        assert_eq!(data[3].seat_id, 820);
+    fn test_seat_map() {
+        // A plane of 4 rows of 2 seats, where the only gap is the first seat in row 2.
+        let input = "FFL\nFFR\nFBL\nFBR\nBFR\nBBL";
+        let data = decode_seats(input, 2, 1).expect("Couldn't convert test input");
+        assert_eq!(find_my_seat(&data), Some(4));
+        assert_eq!(render_seat_map(&seat_map(&data, 2, 1)), "##\n##\nX#\n#.");
 
-        assert_eq!(part_one(&data), Some(820));
+        // With two gaps, both are shown as candidates.
+        let input = "FFL\nFFR\nFBR\nBFR\nBBL";
+        let data = decode_seats(input, 2, 1).expect("Couldn't convert test input");
+        assert_eq!(find_gaps(&data), vec![2, 4]);
+        assert_eq!(render_seat_map(&seat_map(&data, 2, 1)), "##\nX#\nX#\n#.");
     }
 }
@@ -1,65 +1,49 @@
 /// Solution to Advent of Code Challenge Day 06.
-use aoc2020::{get_day_input, print_elapsed_time};
-use std::collections::HashSet;
+use aoc2020::customs::{count_answers, parse_groups, Group, Threshold};
+use aoc2020::{elapsed_time, get_day_input, get_option, print_result};
 use std::io;
-use std::iter::FromIterator;
 
-const DAYNUM: &'static str = "06";
-type ChallengeData = Vec<Vec<HashSet<char>>>;
+const DAYNUM: &str = "06";
+type ChallengeData = Vec<Group>;
 type ChallengeOut = usize;
 
 /// Solution to part one.
 fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
     // Get the number of answers given, where each answer is only required to appear once per group.
-    Some(
-        data.iter()
-            .map(|v| HashSet::<char>::from_iter(v.iter().flatten().map(|c| *c)).len())
-            .sum(),
-    )
+    Some(count_answers(data, Threshold::Any))
 }
 
 /// Solution to part two.
 fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
     // Get the number of answers given, where each answer is required to be given by all members of
     // a group.
-    Some(
-        data.iter()
-            .map(|v| {
-                let mut set = HashSet::new();
-                set = &set | &v[0];
-                for other in &v[1..] {
-                    set = &set & other;
-                }
-                set.len()
-            })
-            .sum(),
-    )
+    Some(count_answers(data, Threshold::All))
 }
 
 fn get_data(input: String) -> Result<ChallengeData, io::Error> {
-    input
-        .split("\n\n")
-        .map(|s| {
-            Ok(s.lines()
-                .map(|s| HashSet::from_iter(s.chars().filter(|c| ('a'..='z').contains(&c))))
-                .collect())
-        })
-        .collect()
+    Ok(parse_groups(&input))
 }
 
 fn main() -> Result<(), io::Error> {
     let input = get_day_input(DAYNUM);
     let data = get_data(input)?;
+    // Other questions can be asked with "--threshold <any|all|n>".
+    if let Some(threshold) = get_option("--threshold")? {
+        println!(
+            "Answers given by {:?} in each group: {}",
+            threshold,
+            count_answers(&data, threshold)
+        );
+        return Ok(());
+    }
     println!("Day {}:", DAYNUM);
     println!("==========");
-    println!(
-        "Part one: {}",
-        print_elapsed_time(|| part_one(&data)).expect("No solution found for part one"),
-    );
-    println!(
-        "Part two: {}",
-        print_elapsed_time(|| part_two(&data)).expect("No solution found for part two"),
-    );
+    let (ans1, took) = elapsed_time(|| part_one(&data));
+    let ans1 = ans1.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
+    let (ans2, took) = elapsed_time(|| part_two(&data));
+    let ans2 = ans2.expect("No solution found for part two");
+    print_result(DAYNUM, 2, ans2, took);
     Ok(())
 }
 
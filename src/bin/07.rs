@@ -1,109 +1,61 @@
 /// Solution to Advent of Code Challenge Day 07.
-use aoc2020::{get_day_input, print_elapsed_time};
-use std::collections::HashMap;
+use aoc2020::bags::{BagGraph, TARGET};
+use aoc2020::{
+    elapsed_time, get_day_input, get_option, has_flag, print_elapsed_time, print_result,
+};
+use std::collections::{BTreeMap, BTreeSet};
 use std::io;
-use std::ops::Mul;
 
-const DAYNUM: &'static str = "07";
-type ChallengeData = HashMap<String, HashMap<String, usize>>;
-type ChallengeOut = usize;
+const DAYNUM: &str = "07";
+type ChallengeData = BagGraph;
 
-/// Recursively determine if a bag type can (eventually) contain at least one of another bag type.
-fn contains_bag_type(data: &ChallengeData, bag_type: &String, contains: &'static str) -> bool {
-    data.get(bag_type)
-        .map(|types| {
-            types
-                .iter()
-                .map(|(key, _)| key)
-                .any(|key| key == contains || contains_bag_type(data, key, contains))
-        })
-        .unwrap_or(false)
+/// Solution to part one: the colours which can contain the target bag.
+fn part_one(data: &ChallengeData, target: &str) -> Option<BTreeSet<String>> {
+    Some(data.containers_of(target).into_iter().collect())
 }
 
-/// Recursively find the number of bags contained within a given bag type.
-fn get_bag_num(data: &ChallengeData, bag_type: &String) -> Option<ChallengeOut> {
-    data.get(bag_type)
-        .map(|types| {
-            types
-                .iter()
-                .map(|(key, val)| get_bag_num(data, key).map(|n| n.mul(val)))
-                .collect::<Option<Vec<_>>>()
-                .map(|s| s.iter().sum::<usize>() + 1)
-        })
-        .flatten()
-}
-
-/// Solution to part one.
-fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
-    Some(
-        data.iter()
-            .map(|(key, _)| key)
-            .filter(|key| contains_bag_type(data, key, "shiny gold"))
-            .count(),
-    )
-}
-
-/// Solution to part two.
-fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
-    // Need number of bags contained: get_bag_num recursion includes the shiny gold bag itself
-    get_bag_num(data, &"shiny gold".to_string()).map(|n| n - 1)
+/// Solution to part two: how many bags of each colour the target bag must contain.
+fn part_two(data: &ChallengeData, target: &str) -> Option<BTreeMap<String, usize>> {
+    data.contents_of(target)
 }
 
 fn get_data(input: String) -> Result<ChallengeData, io::Error> {
-    Ok(input
-        .lines()
-        .map(|s| {
-            // <descr> bags contain (no other bags | {<num> <descr> bag[s]}).
-            let bag_map: Vec<_> = s
-                .split(" bags contain ")
-                .map(|s| s.trim_matches('.'))
-                .collect();
-            (
-                // <descr>
-                bag_map[0].to_string(),
-                // (no other bags | {<num> <descr> bag[s]})
-                bag_map[1]
-                    .split(", ")
-                    .filter(|s| *s != "no other bags")
-                    // {<num> <descr> bag[s]}
-                    .map(|s| {
-                        let v = s
-                            .strip_suffix(" bags")
-                            .or(s.strip_suffix(" bag"))
-                            .expect("Bag description must end in 'bag' or 'bags")
-                            // {<num> <descr>}
-                            .splitn(2, ' ')
-                            .collect::<Vec<_>>();
-                        (
-                            // <descr>
-                            v[1].to_string(),
-                            // <num>
-                            v[0].parse::<usize>().expect("Bag number must be integer"),
-                        )
-                    })
-                    .collect(),
-            )
-        })
-        .collect())
+    input.parse()
 }
 
 fn main() -> Result<(), io::Error> {
+    // Ask about another colour of bag with "--bag <colour>".
+    let target = get_option("--bag")?.unwrap_or_else(|| TARGET.to_string());
+    // List the colours found as well as the totals with "--list".
+    let list = has_flag("--list");
+    if has_flag("--dot") {
+        print!("{}", get_data(get_day_input(DAYNUM))?.to_dot(&target));
+        return Ok(());
+    }
     println!("Day {}:", DAYNUM);
     println!("==========");
     println!("Getting data...");
     let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
     println!("==========");
     println!("Solving part one...");
-    println!(
-        "Answer: {}",
-        print_elapsed_time(|| part_one(&data)).expect("No solution found for part one"),
-    );
+    let (containers, took) = elapsed_time(|| part_one(&data, &target));
+    let containers = containers.expect("No solution found for part one");
+    print_result(DAYNUM, 1, containers.len(), took);
+    if list {
+        for color in &containers {
+            println!("  {}", color);
+        }
+    }
     println!("==========");
     println!("Solving part two...");
-    println!(
-        "Answer: {}",
-        print_elapsed_time(|| part_two(&data)).expect("No solution found for part two"),
-    );
+    let (contents, took) = elapsed_time(|| part_two(&data, &target));
+    let contents = contents.expect("No solution found for part two");
+    print_result(DAYNUM, 2, contents.values().sum::<usize>(), took);
+    if list {
+        for (color, count) in &contents {
+            println!("  {} {}", count, color);
+        }
+    }
     Ok(())
 }
 
@@ -127,22 +79,45 @@ dotted black bags contain no other bags."
 
         // Check the data conversion works.
         assert_eq!(
-            data.get("light red").map(|d| d.get("bright white")),
+            data.contents("light red").map(|d| d.get("bright white")),
             Some(Some(&1))
         );
         assert_eq!(
-            data.get("light red").map(|d| d.get("muted yellow")),
+            data.contents("light red").map(|d| d.get("muted yellow")),
             Some(Some(&2))
         );
         assert_eq!(
-            data.get("bright white").map(|d| d.get("shiny gold")),
+            data.contents("bright white").map(|d| d.get("shiny gold")),
             Some(Some(&1))
         );
-        assert_eq!(data.get("faded blue"), Some(&HashMap::new()));
+        assert_eq!(data.contents("faded blue").map(|d| d.len()), Some(0));
 
         // Assert get the right number.
-        assert_eq!(part_one(&data), Some(4));
-        assert_eq!(part_two(&data), Some(32));
+        assert_eq!(part_one(&data, TARGET).map(|found| found.len()), Some(4));
+        assert_eq!(
+            part_one(&data, "muted yellow"),
+            Some(
+                ["dark orange", "light red"]
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect()
+            )
+        );
+        assert_eq!(
+            part_two(&data, TARGET).map(|found| found.values().sum()),
+            Some(32)
+        );
+        assert_eq!(
+            part_two(&data, "dark olive"),
+            Some(
+                vec![
+                    ("dotted black".to_string(), 4),
+                    ("faded blue".to_string(), 3)
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
     }
 
     #[test]
@@ -158,6 +133,9 @@ dark violet bags contain no other bags."
         let data = get_data(input).expect("Couldn't convert test input");
 
         // Assert get the right number.
-        assert_eq!(part_two(&data), Some(126));
+        assert_eq!(
+            part_two(&data, TARGET).map(|found| found.values().sum()),
+            Some(126)
+        );
     }
 }
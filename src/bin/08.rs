@@ -1,159 +1,215 @@
 /// Solution to Advent of Code Challenge Day 08.
-use aoc2020::{get_day_input, print_elapsed_time};
-use itertools::Itertools;
-use std::collections::HashSet;
-use std::convert::TryInto;
-use std::io;
-use std::str::FromStr;
-
-const DAYNUM: &'static str = "08";
-type ChallengeData = Code;
-type ChallengeOut = i32;
+use aoc2020::vm::{
+    accumulator_after_fix, accumulator_at_loop, parse_code, Code, ExitCode, Hooks, Instruction,
+    InstructionSet, LoopDetector, Machine,
+};
+use aoc2020::{
+    elapsed_time, get_day_input, get_option, has_flag, print_elapsed_time, print_result,
+};
+use rayon::prelude::*;
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
 
-type Code = Vec<Instruction>;
+const DAYNUM: &str = "08";
+type ChallengeData = Code;
+type ChallengeOut = i64;
 
-#[derive(Debug, Copy, Clone)]
-enum ExitCode {
-    LoopDetected,
-    Success,
-    Failure,
+/// Run the program with the instruction at a line switched, if it is a nop or a jmp, giving the
+/// accumulator if it then exits normally.
+fn run_switched(data: &[Instruction], linenum: usize) -> Option<ChallengeOut> {
+    let mut code = data.to_vec();
+    code[linenum] = match code[linenum] {
+        Instruction::Jmp(offset) => Instruction::Nop(offset),
+        Instruction::Nop(offset) => Instruction::Jmp(offset),
+        _ => return None,
+    };
+    let mut machine = Machine::from(code);
+    match machine.run() {
+        ExitCode::Success => Some(machine.accumulator()),
+        _ => None,
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
-enum Instruction {
-    Nop(isize),
-    Acc(i32),
-    Jmp(isize),
+/// Solution to part two by brute force, kept to cross-check `find_fix`.
+fn part_two_brute_force(data: &[Instruction]) -> Option<ChallengeOut> {
+    // For each instruction, if it is a nop or a jmp, try the program with the instruction switched
+    // to see if it can exit normally.
+    (0..data.len()).find_map(|linenum| run_switched(data, linenum))
 }
 
-impl FromStr for Instruction {
-    type Err = io::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (word, num): (&str, &str) = s
-            .split(' ')
-            .next_tuple()
-            .expect("Instruction is not of the correct form: <verb> <amount>");
-        Ok(match word {
-            "nop" => Instruction::Nop(
-                num.parse()
-                    .expect("Amount given in instruction is not a valid integer"),
-            ),
-            "jmp" => Instruction::Jmp(
-                num.parse()
-                    .expect("Amount given in instruction is not a valid integer"),
-            ),
-            "acc" => Instruction::Acc(
-                num.parse()
-                    .expect("Amount given in instruction is not a valid integer"),
-            ),
-            _ => panic!("Invalid instruction verb given: {}", word),
-        })
-    }
+/// Solution to part two by brute force as `part_two_brute_force`, trying the switched programs in
+/// parallel. The first line which fixes the program wins, as it does when trying them in turn.
+fn part_two_brute_force_parallel(data: &[Instruction]) -> Option<ChallengeOut> {
+    (0..data.len())
+        .into_par_iter()
+        .find_map_first(|linenum| run_switched(data, linenum))
 }
 
-#[derive(Debug, Clone)]
-struct Program {
+/// Hooks which write out each instruction executed, with the counter it was at and the
+/// accumulator after it ran.
+struct Tracer<W: Write> {
+    out: W,
     counter: usize,
-    text: Code,
-    data: i32,
+    /// The first error writing the trace, since hooks can't return errors themselves.
+    result: io::Result<()>,
 }
 
-impl From<Code> for Program {
-    fn from(code: Code) -> Self {
-        Program {
-            counter: 0,
-            text: code,
-            data: 0,
-        }
+impl<W: Write> Hooks for Tracer<W> {
+    fn before(&mut self, machine: &Machine, _instruction: Instruction) {
+        self.counter = machine.counter();
     }
-}
 
-impl Program {
-    fn run(&mut self) -> ExitCode {
-        let mut visited: HashSet<usize> = HashSet::new();
-        while self.counter < self.text.len() {
-            if let Some(_) = visited.get(&self.counter) {
-                return ExitCode::LoopDetected;
-            }
-            visited.insert(self.counter);
-            match self.text[self.counter] {
-                Instruction::Nop(_) => {
-                    self.counter += 1;
-                }
-                Instruction::Acc(inc) => {
-                    self.data += inc;
-                    self.counter += 1;
-                }
-                Instruction::Jmp(offset) => {
-                    self.counter = (self.counter as isize + offset)
-                        .try_into()
-                        .expect("Jump instruction took counter out of bounds");
-
-                    // Technically works without this, but the challenge explicitly states this is
-                    // not a valid way to terminate the program (jump further than 1 instruction out
-                    // of the program)
-                    if self.counter > self.text.len() {
-                        return ExitCode::Failure;
-                    }
-                }
-            }
+    fn after(&mut self, machine: &Machine, instruction: Instruction) {
+        if self.result.is_ok() {
+            self.result = writeln!(
+                self.out,
+                "{:>5}: {:<12} acc {}",
+                self.counter,
+                instruction.to_string(),
+                machine.accumulator()
+            );
         }
-        ExitCode::Success
     }
 }
 
-/// Solution to part one.
-fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut program = Program::from(data.to_vec());
-    match program.run() {
-        ExitCode::LoopDetected => Some(program.data),
-        _ => None,
+/// Run the program to completion, writing out each instruction as it executes.
+fn trace<W: Write>(code: Code, out: W) -> io::Result<ExitCode> {
+    let mut tracer = Tracer {
+        out,
+        counter: 0,
+        result: Ok(()),
+    };
+    let exit = Machine::from(code).run_with_hooks(&mut tracer);
+    tracer.result.map(|_| exit)
+}
+
+/// Execute one instruction, treating a return to an earlier state as the program exiting.
+fn debug_step(machine: &mut Machine, detector: &mut LoopDetector) -> Option<ExitCode> {
+    if !detector.visit(machine) {
+        return Some(ExitCode::LoopDetected);
     }
+    machine.step()
 }
 
-/// Solution to part two.
-fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
-    // For each instruction, if it is a nop or a jmp, try the program with the instruction switched
-    // to see if it can exit normally.
-    for linenum in 0..data.len() {
-        let mut code = data.to_vec();
-        code[linenum] = match code[linenum] {
-            Instruction::Acc(_) => continue,
-            Instruction::Jmp(offset) => Instruction::Nop(offset),
-            Instruction::Nop(offset) => Instruction::Jmp(offset),
-        };
-        let mut program = Program::from(code);
-        match program.run() {
-            ExitCode::Success => {
-                return Some(program.data);
+/// Step through the program interactively, reading a command per line of input:
+///
+/// - `s` or an empty line: execute the next instruction
+/// - `c`: continue until a breakpoint is reached or the program exits or loops
+/// - `b <n>` / `d <n>`: set / delete a breakpoint on instruction `n`
+/// - `r`: show the registers
+/// - `q`: quit
+///
+/// Returns how the program exited, if it did before the input ran out.
+fn debug<R: BufRead, W: Write>(code: Code, input: R, out: &mut W) -> io::Result<Option<ExitCode>> {
+    let mut machine = Machine::from(code);
+    let mut detector = LoopDetector::new(machine.code());
+    let mut breakpoints: BTreeSet<usize> = BTreeSet::new();
+
+    let show = |out: &mut W, machine: &Machine| match machine.next_instruction() {
+        Some(instruction) => writeln!(
+            out,
+            "{:>5}: {:<12} acc {}",
+            machine.counter(),
+            instruction.to_string(),
+            machine.accumulator()
+        ),
+        None => writeln!(out, "{:>5}: <end>", machine.counter()),
+    };
+    show(out, &machine)?;
+
+    for line in input.lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let exit = match words[..] {
+            [] | ["s"] => debug_step(&mut machine, &mut detector),
+            ["c"] => loop {
+                let exit = debug_step(&mut machine, &mut detector);
+                if exit.is_some() || breakpoints.contains(&machine.counter()) {
+                    break exit;
+                }
+            },
+            [command @ "b", index] | [command @ "d", index] => {
+                match index.parse() {
+                    Ok(index) if command == "b" => {
+                        breakpoints.insert(index);
+                    }
+                    Ok(index) => {
+                        breakpoints.remove(&index);
+                    }
+                    Err(_) => writeln!(out, "Invalid instruction index {:?}", index)?,
+                }
+                writeln!(out, "Breakpoints: {:?}", breakpoints)?;
+                continue;
+            }
+            ["r"] => {
+                writeln!(out, "Registers: {:?}", machine.registers())?;
+                continue;
+            }
+            ["q"] => return Ok(None),
+            _ => {
+                writeln!(out, "Unknown command {:?}", line)?;
+                continue;
             }
-            _ => (),
+        };
+
+        if let Some(exit) = exit {
+            writeln!(out, "Exited with {:?}, acc {}", exit, machine.accumulator())?;
+            return Ok(Some(exit));
         }
+        show(out, &machine)?;
     }
-    None
+    Ok(None)
 }
 
 fn get_data(input: String) -> Result<ChallengeData, io::Error> {
-    input.lines().map(|s| s.parse()).collect()
+    parse_code(&input, InstructionSet::Handheld)
 }
 
 fn main() -> Result<(), io::Error> {
+    if has_flag("--trace") {
+        let exit = trace(get_data(get_day_input(DAYNUM))?, io::stdout().lock())?;
+        println!("Exited with {:?}", exit);
+        return Ok(());
+    }
+    if has_flag("--step") {
+        let code = get_data(get_day_input(DAYNUM))?;
+        debug(code, io::stdin().lock(), &mut io::stdout().lock())?;
+        return Ok(());
+    }
+
+    // Brute force across this many threads (or as many as there are cores for 0) with
+    // "--threads <n>".
+    let parallel = match get_option("--threads")? {
+        Some(threads) => {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build_global()
+                .map_err(io::Error::other)?;
+            true
+        }
+        None => false,
+    };
+
     println!("Day {}:", DAYNUM);
     println!("==========");
     println!("Getting data...");
     let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
     println!("==========");
     println!("Solving part one...");
-    println!(
-        "Answer: {}",
-        print_elapsed_time(|| part_one(&data)).expect("No solution found for part one"),
-    );
+    let (ans1, took) = elapsed_time(|| accumulator_at_loop(&data));
+    let ans1 = ans1.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
     println!("==========");
     println!("Solving part two...");
-    println!(
-        "Answer: {}",
-        print_elapsed_time(|| part_two(&data)).expect("No solution found for part two"),
-    );
+    // Solve by rerunning the program with each instruction swapped in turn with "--brute-force".
+    let solve = match (has_flag("--brute-force"), parallel) {
+        (true, true) => part_two_brute_force_parallel,
+        (true, false) => part_two_brute_force,
+        (false, _) => accumulator_after_fix,
+    };
+    let (ans2, took) = elapsed_time(|| solve(&data));
+    let ans2 = ans2.expect("No solution found for part two");
+    print_result(DAYNUM, 2, ans2, took);
     Ok(())
 }
 
@@ -176,7 +232,44 @@ acc +6"
         let data = get_data(input).expect("Couldn't convert test input");
 
         // Assert get the right number.
-        assert_eq!(part_one(&data), Some(5));
-        assert_eq!(part_two(&data), Some(8));
+        assert_eq!(accumulator_at_loop(&data), Some(5));
+        assert_eq!(accumulator_after_fix(&data), Some(8));
+        assert_eq!(part_two_brute_force(&data), Some(8));
+        assert_eq!(part_two_brute_force_parallel(&data), Some(8));
+    }
+
+    #[test]
+    fn test_trace_and_debug() {
+        let code = get_data("nop +0\nacc +1\njmp -2".to_string()).unwrap();
+
+        let mut traced = Vec::new();
+        assert_eq!(
+            trace(code.clone(), &mut traced).unwrap(),
+            ExitCode::LoopDetected
+        );
+        assert_eq!(
+            String::from_utf8(traced).unwrap(),
+            "    0: nop +0       acc 0
+    1: acc +1       acc 1
+    2: jmp -2       acc 1
+"
+        );
+
+        let mut out = Vec::new();
+        let commands = "b 2\nc\nr\ns\nc\n";
+        assert_eq!(
+            debug(code, commands.as_bytes(), &mut out).unwrap(),
+            Some(ExitCode::LoopDetected)
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "    0: nop +0       acc 0
+Breakpoints: {2}
+    2: jmp -2       acc 1
+Registers: [1, 0, 0, 0]
+    0: nop +0       acc 1
+Exited with LoopDetected, acc 1
+"
+        );
     }
 }
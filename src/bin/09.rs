@@ -1,94 +1,86 @@
 /// Solution to Advent of Code Challenge Day 09.
-use aoc2020::{get_day_input, print_elapsed_time};
-use std::collections::{HashSet, VecDeque};
+use aoc2020::xmas::{
+    find_all_weaknesses, find_contiguous_run, find_weakness, first_invalid, Weakness, PREAMBLE,
+};
+use aoc2020::{
+    elapsed_time, get_day_input, get_day_reader, get_option, has_flag, parse_lines,
+    print_elapsed_time, print_result,
+};
+use itertools::process_results;
+use std::error::Error;
+use std::io;
 use std::num::ParseIntError;
-use std::ops::Add;
 
 type Number = u64;
 
-const DAYNUM: &'static str = "09";
+const DAYNUM: &str = "09";
 type ChallengeData = Vec<Number>;
 type ChallengeOut = Number;
 
 /// Solution to part one.
-fn part_one(data: &ChallengeData, preamble: usize) -> Option<ChallengeOut> {
-    let mut rolling_queue: VecDeque<Number> = data.iter().take(preamble).map(|n| *n).collect();
-    let mut rolling_set: HashSet<Number> = rolling_queue.iter().map(|n| *n).collect();
-    for number in &data[preamble..] {
-        let number = *number;
-        let mut ans: Option<(Number, Number)> = None;
-
-        for x in &rolling_queue {
-            let x = *x;
-            if number > x {
-                let y: Number = number - x;
-                if rolling_set.contains(&y) {
-                    ans = Some((x, y));
-                    break;
-                }
-            }
-        }
-
-        if ans.is_none() {
-            // This number is the first which does not respect the condition that it must contain
-            // a pair in the last preamble which sum to it set by XMAS.
-            return Some(number);
-        }
-
-        // Set the queue and tracking set to the new preamble given that this number is valid.
-        rolling_queue.push_back(number);
-        let old = rolling_queue.pop_front().unwrap();
-        rolling_set.insert(number);
-        rolling_set.remove(&old);
-    }
-    None
+fn part_one(data: &ChallengeData, preamble: usize) -> io::Result<Option<ChallengeOut>> {
+    first_invalid(data.iter().copied(), preamble)
 }
 
-/// Solution to part two.
-fn part_two(data: &ChallengeData, target: Number) -> Option<ChallengeOut> {
-    // The contiguous set must be at least 2 long, so prepopulate with 1 value.
-    let mut rolling_queue: VecDeque<Number> = data.iter().take(1).map(|n| *n).collect();
-    for number in &data[1..] {
-        let number = *number;
-        rolling_queue.push_back(number);
-        let mut curr_sum: Number = rolling_queue.iter().sum();
-        while curr_sum > target {
-            // We're too high: pop earlier numbers until we go low enough to continue.
-            rolling_queue.pop_front();
-            curr_sum = rolling_queue.iter().sum();
-        }
-        // May now reach the target itself: but if we need more numbers, just carry on.
-        if rolling_queue.len() < 2 {
-            continue;
-        }
-        if curr_sum == target {
-            return rolling_queue
-                .iter()
-                .min()
-                .and_then(|min| Some(min.add(*rolling_queue.iter().max()?)));
-        }
-    }
-    None
+/// Solve both parts while streaming the numbers from the input file, reading it once per part
+/// rather than holding the whole stream in memory.
+fn solve_streaming(preamble: usize) -> io::Result<(Option<Number>, Option<Weakness>)> {
+    let ans1 = process_results(parse_lines(get_day_reader(DAYNUM)?), |numbers| {
+        first_invalid(numbers, preamble)
+    })??;
+    let ans2 = match ans1 {
+        Some(target) => process_results(parse_lines(get_day_reader(DAYNUM)?), |numbers| {
+            find_contiguous_run(numbers, target).map(|(start, values)| Weakness { start, values })
+        })?,
+        None => None,
+    };
+    Ok((ans1, ans2))
 }
 
 fn get_data(input: String) -> Result<ChallengeData, ParseIntError> {
     input.lines().map(|s| s.parse()).collect()
 }
 
-fn main() -> Result<(), ParseIntError> {
+fn main() -> Result<(), Box<dyn Error>> {
+    // Check each number against another number of those before it with "--preamble <n>".
+    let preamble = get_option("--preamble")?.unwrap_or(PREAMBLE);
+
+    if has_flag("--stream") {
+        println!("Day {} (streaming):", DAYNUM);
+        println!("==========");
+        let (answers, took) = elapsed_time(|| solve_streaming(preamble));
+        let (ans1, ans2) = answers?;
+        let ans1 = ans1.expect("No solution found for part one");
+        print_result(DAYNUM, 1, ans1, took);
+        let weakness = ans2.expect("No solution found for part two");
+        print_result(DAYNUM, 2, weakness.encryption_weakness(), took);
+        println!("Range: {}", weakness);
+        return Ok(());
+    }
+
     println!("Day {}:", DAYNUM);
     println!("==========");
     println!("Getting data...");
     let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
     println!("==========");
     println!("Solving part one...");
-    let ans1 = print_elapsed_time(|| part_one(&data, 25)).expect("No solution found for part one");
-    println!("Answer: {}", ans1);
+    let (ans1, took) = elapsed_time(|| part_one(&data, preamble));
+    let ans1 = ans1?.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
     println!("==========");
-    let ans2 =
-        print_elapsed_time(|| part_two(&data, ans1)).expect("No solution found for part two");
     println!("Solving part two...");
-    println!("Answer: {}", ans2);
+    let (weakness, took) = elapsed_time(|| find_weakness(&data, ans1));
+    let weakness = weakness.expect("No solution found for part two");
+    print_result(DAYNUM, 2, weakness.encryption_weakness(), took);
+    println!("Range: {}", weakness);
+    if has_flag("--all") {
+        // List every run summing to the invalid number, not just the first.
+        println!("==========");
+        println!("All ranges summing to {}:", ans1);
+        for weakness in find_all_weaknesses(&data, ans1) {
+            println!("{}", weakness);
+        }
+    }
     Ok(())
 }
 
@@ -119,10 +111,20 @@ mod tests {
 309
 576"
         .to_string();
-        let data = get_data(input).expect("Couldn't convert test input");
+        let data = get_data(input.clone()).expect("Couldn't convert test input");
 
         // Assert get the right number.
-        assert_eq!(part_one(&data, 5), Some(127));
-        assert_eq!(part_two(&data, 127), Some(62));
+        assert_eq!(part_one(&data, 5).unwrap(), Some(127));
+        assert!(part_one(&data, 21).is_err());
+        let weakness = find_weakness(&data, 127).unwrap();
+        assert_eq!(weakness.encryption_weakness(), 62);
+        assert_eq!(weakness.to_string(), "indices 2-5: 15 + 25 + 47 + 40");
+
+        // The streaming solvers give the same answers from a reader.
+        let numbers = parse_lines(input.as_bytes()).map(|n| n.unwrap());
+        assert_eq!(first_invalid(numbers, 5).unwrap(), Some(127));
+        let numbers = parse_lines(input.as_bytes()).map(|n| n.unwrap());
+        let (start, values) = find_contiguous_run(numbers, 127).unwrap();
+        assert_eq!(Weakness { start, values }, weakness);
     }
 }
@@ -1,257 +1,185 @@
 /// Solution to Advent of Code Challenge Day 11.
-use aoc2020::{get_day_input, print_elapsed_time};
-use std::io;
-use std::str::FromStr;
+use aoc2020::animation::save_gif;
+use aoc2020::grid::{Adjacent8, FirstVisible, Grid};
+use aoc2020::seating::{run_naive, SeatingRules, Simulation, Tile};
+use aoc2020::{
+    compare, elapsed_time, get_day_input, get_option, has_flag, print_elapsed_time, print_result,
+};
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+const DAYNUM: &str = "11";
+type ChallengeData = Grid<Tile>;
+type ChallengeOut = usize;
 
-type Number = u32;
-
-const DAYNUM: &'static str = "11";
-type ChallengeData = Vec<Row>;
-type ChallengeOut = Number;
-
-#[derive(PartialEq, Eq, Clone)]
-enum Tile {
-    Floor,
-    Seat(bool),
+/// Solution to part one.
+fn part_one(data: &ChallengeData, parallel: bool) -> Option<ChallengeOut> {
+    Simulation::new(data, SeatingRules::PART_ONE)
+        .parallel(parallel)
+        .run()
 }
 
-impl Tile {
-    fn from_ch(ch: char) -> Option<Self> {
-        match ch {
-            '.' => Some(Self::Floor),
-            'L' => Some(Self::Seat(false)),
-            '#' => Some(Self::Seat(true)),
-            _ => None,
-        }
-    }
-
-    fn occupied(&self) -> bool {
-        match self {
-            Self::Seat(true) => true,
-            _ => false,
-        }
-    }
+/// Solution to part two.
+fn part_two(data: &ChallengeData, parallel: bool) -> Option<ChallengeOut> {
+    Simulation::new(data, SeatingRules::PART_TWO)
+        .parallel(parallel)
+        .run()
 }
 
-#[derive(PartialEq, Eq, Clone)]
-struct Row {
-    tiles: Vec<Tile>,
-    length: usize,
+/// Solution to part one, finding each seat's adjacent seats afresh every generation.
+fn part_one_naive(data: &ChallengeData) -> Option<ChallengeOut> {
+    run_naive(data, &Adjacent8, SeatingRules::PART_ONE.tolerance)
 }
 
-impl FromStr for Row {
-    type Err = io::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let tiles: Vec<_> = s
-            .chars()
-            .map(|ch| Tile::from_ch(ch))
-            .collect::<Option<_>>()
-            .unwrap();
-        Ok(Row {
-            length: tiles.len(),
-            tiles,
-        })
-    }
+/// Solution to part two, scanning each seat's sightlines afresh every generation.
+fn part_two_naive(data: &ChallengeData) -> Option<ChallengeOut> {
+    let sightlines = FirstVisible {
+        is_visible: Tile::is_seat,
+    };
+    run_naive(data, &sightlines, SeatingRules::PART_TWO.tolerance)
 }
 
-/// Gets the number of occupied seats immediately around a given seat.
-fn get_occupied_adjacent(plan: &ChallengeData, row: usize, col: usize) -> Number {
-    let mut adjacent: Vec<&Tile> = Vec::new();
-    let left = col > 0;
-    let right = col < plan[0].length - 1;
-    let up = row > 0;
-    let down = row < plan.len() - 1;
-
-    // Left
-    if left {
-        adjacent.push(&plan[row].tiles[col - 1]);
-        // Up left
-        if up {
-            adjacent.push(&plan[row - 1].tiles[col - 1]);
-        }
-        // Down left
-        if down {
-            adjacent.push(&plan[row + 1].tiles[col - 1]);
+/// The pause between frames of the animation, unless overridden with "--delay <ms>".
+const FRAME_DELAY_MS: u64 = 100;
+
+/// Run the simulation until it stabilises or oscillates, clearing the terminal and redrawing the
+/// floorplan every generation with a pause between frames. Returns the number of occupied seats
+/// if it stabilised.
+fn animate<W: Write>(
+    title: &str,
+    mut simulation: Simulation,
+    out: &mut W,
+    delay: Duration,
+) -> io::Result<Option<usize>> {
+    let mut generation = 0;
+    loop {
+        // Clear the screen and move the cursor back to the top left before drawing.
+        write!(out, "\x1b[2J\x1b[H")?;
+        writeln!(out, "{}: generation {}", title, generation)?;
+        writeln!(out, "{}", simulation.floorplan().render(Tile::to_ch))?;
+        out.flush()?;
+        if !simulation.step() {
+            return Ok(Some(simulation.occupied()));
         }
-    }
-    // Right
-    if right {
-        adjacent.push(&plan[row].tiles[col + 1]);
-        // Up right
-        if up {
-            adjacent.push(&plan[row - 1].tiles[col + 1]);
-        }
-        // Down right
-        if down {
-            adjacent.push(&plan[row + 1].tiles[col + 1]);
+        if simulation.is_oscillating() {
+            return Ok(None);
         }
+        generation += 1;
+        thread::sleep(delay);
     }
-    // Up
-    if up {
-        adjacent.push(&plan[row - 1].tiles[col]);
-    }
-    // Down
-    if down {
-        adjacent.push(&plan[row + 1].tiles[col]);
-    }
-
-    adjacent.iter().map(|tile| tile.occupied() as Number).sum()
 }
 
-/// Gets the number of occupied seats in the sightlines of a given seat.
-fn get_occupied_sightline(plan: &ChallengeData, row: usize, col: usize) -> Number {
-    let mut sightlined: Vec<&Tile> = Vec::new();
-
-    let seek_directions: [(isize, isize); 8] = [
-        (0, -1),
-        (0, 1),
-        (-1, 0),
-        (1, 0),
-        (-1, -1),
-        (-1, 1),
-        (1, -1),
-        (1, 1),
-    ];
-
-    for direction in &seek_directions {
-        let mut seek = (row as isize + direction.0, col as isize + direction.1);
-        while seek.0 >= 0
-            && (seek.0 as usize) < plan.len()
-            && seek.1 >= 0
-            && (seek.1 as usize) < plan[0].length
-        {
-            match plan[seek.0 as usize].tiles[seek.1 as usize] {
-                Tile::Seat(_) => {
-                    sightlined.push(&plan[seek.0 as usize].tiles[seek.1 as usize]);
-                    break;
-                }
-                _ => (),
-            }
-            seek = (seek.0 + direction.0, seek.1 + direction.1);
-        }
+/// Describe the outcome of simulating a set of rules.
+fn describe(occupied: Option<usize>) -> String {
+    match occupied {
+        Some(occupied) => occupied.to_string(),
+        None => "never stabilises".to_string(),
     }
-
-    sightlined
-        .iter()
-        .map(|tile| tile.occupied() as Number)
-        .sum()
 }
 
-/// Apply the seat change rules to produce a new floorplan according to part one rules
-fn iterate_seat_changes_v1(from: &ChallengeData) -> ChallengeData {
-    let mut to = from.to_vec();
-
-    for (row_idx, row) in from.iter().enumerate() {
-        for (col_idx, tile) in row.tiles.iter().enumerate() {
-            match tile {
-                Tile::Floor => continue,
-                Tile::Seat(false) => {
-                    // If seat is empty and no adjacent seats are occupied, it is filled.
-                    if get_occupied_adjacent(from, row_idx, col_idx) == 0 {
-                        to[row_idx].tiles[col_idx] = Tile::Seat(true);
-                    }
-                }
-                Tile::Seat(true) => {
-                    // If seat is occupied and four or more adjacent are too, it is vacated.
-                    if get_occupied_adjacent(from, row_idx, col_idx) >= 4 {
-                        to[row_idx].tiles[col_idx] = Tile::Seat(false);
-                    }
-                }
-            }
+/// Every generation of the floorplan until it stabilises or oscillates, as frames of an animation.
+fn generations(mut simulation: Simulation) -> io::Result<Vec<Grid<char>>> {
+    let mut frames = Vec::new();
+    loop {
+        frames.push(Grid::from_chars(
+            &simulation.floorplan().render(Tile::to_ch),
+            Some,
+        )?);
+        if !simulation.step() || simulation.is_oscillating() {
+            return Ok(frames);
         }
     }
-
-    to
 }
 
-/// Apply the seat change rules to produce a new floorplan according to part two rules
-fn iterate_seat_changes_v2(from: &ChallengeData) -> ChallengeData {
-    let mut to = from.to_vec();
-
-    for (row_idx, row) in from.iter().enumerate() {
-        for (col_idx, tile) in row.tiles.iter().enumerate() {
-            match tile {
-                Tile::Floor => continue,
-                Tile::Seat(false) => {
-                    // If seat is empty and no seats in sightline are occupied, it is filled.
-                    if get_occupied_sightline(from, row_idx, col_idx) == 0 {
-                        to[row_idx].tiles[col_idx] = Tile::Seat(true);
-                    }
-                }
-                Tile::Seat(true) => {
-                    // If seat is occupied and five or more in sightline are too, it is vacated.
-                    if get_occupied_sightline(from, row_idx, col_idx) >= 5 {
-                        to[row_idx].tiles[col_idx] = Tile::Seat(false);
-                    }
-                }
-            }
-        }
-    }
-
-    to
+fn get_data(input: String) -> io::Result<ChallengeData> {
+    Grid::from_chars(&input, Tile::from_ch)
 }
 
-/// Solution to part one.
-fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut from = data.to_vec();
-    let mut to = iterate_seat_changes_v1(&from);
-
-    while to != from {
-        from = to;
-        to = iterate_seat_changes_v1(&from);
+fn main() -> io::Result<()> {
+    // Simulate other rules as well as those of each part with "--rules <tolerance>,<sight>".
+    let rules: Option<SeatingRules> = get_option("--rules")?;
+    // Save every generation under the rules of part two (or "--rules") as a GIF with
+    // "--gif <path>".
+    if let Some(path) = get_option::<String>("--gif")? {
+        let data = get_data(get_day_input(DAYNUM))?;
+        let rules = rules.unwrap_or(SeatingRules::PART_TWO);
+        return save_gif(&path, &generations(Simulation::new(&data, rules))?);
     }
-
-    Some(
-        to.iter()
-            .map(|row| {
-                row.tiles
-                    .iter()
-                    .map(|tile| tile.occupied() as Number)
-                    .sum::<Number>()
-            })
-            .sum::<Number>(),
-    )
-}
-
-/// Solution to part two.
-fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut from = data.to_vec();
-    let mut to = iterate_seat_changes_v2(&from);
-
-    while to != from {
-        from = to;
-        to = iterate_seat_changes_v2(&from);
+    if has_flag("--animate") {
+        let data = get_data(get_day_input(DAYNUM))?;
+        let delay = Duration::from_millis(get_option("--delay")?.unwrap_or(FRAME_DELAY_MS));
+        let mut runs = vec![
+            ("Part one".to_string(), SeatingRules::PART_ONE),
+            ("Part two".to_string(), SeatingRules::PART_TWO),
+        ];
+        runs.extend(rules.map(|rules| (format!("Rules {}", rules), rules)));
+        let out = &mut io::stdout();
+        let mut results = Vec::new();
+        for (title, rules) in runs {
+            let occupied = animate(&title, Simulation::new(&data, rules), out, delay)?;
+            results.push((title, occupied));
+            thread::sleep(delay * 10);
+        }
+        for (title, occupied) in results {
+            println!("{}: {}", title, describe(occupied));
+        }
+        return Ok(());
     }
 
-    Some(
-        to.iter()
-            .map(|row| {
-                row.tiles
-                    .iter()
-                    .map(|tile| tile.occupied() as Number)
-                    .sum::<Number>()
-            })
-            .sum::<Number>(),
-    )
-}
-
-fn get_data(input: String) -> io::Result<ChallengeData> {
-    input.lines().map(|s| s.parse()).collect()
-}
+    // Compute each generation across this many threads (or as many as there are cores for 0)
+    // with "--threads <n>".
+    let parallel = match get_option("--threads")? {
+        Some(threads) => {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build_global()
+                .map_err(io::Error::other)?;
+            true
+        }
+        None => false,
+    };
 
-fn main() -> io::Result<()> {
     println!("Day {}:", DAYNUM);
     println!("==========");
     println!("Getting data...");
     let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
     println!("==========");
     println!("Solving part one...");
-    let ans1 = print_elapsed_time(|| part_one(&data)).expect("No solution found for part one");
-    println!("Answer: {}", ans1);
+    // Time scanning for each seat's neighbours every generation against finding them once up
+    // front, and check they agree, with "--compare".
+    let compare_naive = has_flag("--compare");
+    let (ans1, took) = match compare_naive {
+        true => compare(
+            "Naive",
+            || part_one_naive(&data),
+            "Precomputed",
+            || part_one(&data, parallel),
+        ),
+        false => elapsed_time(|| part_one(&data, parallel)),
+    };
+    let ans1 = ans1.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
     println!("==========");
     println!("Solving part two...");
-    let ans2 = print_elapsed_time(|| part_two(&data)).expect("No solution found for part two");
-    println!("Answer: {}", ans2);
+    let (ans2, took) = match compare_naive {
+        true => compare(
+            "Naive",
+            || part_two_naive(&data),
+            "Precomputed",
+            || part_two(&data, parallel),
+        ),
+        false => elapsed_time(|| part_two(&data, parallel)),
+    };
+    let ans2 = ans2.expect("No solution found for part two");
+    print_result(DAYNUM, 2, ans2, took);
+    if let Some(rules) = rules {
+        println!("==========");
+        println!("Simulating rules {}...", rules);
+        let occupied =
+            print_elapsed_time(|| Simulation::new(&data, rules).parallel(parallel).run());
+        println!("Occupied seats: {}", describe(occupied));
+    }
     Ok(())
 }
 
@@ -275,7 +203,27 @@ L.LLLLL.LL"
         let data = get_data(input).expect("Couldn't convert test input");
 
         // Assert get the right number.
-        assert_eq!(part_one(&data), Some(37));
-        assert_eq!(part_two(&data), Some(26));
+        assert_eq!(part_one(&data, false), Some(37));
+        assert_eq!(part_two(&data, false), Some(26));
+        assert_eq!(part_one(&data, true), Some(37));
+        assert_eq!(part_two(&data, true), Some(26));
+    }
+
+    #[test]
+    fn test_animate() {
+        let data = get_data("L.L\nLLL\n.L.".to_string()).unwrap();
+        let mut out = Vec::new();
+        let simulation = Simulation::new(&data, SeatingRules::PART_ONE);
+        assert_eq!(
+            animate("Test", simulation, &mut out, Duration::from_millis(0)).unwrap(),
+            Some(5)
+        );
+
+        // A frame is drawn for the initial floorplan and each generation until nothing changes.
+        let out = String::from_utf8(out).unwrap();
+        let frames: Vec<&str> = out.split("\x1b[2J\x1b[H").skip(1).collect();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], "Test: generation 0\nL.L\nLLL\n.L.\n");
+        assert_eq!(frames[2], "Test: generation 2\n#.#\n#L#\n.#.\n");
     }
 }
@@ -1,193 +1,181 @@
 /// Solution to Advent of Code Challenge Day 12.
-use aoc2020::{get_day_input, print_elapsed_time};
+use aoc2020::grid::{Grid, Position};
+use aoc2020::navigation::{
+    distance_by_heading, distance_by_waypoint, parse_instructions, tracks, TrackPoint, Vector,
+};
+use aoc2020::{
+    elapsed_time, get_day_input, get_option, has_flag, print_elapsed_time, print_result,
+};
+use itertools::Itertools;
 use std::io;
 use std::str::FromStr;
 
-type Number = u32;
+const DAYNUM: &str = "12";
 
-const DAYNUM: &'static str = "12";
-type ChallengeData = Vec<Instruction>;
-type ChallengeOut = Number;
-
-enum Direction {
-    North,
-    East,
-    South,
-    West,
-    Forward,
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn from_char(ch: char) -> Self {
-        match ch {
-            'N' => Self::North,
-            'E' => Self::East,
-            'S' => Self::South,
-            'W' => Self::West,
-            'F' => Self::Forward,
-            'L' => Self::Left,
-            'R' => Self::Right,
-            _ => panic!("Unknown character for direction in instruction set"),
-        }
-    }
-
-    fn direction_vector(&self) -> (i32, i32) {
-        match self {
-            Self::North => (0, 1),
-            Self::East => (1, 0),
-            Self::South => (0, -1),
-            Self::West => (-1, 0),
-            _ => panic!("Facing does not have a direction"),
-        }
-    }
+/// Formats the ship's tracks can be exported in.
+enum Format {
+    Csv,
+    GeoJson,
 }
 
-struct Instruction {
-    dir: Direction,
-    num: i32,
-}
-
-impl FromStr for Instruction {
+impl FromStr for Format {
     type Err = io::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            dir: Direction::from_char(s.chars().nth(0).unwrap()),
-            num: s[1..].parse().unwrap(),
-        })
+        match s {
+            "csv" => Ok(Self::Csv),
+            "geojson" => Ok(Self::GeoJson),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown export format {:?}: expected csv or geojson", s),
+            )),
+        }
     }
 }
 
-struct Ship {
-    x: i32,
-    y: i32,
-    facing: Direction,
+/// Export the tracks of both parts, with the heading given relative to the ship.
+fn export_csv(tracks: &[Vec<TrackPoint>; 2]) -> String {
+    let mut out = String::from("part,step,east,north,heading_east,heading_north\n");
+    for (part, track) in tracks.iter().enumerate() {
+        for (step, point) in track.iter().enumerate() {
+            out += &format!(
+                "{},{},{},{},{},{}\n",
+                part + 1,
+                step,
+                point.ship.0,
+                point.ship.1,
+                point.heading.0,
+                point.heading.1
+            );
+        }
+    }
+    out
 }
 
-struct Waypoint {
-    x: i32,
-    y: i32,
+/// Export the tracks of both parts as lines on a plane, along with the path the waypoint takes
+/// in part two.
+fn export_geojson(tracks: &[Vec<TrackPoint>; 2]) -> String {
+    let line = |points: &mut dyn Iterator<Item = Vector>| {
+        points
+            .map(|(x, y)| format!("[{}, {}]", x, y))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let feature = |part: usize, object: &str, coordinates: String| {
+        format!(
+            "    {{\"type\": \"Feature\", \"properties\": {{\"part\": {}, \"object\": \"{}\"}}, \
+             \"geometry\": {{\"type\": \"LineString\", \"coordinates\": [{}]}}}}",
+            part, object, coordinates
+        )
+    };
+    let features = [
+        feature(
+            1,
+            "ship",
+            line(&mut tracks[0].iter().map(|point| point.ship)),
+        ),
+        feature(
+            2,
+            "ship",
+            line(&mut tracks[1].iter().map(|point| point.ship)),
+        ),
+        feature(
+            2,
+            "waypoint",
+            line(&mut tracks[1].iter().map(|point| {
+                (
+                    point.ship.0 + point.heading.0,
+                    point.ship.1 + point.heading.1,
+                )
+            })),
+        ),
+    ];
+    format!(
+        "{{\n  \"type\": \"FeatureCollection\",\n  \"features\": [\n{}\n  ]\n}}\n",
+        features.join(",\n")
+    )
 }
 
-impl Ship {
-    fn taxicab_distance(&self) -> Number {
-        (self.x.abs() + self.y.abs()) as Number
-    }
+/// The largest plot drawn, in characters.
+const PLOT_SIZE: (usize, usize) = (78, 36);
+
+/// Plot the ship's track scaled down to fit the plot size with north up, drawing the path as `#`
+/// between the start `S` and end `E`.
+fn plot(track: &[TrackPoint]) -> String {
+    let (min_x, max_x) = track
+        .iter()
+        .map(|point| point.ship.0)
+        .minmax()
+        .into_option()
+        .unwrap_or((0, 0));
+    let (min_y, max_y) = track
+        .iter()
+        .map(|point| point.ship.1)
+        .minmax()
+        .into_option()
+        .unwrap_or((0, 0));
+    let span_x = (max_x - min_x) as i64;
+    let span_y = (max_y - min_y) as i64;
+    let width = (span_x as usize + 1).min(PLOT_SIZE.0);
+    let height = (span_y as usize + 1).min(PLOT_SIZE.1);
+    let cell = |(x, y): Vector| -> Position {
+        let col = (x - min_x) as i64 * (width as i64 - 1) / span_x.max(1);
+        let row = (max_y - y) as i64 * (height as i64 - 1) / span_y.max(1);
+        (row as usize, col as usize)
+    };
 
-    fn update_v1(&mut self, instruction: &Instruction) {
-        match instruction.dir {
-            Direction::North => self.y += instruction.num,
-            Direction::East => self.x += instruction.num,
-            Direction::South => self.y -= instruction.num,
-            Direction::West => self.x -= instruction.num,
-            Direction::Forward => {
-                let dir_vector = self.facing.direction_vector();
-                self.x += dir_vector.0 * instruction.num;
-                self.y += dir_vector.1 * instruction.num;
-            }
-            Direction::Right => {
-                let num_turns: u32 = instruction.num as u32 / 90;
-                for _ in 0..num_turns {
-                    self.facing = match self.facing {
-                        Direction::North => Direction::East,
-                        Direction::East => Direction::South,
-                        Direction::South => Direction::West,
-                        Direction::West => Direction::North,
-                        _ => panic!("Ship cannot face non-cardinal direction"),
-                    };
-                }
-            }
-            Direction::Left => {
-                let num_turns: u32 = instruction.num as u32 / 90;
-                for _ in 0..num_turns {
-                    self.facing = match self.facing {
-                        Direction::North => Direction::West,
-                        Direction::West => Direction::South,
-                        Direction::South => Direction::East,
-                        Direction::East => Direction::North,
-                        _ => panic!("Ship cannot face non-cardinal direction"),
-                    };
-                }
-            }
+    let mut grid =
+        Grid::from_rows(vec![vec!['.'; width]; height]).expect("Plot rows are all the same length");
+    for (from, to) in track.iter().tuple_windows() {
+        let (from, to) = (cell(from.ship), cell(to.ship));
+        let dr = to.0 as isize - from.0 as isize;
+        let dc = to.1 as isize - from.1 as isize;
+        let steps = dr.abs().max(dc.abs()).max(1);
+        for step in 0..=steps {
+            let row = from.0 as isize + dr * step / steps;
+            let col = from.1 as isize + dc * step / steps;
+            grid[(row as usize, col as usize)] = '#';
         }
     }
-
-    fn update_v2(&mut self, instruction: &Instruction, waypoint: &mut Waypoint) {
-        match instruction.dir {
-            Direction::North => waypoint.y += instruction.num,
-            Direction::East => waypoint.x += instruction.num,
-            Direction::South => waypoint.y -= instruction.num,
-            Direction::West => waypoint.x -= instruction.num,
-            Direction::Forward => {
-                self.x += waypoint.x * instruction.num;
-                self.y += waypoint.y * instruction.num;
-            }
-            Direction::Right => {
-                let num_turns: u32 = instruction.num as u32 / 90;
-                for _ in 0..num_turns {
-                    let new_point = (waypoint.y, -waypoint.x);
-                    waypoint.x = new_point.0;
-                    waypoint.y = new_point.1;
-                }
-            }
-            Direction::Left => {
-                let num_turns: u32 = instruction.num as u32 / 90;
-                for _ in 0..num_turns {
-                    let new_point = (-waypoint.y, waypoint.x);
-                    waypoint.x = new_point.0;
-                    waypoint.y = new_point.1;
-                }
-            }
-        }
+    if let (Some(first), Some(last)) = (track.first(), track.last()) {
+        grid[cell(first.ship)] = 'S';
+        grid[cell(last.ship)] = 'E';
     }
+    grid.render(|&ch| ch)
 }
 
-/// Solution to part one.
-fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut ship = Ship {
-        x: 0,
-        y: 0,
-        facing: Direction::East,
-    };
-    for instruction in data {
-        ship.update_v1(instruction);
+fn main() -> io::Result<()> {
+    if let Some(format) = get_option("--export")? {
+        let tracks = tracks(&parse_instructions(&get_day_input(DAYNUM))?);
+        match format {
+            Format::Csv => print!("{}", export_csv(&tracks)),
+            Format::GeoJson => print!("{}", export_geojson(&tracks)),
+        }
+        return Ok(());
     }
-    Some(ship.taxicab_distance())
-}
-
-/// Solution to part two.
-fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut ship = Ship {
-        x: 0,
-        y: 0,
-        facing: Direction::East,
-    };
-    let mut waypoint = Waypoint { x: 10, y: 1 };
-    for instruction in data {
-        ship.update_v2(instruction, &mut waypoint);
+    if has_flag("--plot") {
+        let tracks = tracks(&parse_instructions(&get_day_input(DAYNUM))?);
+        for (part, track) in tracks.iter().enumerate() {
+            println!("Part {}:", part + 1);
+            println!("{}", plot(track));
+        }
+        return Ok(());
     }
-    Some(ship.taxicab_distance())
-}
 
-fn get_data(input: String) -> io::Result<ChallengeData> {
-    input.lines().map(|s| s.parse()).collect()
-}
-
-fn main() -> io::Result<()> {
     println!("Day {}:", DAYNUM);
     println!("==========");
     println!("Getting data...");
-    let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
+    let data = print_elapsed_time(|| parse_instructions(&get_day_input(DAYNUM)))?;
     println!("==========");
     println!("Solving part one...");
-    let ans1 = print_elapsed_time(|| part_one(&data)).expect("No solution found for part one");
-    println!("Answer: {}", ans1);
+    let (ans1, took) = elapsed_time(|| distance_by_heading(&data));
+    let ans1 = ans1.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
     println!("==========");
     println!("Solving part two...");
-    let ans2 = print_elapsed_time(|| part_two(&data)).expect("No solution found for part two");
-    println!("Answer: {}", ans2);
+    let (ans2, took) = elapsed_time(|| distance_by_waypoint(&data));
+    let ans2 = ans2.expect("No solution found for part two");
+    print_result(DAYNUM, 2, ans2, took);
     Ok(())
 }
 
@@ -196,17 +184,27 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_given_example() {
-        let input: String = "F10
-N3
-F7
-R90
-F11"
-        .to_string();
-        let data = get_data(input).expect("Couldn't convert test input");
-
-        // Assert get the right number.
-        assert_eq!(part_one(&data), Some(17 + 8));
-        assert_eq!(part_two(&data), Some(214 + 72));
+    fn test_tracks() {
+        let data = parse_instructions("F10\nN3\nF7\nR90\nF11").unwrap();
+        let [one, two] = tracks(&data);
+        let ships: Vec<Vector> = one.iter().map(|point| point.ship).collect();
+        assert_eq!(
+            ships,
+            vec![(0, 0), (10, 0), (10, 3), (17, 3), (17, 3), (17, -8)]
+        );
+        assert_eq!(two[4].heading, (4, -10));
+
+        let csv = export_csv(&[one.clone(), two]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + 6 + 6);
+        assert_eq!(lines[2], "1,1,10,0,1,0");
+
+        assert_eq!(
+            plot(&one[..4]),
+            "..........#######E
+..........#.......
+..........#.......
+S##########......."
+        );
     }
 }
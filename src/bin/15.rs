@@ -1,77 +1,83 @@
 /// Solution to Advent of Code Challenge Day 15.
-use aoc2020::{get_day_input, print_elapsed_time};
+use aoc2020::snapshot::{self, run_phase};
+use aoc2020::vaneck::VanEck;
+use aoc2020::{
+    elapsed_time, get_day_input, get_option, has_flag, print_elapsed_time, print_result, progress,
+};
+use std::error::Error;
+use std::io;
 use std::num::ParseIntError;
 
 type Number = usize;
 
-const DAYNUM: &'static str = "15";
+const DAYNUM: &str = "15";
 type ChallengeData = Vec<Number>;
 type ChallengeOut = Number;
 
 const TARGET1: usize = 2020;
 const TARGET2: usize = 30000000;
 
-fn solve_for(data: &ChallengeData, target: usize) -> Option<ChallengeOut> {
-    // Try to correct performance issues by using massive allocated array to store history in.
-    let mut last_seen: Vec<Number> = vec![0; target];
-    let mut last_num: Number;
-    let mut curr_turn: usize = 0;
-
-    // Insert the starting numbers (but avoid using the last one yet as setting its history would
-    // break the needed chain).
-    for number in &data[..data.len() - 1] {
-        curr_turn += 1;
-        last_seen[*number] = curr_turn;
+/// Play the memory game until the target turn as a phase of the solve, which can be checkpointed
+/// and resumed, and give the number spoken on it.
+fn solve_for(
+    data: &ChallengeData,
+    phase: &str,
+    target: usize,
+    bitmap: bool,
+) -> io::Result<Option<ChallengeOut>> {
+    // Every number spoken after the starting numbers is a gap between turns, so less than the
+    // target turn.
+    let mut game = VanEck::with_capacity(data, target);
+    if bitmap {
+        game = game.with_bitmap();
     }
-
-    // Set the current "look back" number to the last starting number, then start the algorithm.
-    curr_turn += 1;
-    last_num = *data.last().unwrap();
-
-    while curr_turn < target {
-        let number: Number = match last_seen[last_num] {
-            // Never seen before as there is no turn 0.
-            0 => 0,
-            _ => curr_turn - last_seen[last_num],
-        };
-
-        // Insert the previous num (which is what we're looking at) into the last seen history.
-        last_seen[last_num] = curr_turn;
-
-        curr_turn += 1;
-        last_num = number;
-    }
-
-    Some(last_num)
+    run_phase(DAYNUM, phase, &mut game, target, |game, turns| {
+        game.nth(turns - 1);
+    })?;
+    Ok(game.last_spoken())
 }
 
 /// Solution to part one.
-fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
-    solve_for(data, TARGET1)
+fn part_one(data: &ChallengeData) -> io::Result<Option<ChallengeOut>> {
+    solve_for(data, "part one", TARGET1, false)
 }
 
 /// Solution to part two.
-fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
-    solve_for(data, TARGET2)
+fn part_two(data: &ChallengeData, bitmap: bool) -> io::Result<Option<ChallengeOut>> {
+    solve_for(data, "part two", TARGET2, bitmap)
 }
 
 fn get_data(input: String) -> Result<ChallengeData, ParseIntError> {
     input.trim().split(',').map(|s| s.parse()).collect()
 }
 
-fn main() -> Result<(), ParseIntError> {
+fn main() -> Result<(), Box<dyn Error>> {
+    // Look over a snapshot saved with "--checkpoint <dir>" with "--inspect <path>".
+    if let Some(path) = get_option::<String>("--inspect")? {
+        return Ok(snapshot::inspect::<VanEck>(&path)?);
+    }
+    // Write progress through each part as JSON lines with "--progress-events <path>".
+    progress::init(DAYNUM)?;
     println!("Day {}:", DAYNUM);
     println!("==========");
     println!("Getting data...");
     let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
     println!("==========");
     println!("Solving part one...");
-    let ans1 = print_elapsed_time(|| part_one(&data)).expect("No solution found for part one");
-    println!("Answer: {}", ans1);
+    progress::start("part one");
+    let (ans1, took) = elapsed_time(|| part_one(&data));
+    progress::finish();
+    let ans1 = ans1?.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
     println!("==========");
     println!("Solving part two...");
-    let ans2 = print_elapsed_time(|| part_two(&data)).expect("No solution found for part two");
-    println!("Answer: {}", ans2);
+    // Track which numbers have been spoken in a separate bitmap with "--bitmap".
+    let bitmap = has_flag("--bitmap");
+    progress::start("part two");
+    let (ans2, took) = elapsed_time(|| part_two(&data, bitmap));
+    progress::finish();
+    let ans2 = ans2?.expect("No solution found for part two");
+    print_result(DAYNUM, 2, ans2, took);
     Ok(())
 }
 
@@ -98,9 +104,31 @@ mod tests {
             let data = get_data(input.to_string()).expect("Couldn't convert test input");
 
             // Assert get the right number.
-            assert_eq!(part_one(&data), Some(*answer1));
+            assert_eq!(part_one(&data).unwrap(), Some(*answer1));
             // Part two is disabled for general testing (takes too long).
             //assert_eq!(part_two(&data), Some(*answer2));
         }
     }
+
+    #[test]
+    fn test_history_options() {
+        let data = get_data("0,3,6".to_string()).unwrap();
+        let spoken: Vec<Number> = (1..=10)
+            .map(|turn| solve_for(&data, "test", turn, false).unwrap().unwrap())
+            .collect();
+        assert_eq!(spoken, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+        assert_eq!(solve_for(&data, "test", 0, false).unwrap(), None);
+
+        // The bitmap of spoken numbers doesn't change the game.
+        for &target in &[2, 4, 10, 2020, 100000] {
+            assert_eq!(
+                solve_for(&data, "test", target, true).unwrap(),
+                solve_for(&data, "test", target, false).unwrap()
+            );
+        }
+
+        // Starting numbers beyond the target turn still fit in the history.
+        let data = get_data("100,1".to_string()).unwrap();
+        assert_eq!(solve_for(&data, "test", 5, false).unwrap(), Some(1));
+    }
 }
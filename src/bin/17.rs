@@ -1,184 +1,238 @@
 /// Solution to Advent of Code Challenge Day 17.
-use aoc2020::{get_day_input, print_elapsed_time};
-use std::collections::{HashMap, HashSet};
+use aoc2020::animation::save_gif;
+use aoc2020::conway::{parse_initial, run, simulate, Engine, Point, State};
+use aoc2020::grid::Grid;
+use aoc2020::{
+    compare, elapsed_time, get_day_input, get_option, has_flag, print_elapsed_time, print_result,
+    progress,
+};
+use itertools::Itertools;
+use std::collections::BTreeMap;
 use std::io;
 use std::str::FromStr;
 
-const DAYNUM: &'static str = "17";
-type ChallengeData = InitialState;
+const DAYNUM: &str = "17";
+type ChallengeData = Vec<(i32, i32)>;
 type ChallengeOut = usize;
 
-type Position = Vec<i32>;
+/// The number of cycles both parts run, unless overridden with "--cycles <n>".
+const CYCLES: usize = 6;
 
-fn add_positions(p1: &Position, p2: &Position) -> Position {
-    p1.iter().zip(p2.iter()).map(|(x1, x2)| x1 + x2).collect()
+/// Solution to part one.
+fn part_one(data: &ChallengeData, engine: Engine, parallel: bool) -> Option<ChallengeOut> {
+    Some(run::<3>(data, CYCLES, engine, parallel))
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-enum Cube {
-    Active,
-    Inactive,
+/// Solution to part two.
+fn part_two(data: &ChallengeData, engine: Engine, parallel: bool) -> Option<ChallengeOut> {
+    Some(run::<4>(data, CYCLES, engine, parallel))
 }
 
-impl Cube {
-    fn from_ch(ch: char) -> Self {
-        match ch {
-            '.' => Self::Inactive,
-            '#' => Self::Active,
-            _ => panic!("Invalid character in the initial state data"),
+/// The active cubes before the first cycle and after each one, in order.
+fn history<const N: usize>(data: &ChallengeData, cycles: usize) -> Vec<Vec<Point<N>>> {
+    let mut state = State::<N>::from_plane(data);
+    let mut history = Vec::with_capacity(cycles + 1);
+    for cycle in 0..=cycles {
+        if cycle > 0 {
+            state.cycle();
         }
+        history.push(state.cubes().iter().copied().sorted().collect());
     }
+    history
 }
 
-type Row = Vec<Cube>;
-
-struct InitialState {
-    rows: Vec<Row>,
+/// Formats the active cubes can be exported in.
+enum Format {
+    Json,
+    Obj,
 }
 
-impl FromStr for InitialState {
+impl FromStr for Format {
     type Err = io::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            rows: s
-                .lines()
-                .map(|s| s.chars().map(|ch| Cube::from_ch(ch)).collect())
-                .collect(),
-        })
-    }
-}
 
-impl InitialState {
-    fn get_active_positions(&self) -> HashSet<(i32, i32)> {
-        let mut active_positions = HashSet::new();
-        for (y, row) in self.rows.iter().enumerate() {
-            for (x, cube) in row.iter().enumerate() {
-                match cube {
-                    Cube::Active => {
-                        active_positions.insert((x as i32, y as i32));
-                    }
-                    _ => continue,
-                }
-            }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "obj" => Ok(Self::Obj),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown export format {:?}: expected json or obj", s),
+            )),
         }
-        active_positions
     }
 }
 
-#[derive(Clone, Debug)]
-struct State {
-    cubes: HashSet<Position>,
-    dimensions: usize,
+/// Export the active cubes of a part after each cycle as a list of coordinates per cycle.
+fn export_json<const N: usize>(part: usize, history: &[Vec<Point<N>>]) -> String {
+    let cycles = history
+        .iter()
+        .map(|cubes| {
+            let coordinates = cubes
+                .iter()
+                .map(|Point(coords)| format!("[{}]", coords.iter().join(", ")))
+                .join(", ");
+            format!("[{}]", coordinates)
+        })
+        .join(",\n    ");
+    format!(
+        "{{\"part\": {}, \"dimensions\": {}, \"cycles\": [\n    {}\n]}}",
+        part, N, cycles
+    )
 }
 
-impl State {
-    fn from_initial(initial: &InitialState, dimensions: usize) -> Self {
-        assert!(dimensions >= 2);
-        let mut cubes = HashSet::new();
-        for (x, y) in initial.get_active_positions() {
-            let mut dimension_position = vec![0i32; dimensions];
-            dimension_position[0] = x;
-            dimension_position[1] = y;
-            cubes.insert(dimension_position);
+/// Export the active cubes of a part after each cycle as an OBJ point cloud, with an object of
+/// vertices per cycle. Cubes in more than three dimensions get an object per position in the
+/// dimensions past the third, so each is a 3D slice of the cycle.
+fn export_obj<const N: usize>(part: usize, history: &[Vec<Point<N>>]) -> String {
+    let mut out = String::new();
+    for (cycle, cubes) in history.iter().enumerate() {
+        let mut slices: BTreeMap<&[i32], Vec<&[i32; N]>> = BTreeMap::new();
+        for Point(coords) in cubes {
+            slices.entry(&coords[3.min(N)..]).or_default().push(coords);
         }
-        State { cubes, dimensions }
-    }
-
-    fn cycle(&mut self) {
-        // Create a mapping of all positions in the space that have active neighbours to the number
-        // of active neighbours they have. These positions include inactive cubes that have active
-        // neighbours by adding 1 active neighbour to nearby positions for each active cube, but
-        // also include active cubes that have no active neighbours.
-        let position_to_active_neighbours = self.get_position_to_active_neighbours();
-
-        for (position, neighbours) in position_to_active_neighbours {
-            if self.cubes.contains(&position) && !(2..=3).contains(&neighbours) {
-                self.cubes.remove(&position);
-            } else if neighbours == 3 {
-                self.cubes.insert(position);
+        for (slice, cubes) in slices {
+            out += &format!("o part{}_cycle{}", part, cycle);
+            for coord in slice {
+                out += &format!("_{}", coord);
             }
-        }
-    }
-
-    fn get_neighbour_directions(&self) -> Vec<Position> {
-        let mut neighbour_directions = vec![vec![0i32; self.dimensions]];
-        // For each dimension, add the "-1" and "+1" variants in that dimensions to the already
-        // calculated neighbour directions.
-        for dimension in 0..self.dimensions {
-            let mut new_directions = Vec::new();
-            for direction in &neighbour_directions {
-                let mut new_direction_up = direction.clone();
-                new_direction_up[dimension] = 1;
-                new_directions.push(new_direction_up);
-                let mut new_direction_down = direction.clone();
-                new_direction_down[dimension] = -1;
-                new_directions.push(new_direction_down);
+            out += "\n";
+            for coords in cubes {
+                let z = coords.get(2).copied().unwrap_or(0);
+                out += &format!("v {} {} {}\n", coords[0], coords[1], z);
             }
-            neighbour_directions.extend(new_directions);
         }
-        // This produces all direction including the starting "0" vector, which doesn't point to any
-        // neighbours but the self: remove this.
-        neighbour_directions.remove(0);
-        neighbour_directions
     }
-
-    fn get_neighbours(&self, p: &Position) -> Vec<Position> {
-        self.get_neighbour_directions()
-            .iter()
-            .map(|direction| add_positions(p, direction))
-            .collect()
-    }
-
-    fn get_position_to_active_neighbours(&self) -> HashMap<Position, u32> {
-        let mut position_to_active_neighbours = HashMap::new();
-        for active_pos in &self.cubes {
-            // Ensure active cubes are placed in the mapping, even if they have 0 active neighbours.
-            position_to_active_neighbours
-                .entry(active_pos.to_vec())
-                .or_insert(0);
-            for neighbour in self.get_neighbours(active_pos) {
-                *position_to_active_neighbours.entry(neighbour).or_insert(0) += 1;
-            }
-        }
-        position_to_active_neighbours
-    }
-}
-
-/// Solution to part one.
-fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut state = State::from_initial(data, 3);
-    for _ in 0..6 {
-        state.cycle();
-    }
-    Some(state.cubes.len())
+    out
 }
 
-/// Solution to part two.
-fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut state = State::from_initial(data, 4);
-    for _ in 0..6 {
-        state.cycle();
-    }
-    Some(state.cubes.len())
+/// The slice of each cycle through the plane the cubes start in, as frames of an animation drawing
+/// active cubes as `#` and inactive ones as `.`. Every frame covers every cube that is ever
+/// active in the plane, so the drawing stays still while the cubes spread.
+fn plane_frames<const N: usize>(history: &[Vec<Point<N>>]) -> io::Result<Vec<Grid<char>>> {
+    let in_plane = |Point(coords): &Point<N>| coords[2..].iter().all(|&coord| coord == 0);
+    let cubes = || history.iter().flatten().filter(|point| in_plane(point));
+    let (min_x, max_x) = (
+        cubes().map(|point| point.0[0]).min(),
+        cubes().map(|point| point.0[0]).max(),
+    );
+    let (min_y, max_y) = (
+        cubes().map(|point| point.0[1]).min(),
+        cubes().map(|point| point.0[1]).max(),
+    );
+    let (min_x, max_x, min_y, max_y) = (
+        min_x.unwrap_or(0),
+        max_x.unwrap_or(0),
+        min_y.unwrap_or(0),
+        max_y.unwrap_or(0),
+    );
+    history
+        .iter()
+        .map(|cubes| {
+            let mut rows =
+                vec![vec!['.'; (max_x - min_x + 1) as usize]; (max_y - min_y + 1) as usize];
+            for point in cubes.iter().filter(|point| in_plane(point)) {
+                rows[(point.0[1] - min_y) as usize][(point.0[0] - min_x) as usize] = '#';
+            }
+            Grid::from_rows(rows)
+        })
+        .collect()
 }
 
 fn get_data(input: String) -> Result<ChallengeData, io::Error> {
-    input.parse()
+    parse_initial(&input)
 }
 
 fn main() -> Result<(), io::Error> {
+    // Save each cycle of the slice through the starting plane in three dimensions as a GIF with
+    // "--gif <path>".
+    if let Some(path) = get_option::<String>("--gif")? {
+        let data = get_data(get_day_input(DAYNUM))?;
+        let cycles = get_option("--cycles")?.unwrap_or(CYCLES);
+        return save_gif(&path, &plane_frames(&history::<3>(&data, cycles))?);
+    }
+    if let Some(format) = get_option("--export")? {
+        let data = get_data(get_day_input(DAYNUM))?;
+        let cycles = get_option("--cycles")?.unwrap_or(CYCLES);
+        let (part_one, part_two) = (history::<3>(&data, cycles), history::<4>(&data, cycles));
+        match format {
+            Format::Json => println!(
+                "[{},\n{}]",
+                export_json(1, &part_one),
+                export_json(2, &part_two)
+            ),
+            Format::Obj => print!("{}{}", export_obj(1, &part_one), export_obj(2, &part_two)),
+        }
+        return Ok(());
+    }
+    // Write progress through each part as JSON lines with "--progress-events <path>".
+    progress::init(DAYNUM)?;
     println!("Day {}:", DAYNUM);
     println!("==========");
     println!("Getting data...");
     let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
+    // Store the cubes in a dense array rather than a set with "--engine dense".
+    let engine = get_option("--engine")?.unwrap_or(Engine::Sparse);
+    // Count each cycle's active neighbours across this many threads (or as many as there are
+    // cores for 0) with "--threads <n>".
+    let parallel = match get_option("--threads")? {
+        Some(threads) => {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build_global()
+                .map_err(io::Error::other)?;
+            true
+        }
+        None => false,
+    };
     println!("==========");
     println!("Solving part one...");
-    let ans1 = print_elapsed_time(|| part_one(&data)).expect("No solution found for part one");
-    println!("Answer: {}", ans1);
+    // Time the set of active cubes against the dense array, and check they agree, with
+    // "--compare".
+    let compare_engines = has_flag("--compare");
+    progress::start("part one");
+    let (ans1, took) = match compare_engines {
+        true => compare(
+            "Sparse",
+            || part_one(&data, Engine::Sparse, parallel),
+            "Dense",
+            || part_one(&data, Engine::Dense, parallel),
+        ),
+        false => elapsed_time(|| part_one(&data, engine, parallel)),
+    };
+    progress::finish();
+    let ans1 = ans1.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
     println!("==========");
     println!("Solving part two...");
-    let ans2 = print_elapsed_time(|| part_two(&data)).expect("No solution found for part two");
-    println!("Answer: {}", ans2);
+    progress::start("part two");
+    let (ans2, took) = match compare_engines {
+        true => compare(
+            "Sparse",
+            || part_two(&data, Engine::Sparse, parallel),
+            "Dense",
+            || part_two(&data, Engine::Dense, parallel),
+        ),
+        false => elapsed_time(|| part_two(&data, engine, parallel)),
+    };
+    progress::finish();
+    let ans2 = ans2.expect("No solution found for part two");
+    print_result(DAYNUM, 2, ans2, took);
+    // Simulate other numbers of dimensions and cycles with "--dims <n>" and "--cycles <n>".
+    let dimensions: Option<usize> = get_option("--dims")?;
+    let cycles: Option<usize> = get_option("--cycles")?;
+    if dimensions.is_some() || cycles.is_some() {
+        let dimensions = dimensions.unwrap_or(3);
+        let cycles = cycles.unwrap_or(CYCLES);
+        println!("==========");
+        println!(
+            "Simulating {} cycles in {} dimensions...",
+            cycles, dimensions
+        );
+        progress::start("simulation");
+        let ans = print_elapsed_time(|| simulate(&data, dimensions, cycles, engine, parallel))?;
+        progress::finish();
+        println!("Active cubes: {}", ans);
+    }
     Ok(())
 }
 
@@ -196,7 +250,33 @@ mod tests {
         let data = get_data(input.to_string()).expect("Couldn't convert test input");
 
         // Assert get the right number.
-        assert_eq!(part_one(&data), Some(112));
-        assert_eq!(part_two(&data), Some(848));
+        assert_eq!(part_one(&data, Engine::Sparse, false), Some(112));
+        assert_eq!(part_two(&data, Engine::Sparse, false), Some(848));
+        assert_eq!(part_two(&data, Engine::Sparse, true), Some(848));
+        assert_eq!(part_two(&data, Engine::Dense, false), Some(848));
+    }
+
+    #[test]
+    fn test_export() {
+        let data = get_data(".#.\n..#\n###".to_string()).unwrap();
+        let cycles = history::<3>(&data, 1);
+        assert_eq!(cycles.len(), 2);
+        assert_eq!(cycles[1].len(), 11);
+
+        let json = export_json(1, &history::<3>(&data, 0));
+        assert_eq!(
+            json,
+            "{\"part\": 1, \"dimensions\": 3, \"cycles\": [\n    \
+             [[0, 2, 0], [1, 0, 0], [1, 2, 0], [2, 1, 0], [2, 2, 0]]\n]}"
+        );
+
+        // Each 4D cycle splits into 3D slices along the fourth dimension.
+        let obj = export_obj(2, &history::<4>(&data, 1));
+        assert!(obj.starts_with("o part2_cycle0_0\nv 0 2 0\n"));
+        assert!(obj.contains("o part2_cycle1_-1\n"));
+        assert_eq!(
+            obj.lines().filter(|line| line.starts_with('v')).count(),
+            5 + 29
+        );
     }
 }
@@ -1,156 +1,53 @@
 /// Solution to Advent of Code Challenge Day 22.
-use aoc2020::{get_day_input, print_elapsed_time};
-use itertools::Itertools;
-use std::collections::{HashSet, VecDeque};
+use aoc2020::combat::{combat_score, parse_decks, recursive_combat_score, Card, Rules, Transcript};
+use aoc2020::{elapsed_time, get_day_input, has_flag, print_elapsed_time, print_result};
+use std::collections::VecDeque;
 use std::io;
-use std::str::FromStr;
 
-const DAYNUM: &'static str = "22";
-type ChallengeData = [Deck; 2];
+const DAYNUM: &str = "22";
+type ChallengeData = [VecDeque<Card>; 2];
 type ChallengeOut = u32;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct Deck(VecDeque<u16>);
-
-impl FromStr for Deck {
-    type Err = io::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (_, deck_str) = s.split(":\n").next_tuple().unwrap();
-        Ok(Self(deck_str.lines().map(|s| s.parse().unwrap()).collect()))
-    }
-}
-
-fn calculate_score(winning_hand: &Deck) -> u32 {
-    let mut winner_score: u32 = 0;
-    for (i, card) in winning_hand.0.iter().rev().enumerate() {
-        winner_score += (i as u32 + 1) * (*card as u32);
-    }
-    winner_score
-}
-
-/// Play the round of Recursive Combat.
-///
-/// Returns whether the round means the end of the game for player 1 due to a recursion-stop or
-/// because the decks have run out of cards.
-fn play_round(deck1: &mut Deck, deck2: &mut Deck, rounds_seen: &mut HashSet<(Deck, Deck)>) -> bool {
-    if deck1.0.is_empty() || deck2.0.is_empty() {
-        return true;
-    }
-
-    if rounds_seen.contains(&(deck1.clone(), deck2.clone())) {
-        // This round has been seen: win is for player 1.
-        return true;
-    }
-    // This is a new matchup: record it
-    rounds_seen.insert((deck1.clone(), deck2.clone()));
-
-    let card1 = deck1.0.pop_front().unwrap();
-    let card2 = deck2.0.pop_front().unwrap();
-
-    let player1_wins = if deck1.0.len() >= card1 as usize && deck2.0.len() >= card2 as usize {
-        let mut subdeck1: Deck = Deck(deck1.0.iter().take(card1 as usize).cloned().collect());
-        let mut subdeck2: Deck = Deck(deck2.0.iter().take(card2 as usize).cloned().collect());
-        play_game(&mut subdeck1, &mut subdeck2)
-    } else {
-        card1 > card2
-    };
-
-    match player1_wins {
-        true => {
-            deck1.0.push_back(card1);
-            deck1.0.push_back(card2);
-        }
-        false => {
-            deck2.0.push_back(card2);
-            deck2.0.push_back(card1);
-        }
-    }
-
-    false
-}
-
-/// Play the game of Recursive Combat with the two starting decks.
-///
-/// Returns if player1 wins by the criteria that player1 has cards left.
-fn play_game(deck1: &mut Deck, deck2: &mut Deck) -> bool {
-    // Keep a cache of rounds which have already been played: if the round is seen, the game will
-    // end for player1.
-    let mut rounds_seen: HashSet<(Deck, Deck)> = HashSet::new();
-    let mut end_game = false;
-
-    while !end_game {
-        end_game = play_round(deck1, deck2, &mut rounds_seen);
-    }
-
-    !deck1.0.is_empty()
-}
-
-/// Solution to part one.
-fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut deck1 = data[0].clone();
-    let mut deck2 = data[1].clone();
-
-    while !deck1.0.is_empty() && !deck2.0.is_empty() {
-        let card1 = deck1.0.pop_front().unwrap();
-        let card2 = deck2.0.pop_front().unwrap();
-        match card1 > card2 {
-            true => {
-                deck1.0.push_back(card1);
-                deck1.0.push_back(card2);
-            }
-            false => {
-                deck2.0.push_back(card2);
-                deck2.0.push_back(card1);
-            }
-        }
-    }
-
-    let winning_hand = match deck1.0.is_empty() {
-        true => &deck2,
-        false => &deck1,
-    };
-
-    Some(calculate_score(winning_hand))
-}
-
 /// Solution to part two.
-fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut deck1 = data[0].clone();
-    let mut deck2 = data[1].clone();
-
-    let winning_hand = match play_game(&mut deck1, &mut deck2) {
-        true => &deck1,
-        false => &deck2,
-    };
+fn part_two(data: &ChallengeData, rules: Rules) -> Option<ChallengeOut> {
+    Some(recursive_combat_score(data, rules, &mut ()))
+}
 
-    Some(calculate_score(winning_hand))
+/// Play part two's game, recording everything that happens in it.
+fn transcript(data: &ChallengeData) -> Transcript {
+    let mut transcript = Transcript::new();
+    recursive_combat_score(data, Rules::default(), &mut transcript);
+    transcript
 }
 
 fn get_data(input: String) -> Result<ChallengeData, io::Error> {
-    let mut decks = [Deck(VecDeque::new()), Deck(VecDeque::new())];
-    let deck_vec: Vec<Deck> = input
-        .trim()
-        .split("\n\n")
-        .map(|s| s.parse::<Deck>().unwrap())
-        .collect();
-    decks[0] = deck_vec[0].clone();
-    decks[1] = deck_vec[1].clone();
-    Ok(decks)
+    parse_decks(&input)
 }
 
 fn main() -> Result<(), io::Error> {
+    // Replay every round of part two's game with "--transcript".
+    if has_flag("--transcript") {
+        let data = get_data(get_day_input(DAYNUM))?;
+        print!("{}", transcript(&data).replay());
+        return Ok(());
+    }
     println!("Day {}:", DAYNUM);
     println!("==========");
     println!("Getting data...");
     let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
     println!("==========");
     println!("Solving part one...");
-    let ans1 = print_elapsed_time(|| part_one(&data)).expect("No solution found for part one");
-    println!("Answer: {}", ans1);
+    let (ans1, took) = elapsed_time(|| combat_score(&data));
+    print_result(DAYNUM, 1, ans1, took);
     println!("==========");
     println!("Solving part two...");
-    let ans2 = print_elapsed_time(|| part_two(&data)).expect("No solution found for part two");
-    println!("Answer: {}", ans2);
+    // Decide sub-games player 1 can't lose without playing them with "--shortcut".
+    let rules = Rules {
+        shortcut_sub_games: has_flag("--shortcut"),
+    };
+    let (ans2, took) = elapsed_time(|| part_two(&data, rules));
+    let ans2 = ans2.expect("No solution found for part two");
+    print_result(DAYNUM, 2, ans2, took);
     Ok(())
 }
 
@@ -178,7 +75,14 @@ Player 2:
         let data = get_data(input.to_string()).expect("Couldn't convert test input");
 
         // Assert get the right number.
-        assert_eq!(part_one(&data), Some(306));
-        assert_eq!(part_two(&data), Some(291));
+        assert_eq!(combat_score(&data), 306);
+        assert_eq!(part_two(&data, Rules::default()), Some(291));
+        let shortcut = Rules {
+            shortcut_sub_games: true,
+        };
+        assert_eq!(part_two(&data, shortcut), Some(291));
+        assert!(transcript(&data)
+            .replay()
+            .contains("-- Round 17 (Game 1) --\nPlayer 1's deck: 3\n"));
     }
 }
@@ -1,221 +1,216 @@
 /// Solution to Advent of Code Challenge Day 24.
-use aoc2020::{get_day_input, print_elapsed_time};
-use std::collections::HashSet;
-use std::io;
-use std::str::FromStr;
-
-const DAYNUM: &'static str = "24";
+use aoc2020::animation::save_gif;
+use aoc2020::fasthash::FastSet;
+use aoc2020::grid::Grid;
+use aoc2020::hex::Coord;
+use aoc2020::lobby::{
+    get_initial_tiles, get_initial_tiles_streaming, next_day, render_tiles, run, tile_bounds,
+    Engine, FlipRules, Instruction, DAYS,
+};
+use aoc2020::snapshot::{self, run_phase};
+use aoc2020::{
+    compare, elapsed_time, get_day_input, get_day_reader, get_option, has_flag, print_elapsed_time,
+    print_result, progress,
+};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+const DAYNUM: &str = "24";
 type ChallengeData = Vec<Instruction>;
 type ChallengeOut = usize;
 
-/// Define the coordinate of a tile in the hexagonal grid as follows:
-/// - Reference tile is (0, 0)
-/// - Moving east is (1, 0), west is (-1, 0)
-/// - Draw the lines of "constant vertical" diagonally across the hexagons from north-west to
-///   south-east
-/// - This makes moving north-west (0, 1) but north-east (1, 1)
-/// - South-west is (-1, -1) and south-east (0, -1) (opposites of above)
-/// This accounts for the hexagonal grid because although the tiles don't actually fit on these
-/// lines on a graph, different steps to the same tile will still give the same coordinate and it is
-/// unique to that tile.
-///
-///    / \   / \   / \
-///   /   \ /   \ /   \
-///  |     |     |     |
-///  |-1,1 | 0,1 | 1,1 |
-///  |     |     |     |
-///   \   / \   / \   / \
-///    \ /   \ /   \ /   \
-///     |     |     |     |
-///     |-1,0 | 0,0 | 1,0 |
-///     |     |     |     |
-///      \   / \   / \   /
-///       \ /   \ /   \ /
-///
-type Coord = (i32, i32);
-
-fn vec_add(vec1: Coord, vec2: Coord) -> Coord {
-    (vec1.0 + vec2.0, vec1.1 + vec2.1)
-}
-
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum Direction {
-    E,
-    SE,
-    SW,
-    W,
-    NW,
-    NE,
+/// Solution to part one.
+fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
+    Some(get_initial_tiles(data).len())
 }
 
-impl FromStr for Direction {
-    type Err = io::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "e" => Self::E,
-            "se" => Self::SE,
-            "sw" => Self::SW,
-            "w" => Self::W,
-            "nw" => Self::NW,
-            "ne" => Self::NE,
-            _ => panic!("Couldn't convert string into direction: {}", s),
-        })
+/// The black tiles at the start of the exhibit and after each day of it under some flip rules.
+fn history(black_tiles: FastSet<Coord>, days: usize, rules: FlipRules) -> Vec<FastSet<Coord>> {
+    let mut history = vec![black_tiles];
+    for day in 0..days {
+        history.push(next_day(&history[day], rules));
     }
+    history
 }
 
-impl Direction {
-    fn unit_vec(&self) -> Coord {
-        match self {
-            Self::E => (1, 0),
-            Self::W => (-1, 0),
-            Self::NW => (0, 1),
-            Self::NE => (1, 1),
-            Self::SE => (0, -1),
-            Self::SW => (-1, -1),
-        }
+/// The pause between frames of the animation, unless overridden with "--delay <ms>".
+const FRAME_DELAY_MS: u64 = 100;
+
+/// Run a number of days of the exhibit under some flip rules, clearing the terminal and redrawing
+/// the tiles after each day with a pause between frames. Every frame covers every tile that is
+/// ever black, so the drawing stays still while the exhibit grows.
+fn animate<W: Write>(
+    black_tiles: FastSet<Coord>,
+    days: usize,
+    rules: FlipRules,
+    out: &mut W,
+    delay: Duration,
+) -> io::Result<()> {
+    let history = history(black_tiles, days, rules);
+    let (min, max) = tile_bounds(history.iter().flatten());
+    for (day, black_tiles) in history.iter().enumerate() {
+        // Clear the screen and move the cursor back to the top left before drawing.
+        write!(out, "\x1b[2J\x1b[H")?;
+        writeln!(out, "Day {}: {} black tiles", day, black_tiles.len())?;
+        writeln!(out, "{}", render_tiles(black_tiles, (min, max)))?;
+        out.flush()?;
+        thread::sleep(delay);
     }
+    Ok(())
 }
 
-#[derive(Debug, Clone)]
-struct Instruction {
-    dirs: Vec<Direction>,
+/// Solution to part two.
+fn part_two(data: &ChallengeData, engine: Engine) -> Option<ChallengeOut> {
+    Some(run(
+        get_initial_tiles(data),
+        DAYS,
+        FlipRules::EXHIBIT,
+        engine,
+    ))
 }
 
-impl FromStr for Instruction {
-    type Err = io::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars: Vec<char> = s.chars().rev().collect();
-        let mut dirs = Vec::new();
-        while !chars.is_empty() {
-            let mut dir_str = String::new();
-            dir_str.push(chars.pop().unwrap());
-            // The directions South and North don't exist: and s or n is always followed by a
-            // qualifier
-            if dir_str == "s" || dir_str == "n" {
-                dir_str.push(chars.pop().expect("Cannot have an S or N unqualified"));
+/// Run a number of days of the exhibit with the set of black tiles as a phase of the solve, which
+/// can be checkpointed and resumed, and count the black tiles after.
+fn run_checkpointed(
+    black_tiles: FastSet<Coord>,
+    phase: &str,
+    days: usize,
+    rules: FlipRules,
+) -> io::Result<ChallengeOut> {
+    let mut black_tiles = black_tiles;
+    run_phase(
+        DAYNUM,
+        phase,
+        &mut black_tiles,
+        days,
+        |black_tiles, days| {
+            for _ in 0..days {
+                *black_tiles = next_day(black_tiles, rules);
             }
-            dirs.push(dir_str.parse()?);
-        }
-        Ok(Self { dirs })
-    }
+        },
+    )?;
+    Ok(black_tiles.len())
 }
 
-impl Instruction {
-    /// Convert the set of instructions to a final coordinate.
-    fn to_coord(&self) -> Coord {
-        let mut coord = (0, 0);
-
-        for direction in &self.dirs {
-            coord = vec_add(coord, direction.unit_vec());
-        }
-
-        coord
-    }
-}
-
-/// Find all coords which are adjecent to this one (there will be 6 because hexagons are the
-/// bestagons).
-fn get_adjacent_coords(coord: Coord) -> [Coord; 6] {
-    [
-        vec_add(coord, Direction::E.unit_vec()),
-        vec_add(coord, Direction::W.unit_vec()),
-        vec_add(coord, Direction::NW.unit_vec()),
-        vec_add(coord, Direction::NE.unit_vec()),
-        vec_add(coord, Direction::SE.unit_vec()),
-        vec_add(coord, Direction::SW.unit_vec()),
-    ]
+fn get_data(input: String) -> Result<ChallengeData, io::Error> {
+    input.lines().map(|s| s.parse()).collect()
 }
 
-/// Generate the initial tileset from the given instructions.
-fn get_initial_tiles(instructions: &ChallengeData) -> HashSet<Coord> {
-    let mut black_tiles: HashSet<Coord> = HashSet::new();
-
-    for instruction in instructions {
-        let tile = instruction.to_coord();
-
-        if !black_tiles.remove(&tile) {
-            // Wasn't already flipped to black so insert it into the set of black tiles (if it was
-            // already in the set it flips back to white and is already removed)
-            black_tiles.insert(tile);
-        }
+fn main() -> Result<(), io::Error> {
+    // Look over a snapshot saved with "--checkpoint <dir>" with "--inspect <path>".
+    if let Some(path) = get_option::<String>("--inspect")? {
+        return snapshot::inspect::<FastSet<Coord>>(&path);
     }
-
-    black_tiles
-}
-
-/// Solution to part one.
-fn part_one(data: &ChallengeData) -> Option<ChallengeOut> {
-    Some(get_initial_tiles(data).len())
-}
-
-/// Solution to part two.
-fn part_two(data: &ChallengeData) -> Option<ChallengeOut> {
-    let mut black_tiles: HashSet<Coord> = get_initial_tiles(data);
-
-    // Perform the 100 days of iterations.
-    for _ in 0..100 {
-        let mut new_tiles = HashSet::new();
-
-        for tile in &black_tiles {
-            let neighbours = get_adjacent_coords(*tile);
-            let mut black_neighbours = 0;
-            for neighbour in &neighbours {
-                if black_tiles.contains(neighbour) {
-                    black_neighbours += 1;
-                }
-            }
-
-            // If a black tile has zero or more than 2 neighbours, flip to white (don't re-add it to
-            // the new black tiles)
-            if black_neighbours != 0 && black_neighbours <= 2 {
-                new_tiles.insert(*tile);
-            }
-
-            // Use this black tile to try and find white tiles which have exactly 2 black tile
-            // neighbours.
-            for neighbour in &neighbours {
-                if !black_tiles.contains(neighbour) {
-                    // Is a white tile
-                    let onward_neighbours = get_adjacent_coords(*neighbour);
-                    let mut black_neighbours = 0;
-                    for onward_neighbour in &onward_neighbours {
-                        if black_tiles.contains(onward_neighbour) {
-                            black_neighbours += 1;
-                        }
-                    }
-
-                    if black_neighbours == 2 {
-                        new_tiles.insert(*neighbour);
-                    }
-                }
-            }
-        }
-
-        black_tiles = new_tiles;
+    // Run the exhibit for other numbers of days or under other rules with "--days <n>" and
+    // "--rules B<counts>/S<counts>".
+    let days: Option<usize> = get_option("--days")?;
+    let rules: Option<FlipRules> = get_option("--rules")?;
+    // Store the floor in a dense array rather than a set of black tiles with "--engine dense".
+    let engine = get_option("--engine")?.unwrap_or(Engine::Sparse);
+    // Checkpointing saves the set of black tiles, so runs the exhibit with that whatever the
+    // engine.
+    let checkpoint = get_option::<PathBuf>("--checkpoint")?.is_some();
+
+    // Save every day of the exhibit as a GIF with "--gif <path>", covering every tile that is
+    // ever black in each frame.
+    if let Some(path) = get_option::<String>("--gif")? {
+        let data = get_data(get_day_input(DAYNUM))?;
+        let history = history(
+            get_initial_tiles(&data),
+            days.unwrap_or(DAYS),
+            rules.unwrap_or_default(),
+        );
+        let bounds = tile_bounds(history.iter().flatten());
+        let frames = history
+            .iter()
+            .map(|black_tiles| Grid::from_chars(&render_tiles(black_tiles, bounds), Some))
+            .collect::<io::Result<Vec<_>>>()?;
+        return save_gif(&path, &frames);
     }
 
-    Some(black_tiles.len())
-}
+    if has_flag("--animate") {
+        let data = get_data(get_day_input(DAYNUM))?;
+        let delay = Duration::from_millis(get_option("--delay")?.unwrap_or(FRAME_DELAY_MS));
+        let (days, rules) = (days.unwrap_or(DAYS), rules.unwrap_or_default());
+        return animate(
+            get_initial_tiles(&data),
+            days,
+            rules,
+            &mut io::stdout(),
+            delay,
+        );
+    }
 
-fn get_data(input: String) -> Result<ChallengeData, io::Error> {
-    input.lines().map(|s| s.parse()).collect()
-}
+    if has_flag("--stream") {
+        println!("Day {} (streaming):", DAYNUM);
+        println!("==========");
+        let (black_tiles, took) =
+            elapsed_time(|| get_initial_tiles_streaming(get_day_reader(DAYNUM)?));
+        let black_tiles = black_tiles?;
+        print_result(DAYNUM, 1, black_tiles.len(), took);
+        let (ans2, took) = elapsed_time(|| run(black_tiles, DAYS, FlipRules::EXHIBIT, engine));
+        print_result(DAYNUM, 2, ans2, took);
+        return Ok(());
+    }
 
-fn main() -> Result<(), io::Error> {
+    // Write progress through each part as JSON lines with "--progress-events <path>".
+    progress::init(DAYNUM)?;
     println!("Day {}:", DAYNUM);
     println!("==========");
     println!("Getting data...");
     let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
     println!("==========");
     println!("Solving part one...");
-    let ans1 = print_elapsed_time(|| part_one(&data)).expect("No solution found for part one");
-    println!("Answer: {}", ans1);
+    progress::start("part one");
+    let (ans1, took) = elapsed_time(|| part_one(&data));
+    progress::finish();
+    let ans1 = ans1.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
     println!("==========");
     println!("Solving part two...");
-    let ans2 = print_elapsed_time(|| part_two(&data)).expect("No solution found for part two");
-    println!("Answer: {}", ans2);
+    // Time the set of black tiles against the dense array, and check they agree, with
+    // "--compare".
+    progress::start("part two");
+    let (ans2, took) = match (checkpoint, has_flag("--compare")) {
+        (true, _) => {
+            let (ans2, took) = elapsed_time(|| {
+                run_checkpointed(
+                    get_initial_tiles(&data),
+                    "part two",
+                    DAYS,
+                    FlipRules::EXHIBIT,
+                )
+            });
+            (Some(ans2?), took)
+        }
+        (false, true) => compare(
+            "Sparse",
+            || part_two(&data, Engine::Sparse),
+            "Dense",
+            || part_two(&data, Engine::Dense),
+        ),
+        (false, false) => elapsed_time(|| part_two(&data, engine)),
+    };
+    progress::finish();
+    let ans2 = ans2.expect("No solution found for part two");
+    print_result(DAYNUM, 2, ans2, took);
+    if days.is_some() || rules.is_some() {
+        let days = days.unwrap_or(DAYS);
+        let rules = rules.unwrap_or_default();
+        println!("==========");
+        println!("Simulating {} days with rules {}...", days, rules);
+        progress::start("simulation");
+        let ans = match checkpoint {
+            true => print_elapsed_time(|| {
+                run_checkpointed(get_initial_tiles(&data), "simulation", days, rules)
+            })?,
+            false => print_elapsed_time(|| run(get_initial_tiles(&data), days, rules, engine)),
+        };
+        progress::finish();
+        println!("Black tiles: {}", ans);
+    }
     Ok(())
 }
 
@@ -251,6 +246,26 @@ wseweeenwnesenwwwswnew"
 
         // Assert get the right number.
         assert_eq!(part_one(&data), Some(10));
-        assert_eq!(part_two(&data), Some(2208));
+        assert_eq!(part_two(&data, Engine::Sparse), Some(2208));
+        assert_eq!(part_two(&data, Engine::Dense), Some(2208));
+        assert_eq!(
+            run_checkpointed(get_initial_tiles(&data), "test", DAYS, FlipRules::EXHIBIT).unwrap(),
+            2208
+        );
+
+        // Streaming the instructions flips the same tiles.
+        let streamed = get_initial_tiles_streaming(input.as_bytes()).unwrap();
+        assert_eq!(streamed, get_initial_tiles(&data));
+    }
+
+    #[test]
+    fn test_render() {
+        let tiles: FastSet<Coord> = [(0, 0), (1, 0), (1, 1)].iter().copied().collect();
+        let mut out = Vec::new();
+        let rules = FlipRules::EXHIBIT;
+        animate(tiles, DAYS, rules, &mut out, Duration::from_millis(0)).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Day 0: 3 black tiles\n"));
+        assert!(out.contains(&format!("Day {}: ", DAYS)));
     }
 }
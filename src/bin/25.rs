@@ -0,0 +1,75 @@
+/// Solution to Advent of Code Challenge Day 25.
+use aoc2020::handshake::{encryption_key, Search};
+use aoc2020::{
+    compare, elapsed_time, get_day_input, get_option, has_flag, print_elapsed_time, print_result,
+};
+use itertools::Itertools;
+use std::io;
+
+const DAYNUM: &str = "25";
+type ChallengeData = (u64, u64);
+type ChallengeOut = u64;
+
+/// Solution to part one.
+fn part_one(data: &ChallengeData, search: Search) -> Option<ChallengeOut> {
+    let (card_key, door_key) = *data;
+    encryption_key(card_key, door_key, search)
+}
+
+fn get_data(input: String) -> Result<ChallengeData, io::Error> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Expected the card's and the door's public keys on a line each",
+        )
+    };
+    input
+        .lines()
+        .map(|line| line.trim().parse().map_err(|_| invalid()))
+        .collect::<io::Result<Vec<u64>>>()?
+        .into_iter()
+        .collect_tuple()
+        .ok_or_else(invalid)
+}
+
+fn main() -> Result<(), io::Error> {
+    println!("Day {}:", DAYNUM);
+    println!("==========");
+    println!("Getting data...");
+    let data = print_elapsed_time(|| get_data(get_day_input(DAYNUM)))?;
+    // Try each loop size in turn rather than with baby-step giant-step with "--search naive".
+    let search = get_option("--search")?.unwrap_or(Search::BabyStepGiantStep);
+    println!("==========");
+    println!("Solving part one...");
+    // Time both searches and check they agree with "--compare".
+    let (ans1, took) = match has_flag("--compare") {
+        true => compare(
+            "Baby-step giant-step",
+            || part_one(&data, Search::BabyStepGiantStep),
+            "Naive",
+            || part_one(&data, Search::Naive),
+        ),
+        false => elapsed_time(|| part_one(&data, search)),
+    };
+    let ans1 = ans1.expect("No solution found for part one");
+    print_result(DAYNUM, 1, ans1, took);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_example() {
+        let input = "5764801
+17807724"
+            .to_string();
+
+        let data = get_data(input.to_string()).expect("Couldn't convert test input");
+
+        // Assert get the right number.
+        assert_eq!(part_one(&data, Search::Naive), Some(14897079));
+        assert_eq!(part_one(&data, Search::BabyStepGiantStep), Some(14897079));
+    }
+}
@@ -0,0 +1,582 @@
+/// Runner over every day's solution, for reporting on all of them at once.
+use aoc2020::fasthash::FastSet;
+use aoc2020::grid::Grid;
+use aoc2020::hex::Coord;
+use aoc2020::homework::{self, PrecedenceTable};
+use aoc2020::jigsaw::{assemble, form_image, Puzzle};
+use aoc2020::leaderboard;
+use aoc2020::lobby::{
+    get_initial_tiles, render_tiles, simulate, tile_bounds, FlipRules, Instruction,
+    DAYS as LOBBY_DAYS,
+};
+use aoc2020::puzzle;
+use aoc2020::report::{
+    day_notes, parse_output, to_html, to_json, to_markdown, DayReport, Format, Visual,
+};
+use aoc2020::seating::{SeatingRules, Simulation, Tile};
+use aoc2020::svg::{self, Palette};
+use aoc2020::{get_day_input, STDIN};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// The days with a solution.
+const DAYS: u32 = 25;
+
+/// How many pixels across each cell of an SVG drawing is, unless overridden with
+/// "--cell-size <px>".
+const CELL_SIZE: f64 = 8.0;
+
+/// Run a day's binary, which lives alongside this one, with some arguments, and read its answers
+/// and timings along with everything it printed. A day which panics or returns an error is
+/// reported as failed, with whatever it answered before then.
+fn run_day_with(day: u32, args: &[String]) -> io::Result<(DayReport, String)> {
+    let name = format!("{:02}{}", day, env::consts::EXE_SUFFIX);
+    let binary = env::current_exe()?.with_file_name(&name);
+    if !binary.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "No binary for day {} at {}: build every day first with `cargo build --bins`",
+                day,
+                binary.display()
+            ),
+        ));
+    }
+    // The day is given standard input in case its input is to be read from it.
+    let output = Command::new(binary)
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let mut report = parse_output(day, &stdout);
+    report.notes = day_notes(day);
+    if !output.status.success() {
+        report.failure = Some(output.status.to_string());
+    }
+    Ok((report, stdout))
+}
+
+/// Run a day's binary, which lives alongside this one, and read its answers and timings.
+fn run_day(day: u32) -> io::Result<DayReport> {
+    run_day_with(day, &[]).map(|(report, _)| report)
+}
+
+/// Put some text on the system clipboard.
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(io::Error::other)
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Can't copy to the clipboard: build with --features clipboard",
+    ))
+}
+
+/// Take an option given as "<name> <value>" out of some arguments, if it's there.
+fn take_option(args: &mut Vec<String>, name: &str) -> io::Result<Option<String>> {
+    match args.iter().position(|arg| arg == name) {
+        Some(index) if index + 1 < args.len() => {
+            let value = args.remove(index + 1);
+            args.remove(index);
+            Ok(Some(value))
+        }
+        Some(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Expected a value after {}", name),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Take a flag out of some arguments, returning whether it was there.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != name);
+    args.len() < before
+}
+
+/// Take any lone "-" standing for standard input out of some arguments, returning whether there
+/// was one. A "-" given as the value of "--input" isn't lone, so is left where it is.
+fn take_stdin(args: &mut Vec<String>) -> bool {
+    let mut taken = false;
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == STDIN && (i == 0 || args[i - 1] != "--input") {
+            args.remove(i);
+            taken = true;
+        } else {
+            i += 1;
+        }
+    }
+    taken
+}
+
+/// Show a desktop notification, with `osascript` on macOS and `notify-send` elsewhere.
+fn show_notification(title: &str, body: &str) -> io::Result<()> {
+    let (program, args) = if cfg!(target_os = "macos") {
+        let script = format!("display notification {:?} with title {:?}", body, title);
+        ("osascript", vec!["-e".to_string(), script])
+    } else {
+        ("notify-send", vec![title.to_string(), body.to_string()])
+    };
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|err| io::Error::new(err.kind(), format!("Couldn't run {}: {}", program, err)))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(format!(
+            "{} failed with {}",
+            program, status
+        ))),
+    }
+}
+
+/// How to tell whoever started a long run that it's finished: with a desktop notification with
+/// "--notify", and by posting the answers and timings as JSON to the URL given by
+/// "--webhook <url>".
+#[derive(Default)]
+struct Announce {
+    notify: bool,
+    webhook: Option<String>,
+}
+
+impl Announce {
+    /// Announce the end of a run, with the answers each day gave (or how it failed) and how long it
+    /// all took. Failing to is only warned about, so as not to hide how the run itself went.
+    fn finished(&self, title: &str, reports: &[DayReport], took: Duration) {
+        if self.notify {
+            let answers = reports
+                .iter()
+                .map(|report| {
+                    let answer = |part: usize| match &report.parts[part] {
+                        Some(result) => result.answer.to_string(),
+                        None => "-".to_string(),
+                    };
+                    match &report.failure {
+                        Some(failure) => format!("Day {} failed with {}", report.day, failure),
+                        None => format!("Day {}: {} / {}", report.day, answer(0), answer(1)),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let body = format!("Took {:.1}s\n{}", took.as_secs_f64(), answers);
+            if let Err(err) = show_notification(title, &body) {
+                eprintln!("Couldn't show a notification: {}", err);
+            }
+        }
+        if let Some(url) = &self.webhook {
+            if let Err(err) = aoc2020::post_json(url, &to_json(reports, took)) {
+                eprintln!("Couldn't post to {}: {}", url, err);
+            }
+        }
+    }
+}
+
+/// Run a day, passing it any arguments after the day, and print what it printed. A lone "-" has
+/// it read its input from standard input, as "--input -" would. With "--copy <part>", put the
+/// answer to that part on the clipboard too, ready to paste into the puzzle's answer box, and
+/// announce the day has finished (or failed) as `Announce` does.
+fn run(day: &str, args: &[String]) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let day = day
+        .parse()
+        .ok()
+        .filter(|day| (1..=DAYS).contains(day))
+        .ok_or_else(|| invalid(format!("Invalid day {:?}: expected 1 to {}", day, DAYS)))?;
+    // The day doesn't know about the runner's own options, so isn't given them.
+    let mut day_args = args.to_vec();
+    let copy = match take_option(&mut day_args, "--copy")?.as_deref() {
+        Some("1") => Some(0),
+        Some("2") => Some(1),
+        Some(_) => return Err(invalid("Expected --copy 1 or --copy 2".to_string())),
+        None => None,
+    };
+    let announce = Announce {
+        notify: take_flag(&mut day_args, "--notify"),
+        webhook: take_option(&mut day_args, "--webhook")?,
+    };
+    // An explicit "--input" wins over a lone "-", and "--input -" is passed on as it is.
+    if take_stdin(&mut day_args) && !day_args.iter().any(|arg| arg == "--input") {
+        day_args.extend(["--input".to_string(), STDIN.to_string()]);
+    }
+    let now = Instant::now();
+    let (report, stdout) = run_day_with(day, &day_args)?;
+    print!("{}", stdout);
+    if let Some(failure) = &report.failure {
+        announce.finished(
+            &format!("Day {} failed", day),
+            std::slice::from_ref(&report),
+            now.elapsed(),
+        );
+        return Err(io::Error::other(format!(
+            "Day {} failed with {}",
+            day, failure
+        )));
+    }
+    if let Some(part) = copy {
+        let result = report.parts[part].as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Day {} gave no answer to part {} to copy", day, part + 1),
+            )
+        })?;
+        copy_to_clipboard(&result.answer.to_string())?;
+        eprintln!("Copied {} to the clipboard", result.answer);
+    }
+    announce.finished(&format!("Day {} finished", day), &[report], now.elapsed());
+    Ok(())
+}
+
+/// What a day draws: a grid of cells drawn as characters, or the hexagonal tiles of the lobby
+/// between some corners.
+enum Picture {
+    Grid(Grid<char>),
+    Hexagons(FastSet<Coord>, (Coord, Coord)),
+}
+
+impl Picture {
+    /// Draw the picture as text, a character per cell.
+    fn to_text(&self) -> String {
+        match self {
+            Self::Grid(grid) => grid.render(|&ch| ch),
+            Self::Hexagons(black_tiles, bounds) => render_tiles(black_tiles, *bounds),
+        }
+    }
+
+    /// Draw the picture as SVG, with each cell `size` pixels across.
+    fn to_svg(&self, size: f64, palette: &Palette) -> String {
+        match self {
+            Self::Grid(grid) => svg::squares(grid, size, palette, |&ch| ch),
+            Self::Hexagons(black_tiles, bounds) => {
+                svg::hexagons(*bounds, size / 3f64.sqrt(), palette, |tile| {
+                    if black_tiles.contains(&tile) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// Draw the seats of Day 11 once they stop changing under the rules of part two.
+fn draw_seating(input: &str) -> io::Result<(String, Picture)> {
+    let mut simulation = Simulation::new(
+        &Grid::from_chars(input, Tile::from_ch)?,
+        SeatingRules::PART_TWO,
+    );
+    let caption = match simulation.run() {
+        Some(occupied) => format!("the seats once settled, with {} occupied", occupied),
+        None => "the seats, which never settle".to_string(),
+    };
+    let floorplan = simulation.floorplan().render(Tile::to_ch);
+    Ok((caption, Picture::Grid(Grid::from_chars(&floorplan, Some)?)))
+}
+
+/// Draw the image of Day 20 assembled from its tiles, with their borders stripped.
+fn draw_jigsaw(input: &str) -> io::Result<(String, Picture)> {
+    let tiles = input
+        .split("\n\n")
+        .map(|s| s.parse())
+        .collect::<io::Result<_>>()?;
+    let image = form_image(&assemble(&Puzzle::new(tiles))?)
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|rough| if rough { '#' } else { '.' })
+                .collect()
+        })
+        .collect();
+    Ok((
+        "the assembled image".to_string(),
+        Picture::Grid(Grid::from_rows(image)?),
+    ))
+}
+
+/// Draw the black tiles of the Day 24 lobby after the exhibit has run its course.
+fn draw_lobby(input: &str) -> io::Result<(String, Picture)> {
+    let instructions = input
+        .lines()
+        .map(|line| line.parse())
+        .collect::<io::Result<Vec<Instruction>>>()?;
+    let black_tiles = simulate(
+        get_initial_tiles(&instructions),
+        LOBBY_DAYS,
+        FlipRules::EXHIBIT,
+    );
+    let caption = format!(
+        "the floor after {} days, with {} black tiles",
+        LOBBY_DAYS,
+        black_tiles.len()
+    );
+    let bounds = tile_bounds(black_tiles.iter());
+    Ok((caption, Picture::Hexagons(black_tiles, bounds)))
+}
+
+/// Draws a day's outcome from its input, with a caption saying what it shows.
+type Draw = fn(&str) -> io::Result<(String, Picture)>;
+
+/// The days with something to see, and how to draw each of them.
+const DRAWS: [(u32, Draw); 3] = [(11, draw_seating), (20, draw_jigsaw), (24, draw_lobby)];
+
+/// Draw the days with something to see from their inputs as text, skipping any without one.
+fn draw_visuals() -> io::Result<Vec<Visual>> {
+    let mut visuals = Vec::new();
+    for (day, draw) in DRAWS.iter() {
+        if let Ok(input) = fs::read_to_string(format!("input/{:02}.txt", day)) {
+            eprintln!("Drawing day {}...", day);
+            let (caption, picture) = draw(&input)?;
+            visuals.push(Visual {
+                day: *day,
+                caption,
+                drawing: picture.to_text(),
+            });
+        }
+    }
+    Ok(visuals)
+}
+
+/// Draw a day with something to see from its input as SVG, reading the input from standard input
+/// if given a lone "-".
+fn draw_svg(day: &str) -> io::Result<()> {
+    let draw = day
+        .parse::<u32>()
+        .ok()
+        .and_then(|day| DRAWS.iter().find(|(with, _)| *with == day))
+        .map(|(_, draw)| draw)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Can't draw day {}: expected 11, 20 or 24", day),
+            )
+        })?;
+    let input = match aoc2020::has_flag(STDIN) {
+        true => aoc2020::read_input(STDIN)?,
+        false => get_day_input(day),
+    };
+    let (_, picture) = draw(&input)?;
+    let size = aoc2020::get_option("--cell-size")?.unwrap_or(CELL_SIZE);
+    let palette = aoc2020::get_option("--palette")?.unwrap_or_default();
+    print!("{}", picture.to_svg(size, &palette));
+    Ok(())
+}
+
+/// The session token to sign in to the site with, from "--session <token>" or the AOC_SESSION
+/// environment variable.
+fn session() -> io::Result<Option<String>> {
+    Ok(aoc2020::get_option("--session")?.or_else(|| env::var("AOC_SESSION").ok()))
+}
+
+/// Show a private leaderboard, fetching it signed in with the session token unless it was fetched
+/// in the last 15 minutes.
+fn leaderboard(id: &str) -> io::Result<()> {
+    let json = match leaderboard::cached(id) {
+        Some((json, age)) => {
+            eprintln!(
+                "Fetched {} minutes ago, so not fetching again for another {}",
+                age.as_secs() / 60,
+                (leaderboard::REFRESH - age).as_secs() / 60 + 1
+            );
+            json
+        }
+        None => {
+            let session = session()?.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Expected a session token from --session <token> or AOC_SESSION",
+                )
+            })?;
+            leaderboard::fetch(id, &session)?
+        }
+    };
+    print!("{}", leaderboard::render(&leaderboard::parse(&json)?));
+    Ok(())
+}
+
+/// How many columns puzzle text is wrapped to, unless overridden with "--width <n>".
+const TEXT_WIDTH: usize = 80;
+
+/// Show a day's puzzle, fetching it signed in with the session token (if there is one) unless
+/// it's already cached or given "--refresh", e.g. to see part two once part one is solved.
+fn read(day: &str) -> io::Result<()> {
+    let day = day
+        .parse()
+        .ok()
+        .filter(|day| (1..=DAYS).contains(day))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid day {:?}: expected 1 to {}", day, DAYS),
+            )
+        })?;
+    let html = match fs::read_to_string(puzzle::cache_path(day)) {
+        Ok(html) if !aoc2020::has_flag("--refresh") => html,
+        _ => puzzle::fetch(day, session()?.as_deref())?,
+    };
+    let width = aoc2020::get_option("--width")?.unwrap_or(TEXT_WIDTH);
+    let descriptions = puzzle::descriptions(&html);
+    if descriptions.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("No puzzle found for day {}", day),
+        ));
+    }
+    for description in descriptions {
+        println!("{}", puzzle::render(description, width));
+    }
+    Ok(())
+}
+
+/// Evaluate an expression of the Day 18 homework maths, by the precedence of the part given by
+/// "--precedence one|two", or of both parts if not given.
+fn eval(expr: &str) -> io::Result<()> {
+    match aoc2020::get_option::<PrecedenceTable>("--precedence")? {
+        Some(precedence) => println!("{}", homework::eval(expr, &precedence)?),
+        None => {
+            let one = homework::eval(expr, &PrecedenceTable::PART_ONE)?;
+            let two = homework::eval(expr, &PrecedenceTable::PART_TWO)?;
+            println!("Part one: {}", one);
+            println!("Part two: {}", two);
+        }
+    }
+    Ok(())
+}
+
+/// Run every day with an input and write up what each reported, then announce the run has
+/// finished. Any days which failed are marked as such, and fail the report once it's written.
+fn report(format: Format, announce: &Announce) -> io::Result<()> {
+    let now = Instant::now();
+    let mut reports = Vec::new();
+    for day in 1..=DAYS {
+        if !Path::new(&format!("input/{:02}.txt", day)).exists() {
+            eprintln!("Skipping day {}, which has no input", day);
+            continue;
+        }
+        eprintln!("Running day {}...", day);
+        reports.push(run_day(day)?);
+    }
+    let took = now.elapsed();
+    match format {
+        Format::Markdown => print!("{}", to_markdown(&reports)),
+        Format::Html => print!("{}", to_html(&reports, &draw_visuals()?)),
+    }
+    let failures = reports
+        .iter()
+        .filter_map(|report| {
+            let failure = report.failure.as_ref()?;
+            Some(format!("Day {} failed with {}", report.day, failure))
+        })
+        .collect::<Vec<_>>();
+    let title = match failures.len() {
+        0 => "Every day finished".to_string(),
+        1 => "1 day failed".to_string(),
+        failed => format!("{} days failed", failed),
+    };
+    announce.finished(&title, &reports, took);
+    match failures.is_empty() {
+        true => Ok(()),
+        false => Err(io::Error::other(failures.join("; "))),
+    }
+}
+
+fn main() -> Result<(), io::Error> {
+    match env::args().nth(1).as_deref() {
+        // Run a single day with "run <day>", reading its input from standard input with "-" and
+        // copying an answer with "--copy <part>".
+        Some("run") => match env::args().nth(2) {
+            Some(day) => run(&day, &env::args().skip(3).collect::<Vec<_>>()),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Usage: aoc run <day>",
+            )),
+        },
+        // Write a table of every day's answers and timings with "report --format md", or a page
+        // with drawings of some days too with "report --format html". Either can be announced
+        // when finished with "--notify" and "--webhook <url>".
+        Some("report") => report(
+            aoc2020::get_option("--format")?.unwrap_or(Format::Markdown),
+            &Announce {
+                notify: aoc2020::has_flag("--notify"),
+                webhook: aoc2020::get_option("--webhook")?,
+            },
+        ),
+        // Show a private leaderboard with "leaderboard <id>".
+        Some("leaderboard") => match env::args().nth(2) {
+            Some(id) => leaderboard(&id),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Usage: aoc leaderboard <id>",
+            )),
+        },
+        // Show a day's puzzle with "read <day>".
+        Some("read") => match env::args().nth(2) {
+            Some(day) => read(&day),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Usage: aoc read <day>",
+            )),
+        },
+        // Evaluate an expression of the homework maths with "eval <expression>", by the
+        // precedence of one part with "--precedence one|two".
+        Some("eval") => match env::args().nth(2) {
+            Some(expr) => eval(&expr),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Usage: aoc eval <expression>",
+            )),
+        },
+        // Draw day 11, 20 or 24 as SVG with "draw <day>" (from standard input with "-"),
+        // colouring the cells of each character they'd be drawn as in text with
+        // "--palette <char>=<colour>,...".
+        Some("draw") => match env::args().nth(2) {
+            Some(day) => draw_svg(&day),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Usage: aoc draw <day>",
+            )),
+        },
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Usage: aoc run <day> [-] [--copy 1|2] | aoc report [--format md|html] \
+             (either with [--notify] [--webhook <url>]) | \
+             aoc leaderboard <id> [--session <token>] | \
+             aoc read <day> [--refresh] [--width <n>] [--session <token>] | \
+             aoc eval <expression> [--precedence one|two] | \
+             aoc draw <day> [-] [--cell-size <px>] [--palette <char>=<colour>,...]",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_stdin() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        let mut lone = args(&["-", "--stream"]);
+        assert!(take_stdin(&mut lone));
+        assert_eq!(lone, args(&["--stream"]));
+
+        let mut input = args(&["--input", "-", "--stream"]);
+        assert!(!take_stdin(&mut input));
+        assert_eq!(input, args(&["--input", "-", "--stream"]));
+
+        let mut both = args(&["--input", "-", "-"]);
+        assert!(take_stdin(&mut both));
+        assert_eq!(both, args(&["--input", "-"]));
+    }
+}
@@ -0,0 +1,99 @@
+/// A fixed-length set of bits packed into 64-bit words, for maps of yes-or-no tiles which are
+/// only ever tested and counted.
+use std::iter::FromIterator;
+
+const WORD_BITS: usize = 64;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    /// A set of `len` bits, all clear.
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(WORD_BITS)],
+            len,
+        }
+    }
+
+    /// The number of bits in the set, set or clear.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the bit at an index is set. Panics if the index is out of range.
+    pub fn contains(&self, index: usize) -> bool {
+        assert!(index < self.len, "Bit {} out of range", index);
+        self.words[index / WORD_BITS] & 1 << (index % WORD_BITS) != 0
+    }
+
+    /// Set the bit at an index. Panics if the index is out of range.
+    pub fn insert(&mut self, index: usize) {
+        assert!(index < self.len, "Bit {} out of range", index);
+        self.words[index / WORD_BITS] |= 1 << (index % WORD_BITS);
+    }
+
+    /// The number of bits set.
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The number of bits set in both this set and another of the same length, by popcounting
+    /// each word masked by the other's.
+    pub fn count_common(&self, mask: &BitSet) -> usize {
+        assert_eq!(self.len, mask.len, "Can only mask sets of the same length");
+        self.words
+            .iter()
+            .zip(&mask.words)
+            .map(|(word, mask)| (word & mask).count_ones() as usize)
+            .sum()
+    }
+}
+
+impl FromIterator<bool> for BitSet {
+    /// Set the bits of a set as long as the iterator, one per item which is true.
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut words = Vec::new();
+        let mut len = 0;
+        for bit in iter {
+            if len % WORD_BITS == 0 {
+                words.push(0);
+            }
+            if bit {
+                words[len / WORD_BITS] |= 1 << (len % WORD_BITS);
+            }
+            len += 1;
+        }
+        Self { words, len }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits() {
+        let bits: BitSet = (0..130).map(|index| index % 3 == 0).collect();
+        assert_eq!(bits.len(), 130);
+        assert_eq!(bits.count_ones(), 44);
+        assert!(bits.contains(0) && bits.contains(129) && !bits.contains(64));
+
+        let mut mask = BitSet::new(130);
+        for &index in &[0, 1, 64, 66, 129] {
+            mask.insert(index);
+        }
+        assert_eq!(mask, (0..130).map(|index| mask.contains(index)).collect());
+        assert_eq!(bits.count_common(&mask), 3);
+    }
+}
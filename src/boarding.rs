@@ -0,0 +1,138 @@
+/// Binary space partitioned boarding passes of Day 5.
+use std::collections::HashSet;
+use std::io;
+use std::str::FromStr;
+
+/// The number of bits of a boarding pass code giving the row on the puzzle's plane.
+pub const ROW_BITS: u32 = 7;
+/// The number of bits of a boarding pass code giving the column on the puzzle's plane.
+pub const COL_BITS: u32 = 3;
+
+/// A seat on the plane, as decoded from a boarding pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Seat {
+    pub row: usize,
+    pub col: usize,
+    /// The seat ID, which is the whole code read as a binary number.
+    pub id: usize,
+}
+
+/// Decode a boarding pass code for a plane with `2^row_bits` rows of `2^col_bits` seats.
+///
+/// The code is `row_bits` characters of F (front, 0) or B (back, 1) followed by `col_bits`
+/// characters of L (left, 0) or R (right, 1), so the whole code is the seat ID as a binary number.
+pub fn decode_seat(code: &str, row_bits: u32, col_bits: u32) -> io::Result<Seat> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let bits = row_bits + col_bits;
+    if bits >= usize::BITS {
+        return Err(invalid(format!("A {} bit seat ID is too large", bits)));
+    }
+    if code.len() != bits as usize {
+        return Err(invalid(format!(
+            "Boarding pass {:?} is not {} characters long",
+            code, bits
+        )));
+    }
+
+    let id = code.chars().enumerate().try_fold(0, |id, (i, ch)| {
+        let bit = match (i < row_bits as usize, ch) {
+            (true, 'F') | (false, 'L') => 0,
+            (true, 'B') | (false, 'R') => 1,
+            _ => return Err(invalid(format!("Invalid boarding pass {:?}", code))),
+        };
+        Ok(id << 1 | bit)
+    })?;
+
+    Ok(Seat {
+        row: id >> col_bits,
+        col: id & ((1 << col_bits) - 1),
+        id,
+    })
+}
+
+/// Decode a boarding pass for the puzzle's plane of 128 rows of 8 seats.
+impl FromStr for Seat {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        decode_seat(s, ROW_BITS, COL_BITS)
+    }
+}
+
+/// Decode the boarding passes for a plane of the given geometry.
+pub fn decode_seats(input: &str, row_bits: u32, col_bits: u32) -> io::Result<Vec<Seat>> {
+    input
+        .lines()
+        .map(|s| decode_seat(s, row_bits, col_bits))
+        .collect()
+}
+
+/// Find the highest seat ID on any of the boarding passes, for part one.
+pub fn highest_id(seats: &[Seat]) -> Option<usize> {
+    seats.iter().map(|seat| seat.id).max()
+}
+
+/// Find the free seat IDs with the seats either side of them taken, in order: there should be
+/// exactly one, which is our seat.
+pub fn find_gaps(seats: &[Seat]) -> Vec<usize> {
+    let ids: HashSet<_> = seats.iter().map(|seat| seat.id).collect();
+    // For each taken seat, check if the seat two seats over is taken, but the seat one over is not.
+    let mut gaps: Vec<usize> = ids
+        .iter()
+        .filter(|id| !ids.contains(&(*id + 1)) && ids.contains(&(*id + 2)))
+        .map(|id| *id + 1)
+        .collect();
+    gaps.sort_unstable();
+    gaps
+}
+
+/// Find our seat's ID, the first of the gaps, for part two.
+pub fn find_my_seat(seats: &[Seat]) -> Option<usize> {
+    find_gaps(seats).first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_seat() {
+        let seat = decode_seat("FBFBBFFRLR", ROW_BITS, COL_BITS).unwrap();
+        assert_eq!((seat.row, seat.col, seat.id), (44, 5, 357));
+
+        // A smaller plane with 4 rows of 4 seats.
+        let seat = decode_seat("BFRL", 2, 2).unwrap();
+        assert_eq!((seat.row, seat.col, seat.id), (2, 2, 10));
+
+        assert!(decode_seat("FBFBBFFRL", ROW_BITS, COL_BITS).is_err());
+        assert!(decode_seat("FBFBBFRRLR", ROW_BITS, COL_BITS).is_err());
+    }
+
+    #[test]
+    fn test_given_example() {
+        let input = "FBFBBFFRLR
+BFFFBBFRRR
+FFFBBBFRRR
+BBFFBBFRLL";
+        let data = decode_seats(input, ROW_BITS, COL_BITS).expect("Couldn't convert test input");
+
+        // Check all of the boarding pass data calculated correctly.
+        assert_eq!(data[0].row, 44);
+        assert_eq!(data[0].col, 5);
+        assert_eq!(data[0].id, 357);
+
+        assert_eq!(data[1].row, 70);
+        assert_eq!(data[1].col, 7);
+        assert_eq!(data[1].id, 567);
+
+        assert_eq!(data[2].row, 14);
+        assert_eq!(data[2].col, 7);
+        assert_eq!(data[2].id, 119);
+
+        assert_eq!(data[3].row, 102);
+        assert_eq!(data[3].col, 4);
+        assert_eq!(data[3].id, 820);
+
+        assert_eq!(highest_id(&data), Some(820));
+    }
+}
@@ -0,0 +1,174 @@
+/// The shuttle bus schedule of Day 13, and when the buses line up.
+use crate::checked::{self, Overflow};
+use crate::modular::{crt, gcd, sieve, Congruence};
+use itertools::Itertools;
+use std::io;
+
+/// Wide enough for the timestamps of schedules with many large bus IDs, whose product can be far
+/// beyond 64 bits.
+pub type Number = u128;
+
+/// The earliest timestamp we could depart at, and the IDs of the buses in service, with 0 for
+/// each bus out of service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepartureTarget {
+    pub timestamp: Number,
+    pub buses: Vec<Number>,
+}
+
+/// The ID of the first bus we can take times how long we wait for it, for part one.
+pub fn earliest_bus(data: &DepartureTarget) -> Result<Option<Number>, Overflow> {
+    // Minimise the possible time remainder from our timestamp to the next bus departure for each
+    // bus: each bus can be a maximum of its ID later than our timestamp at the airport so find the
+    // multiple of it which is between our timestamp and our timestamp plus its ID, and get the
+    // difference.
+    let buses: Vec<&Number> = data.buses.iter().filter(|&num| *num != 0).collect();
+    let mut remainders: Vec<Number> = Vec::new();
+    for &id in &buses {
+        let needed_multiples = (data.timestamp / id) + 1;
+        remainders.push(checked::mul(needed_multiples, *id)? - data.timestamp);
+    }
+    let min = match remainders.iter().min() {
+        Some(&min) => min,
+        None => return Ok(None),
+    };
+    let id = buses[remainders.iter().position(|&item| item == min).unwrap()];
+    Ok(Some(checked::mul(min, *id)?))
+}
+
+/// The congruences the timestamp of part two must satisfy.
+pub fn congruences(data: &DepartureTarget) -> Vec<Congruence> {
+    // Bus `id` at offset `i` must depart at t + i, so t ≡ -i (mod id).
+    data.buses
+        .iter()
+        .enumerate()
+        .filter(|(_, &id)| id != 0)
+        .map(|(i, &id)| ((id - i as Number % id) % id, id))
+        .collect()
+}
+
+/// The earliest timestamp at which each bus departs as many minutes after it as its offset in
+/// the list, by the Chinese remainder theorem, for part two.
+pub fn earliest_lineup(data: &DepartureTarget) -> Result<Option<Number>, Overflow> {
+    Ok(crt(&congruences(data))?.map(|(timestamp, _)| timestamp))
+}
+
+/// Find the timestamp of part two by sieving through the departures rather than with the Chinese
+/// remainder theorem. Sieving by the largest bus IDs first narrows the search fastest.
+pub fn earliest_lineup_by_sieve(data: &DepartureTarget) -> Result<Option<Number>, Overflow> {
+    let mut congruences = congruences(data);
+    congruences.sort_unstable_by_key(|&(_, id)| std::cmp::Reverse(id));
+    Ok(sieve(&congruences)?.map(|(timestamp, _)| timestamp))
+}
+
+/// Find the pairs of bus IDs which share a factor, for which part two may have no solution.
+pub fn shared_factors(data: &DepartureTarget) -> Vec<(Number, Number)> {
+    data.buses
+        .iter()
+        .filter(|&&id| id != 0)
+        .tuple_combinations()
+        .filter(|&(&a, &b)| gcd(a, b) != 1)
+        .map(|(&a, &b)| (a, b))
+        .collect()
+}
+
+/// Parse the earliest timestamp from the first line and the bus IDs from the second, with "x" for
+/// a bus out of service.
+pub fn parse_schedule(input: &str) -> io::Result<DepartureTarget> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Expected a timestamp and a list of bus IDs",
+        )
+    };
+    let (timestamp, buses) = input.lines().next_tuple().ok_or_else(invalid)?;
+    Ok(DepartureTarget {
+        timestamp: timestamp.parse().map_err(|_| invalid())?,
+        buses: buses
+            .split(',')
+            .map(|num| if num == "x" { "0" } else { num })
+            .map(|num| num.parse().map_err(|_| invalid()))
+            .collect::<io::Result<_>>()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_example() {
+        let input = "939
+7,13,x,x,59,x,31,19";
+        let data = parse_schedule(input).expect("Couldn't convert test input");
+
+        // Assert get the right number.
+        assert_eq!(earliest_bus(&data), Ok(Some(59 * 5)));
+        assert_eq!(earliest_lineup(&data), Ok(Some(1068781)));
+    }
+
+    #[test]
+    fn test_part_two_examples() {
+        let inputs: [String; 5] = [
+            "0
+17,x,13,19"
+                .to_string(),
+            "0
+67,7,59,61"
+                .to_string(),
+            "0
+67,x,7,59,61"
+                .to_string(),
+            "0
+67,7,x,59,61"
+                .to_string(),
+            "0
+1789,37,47,1889"
+                .to_string(),
+        ];
+
+        let answers: [Number; 5] = [3417, 754018, 779210, 1261476, 1202161486];
+
+        for (input, answer) in inputs.iter().zip(answers.iter()) {
+            let data = parse_schedule(input).expect("Couldn't convert test input");
+
+            assert_eq!(earliest_lineup(&data), Ok(Some(*answer)));
+            assert_eq!(earliest_lineup_by_sieve(&data), Ok(Some(*answer)));
+        }
+    }
+
+    #[test]
+    fn test_shared_factors() {
+        // Buses 4 and 6 both leave at even times, so can't leave a minute apart.
+        let data = parse_schedule("0\n4,6,5").unwrap();
+        assert_eq!(shared_factors(&data), vec![(4, 6)]);
+        assert_eq!(earliest_lineup(&data), Ok(None));
+        assert_eq!(earliest_lineup_by_sieve(&data), Ok(None));
+
+        // But they can leave two minutes apart, every twelve minutes.
+        let data = parse_schedule("0\n4,x,6,5").unwrap();
+        assert_eq!(earliest_lineup(&data), Ok(Some(52)));
+        assert_eq!(earliest_lineup_by_sieve(&data), Ok(Some(52)));
+    }
+
+    #[test]
+    fn test_beyond_64_bits() {
+        let data = parse_schedule("0\n1000000007,998244353,x,1000000009").unwrap();
+        let timestamp = earliest_lineup(&data).unwrap().unwrap();
+        assert!(timestamp > u64::MAX as Number);
+        assert_eq!(timestamp % 1000000007, 0);
+        assert_eq!((timestamp + 1) % 998244353, 0);
+        assert_eq!((timestamp + 3) % 1000000009, 0);
+    }
+
+    #[test]
+    fn test_overflow_reported() {
+        // The next departure of bus 7 is beyond the largest timestamp.
+        let data = parse_schedule(&format!("{}\n7", Number::MAX - 1)).unwrap();
+        assert_eq!(earliest_bus(&data), Err(Overflow));
+
+        // The buses only line up every five times the first bus ID (2^127 - 1), which is too big.
+        let data = parse_schedule("0\n170141183460469231731687303715884105727,5").unwrap();
+        assert_eq!(earliest_lineup(&data), Err(Overflow));
+    }
+}
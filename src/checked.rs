@@ -0,0 +1,90 @@
+/// Overflow-checked arithmetic for accumulating large answers.
+///
+/// Plain integer arithmetic silently wraps in release builds, which turns an unusual input into a
+/// plausible-looking wrong answer. These helpers report the overflow as an error instead.
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// The error for a calculation which does not fit in its integer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Arithmetic overflow")
+    }
+}
+
+impl Error for Overflow {}
+
+impl From<Overflow> for io::Error {
+    fn from(err: Overflow) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// An integer type with overflow-checked addition and multiplication.
+pub trait CheckedNum: Copy {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_num {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedNum for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_num!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Add two numbers, erroring on overflow.
+pub fn add<T: CheckedNum>(lhs: T, rhs: T) -> Result<T, Overflow> {
+    lhs.checked_add(rhs).ok_or(Overflow)
+}
+
+/// Multiply two numbers, erroring on overflow.
+pub fn mul<T: CheckedNum>(lhs: T, rhs: T) -> Result<T, Overflow> {
+    lhs.checked_mul(rhs).ok_or(Overflow)
+}
+
+/// Sum a sequence of numbers, erroring on overflow.
+pub fn sum<T: CheckedNum, I: IntoIterator<Item = T>>(iter: I) -> Result<T, Overflow> {
+    iter.into_iter().try_fold(T::ZERO, add)
+}
+
+/// Multiply together a sequence of numbers, erroring on overflow.
+pub fn product<T: CheckedNum, I: IntoIterator<Item = T>>(iter: I) -> Result<T, Overflow> {
+    iter.into_iter().try_fold(T::ONE, mul)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overflow_reported() {
+        assert_eq!(product(vec![2u32, 3, 7]), Ok(42));
+        assert_eq!(sum(vec![1u8, 2, 3]), Ok(6));
+        assert_eq!(product(vec![u32::MAX, 2]), Err(Overflow));
+        assert_eq!(sum(vec![u8::MAX, 1]), Err(Overflow));
+        assert_eq!(mul(1u64 << 32, 1 << 32), Err(Overflow));
+        assert_eq!(add(i32::MAX, -1), Ok(i32::MAX - 1));
+    }
+}
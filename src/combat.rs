@@ -0,0 +1,775 @@
+/// Game engine for the (Recursive) Combat card game of Day 22.
+use crate::fasthash::FastSet;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::iter::FromIterator;
+
+/// Every card in the puzzle is a small positive number, so a byte holds one.
+pub type Card = u8;
+
+/// A deck of cards which can be drawn from the top and added to at the bottom.
+pub trait Deck: Clone + Eq + Hash + FromIterator<Card> {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Take the card from the top of the deck.
+    fn draw(&mut self) -> Option<Card>;
+
+    /// Place a card at the bottom of the deck.
+    fn push(&mut self, card: Card);
+
+    /// Copy the top `n` cards of the deck to form the deck for a sub-game.
+    fn sub_deck(&self, n: usize) -> Self;
+
+    /// The cards in the deck from top to bottom.
+    fn cards(&self) -> Vec<Card>;
+
+    /// The highest card in the deck.
+    fn max_card(&self) -> Option<Card> {
+        self.cards().into_iter().max()
+    }
+
+    /// A hash of the cards in the deck in order, for telling whether a round has been seen before
+    /// without keeping a copy of every deck. Different decks can share one, but as it's 64 bits
+    /// they are vanishingly unlikely to within a game.
+    fn fingerprint(&self) -> u64 {
+        self.cards()
+            .iter()
+            .fold(0, |hash, &card| push_fingerprint(hash, card))
+    }
+
+    /// Score the deck: the bottom card is worth its value, the next its value times two, etc.
+    fn score(&self) -> u32 {
+        self.cards()
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, &card)| (i as u32 + 1) * card as u32)
+            .sum()
+    }
+}
+
+/// The base of the polynomial the fingerprint of a deck is, with the top card the highest power.
+/// Any odd number works as a base, but a large one mixes the cards into every bit.
+const FINGERPRINT_BASE: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The fingerprint of a deck once a card is placed at the bottom. Each card counts as one more
+/// than its value, so a deck with a 0 card at the top differs from one without it.
+fn push_fingerprint(hash: u64, card: Card) -> u64 {
+    hash.wrapping_mul(FINGERPRINT_BASE)
+        .wrapping_add(card as u64 + 1)
+}
+
+impl Deck for VecDeque<Card> {
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn draw(&mut self) -> Option<Card> {
+        self.pop_front()
+    }
+
+    fn push(&mut self, card: Card) {
+        self.push_back(card);
+    }
+
+    fn sub_deck(&self, n: usize) -> Self {
+        // The sub-game can never hold more cards than this game, so with as much room as this deck
+        // has it never has to grow.
+        let mut deck = VecDeque::with_capacity(self.capacity());
+        deck.extend(self.iter().take(n));
+        deck
+    }
+
+    fn cards(&self) -> Vec<Card> {
+        self.iter().cloned().collect()
+    }
+}
+
+/// The most cards an `ArrayDeck` can hold: a power of two so that wrapping is a mask.
+pub const ARRAY_DECK_CAPACITY: usize = 64;
+
+/// Every power of the fingerprint base an `ArrayDeck` needs, so the top card can be taken out
+/// of its fingerprint.
+const FINGERPRINT_POWERS: [u64; ARRAY_DECK_CAPACITY] = {
+    let mut powers = [1u64; ARRAY_DECK_CAPACITY];
+    let mut i = 1;
+    while i < ARRAY_DECK_CAPACITY {
+        powers[i] = powers[i - 1].wrapping_mul(FINGERPRINT_BASE);
+        i += 1;
+    }
+    powers
+};
+
+/// A fixed-capacity, array-backed ring buffer deck.
+///
+/// There are only 50 cards in the puzzle, so the whole deck fits in a small array which can be
+/// copied on the stack rather than cloned onto the heap for each sub-game. Its fingerprint is kept
+/// up to date as cards are drawn and placed, rather than worked out from every card each round.
+#[derive(Clone, Copy, Debug)]
+pub struct ArrayDeck {
+    cards: [Card; ARRAY_DECK_CAPACITY],
+    start: usize,
+    len: usize,
+    fingerprint: u64,
+}
+
+impl ArrayDeck {
+    pub fn new() -> Self {
+        Self {
+            cards: [0; ARRAY_DECK_CAPACITY],
+            start: 0,
+            len: 0,
+            fingerprint: 0,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = Card> + '_ {
+        (0..self.len).map(move |i| self.cards[(self.start + i) % ARRAY_DECK_CAPACITY])
+    }
+}
+
+impl Default for ArrayDeck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deck for ArrayDeck {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn draw(&mut self) -> Option<Card> {
+        if self.len == 0 {
+            return None;
+        }
+        let card = self.cards[self.start];
+        self.start = (self.start + 1) % ARRAY_DECK_CAPACITY;
+        self.len -= 1;
+        let top = (card as u64 + 1).wrapping_mul(FINGERPRINT_POWERS[self.len]);
+        self.fingerprint = self.fingerprint.wrapping_sub(top);
+        Some(card)
+    }
+
+    fn push(&mut self, card: Card) {
+        assert!(self.len < ARRAY_DECK_CAPACITY, "ArrayDeck is full");
+        self.cards[(self.start + self.len) % ARRAY_DECK_CAPACITY] = card;
+        self.len += 1;
+        self.fingerprint = push_fingerprint(self.fingerprint, card);
+    }
+
+    fn sub_deck(&self, n: usize) -> Self {
+        // Copy the whole buffer and truncate, which is cheaper than pushing each card.
+        let mut deck = *self;
+        deck.len = n.min(self.len);
+        deck.fingerprint = deck.iter().fold(0, push_fingerprint);
+        deck
+    }
+
+    fn cards(&self) -> Vec<Card> {
+        self.iter().collect()
+    }
+
+    fn max_card(&self) -> Option<Card> {
+        self.iter().max()
+    }
+
+    fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+}
+
+impl FromIterator<Card> for ArrayDeck {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> Self {
+        let mut deck = Self::new();
+        for card in iter {
+            deck.push(card);
+        }
+        deck
+    }
+}
+
+// Two decks are the same if they hold the same cards in the same order, regardless of where in
+// the ring buffer they happen to sit.
+impl PartialEq for ArrayDeck {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for ArrayDeck {}
+
+impl Hash for ArrayDeck {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for card in self.iter() {
+            card.hash(state);
+        }
+    }
+}
+
+/// Parse the two players' starting decks, each given as a "Player N:" header and a card per line.
+///
+/// Each deck has room for every card in the game up front, so neither has to grow as it's played.
+pub fn parse_decks(input: &str) -> io::Result<[VecDeque<Card>; 2]> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let decks = input
+        .trim()
+        .split("\n\n")
+        .map(|player| {
+            let (_, cards) = player
+                .split_once(":\n")
+                .ok_or_else(|| invalid("Missing player header"))?;
+            cards
+                .lines()
+                .map(|s| s.parse().map_err(|_| invalid("Invalid card")))
+                .collect()
+        })
+        .collect::<io::Result<Vec<VecDeque<Card>>>>()?;
+    let game_cards = decks.iter().map(VecDeque::len).sum();
+    match <[VecDeque<Card>; 2]>::try_from(decks) {
+        Ok(decks) => Ok(decks.map(|cards| {
+            let mut deck = VecDeque::with_capacity(game_cards);
+            deck.extend(cards);
+            deck
+        })),
+        Err(_) => Err(invalid("Expected exactly two decks")),
+    }
+}
+
+/// Play a game of Combat, returning whether player 1 wins.
+pub fn play_combat<D: Deck>(deck1: &mut D, deck2: &mut D) -> bool {
+    while !deck1.is_empty() && !deck2.is_empty() {
+        let card1 = deck1.draw().unwrap();
+        let card2 = deck2.draw().unwrap();
+        if card1 > card2 {
+            deck1.push(card1);
+            deck1.push(card2);
+        } else {
+            deck2.push(card2);
+            deck2.push(card1);
+        }
+    }
+    !deck1.is_empty()
+}
+
+/// Watches a game of Recursive Combat as it is played, e.g. to record a transcript of it.
+///
+/// Every method does nothing unless overridden, and the engine is generic over the observer, so
+/// playing with `()` compiles to the same code as not watching at all.
+pub trait Observer {
+    /// A game starts, either the first or a sub-game.
+    fn game_start(&mut self) {}
+
+    /// A round starts with these decks, before each player draws their top card.
+    fn round_start<D: Deck>(&mut self, _deck1: &D, _deck2: &D) {}
+
+    /// The decks are the same as at the start of an earlier round of the game, so it ends.
+    fn repeated(&mut self) {}
+
+    /// Both players have at least as many cards left as the cards they drew, so a sub-game decides
+    /// the round.
+    fn sub_game(&mut self) {}
+
+    fn round_end(&mut self, _player1_wins: bool) {}
+
+    fn game_end<D: Deck>(&mut self, _deck1: &D, _deck2: &D, _player1_wins: bool) {}
+}
+
+impl Observer for () {}
+
+/// Optional rules which change how a game is played but never who wins it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rules {
+    /// Decide a sub-game without playing it when player 1 holds the highest card, as long as that
+    /// card is too high to ever start a sub-game of its own. Player 1 then can never lose it (it
+    /// beats every card player 2 can play), so the sub-game ends either with player 2 out of cards
+    /// or with a repeat, and player 1 wins both ways.
+    pub shortcut_sub_games: bool,
+}
+
+/// Whether player 1 is sure to win a game starting from these decks, without playing it.
+fn player1_sure_to_win<D: Deck>(deck1: &D, deck2: &D) -> bool {
+    match (deck1.max_card(), deck2.max_card()) {
+        (Some(max1), Some(max2)) => {
+            // A card drawn can start a sub-game only if there are as many cards left in the
+            // drawer's deck, which can be at most every card but the two in play.
+            max1 > max2 && max1 as usize > deck1.len() + deck2.len() - 2
+        }
+        _ => false,
+    }
+}
+
+/// Play the round of Recursive Combat.
+///
+/// Returns whether the round means the end of the game for player 1 due to a recursion-stop or
+/// because the decks have run out of cards.
+fn play_round<D: Deck, O: Observer>(
+    deck1: &mut D,
+    deck2: &mut D,
+    rounds_seen: &mut FastSet<(u64, u64)>,
+    rules: Rules,
+    observer: &mut O,
+) -> bool {
+    if deck1.is_empty() || deck2.is_empty() {
+        return true;
+    }
+
+    // If this round has been seen the win is for player 1, otherwise record the new matchup.
+    if !rounds_seen.insert((deck1.fingerprint(), deck2.fingerprint())) {
+        observer.repeated();
+        return true;
+    }
+
+    observer.round_start(deck1, deck2);
+    let card1 = deck1.draw().unwrap();
+    let card2 = deck2.draw().unwrap();
+
+    let player1_wins = if deck1.len() >= card1 as usize && deck2.len() >= card2 as usize {
+        let mut subdeck1 = deck1.sub_deck(card1 as usize);
+        let mut subdeck2 = deck2.sub_deck(card2 as usize);
+        if rules.shortcut_sub_games && player1_sure_to_win(&subdeck1, &subdeck2) {
+            // The observer is only told about sub-games which are actually played.
+            true
+        } else {
+            observer.sub_game();
+            play_recursive_combat_with(&mut subdeck1, &mut subdeck2, rules, observer)
+        }
+    } else {
+        card1 > card2
+    };
+    observer.round_end(player1_wins);
+
+    if player1_wins {
+        deck1.push(card1);
+        deck1.push(card2);
+    } else {
+        deck2.push(card2);
+        deck2.push(card1);
+    }
+
+    false
+}
+
+/// Play the game of Recursive Combat with the two starting decks.
+///
+/// Returns if player1 wins by the criteria that player1 has cards left.
+pub fn play_recursive_combat<D: Deck>(deck1: &mut D, deck2: &mut D) -> bool {
+    play_recursive_combat_with(deck1, deck2, Rules::default(), &mut ())
+}
+
+/// Play the game of Recursive Combat with the two starting decks by the given optional rules,
+/// telling an observer about every game and round.
+pub fn play_recursive_combat_with<D: Deck, O: Observer>(
+    deck1: &mut D,
+    deck2: &mut D,
+    rules: Rules,
+    observer: &mut O,
+) -> bool {
+    observer.game_start();
+    // Keep a cache of rounds which have already been played: if the round is seen, the game will
+    // end for player1.
+    let mut rounds_seen: FastSet<(u64, u64)> = FastSet::default();
+    let mut end_game = false;
+
+    while !end_game {
+        end_game = play_round(deck1, deck2, &mut rounds_seen, rules, observer);
+    }
+
+    let player1_wins = !deck1.is_empty();
+    observer.game_end(deck1, deck2, player1_wins);
+    player1_wins
+}
+
+/// Play a game of Combat with the starting decks, giving the winning hand's score, for part one.
+pub fn combat_score(decks: &[VecDeque<Card>; 2]) -> u32 {
+    // Copy the decks with all the room they were parsed with, which cloning would drop.
+    let mut deck1 = decks[0].sub_deck(decks[0].len());
+    let mut deck2 = decks[1].sub_deck(decks[1].len());
+
+    let winning_hand = match play_combat(&mut deck1, &mut deck2) {
+        true => &deck1,
+        false => &deck2,
+    };
+
+    winning_hand.score()
+}
+
+/// Play a game of Recursive Combat with the starting decks held as `D`, giving the winning hand's
+/// score.
+pub fn score_recursive_combat<D: Deck, O: Observer>(
+    decks: &[VecDeque<Card>; 2],
+    rules: Rules,
+    observer: &mut O,
+) -> u32 {
+    let mut deck1: D = decks[0].iter().cloned().collect();
+    let mut deck2: D = decks[1].iter().cloned().collect();
+
+    match play_recursive_combat_with(&mut deck1, &mut deck2, rules, observer) {
+        true => deck1.score(),
+        false => deck2.score(),
+    }
+}
+
+/// Play a game of Recursive Combat with the starting decks, giving the winning hand's score, for
+/// part two.
+///
+/// The decks are copied many times over into sub-games, so use the array-backed deck which can be
+/// copied on the stack, unless there are too many cards for it to hold.
+pub fn recursive_combat_score<O: Observer>(
+    decks: &[VecDeque<Card>; 2],
+    rules: Rules,
+    observer: &mut O,
+) -> u32 {
+    match decks[0].len() + decks[1].len() <= ARRAY_DECK_CAPACITY {
+        true => score_recursive_combat::<ArrayDeck, _>(decks, rules, observer),
+        false => score_recursive_combat::<VecDeque<Card>, _>(decks, rules, observer),
+    }
+}
+
+/// Something that happened in a game of Recursive Combat, where games are numbered in the order
+/// they start and rounds in the order they're played within their game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    GameStart {
+        game: usize,
+    },
+    /// A round starts with these decks, and each player plays the top card of theirs.
+    Round {
+        game: usize,
+        round: usize,
+        deck1: Vec<Card>,
+        deck2: Vec<Card>,
+    },
+    Repeat {
+        game: usize,
+    },
+    /// A sub-game is about to decide the round being played.
+    SubGame,
+    RoundEnd {
+        game: usize,
+        round: usize,
+        player1_wins: bool,
+    },
+    /// A game ends with these decks, and play goes back to the game it's a sub-game of, if any.
+    GameEnd {
+        game: usize,
+        player1_wins: bool,
+        deck1: Vec<Card>,
+        deck2: Vec<Card>,
+        parent: Option<usize>,
+    },
+}
+
+/// A record of everything that happens in a game of Recursive Combat, as a list of events.
+#[derive(Clone, Debug, Default)]
+pub struct Transcript {
+    pub events: Vec<Event>,
+    games: usize,
+    /// The game being played and its round so far, for each game which has started but not ended.
+    playing: Vec<(usize, usize)>,
+}
+
+impl Transcript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write out the game as the puzzle's own worked example does.
+    pub fn replay(&self) -> String {
+        let player = |player1_wins| if player1_wins { 1 } else { 2 };
+        let deck = |cards: &[Card]| {
+            cards
+                .iter()
+                .map(|card| card.to_string())
+                .collect::<Vec<_>>()
+        };
+        let mut out = String::new();
+        for event in &self.events {
+            match event {
+                Event::GameStart { game } => out += &format!("=== Game {} ===\n", game),
+                Event::Round {
+                    game,
+                    round,
+                    deck1,
+                    deck2,
+                } => {
+                    out += &format!("\n-- Round {} (Game {}) --\n", round, game);
+                    out += &format!("Player 1's deck: {}\n", deck(deck1).join(", "));
+                    out += &format!("Player 2's deck: {}\n", deck(deck2).join(", "));
+                    out += &format!("Player 1 plays: {}\n", deck1[0]);
+                    out += &format!("Player 2 plays: {}\n", deck2[0]);
+                }
+                Event::Repeat { game } => {
+                    out += &format!("\nThe decks repeat an earlier round of game {}.\n", game)
+                }
+                Event::SubGame => out += "Playing a sub-game to determine the winner...\n\n",
+                Event::RoundEnd {
+                    game,
+                    round,
+                    player1_wins,
+                } => {
+                    out += &format!(
+                        "Player {} wins round {} of game {}!\n",
+                        player(*player1_wins),
+                        round,
+                        game
+                    )
+                }
+                Event::GameEnd {
+                    game,
+                    player1_wins,
+                    deck1,
+                    deck2,
+                    parent,
+                } => {
+                    out += &format!(
+                        "The winner of game {} is player {}!\n",
+                        game,
+                        player(*player1_wins)
+                    );
+                    match parent {
+                        Some(parent) => out += &format!("\n...anyway, back to game {}.\n", parent),
+                        None => {
+                            out += "\n\n== Post-game results ==\n";
+                            out += &format!("Player 1's deck: {}\n", deck(deck1).join(", "));
+                            out += &format!("Player 2's deck: {}\n", deck(deck2).join(", "));
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn game(&self) -> usize {
+        self.playing.last().map_or(0, |&(game, _)| game)
+    }
+}
+
+impl Observer for Transcript {
+    fn game_start(&mut self) {
+        self.games += 1;
+        self.playing.push((self.games, 0));
+        self.events.push(Event::GameStart { game: self.games });
+    }
+
+    fn round_start<D: Deck>(&mut self, deck1: &D, deck2: &D) {
+        let (game, round) = self.playing.last_mut().unwrap();
+        *round += 1;
+        self.events.push(Event::Round {
+            game: *game,
+            round: *round,
+            deck1: deck1.cards(),
+            deck2: deck2.cards(),
+        });
+    }
+
+    fn repeated(&mut self) {
+        let game = self.game();
+        self.events.push(Event::Repeat { game });
+    }
+
+    fn sub_game(&mut self) {
+        self.events.push(Event::SubGame);
+    }
+
+    fn round_end(&mut self, player1_wins: bool) {
+        let (game, round) = *self.playing.last().unwrap();
+        self.events.push(Event::RoundEnd {
+            game,
+            round,
+            player1_wins,
+        });
+    }
+
+    fn game_end<D: Deck>(&mut self, deck1: &D, deck2: &D, player1_wins: bool) {
+        let (game, _) = self.playing.pop().unwrap();
+        self.events.push(Event::GameEnd {
+            game,
+            player1_wins,
+            deck1: deck1.cards(),
+            deck2: deck2.cards(),
+            parent: self.playing.last().map(|&(game, _)| game),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_deck_wraps() {
+        let mut deck: ArrayDeck = (1..=50).collect();
+        for _ in 0..200 {
+            let card = deck.draw().unwrap();
+            deck.push(card);
+        }
+        assert_eq!(deck.cards(), (1..=50).collect::<Vec<_>>());
+        assert_eq!(deck.sub_deck(3).cards(), vec![1, 2, 3]);
+
+        // Decks holding the same cards at different buffer offsets are equal.
+        let fresh: ArrayDeck = (1..=50).collect();
+        assert_eq!(deck, fresh);
+    }
+
+    #[test]
+    fn test_fingerprints() {
+        let mut array: ArrayDeck = (1..=50).collect();
+        let mut vec: VecDeque<Card> = (1..=50).collect();
+        let fresh = array.fingerprint();
+        assert_eq!(fresh, vec.fingerprint());
+        // The fingerprint kept up to date matches the one worked out from every card.
+        for _ in 0..75 {
+            let card = array.draw().unwrap();
+            vec.draw();
+            assert_eq!(array.fingerprint(), vec.fingerprint());
+            array.push(card);
+            vec.push(card);
+            assert_eq!(array.fingerprint(), vec.fingerprint());
+        }
+        assert_ne!(array.fingerprint(), fresh);
+        assert_eq!(
+            array.sub_deck(3).fingerprint(),
+            vec.sub_deck(3).fingerprint()
+        );
+        // A 0 at the top still changes the fingerprint.
+        let zero: VecDeque<Card> = vec![0, 7].into_iter().collect();
+        let seven: VecDeque<Card> = vec![7].into_iter().collect();
+        assert_ne!(zero.fingerprint(), seven.fingerprint());
+    }
+
+    #[test]
+    fn test_decks_agree() {
+        let (cards1, cards2) = (vec![9, 2, 6, 3, 1], vec![5, 8, 4, 7, 10]);
+
+        let mut vec1: VecDeque<Card> = cards1.iter().cloned().collect();
+        let mut vec2: VecDeque<Card> = cards2.iter().cloned().collect();
+        let mut arr1: ArrayDeck = cards1.iter().cloned().collect();
+        let mut arr2: ArrayDeck = cards2.iter().cloned().collect();
+
+        assert!(!play_recursive_combat(&mut vec1, &mut vec2));
+        assert!(!play_recursive_combat(&mut arr1, &mut arr2));
+        assert_eq!(vec2.score(), 291);
+        assert_eq!(arr2.score(), 291);
+    }
+
+    #[test]
+    fn test_transcript() {
+        let mut deck1: ArrayDeck = vec![9, 2, 6, 3, 1].into_iter().collect();
+        let mut deck2: ArrayDeck = vec![5, 8, 4, 7, 10].into_iter().collect();
+        let mut transcript = Transcript::new();
+        assert!(!play_recursive_combat_with(
+            &mut deck1,
+            &mut deck2,
+            Rules::default(),
+            &mut transcript
+        ));
+        let games = transcript
+            .events
+            .iter()
+            .filter(|event| matches!(event, Event::GameStart { .. }))
+            .count();
+        assert_eq!(games, 5);
+        assert_eq!(
+            transcript.events.last(),
+            Some(&Event::GameEnd {
+                game: 1,
+                player1_wins: false,
+                deck1: vec![],
+                deck2: vec![7, 5, 6, 2, 4, 1, 10, 8, 9, 3],
+                parent: None,
+            })
+        );
+
+        let replay = transcript.replay();
+        assert!(replay.starts_with(
+            "=== Game 1 ===\n\n-- Round 1 (Game 1) --\nPlayer 1's deck: 9, 2, 6, 3, 1\n\
+             Player 2's deck: 5, 8, 4, 7, 10\nPlayer 1 plays: 9\nPlayer 2 plays: 5\n\
+             Player 1 wins round 1 of game 1!\n"
+        ));
+        assert!(replay.contains(
+            "Player 1 plays: 4\nPlayer 2 plays: 3\n\
+             Playing a sub-game to determine the winner...\n\n=== Game 2 ===\n"
+        ));
+        assert!(replay.contains(
+            "The winner of game 2 is player 2!\n\n...anyway, back to game 1.\n\
+             Player 2 wins round 9 of game 1!\n"
+        ));
+        assert!(replay.ends_with(
+            "== Post-game results ==\nPlayer 1's deck: \n\
+             Player 2's deck: 7, 5, 6, 2, 4, 1, 10, 8, 9, 3\n"
+        ));
+    }
+
+    #[test]
+    fn test_shortcut_matches_recursion() {
+        let shortcut = Rules {
+            shortcut_sub_games: true,
+        };
+        // Shuffle the cards many ways, by a fixed linear congruential generator.
+        let mut seed: u64 = 2020;
+        for size in (10..=30).step_by(4) {
+            for _ in 0..20 {
+                let mut cards: Vec<Card> = (1..=size).collect();
+                for i in (1..cards.len()).rev() {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    cards.swap(i, (seed >> 33) as usize % (i + 1));
+                }
+                let (cards1, cards2) = cards.split_at(cards.len() / 2);
+                let mut exhaustive: [ArrayDeck; 2] = [
+                    cards1.iter().copied().collect(),
+                    cards2.iter().copied().collect(),
+                ];
+                let mut shortcut_decks = exhaustive;
+                let [deck1, deck2] = &mut exhaustive;
+                let winner = play_recursive_combat(deck1, deck2);
+                let [short1, short2] = &mut shortcut_decks;
+                let mut transcript = Transcript::new();
+                assert_eq!(
+                    play_recursive_combat_with(short1, short2, shortcut, &mut transcript),
+                    winner
+                );
+                assert_eq!(shortcut_decks, exhaustive);
+            }
+        }
+
+        let high: ArrayDeck = vec![9, 1].into_iter().collect();
+        let low: ArrayDeck = vec![2, 3].into_iter().collect();
+        assert!(player1_sure_to_win(&high, &low));
+        assert!(!player1_sure_to_win(&low, &high));
+        // The highest card could start a sub-game, so the game has to be played.
+        let high: ArrayDeck = vec![4, 1, 2].into_iter().collect();
+        let low: ArrayDeck = vec![3, 3, 3].into_iter().collect();
+        assert!(!player1_sure_to_win(&high, &low));
+    }
+
+    #[test]
+    fn test_many_cards() {
+        // More cards than an array-backed deck can hold are played with a growable deck.
+        let deal = |player: usize| {
+            (1..=80)
+                .filter(|card| card % 2 == player % 2)
+                .map(|card| card.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let input = format!("Player 1:\n{}\n\nPlayer 2:\n{}", deal(1), deal(2));
+        let decks = parse_decks(&input).expect("Couldn't convert test input");
+        let shortcut = Rules {
+            shortcut_sub_games: true,
+        };
+        let score = recursive_combat_score(&decks, Rules::default(), &mut ());
+        assert_eq!(recursive_combat_score(&decks, shortcut, &mut ()), score);
+        let mut transcript = Transcript::new();
+        recursive_combat_score(&decks, Rules::default(), &mut transcript);
+        assert!(transcript.replay().contains("== Post-game results =="));
+    }
+}
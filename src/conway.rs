@@ -0,0 +1,367 @@
+/// Conway cubes of Day 17: the Game of Life in any number of dimensions.
+use crate::automaton;
+use crate::fasthash::FastSet;
+use crate::progress;
+use std::io;
+use std::str::FromStr;
+
+/// A position in `N`-dimensional space, stored inline so positions never touch the heap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point<const N: usize>(pub [i32; N]);
+
+impl<const N: usize> Point<N> {
+    /// The point in the plane of the first two dimensions, at 0 in every other.
+    pub fn from_plane(x: i32, y: i32) -> Self {
+        let mut coords = [0; N];
+        coords[0] = x;
+        coords[1] = y;
+        Self(coords)
+    }
+
+    fn offset(self, direction: &[i32; N]) -> Self {
+        let mut coords = self.0;
+        for (coord, delta) in coords.iter_mut().zip(direction) {
+            *coord += delta;
+        }
+        Self(coords)
+    }
+}
+
+/// Every offset to a neighbouring point in `N` dimensions: each combination of -1, 0 and 1 in
+/// each dimension but all 0s.
+fn neighbour_directions<const N: usize>() -> Vec<[i32; N]> {
+    let count = 3usize.pow(N as u32);
+    (0..count)
+        .map(|mut index| {
+            let mut direction = [0; N];
+            for delta in direction.iter_mut() {
+                *delta = (index % 3) as i32 - 1;
+                index /= 3;
+            }
+            direction
+        })
+        .filter(|direction| direction.iter().any(|&delta| delta != 0))
+        .collect()
+}
+
+/// Parse the initial slice of the pocket dimension, giving the active cubes as (x, y) positions.
+pub fn parse_initial(s: &str) -> io::Result<Vec<(i32, i32)>> {
+    let mut active = Vec::new();
+    for (y, line) in s.lines().enumerate() {
+        for (x, ch) in line.chars().enumerate() {
+            match ch {
+                '#' => active.push((x as i32, y as i32)),
+                '.' => (),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid cube {:?} in the initial state", ch),
+                    ))
+                }
+            }
+        }
+    }
+    Ok(active)
+}
+
+/// The active cubes of an `N`-dimensional pocket dimension.
+#[derive(Clone, Debug)]
+pub struct State<const N: usize> {
+    cubes: FastSet<Point<N>>,
+    directions: Vec<[i32; N]>,
+    parallel: bool,
+}
+
+impl<const N: usize> State<N> {
+    /// Start from active cubes in the plane of the first two dimensions.
+    pub fn from_plane(active: &[(i32, i32)]) -> Self {
+        assert!(N >= 2, "Need at least two dimensions for the initial plane");
+        Self {
+            cubes: active
+                .iter()
+                .map(|&(x, y)| Point::from_plane(x, y))
+                .collect(),
+            directions: neighbour_directions(),
+            parallel: false,
+        }
+    }
+
+    /// Count each cycle's active neighbours in parallel, which gives the same results as counting
+    /// them serially.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    pub fn cubes(&self) -> &FastSet<Point<N>> {
+        &self.cubes
+    }
+
+    /// The number of active cubes.
+    pub fn active(&self) -> usize {
+        self.cubes.len()
+    }
+
+    /// Run a single cycle: active cubes stay active with 2 or 3 active neighbours, and inactive
+    /// cubes become active with exactly 3.
+    pub fn cycle(&mut self) {
+        let directions = &self.directions;
+        let neighbours = |cube: Point<N>| {
+            directions
+                .iter()
+                .map(move |direction| cube.offset(direction))
+        };
+        let rule = |active, neighbours| neighbours == 3 || (neighbours == 2 && active);
+        self.cubes = match self.parallel {
+            true => automaton::step_parallel(&self.cubes, neighbours, rule),
+            false => automaton::step(&self.cubes, neighbours, rule),
+        };
+    }
+}
+
+/// A Game of Life over the pocket dimension, one cycle at a time.
+pub trait Automaton {
+    /// Run a single cycle: active cubes stay active with 2 or 3 active neighbours, and inactive
+    /// cubes become active with exactly 3.
+    fn cycle(&mut self);
+
+    /// The number of active cubes.
+    fn active(&self) -> usize;
+}
+
+impl<const N: usize> Automaton for State<N> {
+    fn cycle(&mut self) {
+        State::cycle(self)
+    }
+
+    fn active(&self) -> usize {
+        State::active(self)
+    }
+}
+
+/// The cubes of an `N`-dimensional pocket dimension as a dense array of every position they can
+/// reach in a given number of cycles.
+///
+/// The active region grows by at most one position per cycle along each axis, so the array covers
+/// the initial plane widened by the number of cycles on every side, plus one more for the
+/// neighbours of the outermost positions. Each cycle then counts neighbours by sweeping the whole
+/// array with fixed index offsets rather than hashing positions, at the cost of memory growing
+/// with the size of the box rather than the number of active cubes.
+#[derive(Clone, Debug)]
+pub struct DenseState<const N: usize> {
+    cells: Vec<bool>,
+    /// The number of active neighbours of each cell, which is only non-zero during a cycle.
+    counts: Vec<u16>,
+    /// The offset in `cells` to each neighbour of a cell.
+    offsets: Vec<isize>,
+    /// How many more cycles the array has room for.
+    remaining: usize,
+}
+
+impl<const N: usize> DenseState<N> {
+    /// Start from active cubes in the plane of the first two dimensions, with room for `cycles`.
+    pub fn from_plane(active: &[(i32, i32)], cycles: usize) -> Self {
+        assert!(N >= 2, "Need at least two dimensions for the initial plane");
+        let min_x = active.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let max_x = active.iter().map(|&(x, _)| x).max().unwrap_or(0);
+        let min_y = active.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let max_y = active.iter().map(|&(_, y)| y).max().unwrap_or(0);
+        let margin = cycles + 1;
+
+        let mut extents = [2 * margin + 1; N];
+        extents[0] = (max_x - min_x) as usize + 2 * margin + 1;
+        extents[1] = (max_y - min_y) as usize + 2 * margin + 1;
+        let mut strides = [1; N];
+        for dimension in 1..N {
+            strides[dimension] = strides[dimension - 1] * extents[dimension - 1];
+        }
+        let size = strides[N - 1] * extents[N - 1];
+
+        // Every dimension past the plane starts at the middle of its extent.
+        let centre: usize = (2..N).map(|dimension| margin * strides[dimension]).sum();
+        let mut cells = vec![false; size];
+        for &(x, y) in active {
+            let x = (x - min_x) as usize + margin;
+            let y = (y - min_y) as usize + margin;
+            cells[centre + x * strides[0] + y * strides[1]] = true;
+        }
+        let offsets = neighbour_directions::<N>()
+            .iter()
+            .map(|direction| {
+                direction
+                    .iter()
+                    .zip(&strides)
+                    .map(|(&delta, &stride)| delta as isize * stride as isize)
+                    .sum()
+            })
+            .collect();
+        Self {
+            cells,
+            counts: vec![0; size],
+            offsets,
+            remaining: cycles,
+        }
+    }
+
+    /// The number of active cubes.
+    pub fn active(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell).count()
+    }
+
+    /// Run a single cycle. Panics if the array has no room left for one.
+    pub fn cycle(&mut self) {
+        assert!(
+            self.remaining > 0,
+            "No room left in the array for another cycle"
+        );
+        self.remaining -= 1;
+        // Active cubes are never within the margin of the edge, so their neighbours are in range.
+        for (index, _) in self.cells.iter().enumerate().filter(|(_, &cell)| cell) {
+            for &offset in &self.offsets {
+                self.counts[(index as isize + offset) as usize] += 1;
+            }
+        }
+        for (cell, count) in self.cells.iter_mut().zip(self.counts.iter_mut()) {
+            *cell = *count == 3 || (*count == 2 && *cell);
+            *count = 0;
+        }
+    }
+}
+
+impl<const N: usize> Automaton for DenseState<N> {
+    fn cycle(&mut self) {
+        DenseState::cycle(self)
+    }
+
+    fn active(&self) -> usize {
+        DenseState::active(self)
+    }
+}
+
+/// How the cubes of the pocket dimension are stored and cycled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    /// A set of the active cubes, which grows with the number of them.
+    Sparse,
+    /// An array of every position the cubes can reach, which grows with the number of cycles.
+    Dense,
+}
+
+impl FromStr for Engine {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sparse" => Ok(Self::Sparse),
+            "dense" => Ok(Self::Dense),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid engine {:?}: expected sparse or dense", s),
+            )),
+        }
+    }
+}
+
+/// Run cycles of an automaton, and count the active cubes after.
+fn run_cycles(mut automaton: impl Automaton, cycles: usize) -> usize {
+    for cycle in 0..cycles {
+        automaton.cycle();
+        progress::advance(cycle + 1, cycles);
+    }
+    automaton.active()
+}
+
+/// Run cycles in `N` dimensions from active cubes in the plane, and count the active cubes after.
+/// The sparse engine can count neighbours in parallel, but the dense engine always runs serially.
+pub fn run<const N: usize>(
+    active: &[(i32, i32)],
+    cycles: usize,
+    engine: Engine,
+    parallel: bool,
+) -> usize {
+    match engine {
+        Engine::Sparse => run_cycles(State::<N>::from_plane(active).parallel(parallel), cycles),
+        Engine::Dense => run_cycles(DenseState::<N>::from_plane(active, cycles), cycles),
+    }
+}
+
+/// The most dimensions `simulate` can be asked for, where each cube already has 6560 neighbours.
+pub const MAX_DIMENSIONS: usize = 8;
+
+/// Run cycles in a number of dimensions chosen at runtime from active cubes in the plane, and
+/// count the active cubes after.
+pub fn simulate(
+    active: &[(i32, i32)],
+    dimensions: usize,
+    cycles: usize,
+    engine: Engine,
+    parallel: bool,
+) -> io::Result<usize> {
+    Ok(match dimensions {
+        2 => run::<2>(active, cycles, engine, parallel),
+        3 => run::<3>(active, cycles, engine, parallel),
+        4 => run::<4>(active, cycles, engine, parallel),
+        5 => run::<5>(active, cycles, engine, parallel),
+        6 => run::<6>(active, cycles, engine, parallel),
+        7 => run::<7>(active, cycles, engine, parallel),
+        8 => run::<8>(active, cycles, engine, parallel),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Cannot simulate {} dimensions: expected 2 to {}",
+                    dimensions, MAX_DIMENSIONS
+                ),
+            ))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycles() {
+        assert_eq!(neighbour_directions::<3>().len(), 26);
+        assert_eq!(neighbour_directions::<4>().len(), 80);
+
+        let active = parse_initial(".#.\n..#\n###").unwrap();
+        let mut state = State::<3>::from_plane(&active);
+        assert_eq!(state.active(), 5);
+        state.cycle();
+        assert_eq!(state.active(), 11);
+        assert!(state.cubes().contains(&Point([0, 1, -1])));
+
+        assert!(parse_initial(".x.").is_err());
+
+        let sparse = Engine::Sparse;
+        assert_eq!(simulate(&active, 3, 6, sparse, false).unwrap(), 112);
+        assert_eq!(simulate(&active, 4, 0, sparse, false).unwrap(), 5);
+        // A glider in the plane keeps its five cubes forever.
+        assert_eq!(simulate(&active, 2, 20, sparse, false).unwrap(), 5);
+        assert!(simulate(&active, 1, 6, sparse, false).is_err());
+        assert!(simulate(&active, MAX_DIMENSIONS + 1, 6, sparse, false).is_err());
+    }
+
+    #[test]
+    fn test_dense_matches_sparse() {
+        let active = parse_initial(".#.\n..#\n###").unwrap();
+        for dimensions in 2..=5 {
+            for cycles in 0..=4 {
+                let sparse = simulate(&active, dimensions, cycles, Engine::Sparse, false).unwrap();
+                assert_eq!(
+                    simulate(&active, dimensions, cycles, Engine::Dense, false).unwrap(),
+                    sparse
+                );
+                assert_eq!(
+                    simulate(&active, dimensions, cycles, Engine::Sparse, true).unwrap(),
+                    sparse
+                );
+            }
+        }
+        assert_eq!(run::<4>(&active, 6, Engine::Dense, false), 848);
+        assert_eq!("dense".parse::<Engine>().unwrap(), Engine::Dense);
+        assert!("hashset".parse::<Engine>().is_err());
+    }
+}
@@ -0,0 +1,316 @@
+/// Crab cups of Day 23: a circle of labelled cups, shuffled a move at a time.
+use crate::snapshot::{self, Snapshot};
+use std::io::{self, Read, Write};
+
+/// What happened in a single move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    /// The cup the move was made from.
+    pub current: u32,
+    /// The three cups clockwise of the current cup, in order, which were picked up.
+    pub picked: [u32; 3],
+    /// The cup the picked up cups were placed clockwise of.
+    pub destination: u32,
+}
+
+/// The cups in the circle, as the cup clockwise of each cup, so that moving cups around is a
+/// matter of changing a few of them rather than shifting the whole circle.
+///
+/// Cups are stored by index, one less than their label, so the hot loop never adjusts between
+/// them. With the "unchecked" feature the successor array is also read and written without bounds
+/// checks, which is sound as every index stored is one of a cup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrabCups {
+    /// The index of the cup clockwise of each cup, by index.
+    next: Vec<u32>,
+    /// The index of the current cup.
+    current: u32,
+}
+
+impl CrabCups {
+    /// Lay out the cups in the order given, followed by every label after the highest given up to
+    /// `size`, starting from the first cup given. Errors unless the labels given are each of 1 up
+    /// to some number once, and there are enough cups to make a move.
+    pub fn new(labels: &[u32], size: u32) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let mut seen = vec![false; labels.len()];
+        for &label in labels {
+            match seen.get_mut((label as usize).wrapping_sub(1)) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(invalid(format!("Invalid or repeated cup label {}", label))),
+            }
+        }
+        if (size as usize) < labels.len().max(5) {
+            return Err(invalid(format!(
+                "Can't play with {} cups: need at least 5, and all those given",
+                size
+            )));
+        }
+
+        let mut indices: Vec<u32> = labels.iter().map(|label| label - 1).collect();
+        indices.extend(labels.len() as u32..size);
+        let mut next = vec![0; size as usize];
+        for cups in indices.windows(2) {
+            next[cups[0] as usize] = cups[1];
+        }
+        // To complete the circle, the last cup needs to point to the first.
+        next[indices[size as usize - 1] as usize] = indices[0];
+        Ok(Self {
+            next,
+            current: indices[0],
+        })
+    }
+
+    /// The index of the cup clockwise of the cup at an index.
+    #[inline(always)]
+    fn next_index(&self, index: u32) -> u32 {
+        #[cfg(feature = "unchecked")]
+        // SAFETY: every index passed in is the current cup's or one read from `next`, all of
+        // which are below its length.
+        unsafe {
+            *self.next.get_unchecked(index as usize)
+        }
+        #[cfg(not(feature = "unchecked"))]
+        self.next[index as usize]
+    }
+
+    #[inline(always)]
+    fn set_next_index(&mut self, index: u32, next: u32) {
+        #[cfg(feature = "unchecked")]
+        // SAFETY: as for `next_index`.
+        unsafe {
+            *self.next.get_unchecked_mut(index as usize) = next;
+        }
+        #[cfg(not(feature = "unchecked"))]
+        {
+            self.next[index as usize] = next;
+        }
+    }
+
+    /// The number of cups in the circle.
+    pub fn len(&self) -> usize {
+        self.next.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next.is_empty()
+    }
+
+    /// The cup the next move will be made from.
+    pub fn current(&self) -> u32 {
+        self.current + 1
+    }
+
+    /// The cup clockwise of a cup.
+    pub fn next(&self, label: u32) -> u32 {
+        self.next[label as usize - 1] + 1
+    }
+
+    /// Make a move: pick up the three cups clockwise of the current cup, place them clockwise of
+    /// the cup labelled one less than the current cup (skipping any picked up, and wrapping round
+    /// to the highest label), then move on to the cup clockwise of the current cup.
+    pub fn step(&mut self) -> Move {
+        let current = self.current;
+        let highest_index = self.next.len() as u32 - 1;
+
+        // Get the three cups after the current cup in the linked list
+        let pick1 = self.next_index(current);
+        let pick2 = self.next_index(pick1);
+        let pick3 = self.next_index(pick2);
+
+        // Remove them from the circle by making the current cup point to the cup which was after
+        // the third cup.
+        self.set_next_index(current, self.next_index(pick3));
+
+        // Seek the destination cup among the remaining cups. If the sought dest cup is amongst
+        // the picked cups, try again, wrapping around from the lowest numbered cup to the highest.
+        let below = |index: u32| if index == 0 { highest_index } else { index - 1 };
+        let mut destination = below(current);
+        while destination == pick1 || destination == pick2 || destination == pick3 {
+            destination = below(destination);
+        }
+
+        // Insert the picked cups as a whole immediately clockwise of the destination cup, by
+        // setting it to point to the first and the third to point to the destination's next.
+        self.set_next_index(pick3, self.next_index(destination));
+        self.set_next_index(destination, pick1);
+
+        // The new current cup is the cup after the current cup
+        self.current = self.next_index(current);
+        Move {
+            current: current + 1,
+            picked: [pick1 + 1, pick2 + 1, pick3 + 1],
+            destination: destination + 1,
+        }
+    }
+
+    /// Make a number of moves.
+    pub fn play(&mut self, moves: usize) {
+        for _ in 0..moves {
+            self.step();
+        }
+    }
+
+    /// An endless iterator making a move each time it's advanced, giving what happened in it.
+    pub fn moves(&mut self) -> Moves<'_> {
+        Moves { cups: self }
+    }
+
+    /// The `n` cups clockwise of a cup, in order.
+    pub fn cups_after(&self, label: u32, n: usize) -> Vec<u32> {
+        let mut cups = Vec::with_capacity(n);
+        let mut cup = label;
+        for _ in 0..n {
+            cup = self.next(cup);
+            cups.push(cup);
+        }
+        cups
+    }
+}
+
+impl Snapshot for CrabCups {
+    const TAG: [u8; 8] = *b"cups\0\0\0\0";
+
+    fn write_state(&self, out: &mut dyn Write) -> io::Result<()> {
+        snapshot::write_u32s(out, &self.next)?;
+        snapshot::write_u64(out, self.current as u64)
+    }
+
+    /// Read the cups back, checking every index is of a cup, which reading them without bounds
+    /// checks relies on.
+    fn read_state(input: &mut dyn Read) -> io::Result<Self> {
+        let next = snapshot::read_u32s(input)?;
+        let current = snapshot::read_u64(input)?;
+        let len = next.len() as u64;
+        if len < 5 || current >= len || next.iter().any(|&index| index as u64 >= len) {
+            return Err(snapshot::invalid("Cup out of range"));
+        }
+        Ok(Self {
+            next,
+            current: current as u32,
+        })
+    }
+
+    fn describe(&self) -> String {
+        let show = |label: u32| {
+            self.cups_after(label, 9.min(self.len() - 1))
+                .iter()
+                .map(|cup| cup.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        format!(
+            "{} cups, with ({}) current and then {}
+After cup 1: {}",
+            self.len(),
+            self.current(),
+            show(self.current()),
+            show(1)
+        )
+    }
+}
+
+/// An iterator over the moves of a game of crab cups, made as it's advanced.
+pub struct Moves<'a> {
+    cups: &'a mut CrabCups,
+}
+
+impl Iterator for Moves<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        Some(self.cups.step())
+    }
+}
+
+/// Lay out the cups as the original solution did, with the label of the cup after the cup
+/// labelled k at index k - 1, kept to compare `CrabCups` against. The labels must be valid, as
+/// `CrabCups::new` checks.
+pub fn label_layout(labels: &[u32], size: u32) -> Vec<u32> {
+    let mut cups = vec![0u32; size as usize];
+    let mut labels = labels.to_vec();
+    labels.extend((labels.len() as u32 + 1)..=size);
+    for labels in labels.windows(2) {
+        cups[labels[0] as usize - 1] = labels[1];
+    }
+    cups[labels[size as usize - 1] as usize - 1] = labels[0];
+    cups
+}
+
+/// Make moves on cups laid out by `label_layout` as the original solution did, adjusting each
+/// label to an index on every access.
+pub fn label_moves(cups: &mut [u32], first_cup: u32, moves: usize) {
+    let mut curr_cup = first_cup;
+    let highest_number = cups.len() as u32;
+    for _ in 0..moves {
+        let pick1 = cups[curr_cup as usize - 1];
+        let pick2 = cups[pick1 as usize - 1];
+        let pick3 = cups[pick2 as usize - 1];
+        cups[curr_cup as usize - 1] = cups[pick3 as usize - 1];
+        let mut dest_cup = curr_cup - 1;
+        while [pick1, pick2, pick3].contains(&dest_cup) || dest_cup == 0 {
+            if dest_cup < 1 {
+                dest_cup = highest_number;
+            } else {
+                dest_cup -= 1;
+            }
+        }
+        cups[pick3 as usize - 1] = cups[dest_cup as usize - 1];
+        cups[dest_cup as usize - 1] = pick1;
+        curr_cup = cups[curr_cup as usize - 1];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves() {
+        let mut cups = CrabCups::new(&[3, 8, 9, 1, 2, 5, 4, 6, 7], 9).unwrap();
+        assert_eq!(cups.current(), 3);
+        assert_eq!(
+            cups.step(),
+            Move {
+                current: 3,
+                picked: [8, 9, 1],
+                destination: 2
+            }
+        );
+        // The circle is now 3 (2) 8 9 1 5 4 6 7.
+        assert_eq!(cups.current(), 2);
+        assert_eq!(cups.cups_after(3, 8), vec![2, 8, 9, 1, 5, 4, 6, 7]);
+
+        // Every cup below 2 is picked up, so the destination wraps round to the highest cup not.
+        let moves: Vec<Move> = cups.moves().take(2).collect();
+        assert_eq!(moves[0].destination, 7);
+        assert_eq!(moves[1].current, 5);
+        assert_eq!(moves[1].destination, 3);
+        assert_eq!(cups.cups_after(1, 8), vec![3, 4, 6, 7, 2, 5, 8, 9]);
+    }
+
+    #[test]
+    fn test_layout() {
+        let cups = CrabCups::new(&[3, 1, 2], 6).unwrap();
+        assert_eq!(cups.cups_after(2, 6), vec![4, 5, 6, 3, 1, 2]);
+        assert_eq!(cups.len(), 6);
+
+        assert!(CrabCups::new(&[1, 2, 4], 6).is_err());
+        assert!(CrabCups::new(&[1, 2, 1], 6).is_err());
+        assert!(CrabCups::new(&[0, 1, 2], 6).is_err());
+        assert!(CrabCups::new(&[1, 2, 3], 4).is_err());
+        assert!(CrabCups::new(&[1, 2, 3, 4, 5, 6], 5).is_err());
+    }
+
+    #[test]
+    fn test_label_moves() {
+        let labels = [3, 8, 9, 1, 2, 5, 4, 6, 7];
+        let mut cups = CrabCups::new(&labels, 20).unwrap();
+        cups.play(100);
+        let mut by_label = label_layout(&labels, 20);
+        label_moves(&mut by_label, labels[0], 100);
+        for label in 1..=20 {
+            assert_eq!(by_label[label as usize - 1], cups.next(label));
+        }
+    }
+}
@@ -0,0 +1,95 @@
+/// Customs declaration form answers of Day 6.
+use std::io;
+use std::str::FromStr;
+
+/// The questions each person in a group answered "yes" to, as a bitmask with question a as the
+/// lowest bit through to question z.
+pub type Group = Vec<u32>;
+
+/// How many people in a group must have answered a question for it to count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threshold {
+    /// Anyone in the group.
+    Any,
+    /// Everyone in the group.
+    All,
+    /// At least this many people in the group.
+    AtLeast(usize),
+}
+
+impl FromStr for Threshold {
+    type Err = io::Error;
+
+    /// Parse "any", "all" or a number of people.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "any" => Ok(Self::Any),
+            "all" => Ok(Self::All),
+            _ => s.parse().map(Self::AtLeast).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid threshold {:?}: expected any, all or a number", s),
+                )
+            }),
+        }
+    }
+}
+
+/// Parse the groups of answers, where groups are separated by a blank line and each person's
+/// answers are on their own line.
+pub fn parse_groups(input: &str) -> Vec<Group> {
+    input
+        .split("\n\n")
+        .map(|group| {
+            group
+                .lines()
+                .map(|person| {
+                    person
+                        .bytes()
+                        .filter(|b| b.is_ascii_lowercase())
+                        .fold(0, |mask, b| mask | 1 << (b - b'a'))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Count, summed over every group, the questions answered by enough people in the group.
+pub fn count_answers(groups: &[Group], threshold: Threshold) -> usize {
+    groups
+        .iter()
+        .map(|group| {
+            let answered = match threshold {
+                Threshold::Any => group.iter().fold(0, |acc, person| acc | person),
+                Threshold::All if group.is_empty() => 0,
+                Threshold::All => group.iter().fold(!0, |acc, person| acc & person),
+                Threshold::AtLeast(k) => (0..26)
+                    .map(|bit| 1 << bit)
+                    .filter(|question| {
+                        let count = group
+                            .iter()
+                            .filter(|&person| person & question != 0)
+                            .count();
+                        count > 0 && count >= k
+                    })
+                    .fold(0, |acc, question| acc | question),
+            };
+            answered.count_ones() as usize
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds() {
+        let groups = parse_groups("abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb");
+        assert_eq!(count_answers(&groups, Threshold::Any), 11);
+        assert_eq!(count_answers(&groups, Threshold::All), 6);
+        assert_eq!(count_answers(&groups, Threshold::AtLeast(2)), 2);
+        assert_eq!(count_answers(&groups, Threshold::AtLeast(0)), 11);
+        assert_eq!("3".parse::<Threshold>().unwrap(), Threshold::AtLeast(3));
+    }
+}
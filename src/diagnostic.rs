@@ -0,0 +1,179 @@
+/// Parse errors which point at the text in the input they're about, so a typo in a hand-edited
+/// input can be found without searching for it.
+///
+/// A diagnostic is made where the offending text is found, from the slice of the input holding
+/// it, and is located in the whole input (its line, column and the text of that line) once it
+/// gets back to whoever has the whole input to hand. Slices are recognised by where they lie in
+/// memory, so text copied out of the input can't be located and is reported with its message
+/// alone.
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Where in the input a diagnostic points, counting lines and columns from 1.
+#[derive(Clone, PartialEq, Eq)]
+struct Location {
+    line: usize,
+    column: usize,
+    /// The whole of the line, to show the text pointed at in context.
+    text: String,
+    /// How many characters of the line are pointed at.
+    width: usize,
+}
+
+/// A parse error about some text in the input.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    message: String,
+    /// The address and length in bytes of the text pointed at.
+    span: (usize, usize),
+    location: Option<Location>,
+}
+
+impl Diagnostic {
+    /// An error about `span`, which should be a slice of the input for it to be located.
+    pub fn new(span: &str, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            span: (span.as_ptr() as usize, span.len()),
+            location: None,
+        }
+    }
+
+    /// Turn an error from parsing `span` into a diagnostic: the diagnostic it already carries if
+    /// it has one, as that points more precisely, and otherwise one pointing at all of `span`.
+    pub fn from_io(err: io::Error, span: &str) -> Self {
+        if err.get_ref().is_some_and(|inner| inner.is::<Self>()) {
+            // Both were checked just above, so can't fail.
+            return *err.into_inner().unwrap().downcast().unwrap();
+        }
+        Self::new(span, err.to_string())
+    }
+
+    /// Find the text pointed at in the whole input, if it's from there and not already located.
+    pub fn locate(mut self, input: &str) -> Self {
+        if self.location.is_some() {
+            return self;
+        }
+        let (address, len) = self.span;
+        let offset = match address.checked_sub(input.as_ptr() as usize) {
+            Some(offset) if offset + len <= input.len() && input.is_char_boundary(offset) => offset,
+            _ => return self,
+        };
+        let line_start = input[..offset].rfind('\n').map_or(0, |newline| newline + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |newline| offset + newline);
+        let text = &input[line_start..line_end];
+        // Text spanning several lines is pointed at up to the end of its first.
+        let pointed = &input[offset..(offset + len).min(line_end)];
+        self.location = Some(Location {
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            text: text.to_string(),
+            width: pointed.chars().count().max(1),
+        });
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The line and column the diagnostic points at, if it's been located.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.location
+            .as_ref()
+            .map(|location| (location.line, location.column))
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// Write the message, followed by the line pointed at with the offending text underlined if
+    /// it's been located, e.g.
+    ///
+    /// ```text
+    /// Invalid ticket value "7x"
+    ///  --> line 5, column 3
+    ///   |
+    /// 5 | 7x,1,14
+    ///   | ^^
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(location) = &self.location {
+            let gutter = " ".repeat(location.line.to_string().len());
+            write!(
+                f,
+                "\n{}--> line {}, column {}\n{} |\n{} | {}\n{} | {}{}",
+                gutter,
+                location.line,
+                location.column,
+                gutter,
+                location.line,
+                location.text,
+                gutter,
+                " ".repeat(location.column - 1),
+                "^".repeat(location.width)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Diagnostic {
+    /// The same as `Display`, so that returning a diagnostic from `main` shows where it points.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for Diagnostic {}
+
+impl From<Diagnostic> for io::Error {
+    fn from(diagnostic: Diagnostic) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, diagnostic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let input = "1,2,3\n4,x5,6\n";
+        let span = &input[8..10];
+        let diagnostic = Diagnostic::new(span, "Invalid value \"x5\"").locate(input);
+        assert_eq!(diagnostic.position(), Some((2, 3)));
+        assert_eq!(
+            diagnostic.to_string(),
+            "Invalid value \"x5\"\n --> line 2, column 3\n  |\n2 | 4,x5,6\n  |   ^^"
+        );
+
+        // Text from elsewhere can't be located.
+        let elsewhere = "x5".to_string();
+        let diagnostic = Diagnostic::new(&elsewhere, "Invalid value").locate(input);
+        assert_eq!(diagnostic.position(), None);
+        assert_eq!(diagnostic.to_string(), "Invalid value");
+    }
+
+    #[test]
+    fn test_from_io() {
+        let input = "mask = 1X0\nmem[8] = ?\n";
+        let line = &input[11..21];
+        // An error which already points somewhere keeps pointing there.
+        let err: io::Error = Diagnostic::new(&input[9..10], "Invalid bit").into();
+        assert_eq!(
+            Diagnostic::from_io(err, line).locate(input).position(),
+            Some((1, 10))
+        );
+        // Any other error points at all of the text it came from.
+        let err = io::Error::new(io::ErrorKind::InvalidData, "Invalid value");
+        let diagnostic = Diagnostic::from_io(err, line).locate(input);
+        assert_eq!(diagnostic.message(), "Invalid value");
+        assert!(diagnostic
+            .to_string()
+            .ends_with("2 | mem[8] = ?\n  | ^^^^^^^^^^"));
+    }
+}
@@ -0,0 +1,348 @@
+/// Emulator for the ferry docking computer of Day 14.
+use crate::checked::{self, Overflow};
+use crate::diagnostic::Diagnostic;
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::io;
+use std::iter;
+use std::str::FromStr;
+
+pub type Number = u64;
+
+/// A bitmask, as the bits it sets to 1, sets to 0 and leaves floating ('X').
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Mask {
+    pub ones: Number,
+    pub zeros: Number,
+    pub floating: Number,
+}
+
+impl Mask {
+    /// Apply the mask to a value as version 1 of the decoder does: 1s and 0s overwrite the bits
+    /// and floating bits are left unchanged.
+    pub fn apply_v1(&self, value: Number) -> Number {
+        value & !self.zeros | self.ones
+    }
+
+    /// Every address written by version 2 of the decoder: 1s overwrite the bits, 0s leave them
+    /// unchanged and floating bits take every combination of values.
+    pub fn addresses_v2(&self, address: Number) -> impl Iterator<Item = Number> {
+        let base = (address | self.ones) & !self.floating;
+        let floating = self.floating;
+        // Count down through the subsets of the floating bits, ending at the empty one.
+        iter::successors(Some(floating), move |&subset| {
+            (subset != 0).then(|| (subset - 1) & floating)
+        })
+        .map(move |subset| base | subset)
+    }
+
+    /// The addresses written by version 2 of the decoder, as a set rather than one by one.
+    pub fn address_set_v2(&self, address: Number) -> AddressSet {
+        AddressSet {
+            fixed: (address | self.ones) & !self.floating,
+            floating: self.floating,
+        }
+    }
+}
+
+/// A set of addresses sharing the same value for every bit which isn't floating.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressSet {
+    /// The value of the fixed bits, with the floating bits all 0.
+    pub fixed: Number,
+    pub floating: Number,
+}
+
+impl AddressSet {
+    /// How many addresses are in the set.
+    pub fn count(&self) -> u128 {
+        1 << self.floating.count_ones()
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        (self.fixed ^ other.fixed) & !self.floating & !other.floating == 0
+    }
+
+    /// Split the addresses not in another set into disjoint sets.
+    fn difference(mut self, other: &Self) -> Vec<Self> {
+        if !self.intersects(other) {
+            return vec![self];
+        }
+        // For each bit floating here but fixed there, the half with the bit the other way is
+        // outside the other set, and the rest is narrowed down to the other set's value.
+        let mut outside = Vec::new();
+        let mut narrowing = self.floating & !other.floating;
+        while narrowing != 0 {
+            let bit = narrowing & narrowing.wrapping_neg();
+            narrowing &= !bit;
+            self.floating &= !bit;
+            outside.push(Self {
+                fixed: self.fixed | !other.fixed & bit,
+                floating: self.floating,
+            });
+            self.fixed |= other.fixed & bit;
+        }
+        // What remains lies wholly within the other set.
+        outside
+    }
+}
+
+impl FromStr for Mask {
+    type Err = io::Error;
+
+    /// Parse a mask of '0', '1' and 'X' with the most significant bit first.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() > Number::BITS as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Mask {:?} is longer than {} bits", s, Number::BITS),
+            ));
+        }
+        let mut mask = Self::default();
+        for (i, (at, ch)) in s.char_indices().rev().enumerate() {
+            let bit = 1 << i;
+            match ch {
+                '1' => mask.ones |= bit,
+                '0' => mask.zeros |= bit,
+                'X' => mask.floating |= bit,
+                _ => {
+                    return Err(Diagnostic::new(
+                        &s[at..at + ch.len_utf8()],
+                        format!("Invalid mask character {:?}", ch),
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(mask)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    SetMask(Mask),
+    Write { address: Number, value: Number },
+}
+
+impl FromStr for Instruction {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid instruction {:?}", s),
+            )
+        };
+        let (target, value) = s
+            .split('=')
+            .map(|s| s.trim())
+            .collect_tuple()
+            .ok_or_else(invalid)?;
+
+        if target == "mask" {
+            return Ok(Self::SetMask(value.parse()?));
+        }
+        let address = target
+            .strip_prefix("mem[")
+            .and_then(|target| target.strip_suffix(']'))
+            .and_then(|address| address.parse().ok())
+            .ok_or_else(invalid)?;
+        let value = value.parse().map_err(|_| invalid())?;
+        Ok(Self::Write { address, value })
+    }
+}
+
+/// Which version of the decoder chip the docking computer has, and so how masks are applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecoderVersion {
+    /// Masks apply to the values written.
+    V1,
+    /// Masks apply to the addresses written to, with floating bits writing to many addresses.
+    V2,
+}
+
+/// The docking computer, with its current mask and memory.
+pub struct DockingComputer {
+    version: DecoderVersion,
+    mask: Mask,
+    memory: HashMap<Number, Number>,
+}
+
+impl DockingComputer {
+    pub fn new(version: DecoderVersion) -> Self {
+        Self {
+            version,
+            mask: Mask::default(),
+            memory: HashMap::new(),
+        }
+    }
+
+    pub fn mask(&self) -> Mask {
+        self.mask
+    }
+
+    /// The value at an address, where memory starts as all 0s.
+    pub fn read(&self, address: Number) -> Number {
+        self.memory.get(&address).copied().unwrap_or(0)
+    }
+
+    /// The addresses which have been written to along with their values.
+    pub fn memory(&self) -> &HashMap<Number, Number> {
+        &self.memory
+    }
+
+    /// The sum of every value in memory, erroring if it doesn't fit in a `Number`.
+    pub fn sum(&self) -> Result<Number, Overflow> {
+        checked::sum(self.memory.values().copied())
+    }
+
+    /// Execute a single instruction.
+    pub fn step(&mut self, instruction: &Instruction) {
+        match *instruction {
+            Instruction::SetMask(mask) => self.mask = mask,
+            Instruction::Write { address, value } => match self.version {
+                DecoderVersion::V1 => {
+                    self.memory.insert(address, self.mask.apply_v1(value));
+                }
+                DecoderVersion::V2 => {
+                    for address in self.mask.addresses_v2(address) {
+                        self.memory.insert(address, value);
+                    }
+                }
+            },
+        }
+    }
+
+    /// Execute each instruction in turn.
+    pub fn run(&mut self, instructions: &[Instruction]) {
+        for instruction in instructions {
+            self.step(instruction);
+        }
+    }
+}
+
+/// Sum the memory after running the program with version 2 of the decoder, without writing to
+/// each address covered by the floating bits.
+///
+/// Working back from the last write, each write's value counts for the addresses it covers which
+/// no later write overwrites, found by removing each later write's addresses from its own. This
+/// grows with the overlaps between writes rather than with the number of floating bits.
+pub fn sum_v2(instructions: &[Instruction]) -> Result<u128, Overflow> {
+    let mut mask = Mask::default();
+    let mut writes = Vec::new();
+    for instruction in instructions {
+        match *instruction {
+            Instruction::SetMask(new) => mask = new,
+            Instruction::Write { address, value } => {
+                writes.push((mask.address_set_v2(address), value))
+            }
+        }
+    }
+
+    let mut overwritten: Vec<AddressSet> = Vec::new();
+    let mut sum: u128 = 0;
+    for &(addresses, value) in writes.iter().rev() {
+        let mut remaining = vec![addresses];
+        for later in &overwritten {
+            remaining = remaining
+                .into_iter()
+                .flat_map(|set| set.difference(later))
+                .collect();
+        }
+        let count = remaining.iter().map(AddressSet::count).sum::<u128>();
+        sum = checked::add(sum, checked::mul(count, value as u128)?)?;
+        overwritten.push(addresses);
+    }
+    Ok(sum)
+}
+
+/// Parse each line of the program, pointing any error at where it is in the input.
+pub fn parse_program(input: &str) -> Result<Vec<Instruction>, Diagnostic> {
+    input
+        .lines()
+        .map(|s| {
+            s.parse()
+                .map_err(|err| Diagnostic::from_io(err, s).locate(input))
+        })
+        .collect()
+}
+
+/// Run the program on a docking computer with the given decoder, and sum the memory after.
+pub fn run_program(
+    instructions: &[Instruction],
+    version: DecoderVersion,
+) -> Result<Number, Overflow> {
+    let mut computer = DockingComputer::new(version);
+    computer.run(instructions);
+    computer.sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoders() {
+        let mask: Mask = "XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X".parse().unwrap();
+        assert_eq!(mask.apply_v1(11), 73);
+        assert_eq!(mask.apply_v1(0), 64);
+
+        let mask: Mask = "000000000000000000000000000000X1001X".parse().unwrap();
+        let mut addresses: Vec<Number> = mask.addresses_v2(42).collect();
+        addresses.sort_unstable();
+        assert_eq!(addresses, vec![26, 27, 58, 59]);
+
+        // Stepping through shows the memory as it changes.
+        let mut computer = DockingComputer::new(DecoderVersion::V2);
+        computer.step(&Instruction::SetMask(mask));
+        assert_eq!(computer.mask(), mask);
+        computer.step(&"mem[42] = 100".parse().unwrap());
+        assert_eq!(computer.read(58), 100);
+        assert_eq!(computer.read(42), 0);
+        assert_eq!(computer.memory().len(), 4);
+
+        assert!("mem[x] = 1".parse::<Instruction>().is_err());
+        assert!("mask = 10Y".parse::<Instruction>().is_err());
+    }
+
+    #[test]
+    fn test_sum_v2() {
+        let program: Vec<Instruction> = "mask = 000000000000000000000000000000X1001X
+mem[42] = 100
+mask = 00000000000000000000000000000000X0XX
+mem[26] = 1"
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        assert_eq!(sum_v2(&program), Ok(208));
+
+        // Writing to every address at once would take far too long one by one.
+        let program = vec![
+            Instruction::SetMask("X".repeat(36).parse().unwrap()),
+            Instruction::Write {
+                address: 0,
+                value: 1,
+            },
+            Instruction::SetMask("0".repeat(36).parse().unwrap()),
+            Instruction::Write {
+                address: 5,
+                value: 10,
+            },
+        ];
+        assert_eq!(sum_v2(&program), Ok((1 << 36) - 1 + 10));
+
+        // Writing the computer's memory one address at a time sums it checked too.
+        let program = vec![
+            Instruction::SetMask(format!("{}X", "0".repeat(35)).parse().unwrap()),
+            Instruction::Write {
+                address: 0,
+                value: 1 << 63,
+            },
+        ];
+        let mut computer = DockingComputer::new(DecoderVersion::V2);
+        computer.run(&program);
+        assert_eq!(computer.sum(), Err(Overflow));
+        assert_eq!(sum_v2(&program), Ok(1 << 64));
+    }
+}
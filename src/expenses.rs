@@ -0,0 +1,192 @@
+/// Expense report entries of Day 1 and the sets of them which sum to a target.
+use crate::checked::{self, Overflow};
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The sum the entries must reach in the puzzle.
+pub const TARGET: u32 = 2020;
+
+/// A set of entries which sum to the target in ascending order, along with their product (the
+/// puzzle answer).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub entries: Vec<u32>,
+    pub product: u32,
+}
+
+impl Solution {
+    pub fn new(mut entries: Vec<u32>) -> Result<Self, Overflow> {
+        entries.sort_unstable();
+        let product = checked::product(entries.iter().copied())?;
+        Ok(Self { entries, product })
+    }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (entries {})",
+            self.product,
+            self.entries.iter().join(", ")
+        )
+    }
+}
+
+/// Find two entries which sum to the target value.
+///
+/// Remember the entries seen so far to avoid two loops and make this O(N). Each entry is only
+/// paired with those before it, so never with itself, though it can be with another of the same
+/// value.
+pub fn find_pair(input: &[u32], target: u32) -> Result<Option<Solution>, Overflow> {
+    let mut seen = HashSet::with_capacity(input.len());
+    for &num2 in input {
+        if let Some(num1) = target.checked_sub(num2) {
+            if seen.contains(&num1) {
+                return Ok(Some(Solution::new(vec![num1, num2])?));
+            }
+        }
+        seen.insert(num2);
+    }
+    Ok(None)
+}
+
+/// Find three entries which sum to the target value.
+///
+/// Use `find_pair` to make this O(N^2).
+pub fn find_triple(input: &[u32], target: u32) -> Result<Option<Solution>, Overflow> {
+    for (i, &num1) in input.iter().enumerate() {
+        // Can reuse find_pair, using the sub-problem of finding two of the later entries which
+        // sum to the target less the current one. If the current entry is greater than the
+        // target, it can't be part of a solution.
+        if let Some(rest) = target.checked_sub(num1) {
+            if let Some(pair) = find_pair(&input[i + 1..], rest)? {
+                let mut entries = vec![num1];
+                entries.extend(pair.entries);
+                return Ok(Some(Solution::new(entries)?));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Find every set of `count` distinct entries which sum to the target, rather than stopping at
+/// the first. Each set's entries are in ascending order, and sets of the same values made from
+/// different entries are only given once.
+pub fn find_all(input: &[u32], target: u32, count: usize) -> Result<Vec<Solution>, Overflow> {
+    let mut sorted = input.to_vec();
+    sorted.sort_unstable();
+    let mut solutions = Vec::new();
+    collect_sums(&sorted, target, count, &mut Vec::new(), &mut solutions)?;
+    // The sets are found in order, so any repeated ones are next to each other.
+    solutions.dedup();
+    Ok(solutions)
+}
+
+/// Recursively choose entries in ascending order from the sorted candidates until `count` have
+/// been chosen, recording each choice which sums to the target.
+fn collect_sums(
+    candidates: &[u32],
+    target: u32,
+    count: usize,
+    chosen: &mut Vec<u32>,
+    solutions: &mut Vec<Solution>,
+) -> Result<(), Overflow> {
+    if count <= 1 {
+        // The last entry is fully determined, so look it up rather than trying each candidate.
+        if count == 1 && candidates.binary_search(&target).is_ok() {
+            chosen.push(target);
+            solutions.push(Solution::new(chosen.clone())?);
+            chosen.pop();
+        }
+        return Ok(());
+    }
+    for (i, &entry) in candidates.iter().enumerate() {
+        if entry > target {
+            // The candidates are sorted, so every later one is too big as well.
+            break;
+        }
+        chosen.push(entry);
+        collect_sums(
+            &candidates[i + 1..],
+            target - entry,
+            count - 1,
+            chosen,
+            solutions,
+        )?;
+        chosen.pop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_example() {
+        let input = [1721, 979, 366, 299, 675, 1456];
+
+        // If we give 0 here, there is no solution.
+        assert_eq!(find_pair(&input, 0), Ok(None));
+        assert_eq!(find_triple(&input, 0), Ok(None));
+
+        // Check they can reach the target.
+        let pair = find_pair(&input, TARGET).unwrap().unwrap();
+        assert_eq!(pair.product, 1721 * 299);
+        assert_eq!(pair.entries, vec![299, 1721]);
+
+        let triple = find_triple(&input, TARGET).unwrap().unwrap();
+        assert_eq!(triple.product, 979 * 366 * 675);
+        assert_eq!(triple.entries, vec![366, 675, 979]);
+    }
+
+    #[test]
+    fn test_find_all() {
+        let input = [1721, 979, 366, 299, 675, 1456];
+        assert_eq!(
+            find_all(&input, TARGET, 2),
+            Ok(vec![Solution::new(vec![299, 1721]).unwrap()])
+        );
+        assert_eq!(
+            find_all(&input, TARGET, 3),
+            Ok(vec![Solution::new(vec![366, 675, 979]).unwrap()])
+        );
+
+        // Inputs can have more than one solution.
+        let input = [1, 2, 3, 4, 5];
+        let pairs: Vec<Vec<u32>> = find_all(&input, 6, 2)
+            .unwrap()
+            .into_iter()
+            .map(|solution| solution.entries)
+            .collect();
+        assert_eq!(pairs, vec![vec![1, 5], vec![2, 4]]);
+        assert_eq!(find_all(&input, 100, 3), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_entries_used_once() {
+        // An entry can't be added to itself, but can to another of the same value.
+        let input = [1000, 20, 500];
+        assert_eq!(find_pair(&input, 2000), Ok(None));
+        assert_eq!(find_triple(&input, 2000), Ok(None));
+        assert_eq!(find_all(&input, 2000, 2), Ok(vec![]));
+        assert_eq!(find_all(&input, 2000, 3), Ok(vec![]));
+
+        let input = [1000, 20, 500, 1000, 500];
+        let pair = find_pair(&input, 2000).unwrap().unwrap();
+        assert_eq!(pair.entries, vec![1000, 1000]);
+        let triple = find_triple(&input, 2000).unwrap().unwrap();
+        assert_eq!(triple.entries, vec![500, 500, 1000]);
+        assert_eq!(find_all(&input, 2000, 2), Ok(vec![pair]));
+        assert_eq!(find_all(&input, 2000, 3), Ok(vec![triple]));
+    }
+
+    #[test]
+    fn test_overflowing_product() {
+        let input = [1 << 20, 1 << 21];
+        assert_eq!(find_pair(&input, 3 << 20), Err(Overflow));
+        assert_eq!(find_all(&input, 3 << 20, 2), Err(Overflow));
+    }
+}
@@ -0,0 +1,15 @@
+/// Hash maps and sets for the hash-heavy days, whose keys are small numbers and coordinates.
+///
+/// With the "fast-hash" feature these hash with FxHash, which is much quicker than the standard
+/// library's SipHash on such keys but gives no protection against keys chosen to collide. Without
+/// it they are the standard maps and sets. Either way, make them with `default()` or by
+/// collecting.
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "fast-hash")]
+pub type FastHasher = std::hash::BuildHasherDefault<rustc_hash::FxHasher>;
+#[cfg(not(feature = "fast-hash"))]
+pub type FastHasher = std::collections::hash_map::RandomState;
+
+pub type FastMap<K, V> = HashMap<K, V, FastHasher>;
+pub type FastSet<T> = HashSet<T, FastHasher>;
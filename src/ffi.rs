@@ -0,0 +1,372 @@
+/// Solving days from other languages, for embedding the solutions in harnesses not written in Rust
+/// and checking them against implementations in other languages.
+///
+/// Every day can be solved this way, with the defaults its binary uses when given no flags. With
+/// the `cdylib` feature the library exports `aoc_solve` for C callers, declared in
+/// `include/aoc2020.h`.
+use crate::adapters::{count_arrangements, joltage_differences, parse_adapters};
+use crate::allergens::{count_safe_appearances, dangerous_list, parse_menu, DangerousList};
+use crate::bags::{self, BagGraph};
+use crate::boarding::{decode_seats, find_my_seat, highest_id, COL_BITS, ROW_BITS};
+use crate::buses::{earliest_bus, earliest_lineup, parse_schedule};
+use crate::combat::{combat_score, parse_decks, recursive_combat_score, Rules};
+use crate::conway::{self, parse_initial};
+use crate::cups::CrabCups;
+use crate::customs::{count_answers, parse_groups, Threshold};
+use crate::docking::{parse_program, run_program, sum_v2, DecoderVersion};
+use crate::expenses;
+use crate::grammar::{count_matching, count_matching_with_loops, parse_transmission};
+use crate::grid::Grid;
+use crate::handshake::{encryption_key, Search};
+use crate::homework::{eval, PrecedenceTable};
+use crate::jigsaw::{corner_product, parse_puzzle, water_roughness, Pattern};
+use crate::lobby::{self, get_initial_tiles, FlipRules};
+use crate::navigation::{distance_by_heading, distance_by_waypoint, parse_instructions};
+use crate::parse_lines;
+use crate::passports::{count_complete, count_valid, parse_passports};
+use crate::password::count_valid_streaming;
+use crate::seating::{SeatingRules, Simulation, Tile};
+use crate::tickets::{error_rate, field_product, parse_notes};
+use crate::toboggan::{self, trees_hit_streaming};
+use crate::vaneck::VanEck;
+use crate::vm::{accumulator_after_fix, accumulator_at_loop, parse_code, InstructionSet};
+use crate::xmas::{find_weakness, first_invalid, PREAMBLE};
+use itertools::Itertools;
+use std::io;
+
+/// The days which can be solved without their binaries.
+pub const DAYS: [u32; 25] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+];
+
+/// An answer to a part as `solve` gives it, if there is one.
+trait ToAnswer {
+    fn to_answer(self) -> Option<String>;
+}
+
+impl<T: ToString> ToAnswer for Option<T> {
+    fn to_answer(self) -> Option<String> {
+        self.map(|answer| answer.to_string())
+    }
+}
+
+/// Parts whose solution always has an answer.
+macro_rules! always_answered {
+    ($($ty:ty),*) => {
+        $(impl ToAnswer for $ty {
+            fn to_answer(self) -> Option<String> {
+                Some(self.to_string())
+            }
+        })*
+    };
+}
+
+always_answered!(u32, u64, u128, i64, usize, String, DangerousList);
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Play the Day 15 memory game from a list of starting numbers until the target turn.
+fn memory_game(input: &str, target: usize) -> io::Result<Option<String>> {
+    let starting = input
+        .trim()
+        .split(',')
+        .map(|s| s.parse().map_err(|_| invalid("Expected a list of numbers")))
+        .collect::<io::Result<Vec<usize>>>()?;
+    Ok(VanEck::with_capacity(&starting, target)
+        .nth(target - 1)
+        .to_answer())
+}
+
+/// Play the Day 23 crab's game with the cups labelled in the input, and as many more as it takes
+/// to make up `size`.
+fn crab_cups(input: &str, size: u32, moves: usize) -> io::Result<CrabCups> {
+    let labels = input
+        .trim()
+        .chars()
+        .map(|ch| {
+            ch.to_digit(10)
+                .ok_or_else(|| invalid("Expected a cup label"))
+        })
+        .collect::<io::Result<Vec<u32>>>()?;
+    let mut cups = CrabCups::new(&labels, size.max(labels.len() as u32))?;
+    cups.play(moves);
+    Ok(cups)
+}
+
+/// Solve a part of a day from its puzzle input, as the day's binary would. Gives an error of kind
+/// `Unsupported` for a day or part which doesn't exist, and `None` if the input has no answer.
+pub fn solve(day: u32, part: u32, input: &str) -> io::Result<Option<String>> {
+    let answer = match (day, part) {
+        (1, 1) | (1, 2) => {
+            let entries = parse_lines(input.as_bytes()).collect::<io::Result<Vec<u32>>>()?;
+            match part {
+                1 => expenses::find_pair(&entries, expenses::TARGET)?,
+                _ => expenses::find_triple(&entries, expenses::TARGET)?,
+            }
+            .map(|solution| solution.product)
+            .to_answer()
+        }
+        (2, 1) => count_valid_streaming(input.as_bytes())?.0.to_answer(),
+        (2, 2) => count_valid_streaming(input.as_bytes())?.1.to_answer(),
+        (3, 1) => trees_hit_streaming(input.as_bytes(), &[(3, 1)])?[0].to_answer(),
+        (3, 2) => trees_hit_streaming(input.as_bytes(), &toboggan::SLOPES)?
+            .iter()
+            .product::<u64>()
+            .to_answer(),
+        (4, 1) => count_complete(&parse_passports(input)?).to_answer(),
+        (4, 2) => count_valid(&parse_passports(input)?).to_answer(),
+        (5, 1) | (5, 2) => {
+            let seats = decode_seats(input, ROW_BITS, COL_BITS)?;
+            match part {
+                1 => highest_id(&seats),
+                _ => find_my_seat(&seats),
+            }
+            .to_answer()
+        }
+        (6, 1) => count_answers(&parse_groups(input), Threshold::Any).to_answer(),
+        (6, 2) => count_answers(&parse_groups(input), Threshold::All).to_answer(),
+        (7, 1) => input
+            .parse::<BagGraph>()?
+            .containers_of(bags::TARGET)
+            .len()
+            .to_answer(),
+        (7, 2) => input
+            .parse::<BagGraph>()?
+            .contents_of(bags::TARGET)
+            .map(|contents| contents.values().sum::<usize>())
+            .to_answer(),
+        (8, 1) => accumulator_at_loop(&parse_code(input, InstructionSet::Handheld)?).to_answer(),
+        (8, 2) => accumulator_after_fix(&parse_code(input, InstructionSet::Handheld)?).to_answer(),
+        (9, 1) | (9, 2) => {
+            let numbers = parse_lines(input.as_bytes()).collect::<io::Result<Vec<u64>>>()?;
+            let invalid = first_invalid(numbers.iter().copied(), PREAMBLE)?;
+            match part {
+                1 => invalid,
+                _ => invalid
+                    .and_then(|target| find_weakness(&numbers, target))
+                    .map(|weakness| weakness.encryption_weakness()),
+            }
+            .to_answer()
+        }
+        (10, 1) | (10, 2) => {
+            let adapters = parse_adapters(input).map_err(|err| invalid(&err.to_string()))?;
+            match part {
+                1 => joltage_differences(&adapters),
+                _ => count_arrangements(&adapters),
+            }
+            .to_answer()
+        }
+        (11, 1) | (11, 2) => {
+            let floorplan = Grid::from_chars(input, Tile::from_ch)?;
+            let rules = match part {
+                1 => SeatingRules::PART_ONE,
+                _ => SeatingRules::PART_TWO,
+            };
+            Simulation::new(&floorplan, rules).run().to_answer()
+        }
+        (12, 1) => distance_by_heading(&parse_instructions(input)?).to_answer(),
+        (12, 2) => distance_by_waypoint(&parse_instructions(input)?).to_answer(),
+        (13, 1) => earliest_bus(&parse_schedule(input)?)?.to_answer(),
+        (13, 2) => earliest_lineup(&parse_schedule(input)?)?.to_answer(),
+        (14, 1) => run_program(&parse_program(input)?, DecoderVersion::V1)?.to_answer(),
+        (14, 2) => sum_v2(&parse_program(input)?)?.to_answer(),
+        (15, 1) => memory_game(input, 2020)?,
+        (15, 2) => memory_game(input, 30_000_000)?,
+        (16, 1) => error_rate(&parse_notes(input)?).to_answer(),
+        (16, 2) => field_product(&parse_notes(input)?, "departure").to_answer(),
+        (17, 1) | (17, 2) => {
+            let active = parse_initial(input)?;
+            match part {
+                1 => conway::run::<3>(&active, 6, conway::Engine::Sparse, false),
+                _ => conway::run::<4>(&active, 6, conway::Engine::Sparse, false),
+            }
+            .to_answer()
+        }
+        (18, 1) | (18, 2) => {
+            let precedence = match part {
+                1 => PrecedenceTable::PART_ONE,
+                _ => PrecedenceTable::PART_TWO,
+            };
+            input
+                .lines()
+                .map(|line| eval(line, &precedence))
+                .sum::<Result<i64, _>>()?
+                .to_answer()
+        }
+        (19, 1) => count_matching(&parse_transmission(input)?).to_answer(),
+        (19, 2) => count_matching_with_loops(&parse_transmission(input)?).to_answer(),
+        (20, 1) => corner_product(&parse_puzzle(input)?)?.to_answer(),
+        (20, 2) => water_roughness(&parse_puzzle(input)?, &Pattern::sea_monster())?.to_answer(),
+        (21, 1) => count_safe_appearances(&parse_menu(input)?).to_answer(),
+        (21, 2) => dangerous_list(&parse_menu(input)?)?.to_answer(),
+        (22, 1) => combat_score(&parse_decks(input)?).to_answer(),
+        (22, 2) => {
+            recursive_combat_score(&parse_decks(input)?, Rules::default(), &mut ()).to_answer()
+        }
+        (23, 1) => {
+            let cups = crab_cups(input, 0, 100)?;
+            cups.cups_after(1, cups.len() - 1)
+                .iter()
+                .map(|cup| cup.to_string())
+                .collect::<String>()
+                .to_answer()
+        }
+        (23, 2) => crab_cups(input, 1_000_000, 10_000_000)?
+            .cups_after(1, 2)
+            .iter()
+            .map(|&cup| cup as u64)
+            .product::<u64>()
+            .to_answer(),
+        (24, 1) | (24, 2) => {
+            let instructions = input
+                .lines()
+                .map(|line| line.parse())
+                .collect::<io::Result<Vec<lobby::Instruction>>>()?;
+            let black_tiles = get_initial_tiles(&instructions);
+            match part {
+                1 => black_tiles.len(),
+                _ => lobby::run(
+                    black_tiles,
+                    lobby::DAYS,
+                    FlipRules::EXHIBIT,
+                    lobby::Engine::Sparse,
+                ),
+            }
+            .to_answer()
+        }
+        (25, 1) => {
+            let (card_key, door_key) = input
+                .lines()
+                .map(|line| line.trim().parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+                .ok()
+                .and_then(|keys| keys.into_iter().collect_tuple())
+                .ok_or_else(|| invalid("Expected the card's and the door's public keys"))?;
+            encryption_key(card_key, door_key, Search::BabyStepGiantStep).to_answer()
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("There's no part {} of day {} to solve", part, day),
+            ))
+        }
+    };
+    Ok(answer)
+}
+
+/// `aoc_solve` was given a day or part which doesn't exist.
+pub const AOC_UNSUPPORTED: i32 = -1;
+/// `aoc_solve` was given an input which isn't UTF-8 or isn't a puzzle input for the day.
+pub const AOC_INVALID_INPUT: i32 = -2;
+/// `aoc_solve` found the input has no answer.
+pub const AOC_NO_ANSWER: i32 = -3;
+/// `aoc_solve` was given a buffer too small for the answer and its terminating NUL.
+pub const AOC_BUFFER_TOO_SMALL: i32 = -4;
+
+/// Solve a part of a day from its puzzle input, writing the answer to a buffer as a NUL-terminated
+/// string. Returns the length of the answer, not counting the NUL, or else one of the negative
+/// `AOC_` codes, in which case the buffer is left as it was.
+///
+/// # Safety
+///
+/// `input` must point to `len` readable bytes, and `out_buf` to `out_len` writable bytes.
+#[cfg(feature = "cdylib")]
+#[no_mangle]
+pub unsafe extern "C" fn aoc_solve(
+    day: u32,
+    part: u32,
+    input: *const u8,
+    len: usize,
+    out_buf: *mut std::os::raw::c_char,
+    out_len: usize,
+) -> i32 {
+    let input = match std::str::from_utf8(std::slice::from_raw_parts(input, len)) {
+        Ok(input) => input,
+        Err(_) => return AOC_INVALID_INPUT,
+    };
+    // A panic can't unwind into C, so is reported as input the solution couldn't cope with.
+    let answer = match std::panic::catch_unwind(|| solve(day, part, input)) {
+        Ok(Ok(Some(answer))) => answer,
+        Ok(Ok(None)) => return AOC_NO_ANSWER,
+        Ok(Err(e)) if e.kind() == io::ErrorKind::Unsupported => return AOC_UNSUPPORTED,
+        Ok(Err(_)) | Err(_) => return AOC_INVALID_INPUT,
+    };
+    if answer.len() >= out_len {
+        return AOC_BUFFER_TOO_SMALL;
+    }
+    std::ptr::copy_nonoverlapping(answer.as_ptr(), out_buf as *mut u8, answer.len());
+    *out_buf.add(answer.len()) = 0;
+    answer.len() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        let groups = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
+        assert_eq!(solve(6, 1, groups).unwrap().as_deref(), Some("11"));
+        assert_eq!(solve(6, 2, groups).unwrap().as_deref(), Some("6"));
+        assert_eq!(solve(15, 1, "0,3,6\n").unwrap().as_deref(), Some("436"));
+        assert_eq!(
+            solve(18, 2, "1 + (2 * 3) + (4 * (5 + 6))\n2 * 3 + (4 * 5)\n")
+                .unwrap()
+                .as_deref(),
+            Some("97")
+        );
+        assert_eq!(
+            solve(23, 1, "389125467").unwrap().as_deref(),
+            Some("67384529")
+        );
+        assert_eq!(
+            solve(25, 1, "5764801\n17807724\n").unwrap().as_deref(),
+            Some("14897079")
+        );
+
+        assert_eq!(
+            solve(1, 1, "1721\n979\n366\n299\n675\n1456\n")
+                .unwrap()
+                .as_deref(),
+            Some("514579")
+        );
+        assert_eq!(
+            solve(26, 1, "").unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+        assert_eq!(
+            solve(25, 2, "").unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+        assert_eq!(
+            solve(25, 1, "5764801\n").unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[cfg(feature = "cdylib")]
+    #[test]
+    fn test_aoc_solve() {
+        let input = b"0,3,6";
+        let mut out = [0x7f as std::os::raw::c_char; 8];
+        let solved = unsafe {
+            aoc_solve(
+                15,
+                1,
+                input.as_ptr(),
+                input.len(),
+                out.as_mut_ptr(),
+                out.len(),
+            )
+        };
+        assert_eq!(solved, 3);
+        assert_eq!(&out[..4], &[b'4' as _, b'3' as _, b'6' as _, 0]);
+
+        let solved = unsafe { aoc_solve(15, 1, input.as_ptr(), input.len(), out.as_mut_ptr(), 3) };
+        assert_eq!(solved, AOC_BUFFER_TOO_SMALL);
+        let solved = unsafe { aoc_solve(26, 1, input.as_ptr(), input.len(), out.as_mut_ptr(), 8) };
+        assert_eq!(solved, AOC_UNSUPPORTED);
+    }
+}
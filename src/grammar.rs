@@ -0,0 +1,542 @@
+/// Message rules of Day 19, and automata for matching messages against them.
+use crate::diagnostic::Diagnostic;
+use crate::fasthash::FastMap;
+use itertools::Itertools;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::rc::Rc;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Match {
+    // Matches another rule
+    Rule(u32),
+    // Matches a character directly
+    Char(char),
+}
+
+impl FromStr for Match {
+    type Err = io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(ch) = s
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .and_then(|s| s.parse().ok())
+        {
+            return Ok(Self::Char(ch));
+        }
+        s.parse().map(Self::Rule).map_err(|_| {
+            Diagnostic::new(
+                s,
+                format!(
+                    "Invalid match {:?}: expected a rule number or quoted character",
+                    s
+                ),
+            )
+            .into()
+        })
+    }
+}
+
+impl fmt::Display for Match {
+    /// Write the match as BNF, with rules in angle brackets and characters quoted.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Rule(key) => write!(f, "<{}>", key),
+            Self::Char(ch) => write!(f, "{:?}", ch.to_string()),
+        }
+    }
+}
+
+/// The alternatives a rule can match, each a series of matches in turn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub alternatives: Vec<Vec<Match>>,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let alternatives = self
+            .alternatives
+            .iter()
+            .map(|series| series.iter().join(" "))
+            .join(" | ");
+        write!(f, "{}", alternatives)
+    }
+}
+
+pub type Rules = FastMap<u32, Rule>;
+
+/// Write out every rule as BNF, with rule 0 first and the rest in order.
+pub fn to_bnf(rules: &Rules) -> String {
+    rules
+        .iter()
+        .sorted_by_key(|&(&key, _)| key)
+        .map(|(key, rule)| format!("<{}> ::= {}\n", key, rule))
+        .collect()
+}
+
+/// Parse rules of the form "<key>: <match> <match> | <match> ...", one per line. Errors carry a
+/// `Diagnostic` pointing at the rule or match at fault.
+pub fn parse_rules(s: &str) -> io::Result<Rules> {
+    s.lines()
+        .map(|line| {
+            let invalid = || Diagnostic::new(line, format!("Invalid rule {:?}", line));
+            let (key, rule) = line
+                .split(':')
+                .map(|s| s.trim())
+                .collect_tuple()
+                .ok_or_else(invalid)?;
+            let key = key.parse().map_err(|_| invalid())?;
+            let alternatives = rule
+                .split('|')
+                .map(|series| series.split_whitespace().map(|s| s.parse()).collect())
+                .collect::<io::Result<_>>()?;
+            Ok((key, Rule { alternatives }))
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+enum State {
+    /// Move on to the next state by reading a character.
+    Char(char, usize),
+    /// Move on to any of the next states without reading anything.
+    Split(Vec<usize>),
+    Accept,
+}
+
+/// A nondeterministic finite automaton matching the same strings as a rule.
+///
+/// Matching runs every path through the automaton at once, a character at a time, so unlike
+/// listing every string a rule matches it stays small however many strings that is.
+#[derive(Clone, Debug)]
+pub struct Nfa {
+    states: Vec<State>,
+    start: usize,
+}
+
+const ACCEPT: usize = 0;
+
+impl Nfa {
+    /// Compile a rule into an automaton, by Thompson's construction with every rule it refers to
+    /// inlined. Errors if the rule refers to one missing, or to itself (so isn't regular).
+    pub fn compile(rules: &Rules, key: u32) -> io::Result<Self> {
+        let mut nfa = Self {
+            states: vec![State::Accept],
+            start: ACCEPT,
+        };
+        nfa.start = nfa.compile_rule(rules, key, ACCEPT, &mut Vec::new())?;
+        Ok(nfa)
+    }
+
+    fn push(&mut self, state: State) -> usize {
+        self.states.push(state);
+        self.states.len() - 1
+    }
+
+    /// Add states matching a rule and then moving on to `next`, returning the first of them.
+    fn compile_rule(
+        &mut self,
+        rules: &Rules,
+        key: u32,
+        next: usize,
+        compiling: &mut Vec<u32>,
+    ) -> io::Result<usize> {
+        if compiling.contains(&key) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Rule {} refers to itself so can't be compiled", key),
+            ));
+        }
+        let rule = rules.get(&key).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Missing rule {}", key))
+        })?;
+        compiling.push(key);
+        let mut starts = Vec::with_capacity(rule.alternatives.len());
+        for series in &rule.alternatives {
+            // Build each series back to front, since each match needs to know what follows it.
+            let mut start = next;
+            for item in series.iter().rev() {
+                start = match *item {
+                    Match::Char(ch) => self.push(State::Char(ch, start)),
+                    Match::Rule(key) => self.compile_rule(rules, key, start, compiling)?,
+                };
+            }
+            starts.push(start);
+        }
+        compiling.pop();
+        Ok(match starts.len() {
+            1 => starts[0],
+            _ => self.push(State::Split(starts)),
+        })
+    }
+
+    /// Add a state and every state reachable from it without reading to a set of states.
+    fn add_closure(&self, state: usize, set: &mut Vec<usize>, in_set: &mut [bool]) {
+        if in_set[state] {
+            return;
+        }
+        in_set[state] = true;
+        set.push(state);
+        if let State::Split(next) = &self.states[state] {
+            for &next in next {
+                self.add_closure(next, set, in_set);
+            }
+        }
+    }
+
+    /// The length in bytes of every prefix of the message the automaton matches, shortest first.
+    pub fn prefix_ends(&self, message: &str) -> Vec<usize> {
+        let mut ends = Vec::new();
+        let mut current = Vec::new();
+        let mut in_current = vec![false; self.states.len()];
+        self.add_closure(self.start, &mut current, &mut in_current);
+        let mut next = Vec::new();
+        let mut in_next = vec![false; self.states.len()];
+        for (offset, ch) in message.char_indices() {
+            if in_current[ACCEPT] {
+                ends.push(offset);
+            }
+            for &state in &current {
+                in_current[state] = false;
+                if let State::Char(expected, state) = self.states[state] {
+                    if expected == ch {
+                        self.add_closure(state, &mut next, &mut in_next);
+                    }
+                }
+            }
+            if next.is_empty() {
+                return ends;
+            }
+            current.clear();
+            mem::swap(&mut current, &mut next);
+            mem::swap(&mut in_current, &mut in_next);
+        }
+        if in_current[ACCEPT] {
+            ends.push(message.len());
+        }
+        ends
+    }
+
+    /// Whether the automaton matches the whole message.
+    pub fn is_match(&self, message: &str) -> bool {
+        self.prefix_ends(message).last() == Some(&message.len())
+    }
+}
+
+/// Whether a rule matches the whole of a message, for any rules at all.
+///
+/// Rules which refer to themselves can match strings no automaton can (like some number of one
+/// rule followed by as many of another), so this matches from the top down instead: see `TopDown`.
+pub fn matches(rules: &Rules, key: u32, message: &str) -> io::Result<bool> {
+    TopDown::new(rules).matches(key, message)
+}
+
+/// Matches messages against rules from the top down: for each offset into a message, every offset
+/// each rule can match up to from there is worked out once and remembered, matching characters
+/// against the rest of the message from the offset without copying it.
+///
+/// The rules are numbered afresh from 0 so the tables are only as big as the number of rules,
+/// however large their own numbers, and the tables are kept from one message to the next.
+pub struct TopDown<'a> {
+    rules: &'a Rules,
+    /// The dense number of each rule, indexing its row of the tables.
+    index: FastMap<u32, usize>,
+    /// How many offsets there are into the message being matched, including its end.
+    width: usize,
+    /// Every byte offset each rule matches up to from each offset it has been matched from, by
+    /// `slot`.
+    ends: Vec<Option<Rc<Vec<usize>>>>,
+    /// The rules being matched from each offset, to catch a rule needing itself there.
+    matching: Vec<bool>,
+}
+
+impl<'a> TopDown<'a> {
+    pub fn new(rules: &'a Rules) -> Self {
+        Self {
+            rules,
+            index: rules.keys().enumerate().map(|(i, &key)| (key, i)).collect(),
+            width: 0,
+            ends: Vec::new(),
+            matching: Vec::new(),
+        }
+    }
+
+    /// Whether a rule matches the whole of a message. Errors if a rule refers to one missing, or
+    /// to itself without matching anything first, which would never finish.
+    pub fn matches(&mut self, key: u32, message: &str) -> io::Result<bool> {
+        self.width = message.len() + 1;
+        let slots = self.index.len() * self.width;
+        self.ends.clear();
+        self.ends.resize(slots, None);
+        self.matching.clear();
+        self.matching.resize(slots, false);
+        Ok(self.rule_ends(key, message, 0)?.contains(&message.len()))
+    }
+
+    /// The index into the tables of a rule matched from an offset, so they needn't be hashed.
+    fn slot(&self, key: u32, start: usize) -> usize {
+        self.index[&key] * self.width + start
+    }
+
+    fn rule_ends(&mut self, key: u32, message: &str, start: usize) -> io::Result<Rc<Vec<usize>>> {
+        let rule = self.rules.get(&key).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Missing rule {}", key))
+        })?;
+        let slot = self.slot(key, start);
+        if let Some(ends) = &self.ends[slot] {
+            return Ok(Rc::clone(ends));
+        }
+        if mem::replace(&mut self.matching[slot], true) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Rule {} refers to itself without matching anything first",
+                    key
+                ),
+            ));
+        }
+        let mut ends = Vec::new();
+        for series in &rule.alternatives {
+            // Follow every way of matching the series so far, a match at a time.
+            let mut positions = vec![start];
+            for item in series {
+                let mut next = Vec::new();
+                for position in positions {
+                    match *item {
+                        Match::Char(ch) => {
+                            if message[position..].starts_with(ch) {
+                                next.push(position + ch.len_utf8());
+                            }
+                        }
+                        Match::Rule(key) => {
+                            next.extend(self.rule_ends(key, message, position)?.iter())
+                        }
+                    }
+                }
+                next.sort_unstable();
+                next.dedup();
+                positions = next;
+            }
+            ends.extend(positions);
+        }
+        ends.sort_unstable();
+        ends.dedup();
+        self.matching[slot] = false;
+        let ends = Rc::new(ends);
+        self.ends[slot] = Some(Rc::clone(&ends));
+        Ok(ends)
+    }
+}
+
+/// The rules and the messages received from the satellite.
+#[derive(Clone, Debug)]
+pub struct Transmission {
+    pub rules: Rules,
+    pub messages: Vec<String>,
+}
+
+/// Count the messages matching rule 0, with an automaton compiled from the rules, for part one.
+pub fn count_matching(data: &Transmission) -> Option<usize> {
+    let nfa = Nfa::compile(&data.rules, 0).ok()?;
+    Some(
+        data.messages
+            .iter()
+            .filter(|message| nfa.is_match(message))
+            .count(),
+    )
+}
+
+/// Count the messages matching rule 0, matching each from the top down.
+pub fn count_top_down(rules: &Rules, messages: &[String]) -> Option<usize> {
+    let mut matcher = TopDown::new(rules);
+    let matched: Option<Vec<bool>> = messages
+        .iter()
+        .map(|message| matcher.matches(0, message).ok())
+        .collect();
+    Some(matched?.into_iter().filter(|&matched| matched).count())
+}
+
+/// Count the messages for part one by matching from the top down, as part two must, rather than
+/// with an automaton.
+pub fn count_matching_top_down(data: &Transmission) -> Option<usize> {
+    count_top_down(&data.rules, &data.messages)
+}
+
+/// Count the messages matching rule 0 once rules 8 and 11 loop, for part two.
+pub fn count_matching_with_loops(data: &Transmission) -> Option<usize> {
+    // Rules 8 and 11 are replaced by ones which refer to themselves:
+    //     8: 42 | 42 8
+    //     11: 42 31 | 42 11 31
+    // So rule 0 no longer matches a finite set of strings, and needs the general matcher.
+    let mut rules = data.rules.clone();
+    rules.extend(parse_rules("8: 42 | 42 8\n11: 42 31 | 42 11 31").ok()?);
+    count_top_down(&rules, &data.messages)
+}
+
+/// Parse the rules and messages, pointing any error at where it is in the input.
+pub fn parse_transmission(input: &str) -> Result<Transmission, Diagnostic> {
+    let (rules, messages) = input.split("\n\n").next_tuple().ok_or_else(|| {
+        Diagnostic::new(
+            &input[input.len()..],
+            "Expected rules and messages separated by a blank line",
+        )
+        .locate(input)
+    })?;
+    Ok(Transmission {
+        rules: parse_rules(rules).map_err(|err| Diagnostic::from_io(err, rules).locate(input))?,
+        messages: messages.lines().map(|s| s.to_string()).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nfa() {
+        let rules =
+            parse_rules("0: 4 1 5\n1: 2 3 | 3 2\n2: 4 4 | 5 5\n3: 4 5 | 5 4\n4: \"a\"\n5: \"b\"")
+                .unwrap();
+        let nfa = Nfa::compile(&rules, 0).unwrap();
+        assert!(nfa.is_match("ababbb"));
+        assert!(nfa.is_match("abbbab"));
+        assert!(!nfa.is_match("bababa"));
+        assert!(!nfa.is_match("aaaabbb"));
+        assert!(!nfa.is_match(""));
+        assert_eq!(
+            Nfa::compile(&rules, 2).unwrap().prefix_ends("aabb"),
+            vec![2]
+        );
+
+        // Rules can match strings of different lengths, and long runs of characters.
+        let rules = parse_rules("0: 1 | 1 0 1\n1: \"x\"").unwrap();
+        assert!(Nfa::compile(&rules, 0).is_err());
+        let rules = parse_rules("0: 1 1 1 1 | 1\n1: 2 2 2 2\n2: \"x\"").unwrap();
+        let nfa = Nfa::compile(&rules, 0).unwrap();
+        assert_eq!(nfa.prefix_ends(&"x".repeat(20)), vec![4, 16]);
+        assert!(Nfa::compile(&rules, 3).is_err());
+        assert!(parse_rules("0: 1 | x").is_err());
+    }
+
+    #[test]
+    fn test_recursive() {
+        // As many of rule 1 as rule 2, which no automaton can match.
+        let rules = parse_rules("0: 1 2 | 1 0 2\n1: \"a\"\n2: \"b\"").unwrap();
+        assert!(matches(&rules, 0, "aaabbb").unwrap());
+        assert!(!matches(&rules, 0, "aaabb").unwrap());
+        assert!(!matches(&rules, 0, "").unwrap());
+
+        // Rules matched only to look ahead at the rest of the message are fine, but one needing
+        // itself before matching anything is not.
+        let rules = parse_rules("0: 1 | 1 0\n1: \"a\" | \"a\" \"a\"").unwrap();
+        assert!(matches(&rules, 0, "aaaaa").unwrap());
+        let rules = parse_rules("0: 0 1 | 1\n1: \"a\"").unwrap();
+        assert!(matches(&rules, 0, "aa").is_err());
+        assert!(matches(&rules, 3, "a").is_err());
+
+        // Characters needn't be a single byte.
+        let rules = parse_rules("0: 1 2 | 1 0 2\n1: \"é\"\n2: \"b\"").unwrap();
+        assert!(matches(&rules, 0, "éébb").unwrap());
+        assert!(!matches(&rules, 0, "ééb").unwrap());
+
+        // Rule numbers needn't be small or follow on from each other.
+        let rules = parse_rules("0: 4000000000 1\n4000000000: \"a\"\n1: \"b\"").unwrap();
+        let mut matcher = TopDown::new(&rules);
+        assert!(matcher.matches(0, "ab").unwrap());
+        assert!(!matcher.matches(0, "ba").unwrap());
+        assert!(matcher.matches(4000000000, "a").unwrap());
+    }
+
+    #[test]
+    fn test_bnf() {
+        let rules = parse_rules("1: 2 3 | 3 2\n0: 4 1 5\n4: \"a\"\n10: 4").unwrap();
+        let bnf = to_bnf(&rules);
+        assert_eq!(
+            bnf,
+            "<0> ::= <4> <1> <5>\n<1> ::= <2> <3> | <3> <2>\n<4> ::= \"a\"\n<10> ::= <4>\n"
+        );
+        assert_eq!(rules[&4].to_string(), "\"a\"");
+    }
+
+    #[test]
+    fn test_given_example() {
+        let input = "0: 4 1 5
+1: 2 3 | 3 2
+2: 4 4 | 5 5
+3: 4 5 | 5 4
+4: \"a\"
+5: \"b\"
+
+ababbb
+bababa
+abbbab
+aaabbb
+aaaabbb";
+
+        let data = parse_transmission(input).expect("Couldn't convert test input");
+
+        // Assert get the right number.
+        assert_eq!(count_matching(&data), Some(2));
+        assert_eq!(count_matching_top_down(&data), Some(2));
+    }
+
+    #[test]
+    fn test_given_example_part_two() {
+        let input = "42: 9 14 | 10 1
+9: 14 27 | 1 26
+10: 23 14 | 28 1
+1: \"a\"
+11: 42 31
+5: 1 14 | 15 1
+19: 14 1 | 14 14
+12: 24 14 | 19 1
+16: 15 1 | 14 14
+31: 14 17 | 1 13
+6: 14 14 | 1 14
+2: 1 24 | 14 4
+0: 8 11
+13: 14 3 | 1 12
+15: 1 | 14
+17: 14 2 | 1 7
+23: 25 1 | 22 14
+28: 16 1
+4: 1 1
+20: 14 14 | 1 15
+3: 5 14 | 16 1
+27: 1 6 | 14 18
+14: \"b\"
+21: 14 1 | 1 14
+25: 1 1 | 1 14
+22: 14 14
+8: 42
+26: 14 22 | 1 20
+18: 15 15
+7: 14 5 | 1 21
+24: 14 1
+
+abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa
+bbabbbbaabaabba
+babbbbaabbbbbabbbbbbaabaaabaaa
+aaabbbbbbaaaabaababaabababbabaaabbababababaaa
+bbbbbbbaaaabbbbaaabbabaaa
+bbbababbbbaaaaaaaabbababaaababaabab
+ababaaaaaabaaab
+ababaaaaabbbaba
+baabbaaaabbaaaababbaababb
+abbbbabbbbaaaababbbbbbaaaababb
+aaaaabbaabaaaaababaa
+aaaabbaaaabbaaa
+aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
+babaaabbbaaabaababbaabababaaab
+aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba";
+
+        let data = parse_transmission(input).expect("Couldn't convert test input");
+
+        // Assert get the right number.
+        assert_eq!(count_matching(&data), Some(3));
+        assert_eq!(count_matching_with_loops(&data), Some(12));
+    }
+}
@@ -0,0 +1,305 @@
+/// Shared dense 2D grid type and neighbourhood strategies for scanning it.
+use std::io;
+use std::ops::{Index, IndexMut};
+
+/// A (row, column) position within a grid.
+pub type Position = (usize, usize);
+
+/// The eight compass directions as (row, column) offsets.
+pub const DIRECTIONS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// The four orthogonal directions as (row, column) offsets.
+pub const DIRECTIONS_4: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// A rectangular grid of cells stored row-major in a single flat buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    /// Build a grid from a list of rows, which must all be the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> io::Result<Self> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Grid rows are not all the same length",
+            ));
+        }
+        Ok(Self {
+            cells: rows.into_iter().flatten().collect(),
+            width,
+            height,
+        })
+    }
+
+    /// Parse a grid from lines of characters, converting each with the given function.
+    pub fn from_chars<F>(s: &str, convert: F) -> io::Result<Self>
+    where
+        F: Fn(char) -> Option<T>,
+    {
+        let rows = s
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|ch| {
+                        convert(ch).ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("Invalid grid character {:?}", ch),
+                            )
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<io::Result<_>>()?;
+        Self::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get the cell at a position, if it is within the grid.
+    pub fn get(&self, (row, col): Position) -> Option<&T> {
+        if row < self.height && col < self.width {
+            Some(&self.cells[row * self.width + col])
+        } else {
+            None
+        }
+    }
+
+    /// The cells of the grid in row-major order, where the cell at (row, column) is at index
+    /// `row * width + column`.
+    pub fn as_slice(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// The cells of the grid in row-major order, mutably.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    /// Iterate over the rows of the grid as slices.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Iterate over every cell in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    /// Iterate mutably over every cell in row-major order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    /// Iterate over every cell along with its position in row-major order.
+    pub fn enumerate(&self) -> impl Iterator<Item = (Position, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(idx, cell)| ((idx / width, idx % width), cell))
+    }
+
+    /// Render the grid as text, drawing each cell as a character and each row on its own line.
+    pub fn render<F>(&self, draw: F) -> String
+    where
+        F: Fn(&T) -> char,
+    {
+        self.rows()
+            .map(|row| row.iter().map(&draw).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Step from a position by a (row, column) offset, if the result stays within the grid.
+    pub fn offset(&self, (row, col): Position, (drow, dcol): (isize, isize)) -> Option<Position> {
+        let row = row as isize + drow;
+        let col = col as isize + dcol;
+        if row >= 0 && col >= 0 && (row as usize) < self.height && (col as usize) < self.width {
+            Some((row as usize, col as usize))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): Position) -> &T {
+        assert!(col < self.width, "Column {} out of bounds", col);
+        &self.cells[row * self.width + col]
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, (row, col): Position) -> &mut T {
+        assert!(col < self.width, "Column {} out of bounds", col);
+        &mut self.cells[row * self.width + col]
+    }
+}
+
+/// A view of a grid which repeats infinitely to the right, so any column index is wrapped back
+/// into the grid.
+#[derive(Debug, Clone, Copy)]
+pub struct WrappedGrid<'a, T> {
+    grid: &'a Grid<T>,
+}
+
+impl<'a, T> WrappedGrid<'a, T> {
+    pub fn new(grid: &'a Grid<T>) -> Self {
+        Self { grid }
+    }
+
+    /// The width of a single repeat of the grid.
+    pub fn width(&self) -> usize {
+        self.grid.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height
+    }
+
+    /// Get the cell at a position, if its row is within the grid (and the grid is not empty).
+    pub fn get(&self, (row, col): Position) -> Option<&'a T> {
+        if self.grid.width == 0 {
+            return None;
+        }
+        self.grid.get((row, col % self.grid.width))
+    }
+}
+
+impl<'a, T> Index<Position> for WrappedGrid<'a, T> {
+    type Output = T;
+
+    fn index(&self, (row, col): Position) -> &T {
+        &self.grid[(row, col % self.grid.width)]
+    }
+}
+
+/// A strategy for choosing which cells count as the neighbours of a given cell.
+pub trait Neighborhood<T> {
+    /// Get the positions of the neighbours of the cell at the given position.
+    fn neighbours(&self, grid: &Grid<T>, pos: Position) -> Vec<Position>;
+
+    /// Count the neighbours of the cell at the given position which satisfy a predicate.
+    fn count<P>(&self, grid: &Grid<T>, pos: Position, predicate: P) -> usize
+    where
+        P: Fn(&T) -> bool,
+    {
+        self.neighbours(grid, pos)
+            .into_iter()
+            .filter(|&neighbour| predicate(&grid[neighbour]))
+            .count()
+    }
+}
+
+/// The eight cells immediately surrounding a cell, including diagonals.
+pub struct Adjacent8;
+
+impl<T> Neighborhood<T> for Adjacent8 {
+    fn neighbours(&self, grid: &Grid<T>, pos: Position) -> Vec<Position> {
+        DIRECTIONS_8
+            .iter()
+            .filter_map(|&direction| grid.offset(pos, direction))
+            .collect()
+    }
+}
+
+/// The four cells orthogonally adjacent to a cell.
+pub struct Adjacent4;
+
+impl<T> Neighborhood<T> for Adjacent4 {
+    fn neighbours(&self, grid: &Grid<T>, pos: Position) -> Vec<Position> {
+        DIRECTIONS_4
+            .iter()
+            .filter_map(|&direction| grid.offset(pos, direction))
+            .collect()
+    }
+}
+
+/// The first visible cell along each of the eight rays out from a cell, where cells failing the
+/// visibility predicate are looked straight through.
+pub struct FirstVisible<F> {
+    pub is_visible: F,
+}
+
+impl<T, F> Neighborhood<T> for FirstVisible<F>
+where
+    F: Fn(&T) -> bool,
+{
+    fn neighbours(&self, grid: &Grid<T>, pos: Position) -> Vec<Position> {
+        let mut visible = Vec::new();
+        for &direction in &DIRECTIONS_8 {
+            let mut seek = grid.offset(pos, direction);
+            while let Some(seen) = seek {
+                if (self.is_visible)(&grid[seen]) {
+                    visible.push(seen);
+                    break;
+                }
+                seek = grid.offset(seen, direction);
+            }
+        }
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighborhoods() {
+        let grid = Grid::from_chars("#..\n...\n..#", |ch| Some(ch == '#')).unwrap();
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 3);
+
+        // Corners only see their immediate surroundings.
+        assert_eq!(Adjacent8.neighbours(&grid, (0, 0)).len(), 3);
+        assert_eq!(Adjacent4.neighbours(&grid, (0, 0)).len(), 2);
+        assert_eq!(Adjacent8.count(&grid, (1, 1), |&cell| cell), 2);
+        assert_eq!(Adjacent4.count(&grid, (1, 1), |&cell| cell), 0);
+
+        // Looking along the diagonal from one corner finds the other.
+        let rays = FirstVisible {
+            is_visible: |&cell: &bool| cell,
+        };
+        assert_eq!(rays.neighbours(&grid, (0, 0)), vec![(2, 2)]);
+        assert_eq!(rays.count(&grid, (2, 2), |&cell| cell), 1);
+    }
+
+    #[test]
+    fn test_wrapped_grid() {
+        let grid = Grid::from_chars("#..\n.#.", |ch| Some(ch == '#')).unwrap();
+        let wrapped = WrappedGrid::new(&grid);
+        assert!(wrapped[(0, 3)]);
+        assert_eq!(wrapped.get((1, 301)), Some(&true));
+        assert_eq!(wrapped.get((2, 0)), None);
+    }
+
+    #[test]
+    fn test_ragged_rows_rejected() {
+        assert!(Grid::from_chars("..\n.", Some).is_err());
+    }
+}
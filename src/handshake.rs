@@ -0,0 +1,139 @@
+/// Combo breaker of Day 25: the handshake between the room key card and the door, which each
+/// transform a subject number by multiplying it into itself a secret number of times.
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+
+/// The number every value is taken the remainder of, which is prime.
+pub const MODULUS: u64 = 20201227;
+
+/// The subject number the card and the door transform to get their public keys.
+pub const SUBJECT: u64 = 7;
+
+/// Transform a subject number with a loop size: start with 1, then a loop size number of times
+/// multiply by the subject number and take the remainder modulo 20201227. Done by squaring, so
+/// takes the log of the loop size rather than the loop size.
+pub fn transform(subject: u64, mut loop_size: u64) -> u64 {
+    let mut base = subject % MODULUS;
+    let mut value = 1;
+    while loop_size != 0 {
+        if loop_size & 1 == 1 {
+            value = value * base % MODULUS;
+        }
+        base = base * base % MODULUS;
+        loop_size >>= 1;
+    }
+    value
+}
+
+/// How to search for the loop size which transforms the subject number into a public key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Search {
+    /// Transform the subject number a loop at a time until it matches, which takes as many steps
+    /// as the loop size (up to the modulus).
+    Naive,
+    /// Baby-step giant-step: remember the first √m transforms, then jump √m loops at a time from
+    /// the public key looking for one of them, which takes about 2√m steps for a modulus m.
+    BabyStepGiantStep,
+}
+
+impl FromStr for Search {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "naive" => Ok(Self::Naive),
+            "bsgs" => Ok(Self::BabyStepGiantStep),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid search {:?}: expected naive or bsgs", s),
+            )),
+        }
+    }
+}
+
+/// Find the smallest loop size transforming the subject number into a public key by trying each
+/// loop size in turn.
+fn naive_loop_size(public_key: u64) -> Option<u64> {
+    let mut value = 1;
+    for loop_size in 0..MODULUS - 1 {
+        if value == public_key {
+            return Some(loop_size);
+        }
+        value = value * SUBJECT % MODULUS;
+    }
+    None
+}
+
+/// Find the smallest loop size transforming the subject number into a public key with
+/// baby-step giant-step.
+///
+/// Any loop size below m - 1 can be written as i n + j, for n = ⌈√(m - 1)⌉ and i and j below n.
+/// The key is then 7^(i n + j), so multiplying it by 7^(-n) i times gives 7^j. The baby steps are
+/// a table of 7^j for every j, and the giant steps multiply the key by 7^(-n) until it lands in
+/// the table.
+fn baby_step_giant_step_loop_size(public_key: u64) -> Option<u64> {
+    let steps = ((MODULUS - 1) as f64).sqrt().ceil() as u64;
+    let mut baby_steps: HashMap<u64, u64> = HashMap::with_capacity(steps as usize);
+    let mut value = 1;
+    for j in 0..steps {
+        // Keep the smallest j for each value so the smallest loop size is found.
+        baby_steps.entry(value).or_insert(j);
+        value = value * SUBJECT % MODULUS;
+    }
+
+    // 7^(m - 1) is 1 as the modulus is prime, so 7^(m - 1 - n) is the inverse of 7^n.
+    let giant_step = transform(SUBJECT, MODULUS - 1 - steps);
+    let mut value = public_key;
+    for i in 0..steps {
+        if let Some(j) = baby_steps.get(&value) {
+            return Some(i * steps + j);
+        }
+        value = value * giant_step % MODULUS;
+    }
+    None
+}
+
+/// Find the smallest loop size transforming the subject number into a public key, or none if no
+/// loop size does.
+pub fn loop_size(public_key: u64, search: Search) -> Option<u64> {
+    if public_key >= MODULUS {
+        return None;
+    }
+    match search {
+        Search::Naive => naive_loop_size(public_key),
+        Search::BabyStepGiantStep => baby_step_giant_step_loop_size(public_key),
+    }
+}
+
+/// The encryption key the card and door agree on from their public keys, found by cracking the
+/// card's loop size and transforming the door's public key with it.
+pub fn encryption_key(card_key: u64, door_key: u64, search: Search) -> Option<u64> {
+    loop_size(card_key, search).map(|card_loop_size| transform(door_key, card_loop_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_size() {
+        for &search in &[Search::Naive, Search::BabyStepGiantStep] {
+            assert_eq!(loop_size(5764801, search), Some(8));
+            assert_eq!(loop_size(17807724, search), Some(11));
+            assert_eq!(loop_size(1, search), Some(0));
+            assert_eq!(loop_size(MODULUS, search), None);
+        }
+        for &loop_size_given in &[1, 3_000, 12_345_678, MODULUS - 2] {
+            let public_key = transform(SUBJECT, loop_size_given);
+            assert_eq!(
+                loop_size(public_key, Search::BabyStepGiantStep),
+                Some(loop_size_given)
+            );
+        }
+        assert_eq!(
+            encryption_key(5764801, 17807724, Search::BabyStepGiantStep),
+            Some(14897079)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+/// Coordinates of tiles on a hexagonal grid, as laid out in the lobby of Day 24.
+use std::io;
+use std::str::FromStr;
+
+/// Define the coordinate of a tile in the hexagonal grid as follows:
+/// - Reference tile is (0, 0)
+/// - Moving east is (1, 0), west is (-1, 0)
+/// - Draw the lines of "constant vertical" diagonally across the hexagons from north-west to
+///   south-east
+/// - This makes moving north-west (0, 1) but north-east (1, 1)
+/// - South-west is (-1, -1) and south-east (0, -1) (opposites of above)
+///
+/// This accounts for the hexagonal grid because although the tiles don't actually fit on these
+/// lines on a graph, different steps to the same tile will still give the same coordinate and it is
+/// unique to that tile.
+///
+/// ```text
+///    / \   / \   / \
+///   /   \ /   \ /   \
+///  |     |     |     |
+///  |-1,1 | 0,1 | 1,1 |
+///  |     |     |     |
+///   \   / \   / \   / \
+///    \ /   \ /   \ /   \
+///     |     |     |     |
+///     |-1,0 | 0,0 | 1,0 |
+///     |     |     |     |
+///      \   / \   / \   /
+///       \ /   \ /   \ /
+/// ```
+pub type Coord = (i32, i32);
+
+pub fn vec_add(vec1: Coord, vec2: Coord) -> Coord {
+    (vec1.0 + vec2.0, vec1.1 + vec2.1)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Direction {
+    E,
+    SE,
+    SW,
+    W,
+    NW,
+    NE,
+}
+
+impl FromStr for Direction {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "e" => Self::E,
+            "se" => Self::SE,
+            "sw" => Self::SW,
+            "w" => Self::W,
+            "nw" => Self::NW,
+            "ne" => Self::NE,
+            _ => panic!("Couldn't convert string into direction: {}", s),
+        })
+    }
+}
+
+impl Direction {
+    pub fn unit_vec(&self) -> Coord {
+        match self {
+            Self::E => (1, 0),
+            Self::W => (-1, 0),
+            Self::NW => (0, 1),
+            Self::NE => (1, 1),
+            Self::SE => (0, -1),
+            Self::SW => (-1, -1),
+        }
+    }
+}
+
+/// Find all coords which are adjecent to this one (there will be 6 because hexagons are the
+/// bestagons).
+pub fn get_adjacent_coords(coord: Coord) -> [Coord; 6] {
+    [
+        vec_add(coord, Direction::E.unit_vec()),
+        vec_add(coord, Direction::W.unit_vec()),
+        vec_add(coord, Direction::NW.unit_vec()),
+        vec_add(coord, Direction::NE.unit_vec()),
+        vec_add(coord, Direction::SE.unit_vec()),
+        vec_add(coord, Direction::SW.unit_vec()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_adjacent_coords() {
+        // Going north-west, west, south-west, east and east comes back round to the start.
+        let steps = ["nw", "w", "sw", "e", "e"];
+        let end = steps.iter().fold((0, 0), |coord, step| {
+            vec_add(coord, step.parse::<Direction>().unwrap().unit_vec())
+        });
+        assert_eq!(end, (0, 0));
+
+        // Every tile is adjacent to each tile adjacent to it, which are all different.
+        let adjacent = get_adjacent_coords((2, -1));
+        for &tile in &adjacent {
+            assert!(get_adjacent_coords(tile).contains(&(2, -1)));
+        }
+        assert_eq!(adjacent.iter().collect::<HashSet<_>>().len(), 6);
+    }
+}
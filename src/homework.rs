@@ -0,0 +1,356 @@
+/// Evaluator for the homework maths of Day 18, where operators needn't follow the usual order.
+use crate::checked::Overflow;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::iter::Peekable;
+use std::str::FromStr;
+
+pub type Number = i64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token {
+    Number(Number),
+    Operator(Operator),
+    Open,
+    Close,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    /// Subtraction, or negation before an operand.
+    Subtract,
+    Multiply,
+    /// Integer division, rounding towards zero.
+    Divide,
+}
+
+impl Operator {
+    fn apply(self, lhs: Number, rhs: Number) -> Result<Number, ExprError> {
+        let result = match self {
+            Self::Add => lhs.checked_add(rhs),
+            Self::Subtract => lhs.checked_sub(rhs),
+            Self::Multiply => lhs.checked_mul(rhs),
+            Self::Divide if rhs == 0 => return Err(ExprError::DivisionByZero),
+            Self::Divide => lhs.checked_div(rhs),
+        };
+        result.ok_or(ExprError::Overflow)
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Self::Add => '+',
+            Self::Subtract => '-',
+            Self::Multiply => '*',
+            Self::Divide => '/',
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// The ways an expression can fail to be tokenized, parsed or evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprError {
+    InvalidCharacter(char),
+    /// A number literal which doesn't fit in a `Number`.
+    NumberTooLarge(String),
+    UnexpectedToken(Token),
+    UnexpectedEnd,
+    DivisionByZero,
+    Overflow,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidCharacter(ch) => write!(f, "Invalid character {:?} in expression", ch),
+            Self::NumberTooLarge(literal) => {
+                write!(f, "Number {} in expression is too large", literal)
+            }
+            Self::UnexpectedToken(token) => write!(f, "Unexpected {:?} in expression", token),
+            Self::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            Self::DivisionByZero => write!(f, "Division by zero"),
+            Self::Overflow => write!(f, "{}", Overflow),
+        }
+    }
+}
+
+impl Error for ExprError {}
+
+impl From<ExprError> for io::Error {
+    fn from(err: ExprError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Evaluate an expression, with operators binding by the given precedence.
+pub fn eval(expr: &str, precedence: &PrecedenceTable) -> Result<Number, ExprError> {
+    Expression::parse(&tokenize(expr)?, precedence)?.evaluate()
+}
+
+/// Split an expression into tokens, ignoring whitespace.
+pub fn tokenize(s: &str) -> Result<Vec<Token>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let token = match ch {
+            '+' => Token::Operator(Operator::Add),
+            '-' => Token::Operator(Operator::Subtract),
+            '*' => Token::Operator(Operator::Multiply),
+            '/' => Token::Operator(Operator::Divide),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            _ if ch.is_whitespace() => continue,
+            _ if ch.is_ascii_digit() => {
+                let mut literal = ch.to_string();
+                while let Some(&digit) = chars.peek().filter(|ch| ch.is_ascii_digit()) {
+                    literal.push(digit);
+                    chars.next();
+                }
+                match literal.parse() {
+                    Ok(number) => Token::Number(number),
+                    Err(_) => return Err(ExprError::NumberTooLarge(literal)),
+                }
+            }
+            _ => return Err(ExprError::InvalidCharacter(ch)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// How tightly each operator binds, where higher binds tighter and operators which bind equally
+/// are evaluated left to right.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrecedenceTable {
+    pub add: u8,
+    pub subtract: u8,
+    pub multiply: u8,
+    pub divide: u8,
+    /// How tightly a leading minus binds to the operand after it.
+    pub negate: u8,
+}
+
+impl PrecedenceTable {
+    /// The precedence of part one: every binary operator binds equally.
+    pub const PART_ONE: Self = Self {
+        add: 1,
+        subtract: 1,
+        multiply: 1,
+        divide: 1,
+        negate: 3,
+    };
+
+    /// The precedence of part two: addition and subtraction bind tighter than multiplication and
+    /// division.
+    pub const PART_TWO: Self = Self {
+        add: 2,
+        subtract: 2,
+        multiply: 1,
+        divide: 1,
+        negate: 3,
+    };
+
+    pub fn precedence(&self, operator: Operator) -> u8 {
+        match operator {
+            Operator::Add => self.add,
+            Operator::Subtract => self.subtract,
+            Operator::Multiply => self.multiply,
+            Operator::Divide => self.divide,
+        }
+    }
+}
+
+impl FromStr for PrecedenceTable {
+    type Err = io::Error;
+
+    /// Parse the precedence of a part by its name, "one" or "two".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one" => Ok(Self::PART_ONE),
+            "two" => Ok(Self::PART_TWO),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid precedence {:?}: expected one or two", s),
+            )),
+        }
+    }
+}
+
+/// An expression as a tree of operations, with the brackets and precedence already applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expression {
+    Number(Number),
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Parse the tokens of an expression, with operators binding by the given precedence.
+    pub fn parse(tokens: &[Token], precedence: &PrecedenceTable) -> Result<Self, ExprError> {
+        let mut tokens = tokens.iter().copied().peekable();
+        let expression = parse_binding(&mut tokens, precedence, 0)?;
+        match tokens.next() {
+            None => Ok(expression),
+            Some(token) => Err(ExprError::UnexpectedToken(token)),
+        }
+    }
+
+    /// The value of the expression, erroring on division by zero or overflow.
+    pub fn evaluate(&self) -> Result<Number, ExprError> {
+        match self {
+            Self::Number(number) => Ok(*number),
+            Self::Negate(operand) => operand.evaluate()?.checked_neg().ok_or(ExprError::Overflow),
+            Self::Binary(operator, lhs, rhs) => operator.apply(lhs.evaluate()?, rhs.evaluate()?),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    /// Write the expression back out with every operation in brackets, so it parses to the same
+    /// tree whatever the precedence.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{}", number),
+            Self::Negate(operand) => write!(f, "-{}", operand),
+            Self::Binary(operator, lhs, rhs) => write!(f, "({} {} {})", lhs, operator, rhs),
+        }
+    }
+}
+
+fn unexpected(token: Option<Token>) -> ExprError {
+    match token {
+        Some(token) => ExprError::UnexpectedToken(token),
+        None => ExprError::UnexpectedEnd,
+    }
+}
+
+/// Parse an expression made of operators binding tighter than `min_precedence`, by Pratt parsing:
+/// each operand is followed by operators for as long as they bind tighter than the operator it
+/// was the right-hand side of, which then take it as their left-hand side.
+fn parse_binding<I>(
+    tokens: &mut Peekable<I>,
+    precedence: &PrecedenceTable,
+    min_precedence: u8,
+) -> Result<Expression, ExprError>
+where
+    I: Iterator<Item = Token>,
+{
+    let mut lhs = match tokens.next() {
+        Some(Token::Number(number)) => Expression::Number(number),
+        // A minus with no left-hand side negates the operand after it, along with any operators
+        // binding tighter than it.
+        Some(Token::Operator(Operator::Subtract)) => {
+            let operand = parse_binding(tokens, precedence, precedence.negate)?;
+            Expression::Negate(Box::new(operand))
+        }
+        Some(Token::Open) => {
+            let inner = parse_binding(tokens, precedence, 0)?;
+            match tokens.next() {
+                Some(Token::Close) => inner,
+                token => return Err(unexpected(token)),
+            }
+        }
+        token => return Err(unexpected(token)),
+    };
+    while let Some(&Token::Operator(operator)) = tokens.peek() {
+        let binding = precedence.precedence(operator);
+        if binding <= min_precedence {
+            break;
+        }
+        tokens.next();
+        // Operators binding equally are left-associative, so the right-hand side stops at them.
+        let rhs = parse_binding(tokens, precedence, binding)?;
+        lhs = Expression::Binary(operator, Box::new(lhs), Box::new(rhs));
+    }
+    Ok(lhs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let tokens = tokenize("2 * 3 + (4 * 5)").unwrap();
+        let number = |n| Box::new(Expression::Number(n));
+        assert_eq!(
+            Expression::parse(&tokens, &PrecedenceTable::PART_ONE).unwrap(),
+            Expression::Binary(
+                Operator::Add,
+                Box::new(Expression::Binary(Operator::Multiply, number(2), number(3))),
+                Box::new(Expression::Binary(Operator::Multiply, number(4), number(5))),
+            )
+        );
+        assert_eq!(
+            Expression::parse(&tokens, &PrecedenceTable::PART_TWO)
+                .unwrap()
+                .evaluate()
+                .unwrap(),
+            46
+        );
+
+        assert!(tokenize("1 % 2").is_err());
+        assert!(tokenize("99999999999999999999").is_err());
+        for invalid in &["1 +", "(1 + 2", "1 + 2)", "1 2", "* 3", ""] {
+            let tokens = tokenize(invalid).unwrap();
+            assert!(Expression::parse(&tokens, &PrecedenceTable::PART_ONE).is_err());
+        }
+    }
+
+    #[test]
+    fn test_more_operators() {
+        let (one, two) = (&PrecedenceTable::PART_ONE, &PrecedenceTable::PART_TWO);
+        assert_eq!("two".parse::<PrecedenceTable>().unwrap(), *two);
+        assert!("three".parse::<PrecedenceTable>().is_err());
+        assert_eq!(eval("12 + 345 * 10", one).unwrap(), 3570);
+        assert_eq!(eval("100 - 10 - 1", one).unwrap(), 89);
+        assert_eq!(eval("7 / 2 * 2", one).unwrap(), 6);
+        assert_eq!(eval("2 * 10 - 4 / 2", two).unwrap(), 6);
+        assert_eq!(eval("-3 * -(2 + -1)", one).unwrap(), 3);
+        // Negation binds tighter than any binary operator, so only takes the first operand.
+        assert_eq!(eval("-2 + 5", one).unwrap(), 3);
+        assert_eq!(eval("--7", one).unwrap(), 7);
+
+        assert_eq!(eval("1 / (2 - 2)", one), Err(ExprError::DivisionByZero));
+        assert_eq!(
+            eval("9223372036854775807 + 1", one),
+            Err(ExprError::Overflow)
+        );
+        assert_eq!(eval("(1 + 2", one), Err(ExprError::UnexpectedEnd));
+        assert_eq!(
+            eval("1 + 2)", one),
+            Err(ExprError::UnexpectedToken(Token::Close))
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let parse = |s: &str, precedence| Expression::parse(&tokenize(s).unwrap(), precedence);
+        let (one, two) = (&PrecedenceTable::PART_ONE, &PrecedenceTable::PART_TWO);
+        assert_eq!(
+            parse("1 + 2 * -(3 - 4)", two).unwrap().to_string(),
+            "((1 + 2) * -(3 - 4))"
+        );
+
+        let examples = [
+            "1 + 2 * 3 + 4 * 5 + 6",
+            "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))",
+            "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2",
+            "--12 / -(3 - 40) * 7 - -1",
+        ];
+        for example in &examples {
+            for &precedence in &[one, two] {
+                let expression = parse(example, precedence).unwrap();
+                let printed = expression.to_string();
+                // Once printed, the brackets alone decide the tree, whatever the precedence.
+                assert_eq!(parse(&printed, one).unwrap(), expression);
+                assert_eq!(parse(&printed, two).unwrap(), expression);
+                assert_eq!(eval(&printed, one), expression.evaluate());
+            }
+        }
+    }
+}
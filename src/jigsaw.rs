@@ -0,0 +1,653 @@
+/// Jigsaw of image tiles of Day 20, fitted together by matching their edges.
+use crate::arena::{self, Scratch, ScratchMap, ScratchVec};
+use crate::checked::{self, Overflow};
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+
+// Respresent a row as a bitfield representing on (#) or off (.) pixels in a 10 byte array.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TileRow(pub u16);
+
+impl TileRow {
+    pub fn from_vec(v: &[bool]) -> Self {
+        let mut bitfield: u16 = 0;
+        for (i, p) in v.iter().rev().enumerate() {
+            if *p {
+                bitfield |= 1 << i;
+            }
+        }
+        Self(bitfield)
+    }
+
+    /// Flip the binary represention i.e. 1010101011 -> 1101010101
+    pub fn flip(&self) -> Self {
+        let mut new = 0;
+        for i in 0..10 {
+            if (self.0 & 1 << i) != 0 {
+                new |= 1 << (10 - 1 - i)
+            }
+        }
+        Self(new)
+    }
+
+    /// The same value for an edge whichever way it is read, so edges which can be fitted
+    /// together (with either tile flipped) share it.
+    pub fn canonical(&self) -> u16 {
+        self.0.min(self.flip().0)
+    }
+}
+
+/// The length of the side of every tile, which each edge has a bit for.
+pub const TILE_SIZE: usize = 10;
+
+/// How a tile has been turned from how it was given: flipped about the vertical axis first if at
+/// all, then rotated some quarter turns clockwise.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transform {
+    pub flipped: bool,
+    pub rotations: u8,
+}
+
+impl Transform {
+    /// Every way a tile can be turned.
+    pub fn all() -> impl Iterator<Item = Self> {
+        [false, true]
+            .iter()
+            .flat_map(|&flipped| (0..4).map(move |rotations| Self { flipped, rotations }))
+    }
+
+    /// The edges of a tile once turned, from its edges before, in [top, right, bottom, left] order.
+    pub fn apply_to_edges(&self, edges: &[TileRow; 4]) -> [TileRow; 4] {
+        let [top, right, bottom, left] = *edges;
+        let mut edges = if self.flipped {
+            // Flipping swaps the left and right edges, and every edge is then read the other way
+            // around the tile.
+            [top.flip(), left.flip(), bottom.flip(), right.flip()]
+        } else {
+            *edges
+        };
+        // Rotating clockwise moves each edge on to the next side, read the same way.
+        edges.rotate_right(self.rotations as usize);
+        edges
+    }
+
+    /// The transform which turns a tile back to how it was before this one.
+    pub fn inverse(&self) -> Self {
+        // Flipping then rotating one way is the same as rotating the other way then flipping.
+        Self {
+            flipped: self.flipped,
+            rotations: if self.flipped {
+                self.rotations
+            } else {
+                (4 - self.rotations) % 4
+            },
+        }
+    }
+
+    /// Turn a square grid of pixels the same way, moving every pixel once.
+    pub fn apply_to_pixels(&self, pixels: &mut Vec<Vec<bool>>) {
+        if *self == Self::default() {
+            return;
+        }
+        let len = pixels.len();
+        let turned = (0..len)
+            .map(|row| {
+                (0..len)
+                    .map(|col| {
+                        // Undo each quarter turn and then the flip to find where the pixel was.
+                        let (mut row, mut col) = (row, col);
+                        for _ in 0..self.rotations {
+                            let (prev_row, prev_col) = (len - 1 - col, row);
+                            row = prev_row;
+                            col = prev_col;
+                        }
+                        if self.flipped {
+                            col = len - 1 - col;
+                        }
+                        pixels[row][col]
+                    })
+                    .collect()
+            })
+            .collect();
+        *pixels = turned;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Tile {
+    pub id: u32,
+    /// The pixels of the tile as it is currently turned.
+    pub rows: Vec<Vec<bool>>,
+    pub transform: Transform,
+    /// The edges of the tile as it was given, which every other orientation's are derived from.
+    given_edges: [TileRow; 4],
+}
+
+impl FromStr for Tile {
+    type Err = io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let (label, grid) = s
+            .split(":\n")
+            .next_tuple()
+            .ok_or_else(|| invalid(format!("Expected a tile label and pixels in {:?}", s)))?;
+        let id = label
+            .trim_start_matches("Tile ")
+            .parse()
+            .map_err(|_| invalid(format!("Invalid tile label {:?}", label)))?;
+        let rows = grid
+            .lines()
+            .map(|s| {
+                s.chars()
+                    .map(|ch| match ch {
+                        '#' => Ok(true),
+                        '.' => Ok(false),
+                        _ => Err(invalid(format!("Bad character {:?} in tile {}", ch, id))),
+                    })
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        if rows.len() != TILE_SIZE || rows.iter().any(|row| row.len() != TILE_SIZE) {
+            return Err(invalid(format!(
+                "Tile {} is not {} pixels square",
+                id, TILE_SIZE
+            )));
+        }
+        Ok(Self {
+            id,
+            given_edges: pixel_edges(&rows),
+            rows,
+            transform: Transform::default(),
+        })
+    }
+}
+
+/// Read the edges of a grid of pixels in [top, right, bottom, left] order, each clockwise around
+/// the grid.
+pub fn pixel_edges(rows: &[Vec<bool>]) -> [TileRow; 4] {
+    let top = TileRow::from_vec(&rows[0]);
+    // Convention for the bottom edge is if the tile were rotated 180 degrees, it would have the
+    // same value as the bottom edge, i.e. convention is left to right
+    let bottom = TileRow::from_vec(&rows[rows.len() - 1]).flip();
+    // Convention for right edge is top to bottom
+    let mut right = TileRow(0);
+    // Convention for left edge is bottom to top
+    let mut left = TileRow(0);
+    for (i, row) in rows.iter().enumerate() {
+        // Pixel is "on" on LHS
+        if row[0] {
+            left.0 |= 1 << i;
+        }
+        // Pixel is "on" on RHS
+        if row[rows.len() - 1] {
+            right.0 |= 1 << (rows.len() - 1 - i);
+        }
+    }
+    [top, right, bottom, left]
+}
+
+impl Tile {
+    /// Return the edges of the tile in [top, right, bottom, left] order for its current
+    /// orientation.
+    pub fn edges(&self) -> [TileRow; 4] {
+        self.edges_turned(self.transform)
+    }
+
+    /// Return the edges the tile would have if turned from how it was given, without turning its
+    /// pixels.
+    pub fn edges_turned(&self, transform: Transform) -> [TileRow; 4] {
+        transform.apply_to_edges(&self.given_edges)
+    }
+
+    /// Turn the tile from how it was given.
+    pub fn turn(&mut self, transform: Transform) {
+        self.transform.inverse().apply_to_pixels(&mut self.rows);
+        transform.apply_to_pixels(&mut self.rows);
+        self.transform = transform;
+    }
+}
+
+/// Flip a set of pixels as a grid about the vertical axis.
+pub fn flip_pixels(grid: &mut [Vec<bool>]) {
+    for row in grid.iter_mut() {
+        row.reverse();
+    }
+}
+
+/// Rotate a set of pixels as a grid 90 degrees clockwise.
+pub fn rotate_pixels(grid: &mut Vec<Vec<bool>>) {
+    let len = grid[0].len();
+    *grid = (0..len)
+        .map(|x| (1..=len).map(|y| grid[len - y][x]).collect())
+        .collect();
+}
+
+/// The tiles along with the index of every tile by each of its edges, which both parts share.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub tiles: Vec<Tile>,
+    /// The index in `tiles` of every tile with each canonical edge value.
+    pub edges: HashMap<u16, Vec<usize>>,
+}
+
+impl Puzzle {
+    pub fn new(tiles: Vec<Tile>) -> Self {
+        // One pass over every edge buckets the tiles which can fit together, rather than
+        // comparing every pair of tiles.
+        let mut edges: HashMap<u16, Vec<usize>> = HashMap::new();
+        for (index, tile) in tiles.iter().enumerate() {
+            for edge in tile.edges().iter() {
+                edges.entry(edge.canonical()).or_default().push(index);
+            }
+        }
+        Self { tiles, edges }
+    }
+
+    /// The index of the tile other than `index` which has an edge fitting `edge`, if any.
+    pub fn fitting(&self, index: usize, edge: TileRow) -> Option<usize> {
+        self.edges
+            .get(&edge.canonical())?
+            .iter()
+            .copied()
+            .find(|&other| other != index)
+    }
+}
+
+/// A tile fitted into the assembled image.
+#[derive(Clone, Debug)]
+pub struct Placement {
+    /// The tile, turned to fit.
+    pub tile: Tile,
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Every tile fitted together into a square, in order along each row from the top left.
+#[derive(Clone, Debug)]
+pub struct Assembly {
+    /// The number of tiles along each side.
+    pub size: usize,
+    pub placements: Vec<Placement>,
+}
+
+/// The offset in (row, col) to the neighbour past each edge in [top, right, bottom, left] order.
+pub const NEIGHBOUR_OFFSETS: [(i64, i64); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+fn invalid_puzzle(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Fit every tile together, flipping and rotating the tiles as appropriate.
+///
+/// Start with the first tile. This is placed as it is, and build the rest of the puzzle around
+/// this tile. Each of its sides will match a side from at most one other tile, found by looking
+/// up the edge in the puzzle's index.
+///
+/// When a tile is found that matches and isn't yet placed, the orientation which puts the edge in
+/// position is found from its cached edges, then it is turned that way, placed and added to the
+/// stack to process its own sides in turn. Then pop the stack and process the next tile until
+/// there are no tiles left: the puzzle pieces should all be connected together and oriented
+/// correctly.
+///
+/// Errors if the tiles don't fit into a square: an edge fits more than one other tile, a tile
+/// fits none of the others, or the tiles fit together into any other shape.
+pub fn assemble(puzzle: &Puzzle) -> io::Result<Assembly> {
+    if let Some(tiles) = puzzle.edges.values().find(|tiles| tiles.len() > 2) {
+        return Err(invalid_puzzle(format!(
+            "Ambiguous edge shared by tiles {}",
+            tiles.iter().map(|&index| puzzle.tiles[index].id).join(", ")
+        )));
+    }
+    let size = (puzzle.tiles.len() as f64).sqrt().round() as usize;
+    if size * size != puzzle.tiles.len() || size == 0 {
+        return Err(invalid_puzzle(format!(
+            "{} tiles can't form a square",
+            puzzle.tiles.len()
+        )));
+    }
+    arena::with_scratch(|scratch| place_tiles(puzzle, size, scratch))
+}
+
+/// Place every tile of a puzzle into a square of the given size, keeping track of where tiles
+/// are placed and which are left to process in the scratch space.
+fn place_tiles(puzzle: &Puzzle, size: usize, scratch: &Scratch) -> io::Result<Assembly> {
+    let mut tiles = puzzle.tiles.clone();
+    let mut positions: ScratchVec<Option<(i64, i64)>> = scratch.vec(tiles.len());
+    positions.resize(tiles.len(), None);
+    let mut occupied: ScratchMap<(i64, i64), usize> = scratch.map(tiles.len());
+    positions[0] = Some((0, 0));
+    occupied.insert((0, 0), 0);
+    let mut processing_stack: ScratchVec<usize> = scratch.vec(tiles.len());
+    processing_stack.push(0);
+
+    while let Some(index) = processing_stack.pop() {
+        let (row, col) = positions[index].unwrap();
+        // Go over the current tile in [top, right, bottom, left] order.
+        for (i, edge) in tiles[index].edges().iter().map(|e| e.flip()).enumerate() {
+            // Seek the tile which contains this edge (or this edge flipped).
+            let other = match puzzle.fitting(index, edge) {
+                Some(other) => other,
+                None => continue,
+            };
+            let position = (row + NEIGHBOUR_OFFSETS[i].0, col + NEIGHBOUR_OFFSETS[i].1);
+            let edge_match = &mut tiles[other];
+            if positions[other].is_none() {
+                // Turn the tile so that edge matches our edge if the tile were rotated 180
+                // degrees, which some orientation does as the edges fit.
+                let transform = Transform::all()
+                    .find(|&transform| edge_match.edges_turned(transform)[(i + 2) % 4] == edge)
+                    .unwrap();
+                edge_match.turn(transform);
+                if let Some(&clash) = occupied.get(&position) {
+                    return Err(invalid_puzzle(format!(
+                        "Tiles {} and {} both fit in the same place",
+                        puzzle.tiles[clash].id, edge_match.id
+                    )));
+                }
+                positions[other] = Some(position);
+                occupied.insert(position, other);
+                processing_stack.push(other);
+            }
+
+            // Check the piece is in the correct orientation and place.
+            if edge_match.edges()[(i + 2) % 4] != edge || positions[other] != Some(position) {
+                return Err(invalid_puzzle(format!(
+                    "Tile {} fits tile {} but was already placed elsewhere",
+                    edge_match.id, puzzle.tiles[index].id
+                )));
+            }
+        }
+    }
+
+    if let Some(index) = positions.iter().position(Option::is_none) {
+        return Err(invalid_puzzle(format!(
+            "Tile {} doesn't fit with the others",
+            tiles[index].id
+        )));
+    }
+    // Every tile is placed, so the puzzle is square if they fill a square.
+    let top = occupied.keys().map(|&(row, _)| row).min().unwrap();
+    let left = occupied.keys().map(|&(_, col)| col).min().unwrap();
+    let mut placements = Vec::with_capacity(tiles.len());
+    for (tile, position) in tiles.into_iter().zip(positions) {
+        let (row, col) = position.unwrap();
+        let (row, col) = ((row - top) as usize, (col - left) as usize);
+        if row >= size || col >= size {
+            return Err(invalid_puzzle(format!(
+                "Tiles fit together but not into a {} by {} square",
+                size, size
+            )));
+        }
+        placements.push(Placement { tile, row, col });
+    }
+    placements.sort_by_key(|placement| (placement.row, placement.col));
+    Ok(Assembly { size, placements })
+}
+
+pub fn strip_border(grid: &mut Vec<Vec<bool>>) {
+    grid.remove(0);
+    grid.pop();
+    for row in grid.iter_mut() {
+        row.remove(0);
+        row.pop();
+    }
+}
+
+/// Strip borders from each tile and then form them into a singular image of "pixels", using their
+/// places in the assembly.
+pub fn form_image(assembly: &Assembly) -> Vec<Vec<bool>> {
+    let mut image: Vec<Vec<bool>> = Vec::new();
+    for row in assembly.placements.chunks(assembly.size) {
+        let mut new_rows = vec![Vec::<bool>::new(); TILE_SIZE - 2];
+        for placement in row {
+            let mut pixels = placement.tile.rows.clone();
+            strip_border(&mut pixels);
+            for (i, row) in pixels.iter().enumerate() {
+                new_rows[i].extend(row);
+            }
+        }
+        image.extend(new_rows);
+    }
+    image
+}
+
+/// A sketch of a sea monster, where '#' marks its pixels.
+pub const SEA_MONSTER: &str = "                  # \n#    ##    ##    ###\n #  #  #  #  #  #   ";
+
+/// A shape of pixels to search the image for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    /// The pixels of the shape, as (row, col) offsets from the top left of the space it fills.
+    cells: Vec<(usize, usize)>,
+    height: usize,
+    width: usize,
+}
+
+impl FromStr for Pattern {
+    type Err = io::Error;
+
+    /// Parse a sketch of the shape with '#' marking its pixels and ' ' or '.' the gaps, with rows
+    /// on separate lines or separated by '/' to fit on the command line.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cells = Vec::new();
+        for (row, line) in s.split(&['\n', '/'][..]).enumerate() {
+            for (col, ch) in line.chars().enumerate() {
+                match ch {
+                    '#' => cells.push((row, col)),
+                    ' ' | '.' => (),
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Bad character {:?} in pattern", ch),
+                        ))
+                    }
+                }
+            }
+        }
+        if cells.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Pattern has no pixels to search for",
+            ));
+        }
+        // Blank rows and columns around the pixels don't need room in the image.
+        let top = cells.iter().map(|&(row, _)| row).min().unwrap();
+        let left = cells.iter().map(|&(_, col)| col).min().unwrap();
+        for (row, col) in cells.iter_mut() {
+            *row -= top;
+            *col -= left;
+        }
+        let height = cells.iter().map(|&(row, _)| row).max().unwrap() + 1;
+        let width = cells.iter().map(|&(_, col)| col).max().unwrap() + 1;
+        Ok(Self {
+            cells,
+            height,
+            width,
+        })
+    }
+}
+
+impl Pattern {
+    /// The sea monster of the puzzle.
+    pub fn sea_monster() -> Self {
+        SEA_MONSTER.parse().expect("Invalid sea monster sketch")
+    }
+
+    /// Find the top left of the space filled by every match of the pattern in an image, which
+    /// may overlap.
+    pub fn find(&self, image: &[Vec<bool>]) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        let width = image.first().map_or(0, Vec::len);
+        for x in 0..(image.len() + 1).saturating_sub(self.height) {
+            for y in 0..(width + 1).saturating_sub(self.width) {
+                if self.cells.iter().all(|(dx, dy)| image[x + dx][y + dy]) {
+                    found.push((x, y));
+                }
+            }
+        }
+        found
+    }
+}
+
+/// An image turned to where sea monsters were found, along with the top left of each of them.
+pub type Sighting = (Vec<Vec<bool>>, Vec<(usize, usize)>);
+
+/// Seek the image for sea monsters in each orientation, giving the image turned to the first
+/// orientation which contains them along with where they are.
+pub fn orient_for_monsters(image: &[Vec<bool>], monster: &Pattern) -> Option<Sighting> {
+    let mut image = image.to_vec();
+    for orientation in 0..8 {
+        let monsters = monster.find(&image);
+        if !monsters.is_empty() {
+            return Some((image, monsters));
+        }
+        rotate_pixels(&mut image);
+        if orientation == 3 {
+            // Tried every rotation, so try them all again flipped
+            flip_pixels(&mut image);
+        }
+    }
+    None
+}
+
+/// Seek the image for sea monsters, then get the water roughness of the orientation of the image
+/// which contains them.
+fn get_water_roughness(image: &[Vec<bool>], monster: &Pattern) -> Option<u64> {
+    let (image, monsters) = orient_for_monsters(image, monster)?;
+    // Count the filled pixels which aren't part of any sea monster, which counts pixels shared by
+    // overlapping monsters once.
+    let pixels = classify(&image, &monsters, monster);
+    Some(
+        pixels
+            .iter()
+            .flatten()
+            .filter(|&&p| p == Pixel::Rough)
+            .count() as u64,
+    )
+}
+
+/// What each pixel of the image shows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pixel {
+    Calm,
+    Rough,
+    Monster,
+}
+
+/// Classify every pixel of the image, given the sea monsters found in it.
+pub fn classify(
+    image: &[Vec<bool>],
+    monsters: &[(usize, usize)],
+    monster: &Pattern,
+) -> Vec<Vec<Pixel>> {
+    let mut pixels: Vec<Vec<Pixel>> = image
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&on| if on { Pixel::Rough } else { Pixel::Calm })
+                .collect()
+        })
+        .collect();
+    for &(x, y) in monsters {
+        for (dx, dy) in monster.cells.iter() {
+            pixels[x + dx][y + dy] = Pixel::Monster;
+        }
+    }
+    pixels
+}
+
+/// Multiply together the IDs of the corner tiles, for part one.
+pub fn corner_product(data: &Puzzle) -> Result<Option<u64>, Overflow> {
+    // Corners are defined as tiles which have two sides which no matter how they are flipped are
+    // not the same as any other edge (or its mirror).
+    let corners = data.tiles.iter().enumerate().filter(|&(index, tile)| {
+        let non_fitting_edges = tile
+            .edges()
+            .iter()
+            .filter(|&&edge| data.fitting(index, edge).is_none())
+            .count();
+        non_fitting_edges > 1
+    });
+    Ok(Some(checked::product(
+        corners.map(|(_, tile)| tile.id as u64),
+    )?))
+}
+
+/// Assemble the image and find how rough the water is where there are no sea monsters, for part
+/// two.
+pub fn water_roughness(data: &Puzzle, monster: &Pattern) -> io::Result<Option<u64>> {
+    let image = form_image(&assemble(data)?);
+    Ok(get_water_roughness(&image, monster))
+}
+
+/// Parse every tile, separated by blank lines, into a puzzle.
+pub fn parse_puzzle(input: &str) -> io::Result<Puzzle> {
+    let tiles = input
+        .split("\n\n")
+        .map(|s| s.parse())
+        .collect::<io::Result<_>>()?;
+    Ok(Puzzle::new(tiles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transforms() {
+        let rows: Vec<Vec<bool>> = (0..TILE_SIZE)
+            .map(|row| {
+                (0..TILE_SIZE)
+                    .map(|col| (row * 7 + col * col) % 3 == 0)
+                    .collect()
+            })
+            .collect();
+        let tile = Tile {
+            id: 1,
+            given_edges: pixel_edges(&rows),
+            rows: rows.clone(),
+            transform: Transform::default(),
+        };
+        assert_eq!(Transform::all().count(), 8);
+        // The cached edges of every orientation match those read from the turned pixels.
+        for transform in Transform::all() {
+            let mut turned = tile.clone();
+            turned.turn(transform);
+            assert_eq!(turned.edges(), pixel_edges(&turned.rows));
+            let mut pixels = rows.clone();
+            if transform.flipped {
+                flip_pixels(&mut pixels);
+            }
+            for _ in 0..transform.rotations {
+                rotate_pixels(&mut pixels);
+            }
+            assert_eq!(turned.rows, pixels);
+            // Turning an already turned tile starts again from how it was given.
+            turned.turn(Transform::default());
+            assert_eq!(turned.rows, rows);
+        }
+    }
+
+    #[test]
+    fn test_pattern() {
+        let monster = Pattern::sea_monster();
+        assert_eq!(
+            (monster.cells.len(), monster.height, monster.width),
+            (15, 3, 20)
+        );
+        let pattern: Pattern = "../.##/.#.".parse().unwrap();
+        assert_eq!(pattern.cells, vec![(0, 0), (0, 1), (1, 0)]);
+        assert!("..".parse::<Pattern>().is_err());
+        assert!("#x".parse::<Pattern>().is_err());
+
+        // Overlapping matches share pixels, which are only taken out of the roughness once.
+        let image = vec![vec![true; 3], vec![false; 3], vec![false; 3]];
+        let pair: Pattern = "##".parse().unwrap();
+        assert_eq!(pair.find(&image), vec![(0, 0), (0, 1)]);
+        assert_eq!(get_water_roughness(&image, &pair), Some(0));
+        let image = vec![vec![false; 3], vec![true, false, true], vec![false; 3]];
+        assert_eq!(get_water_roughness(&image, &pair), None);
+    }
+}
@@ -0,0 +1,199 @@
+/// Private leaderboards, fetched from the Advent of Code API and cached so that it isn't asked
+/// more often than every 15 minutes, as the API asks.
+use serde_json::Value;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// The year of the event whose leaderboards are shown.
+pub const YEAR: u32 = 2020;
+
+/// How long a fetched leaderboard is kept before fetching it again.
+pub const REFRESH: Duration = Duration::from_secs(15 * 60);
+
+/// A member of a leaderboard and the stars they've got.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    pub local_score: u64,
+    /// The number of stars got on each day, from 0 to 2.
+    pub stars: [u8; 25],
+}
+
+impl Member {
+    pub fn total_stars(&self) -> u32 {
+        self.stars.iter().map(|&stars| stars as u32).sum()
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Read the members of a leaderboard from its JSON, with the highest scores first.
+pub fn parse(json: &str) -> io::Result<Vec<Member>> {
+    let leaderboard: Value = serde_json::from_str(json).map_err(io::Error::from)?;
+    let members = leaderboard["members"]
+        .as_object()
+        .ok_or_else(|| invalid("Expected a leaderboard with members"))?;
+    let mut parsed = members
+        .iter()
+        .map(|(id, member)| {
+            let name = match member["name"].as_str() {
+                Some(name) => name.to_string(),
+                None => format!("(anonymous user #{})", id),
+            };
+            let mut stars = [0; 25];
+            if let Some(days) = member["completion_day_level"].as_object() {
+                for (day, parts) in days {
+                    let day: usize = day.parse().map_err(|_| invalid("Expected a day"))?;
+                    if let (Some(slot), Some(parts)) =
+                        (stars.get_mut(day.wrapping_sub(1)), parts.as_object())
+                    {
+                        *slot = parts.len().min(2) as u8;
+                    }
+                }
+            }
+            Ok(Member {
+                name,
+                local_score: member["local_score"].as_u64().unwrap_or(0),
+                stars,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    parsed.sort_by(|a, b| {
+        b.local_score
+            .cmp(&a.local_score)
+            .then_with(|| b.total_stars().cmp(&a.total_stars()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(parsed)
+}
+
+/// Draw the members of a leaderboard in order, as the site does: each one's rank and score, then
+/// a `*` for each day with both stars and a `+` for each with one, then their name.
+pub fn render(members: &[Member]) -> String {
+    let mut out = String::new();
+    let prefix = " ".repeat(10);
+    let tens: String = (1..=25)
+        .map(|day| match day / 10 {
+            0 => ' ',
+            tens => (b'0' + tens) as char,
+        })
+        .collect();
+    let units: String = (1..=25).map(|day| (b'0' + day % 10) as char).collect();
+    writeln!(out, "{}{}", prefix, tens.trim_end()).unwrap();
+    writeln!(out, "{}{}", prefix, units).unwrap();
+    for (rank, member) in members.iter().enumerate() {
+        let stars: String = member
+            .stars
+            .iter()
+            .map(|stars| match stars {
+                0 => ' ',
+                1 => '+',
+                _ => '*',
+            })
+            .collect();
+        writeln!(
+            out,
+            "{:>3}) {:>4} {} {}",
+            rank + 1,
+            member.local_score,
+            stars,
+            member.name
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Where a leaderboard is cached between fetches.
+pub fn cache_path(id: &str) -> PathBuf {
+    PathBuf::from(format!("cache/leaderboard-{}-{}.json", YEAR, id))
+}
+
+/// The leaderboard's JSON as cached, with how long ago it was fetched, if it's still fresh.
+pub fn cached(id: &str) -> Option<(String, Duration)> {
+    let path = cache_path(id);
+    let age = SystemTime::now()
+        .duration_since(fs::metadata(&path).ok()?.modified().ok()?)
+        .unwrap_or_default();
+    if age >= REFRESH {
+        return None;
+    }
+    Some((fs::read_to_string(path).ok()?, age))
+}
+
+/// Fetch a leaderboard's JSON, signed in with a session token, and cache it.
+pub fn fetch(id: &str, session: &str) -> io::Result<String> {
+    let url = format!(
+        "https://adventofcode.com/{}/leaderboard/private/view/{}.json",
+        YEAR, id
+    );
+    let json = crate::fetch_page(&url, Some(session))?;
+    // Check it's a leaderboard before caching it, as a bad session gets a page asking to log in.
+    parse(&json)?;
+    let path = cache_path(id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, &json)?;
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEADERBOARD: &str = r#"{
+        "event": "2020",
+        "owner_id": 1,
+        "members": {
+            "1": {
+                "name": "Ada",
+                "local_score": 10,
+                "stars": 3,
+                "completion_day_level": {
+                    "1": {"1": {"get_star_ts": 1}, "2": {"get_star_ts": 2}},
+                    "3": {"1": {"get_star_ts": 3}}
+                }
+            },
+            "2": {
+                "name": null,
+                "local_score": 12,
+                "stars": 2,
+                "completion_day_level": {
+                    "25": {"1": {"get_star_ts": 4}, "2": {"get_star_ts": 5}}
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_parse() {
+        let members = parse(LEADERBOARD).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].name, "(anonymous user #2)");
+        assert_eq!(members[0].stars[24], 2);
+        assert_eq!(members[1].name, "Ada");
+        assert_eq!(&members[1].stars[..4], &[2, 0, 1, 0]);
+        assert_eq!(members[1].total_stars(), 3);
+        assert!(parse("<html>Log in</html>").is_err());
+        assert!(parse("{}").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = render(&parse(LEADERBOARD).unwrap());
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "                   1111111111222222");
+        assert_eq!(lines[1], "          1234567890123456789012345");
+        assert_eq!(
+            lines[2],
+            "  1)   12                         * (anonymous user #2)"
+        );
+        assert_eq!(lines[3], "  2)   10 * +                       Ada");
+    }
+}
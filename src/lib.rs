@@ -2,31 +2,367 @@
 ///
 /// Public API should be accessible within compiled binaries.
 ///
-use std::collections::HashSet;
-use std::fs;
-use std::time::Instant;
+use crate::answer::{Answer, DayResult};
+use std::env;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-/// Time a closure in microseconds and print the results.
-pub fn print_elapsed_time<T, F>(function: F) -> T
+pub mod adapters;
+pub mod allergens;
+pub mod allocations;
+pub mod animation;
+pub mod answer;
+pub mod arena;
+pub mod automaton;
+pub mod bags;
+pub mod bitset;
+pub mod boarding;
+pub mod buses;
+pub mod checked;
+pub mod combat;
+pub mod conway;
+pub mod cups;
+pub mod customs;
+pub mod diagnostic;
+pub mod docking;
+pub mod expenses;
+pub mod fasthash;
+pub mod ffi;
+pub mod grammar;
+pub mod grid;
+pub mod handshake;
+pub mod hex;
+pub mod homework;
+pub mod jigsaw;
+pub mod leaderboard;
+pub mod lobby;
+pub mod matching;
+pub mod modular;
+pub mod navigation;
+pub mod passports;
+pub mod password;
+pub mod progress;
+pub mod puzzle;
+pub mod report;
+pub mod seating;
+pub mod snapshot;
+pub mod svg;
+pub mod tickets;
+pub mod toboggan;
+pub mod vaneck;
+pub mod vm;
+pub mod xmas;
+
+/// Time a closure, giving what it returned and how long it took, and print the number of
+/// allocations it made if they are being counted.
+pub fn elapsed_time<T, F>(function: F) -> (T, Duration)
 where
-    F: Fn() -> T,
+    F: FnOnce() -> T,
 {
+    let allocations = allocations::allocations();
     let now = Instant::now();
     let ret = function();
-    println!("Took {}µs.", now.elapsed().as_micros());
+    let took = now.elapsed();
+    if let (Some(before), Some(after)) = (allocations, allocations::allocations()) {
+        println!("Made {} allocations.", after - before);
+    }
+    (ret, took)
+}
+
+/// Time a closure in microseconds and print the results, along with the number of allocations
+/// it made if they are being counted.
+pub fn print_elapsed_time<T, F>(function: F) -> T
+where
+    F: Fn() -> T,
+{
+    let (ret, took) = elapsed_time(function);
+    println!("Took {}µs.", took.as_micros());
     ret
 }
 
-/// Get a string read from a file in the "input" folder.
-pub fn get_day_input(day: &'static str) -> String {
-    let input_file = format!("input/{}.txt", day);
-    fs::read_to_string(&input_file).expect(&format!("Could not read input file {}", &input_file))
+/// Print the answer a day gave to a part and how long it took as a `DayResult`, e.g. "Day 7 part 1:
+/// 355 (375µs)", which is how the runner and its reports read the answers back.
+pub fn print_result<A: ToString>(day: &str, part: u32, answer: A, took: Duration) {
+    let answer = answer.to_string();
+    let result = DayResult {
+        day: day.parse().expect("Days are numbered"),
+        part,
+        answer: answer.parse().unwrap_or(Answer::Text(answer)),
+        duration: Some(took),
+    };
+    println!("{}", result);
+}
+
+/// Time two ways of solving the same thing against each other, printing how long each took and
+/// how many times faster the second was than the first.
+///
+/// Gives the second's answer and how long it took, as `elapsed_time` does. Panics if they give
+/// different answers, so doubles as a cross-check of the alternatives.
+pub fn compare<T, A, B>(first: &str, run_first: A, second: &str, run_second: B) -> (T, Duration)
+where
+    T: PartialEq + Debug,
+    A: Fn() -> T,
+    B: Fn() -> T,
+{
+    let now = Instant::now();
+    let first_ans = run_first();
+    let first_time = now.elapsed();
+    println!("{} took {}µs.", first, first_time.as_micros());
+    let now = Instant::now();
+    let second_ans = run_second();
+    let second_time = now.elapsed();
+    println!("{} took {}µs.", second, second_time.as_micros());
+    assert_eq!(
+        first_ans, second_ans,
+        "{} and {} gave different answers",
+        first, second
+    );
+    println!(
+        "{} was {:.2}x as fast as {}.",
+        second,
+        first_time.as_secs_f64() / second_time.as_secs_f64().max(f64::MIN_POSITIVE),
+        first
+    );
+    (second_ans, second_time)
+}
+
+/// The input source which stands for standard input, so that inputs can be piped in.
+pub const STDIN: &str = "-";
+
+/// Where a day's input comes from: the path or URL given by "--input <path or URL>", or else by
+/// the AOC_INPUT environment variable, or else the day's file in the "input" folder.
+fn input_source(day: &str) -> io::Result<String> {
+    Ok(get_option("--input")?
+        .or_else(|| env::var("AOC_INPUT").ok())
+        .unwrap_or_else(|| format!("input/{}.txt", day)))
+}
+
+/// Open an input, which is read from standard input if it's "-", fetched if it's an "http://" or
+/// "https://" URL and read from a file otherwise.
+pub fn open_input(source: &str) -> io::Result<Box<dyn Read>> {
+    if source == STDIN {
+        Ok(Box::new(io::stdin()))
+    } else if source.starts_with("http://") || source.starts_with("https://") {
+        fetch(source)
+    } else {
+        Ok(Box::new(File::open(source)?))
+    }
+}
+
+/// Add the header given by "--header <name: value>" to a request if there is one, e.g. to
+/// authorise it.
+#[cfg(feature = "fetch")]
+fn with_header(request: ureq::Request) -> io::Result<ureq::Request> {
+    match get_option::<String>("--header")? {
+        Some(header) => {
+            let (name, value) = header.split_once(':').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid header {:?}: expected \"name: value\"", header),
+                )
+            })?;
+            Ok(request.set(name.trim(), value.trim()))
+        }
+        None => Ok(request),
+    }
+}
+
+/// Fetch an input from a URL, sending the header given by "--header" with the request.
+#[cfg(feature = "fetch")]
+fn fetch(url: &str) -> io::Result<Box<dyn Read>> {
+    let response = with_header(ureq::get(url))?
+        .call()
+        .map_err(io::Error::other)?;
+    Ok(Box::new(response.into_reader()))
+}
+
+#[cfg(not(feature = "fetch"))]
+fn fetch(url: &str) -> io::Result<Box<dyn Read>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Can't fetch {}: build with --features fetch", url),
+    ))
+}
+
+/// Fetch a page from Advent of Code as text, signed in with a session token if given one.
+#[cfg(feature = "fetch")]
+pub fn fetch_page(url: &str, session: Option<&str>) -> io::Result<String> {
+    let mut request = ureq::get(url);
+    if let Some(session) = session {
+        request = request.set("Cookie", &format!("session={}", session));
+    }
+    request.call().map_err(io::Error::other)?.into_string()
+}
+
+#[cfg(not(feature = "fetch"))]
+pub fn fetch_page(url: &str, _: Option<&str>) -> io::Result<String> {
+    fetch(url).map(|_| String::new())
 }
 
-/// Get a set of numbers from an input string.
-pub fn get_num_set(input: String) -> HashSet<u32> {
+/// Post a JSON document to a URL, sending the header given by "--header" with the request.
+#[cfg(feature = "fetch")]
+pub fn post_json(url: &str, body: &str) -> io::Result<()> {
+    with_header(ureq::post(url))?
+        .set("Content-Type", "application/json")
+        .send_string(body)
+        .map_err(io::Error::other)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "fetch"))]
+pub fn post_json(url: &str, _: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Can't post to {}: build with --features fetch", url),
+    ))
+}
+
+/// Read the whole of an input as a string, from wherever `open_input` opens it.
+pub fn read_input(source: &str) -> io::Result<String> {
+    let mut input = String::new();
+    open_input(source)?.read_to_string(&mut input)?;
+    Ok(input)
+}
+
+/// Get a day's input as a string, read from its file in the "input" folder unless another file,
+/// a URL or "-" for standard input is given by "--input" or AOC_INPUT.
+pub fn get_day_input(day: &str) -> String {
+    let source = input_source(day).unwrap_or_else(|err| panic!("{}", err));
+    read_input(&source).unwrap_or_else(|err| panic!("Could not read input {}: {}", &source, err))
+}
+
+/// Get a buffered reader over a day's input, for streaming it line-by-line rather than reading
+/// it all into memory. The input comes from the same place as for `get_day_input`.
+pub fn get_day_reader(day: &str) -> io::Result<BufReader<Box<dyn Read>>> {
+    open_input(&input_source(day)?).map(BufReader::new)
+}
+
+/// Lazily parse each line of a reader into a value, so that a large input can be processed
+/// without ever holding it all in memory.
+pub fn parse_lines<R, T>(reader: R) -> impl Iterator<Item = io::Result<T>>
+where
+    R: BufRead,
+    T: FromStr,
+    T::Err: Debug,
+{
+    reader.lines().map(|line| {
+        let line = line?;
+        line.parse().map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Could not parse line {:?}: {:?}", line, err),
+            )
+        })
+    })
+}
+
+/// Print records parsed from an input, each with its index and pretty-printed debug formatting,
+/// for checking what a day's parser made of its input.
+pub fn dump_parsed<T: Debug>(kind: &str, records: impl IntoIterator<Item = T>) {
+    for (index, record) in records.into_iter().enumerate() {
+        println!("{} {}: {:#?}", kind, index, record);
+    }
+}
+
+/// Check whether a flag (e.g. "--stream") was passed on the command line.
+pub fn has_flag(flag: &str) -> bool {
+    env::args().skip(1).any(|arg| arg == flag)
+}
+
+/// Get the value of an option passed on the command line as "--name value", if present.
+pub fn get_option<T>(name: &str) -> io::Result<Option<T>>
+where
+    T: FromStr,
+{
+    let mut args = env::args().skip(1).skip_while(|arg| arg != name);
+    if args.next().is_none() {
+        return Ok(None);
+    }
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let value = args
+        .next()
+        .ok_or_else(|| invalid(format!("Missing value for {}", name)))?;
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| invalid(format!("Invalid value for {}: {:?}", name, value)))
+}
+
+/// Get a list of numbers from an input string.
+pub fn get_num_list(input: String) -> Vec<u32> {
     input
         .lines()
         .map(|s| s.parse().expect("Failed to parse input"))
         .collect()
 }
+
+/// Parse an inclusive range written as "a-b" (e.g. "1-3").
+pub fn parse_range<T: FromStr>(s: &str) -> Result<RangeInclusive<T>, io::Error> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("Invalid range {:?}", s));
+    let (low, high) = s.trim().split_once('-').ok_or_else(invalid)?;
+    let low = low.parse().map_err(|_| invalid())?;
+    let high = high.parse().map_err(|_| invalid())?;
+    Ok(low..=high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range::<u32>("1-3").unwrap(), 1..=3);
+        assert_eq!(parse_range::<usize>("10-20").unwrap(), 10..=20);
+        assert!(parse_range::<u32>("1").is_err());
+        assert!(parse_range::<u32>("a-3").is_err());
+        assert!(parse_range::<u32>("1-3-5").is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(
+            compare("sum", || (1..=10).sum::<u32>(), "formula", || 10 * 11 / 2).0,
+            55
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "gave different answers")]
+    fn test_compare_mismatch() {
+        compare("one", || 1, "two", || 2);
+    }
+
+    #[test]
+    fn test_open_input() {
+        let mut manifest = String::new();
+        open_input("Cargo.toml")
+            .unwrap()
+            .read_to_string(&mut manifest)
+            .unwrap();
+        assert!(manifest.starts_with("[package]"));
+        assert!(open_input("input/missing.txt").is_err());
+        #[cfg(not(feature = "fetch"))]
+        assert_eq!(
+            open_input("https://example.com/input.txt")
+                .err()
+                .map(|err| err.kind()),
+            Some(io::ErrorKind::Unsupported)
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let parsed: Vec<u32> = parse_lines("1\n2\n3\n".as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(parsed, vec![1, 2, 3]);
+
+        let mut bad = parse_lines::<_, u32>("1\nx\n".as_bytes());
+        assert!(bad.next().unwrap().is_ok());
+        assert!(bad.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,481 @@
+/// Lobby layout of Day 24: a floor of hexagonal tiles flipped between white and black.
+use crate::automaton;
+use crate::fasthash::FastSet;
+use crate::grid::Grid;
+use crate::hex::{get_adjacent_coords, vec_add, Coord, Direction};
+use crate::parse_lines;
+use crate::progress;
+use crate::snapshot::{self, Snapshot};
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    dirs: Vec<Direction>,
+}
+
+impl FromStr for Instruction {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars: Vec<char> = s.chars().rev().collect();
+        let mut dirs = Vec::new();
+        while !chars.is_empty() {
+            let mut dir_str = String::new();
+            dir_str.push(chars.pop().unwrap());
+            // The directions South and North don't exist: and s or n is always followed by a
+            // qualifier
+            if dir_str == "s" || dir_str == "n" {
+                dir_str.push(chars.pop().expect("Cannot have an S or N unqualified"));
+            }
+            dirs.push(dir_str.parse()?);
+        }
+        Ok(Self { dirs })
+    }
+}
+
+impl Instruction {
+    /// Convert the set of instructions to a final coordinate.
+    pub fn to_coord(&self) -> Coord {
+        let mut coord = (0, 0);
+
+        for direction in &self.dirs {
+            coord = vec_add(coord, direction.unit_vec());
+        }
+
+        coord
+    }
+}
+
+/// Flip the tile the instruction leads to.
+fn flip_tile(black_tiles: &mut FastSet<Coord>, instruction: &Instruction) {
+    let tile = instruction.to_coord();
+
+    if !black_tiles.remove(&tile) {
+        // Wasn't already flipped to black so insert it into the set of black tiles (if it was
+        // already in the set it flips back to white and is already removed)
+        black_tiles.insert(tile);
+    }
+}
+
+/// Generate the initial tileset from the given instructions.
+pub fn get_initial_tiles(instructions: &[Instruction]) -> FastSet<Coord> {
+    let mut black_tiles: FastSet<Coord> = FastSet::default();
+
+    for instruction in instructions {
+        flip_tile(&mut black_tiles, instruction);
+    }
+
+    black_tiles
+}
+
+/// Generate the initial tileset while streaming the instructions from a reader, so only the
+/// flipped tiles are held in memory rather than every instruction.
+pub fn get_initial_tiles_streaming<R: BufRead>(reader: R) -> io::Result<FastSet<Coord>> {
+    let mut black_tiles: FastSet<Coord> = FastSet::default();
+
+    for instruction in parse_lines::<_, Instruction>(reader) {
+        flip_tile(&mut black_tiles, &instruction?);
+    }
+
+    Ok(black_tiles)
+}
+
+/// The number of days the living art exhibit runs for in part two.
+pub const DAYS: usize = 100;
+
+/// Which numbers of black neighbours flip a tile each day, as a set of counts from 0 to 6 for
+/// each colour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlipRules {
+    /// A bit for each number of black neighbours which turns a white tile black.
+    turn_black: u8,
+    /// A bit for each number of black neighbours which keeps a black tile black.
+    stay_black: u8,
+}
+
+impl FlipRules {
+    /// The rules of the living art exhibit: white tiles with exactly 2 black neighbours turn
+    /// black, and black tiles with 0 or more than 2 turn white.
+    pub const EXHIBIT: Self = Self {
+        turn_black: 1 << 2,
+        stay_black: 1 << 1 | 1 << 2,
+    };
+
+    /// Whether a white tile with a number of black neighbours turns black.
+    pub fn turns_black(&self, black_neighbours: usize) -> bool {
+        black_neighbours < 8 && self.turn_black & 1 << black_neighbours != 0
+    }
+
+    /// Whether a black tile with a number of black neighbours stays black.
+    pub fn stays_black(&self, black_neighbours: usize) -> bool {
+        black_neighbours < 8 && self.stay_black & 1 << black_neighbours != 0
+    }
+}
+
+impl Default for FlipRules {
+    fn default() -> Self {
+        Self::EXHIBIT
+    }
+}
+
+impl FromStr for FlipRules {
+    type Err = io::Error;
+
+    /// Parse rules in the "B<counts>/S<counts>" notation of the Game of Life, giving the numbers
+    /// of black neighbours which turn white tiles black and keep black tiles black.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Invalid flip rules {:?}: expected B<counts>/S<counts> with counts from 0 to \
+                     6, and no B0",
+                    s
+                ),
+            )
+        };
+        let counts = |digits: &str| {
+            digits.chars().try_fold(0u8, |counts, ch| match ch {
+                '0'..='6' => Ok(counts | 1 << (ch as u8 - b'0')),
+                _ => Err(invalid()),
+            })
+        };
+        let mut parts = s.split('/');
+        let (turn_black, stay_black) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(birth), Some(survival), None)
+                if birth.starts_with('B') && survival.starts_with('S') =>
+            {
+                (counts(&birth[1..])?, counts(&survival[1..])?)
+            }
+            _ => return Err(invalid()),
+        };
+        // White tiles with no black neighbours stretch out forever, so would all turn black.
+        if turn_black & 1 != 0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            turn_black,
+            stay_black,
+        })
+    }
+}
+
+impl fmt::Display for FlipRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = |bits: u8| -> String {
+            (0..=6)
+                .filter(|count| bits & 1 << count != 0)
+                .map(|count| count.to_string())
+                .collect()
+        };
+        write!(
+            f,
+            "B{}/S{}",
+            counts(self.turn_black),
+            counts(self.stay_black)
+        )
+    }
+}
+
+/// The corners of the smallest box of coordinates covering some tiles, as the least and greatest
+/// of each coordinate, or the reference tile for no tiles at all.
+pub fn tile_bounds<'a>(tiles: impl Iterator<Item = &'a Coord> + Clone) -> (Coord, Coord) {
+    let min = (
+        tiles.clone().map(|tile| tile.0).min().unwrap_or(0),
+        tiles.clone().map(|tile| tile.1).min().unwrap_or(0),
+    );
+    let max = (
+        tiles.clone().map(|tile| tile.0).max().unwrap_or(0),
+        tiles.map(|tile| tile.1).max().unwrap_or(0),
+    );
+    (min, max)
+}
+
+/// The columns and rows, from the top left, the tiles within some coordinates take up once drawn.
+///
+/// Each row of hexagons is drawn a character apart, with a gap between each tile so that moving
+/// north-east or north-west can be drawn half a tile to either side: a tile at (x, y) is drawn at
+/// column 2x - y, on the row y up from the bottom.
+fn project((x, y): Coord, (min, max): (Coord, Coord)) -> (usize, usize) {
+    let left = 2 * min.0 - max.1;
+    ((max.1 - y) as usize, (2 * x - y - left) as usize)
+}
+
+/// Draw the black tiles as `#` and white tiles as `.` on a grid, to cover every coordinate
+/// between the given corners.
+pub fn render_tiles(black_tiles: &FastSet<Coord>, (min, max): (Coord, Coord)) -> String {
+    let height = (max.1 - min.1 + 1) as usize;
+    let width = (2 * (max.0 - min.0) + (max.1 - min.1) + 1) as usize;
+    let mut rows = vec![vec![' '; width]; height];
+    for x in min.0..=max.0 {
+        for y in min.1..=max.1 {
+            let (row, col) = project((x, y), (min, max));
+            rows[row][col] = if black_tiles.contains(&(x, y)) {
+                '#'
+            } else {
+                '.'
+            };
+        }
+    }
+    Grid::from_rows(rows)
+        .expect("Rows are all the same width")
+        .render(|&ch| ch)
+}
+
+/// Run a number of days of the exhibit under some flip rules from the initial tiles, returning the
+/// final tiles.
+pub fn simulate(mut black_tiles: FastSet<Coord>, days: usize, rules: FlipRules) -> FastSet<Coord> {
+    for day in 0..days {
+        black_tiles = next_day(&black_tiles, rules);
+        progress::advance(day + 1, days);
+    }
+    black_tiles
+}
+
+/// Flip the tiles for a day of the exhibit.
+pub fn next_day(black_tiles: &FastSet<Coord>, rules: FlipRules) -> FastSet<Coord> {
+    automaton::step(
+        black_tiles,
+        get_adjacent_coords,
+        |black, black_neighbours| {
+            if black {
+                rules.stays_black(black_neighbours)
+            } else {
+                rules.turns_black(black_neighbours)
+            }
+        },
+    )
+}
+
+/// The black tiles of the floor can be saved part way through the exhibit.
+impl Snapshot for FastSet<Coord> {
+    const TAG: [u8; 8] = *b"lobby\0\0\0";
+
+    fn write_state(&self, out: &mut dyn Write) -> io::Result<()> {
+        snapshot::write_u64(out, self.len() as u64)?;
+        self.iter().try_for_each(|&(x, y)| {
+            snapshot::write_u64(out, (x as u32 as u64) << 32 | y as u32 as u64)
+        })
+    }
+
+    fn read_state(input: &mut dyn Read) -> io::Result<Self> {
+        let len = snapshot::read_u64(input)?;
+        (0..len)
+            .map(|_| {
+                let tile = snapshot::read_u64(input)?;
+                Ok(((tile >> 32) as u32 as i32, tile as u32 as i32))
+            })
+            .collect()
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} black tiles\n{}",
+            self.len(),
+            render_tiles(self, tile_bounds(self.iter()))
+        )
+    }
+}
+
+/// The floor as a dense array of every tile the exhibit can reach in a given number of days.
+///
+/// Black tiles spread by at most one tile a day along each axis, so the array covers the initial
+/// tiles widened by the number of days on every side, plus one more for the neighbours of the
+/// outermost tiles. Each day only sweeps the part of the array the black tiles could have spread
+/// to by then, counting neighbours with fixed index offsets rather than hashing coordinates.
+#[derive(Clone, Debug)]
+pub struct DenseFloor {
+    cells: Vec<bool>,
+    /// The number of black neighbours of each tile, which is only non-zero during a day.
+    counts: Vec<u8>,
+    width: usize,
+    /// The coordinate of the tile at the start of the array.
+    origin: Coord,
+    /// The offset in `cells` to each neighbour of a tile.
+    offsets: [isize; 6],
+    /// The first and last (column, row) black tiles could have spread to so far.
+    reach: ((usize, usize), (usize, usize)),
+    /// How many more days the array has room for.
+    remaining: usize,
+    rules: FlipRules,
+}
+
+impl DenseFloor {
+    /// Lay out the initial black tiles, with room for `days` under the flip rules.
+    pub fn new(black_tiles: &FastSet<Coord>, days: usize, rules: FlipRules) -> Self {
+        let min_x = black_tiles.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let max_x = black_tiles.iter().map(|&(x, _)| x).max().unwrap_or(0);
+        let min_y = black_tiles.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let max_y = black_tiles.iter().map(|&(_, y)| y).max().unwrap_or(0);
+        let margin = days + 1;
+        let width = (max_x - min_x) as usize + 2 * margin + 1;
+        let height = (max_y - min_y) as usize + 2 * margin + 1;
+        let origin = (min_x - margin as i32, min_y - margin as i32);
+
+        let mut cells = vec![false; width * height];
+        for &(x, y) in black_tiles {
+            cells[(y - origin.1) as usize * width + (x - origin.0) as usize] = true;
+        }
+        let mut offsets = [0; 6];
+        for (offset, (dx, dy)) in offsets.iter_mut().zip(get_adjacent_coords((0, 0)).iter()) {
+            *offset = *dx as isize + *dy as isize * width as isize;
+        }
+        Self {
+            cells,
+            counts: vec![0; width * height],
+            width,
+            origin,
+            offsets,
+            reach: ((margin, margin), (width - margin - 1, height - margin - 1)),
+            remaining: days,
+            rules,
+        }
+    }
+
+    /// The number of black tiles.
+    pub fn count_black(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell).count()
+    }
+
+    /// The coordinates of the black tiles.
+    pub fn black_tiles(&self) -> FastSet<Coord> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, &cell)| cell)
+            .map(|(index, _)| {
+                let (col, row) = (index % self.width, index / self.width);
+                (self.origin.0 + col as i32, self.origin.1 + row as i32)
+            })
+            .collect()
+    }
+
+    /// Flip the tiles for a day of the exhibit. Panics if the array has no room left for one.
+    pub fn next_day(&mut self) {
+        assert!(
+            self.remaining > 0,
+            "No room left in the array for another day"
+        );
+        self.remaining -= 1;
+        let ((left, top), (right, bottom)) = self.reach;
+        // Black tiles are never within the margin of the edge, so their neighbours are in range.
+        for row in top..=bottom {
+            for index in row * self.width + left..=row * self.width + right {
+                if self.cells[index] {
+                    for &offset in &self.offsets {
+                        self.counts[(index as isize + offset) as usize] += 1;
+                    }
+                }
+            }
+        }
+        self.reach = ((left - 1, top - 1), (right + 1, bottom + 1));
+        for row in top - 1..=bottom + 1 {
+            for index in row * self.width + left - 1..=row * self.width + right + 1 {
+                let count = self.counts[index] as usize;
+                self.cells[index] = match self.cells[index] {
+                    true => self.rules.stays_black(count),
+                    false => self.rules.turns_black(count),
+                };
+                self.counts[index] = 0;
+            }
+        }
+    }
+}
+
+/// How the tiles of the floor are stored and flipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    /// A set of the black tiles, which grows with the number of them.
+    Sparse,
+    /// An array of every tile the black tiles can reach, which grows with the number of days.
+    Dense,
+}
+
+impl FromStr for Engine {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sparse" => Ok(Self::Sparse),
+            "dense" => Ok(Self::Dense),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid engine {:?}: expected sparse or dense", s),
+            )),
+        }
+    }
+}
+
+/// Run a number of days of the exhibit under some flip rules from the initial tiles, and count
+/// the black tiles after.
+pub fn run(black_tiles: FastSet<Coord>, days: usize, rules: FlipRules, engine: Engine) -> usize {
+    match engine {
+        Engine::Sparse => simulate(black_tiles, days, rules).len(),
+        Engine::Dense => {
+            let mut floor = DenseFloor::new(&black_tiles, days, rules);
+            for day in 0..days {
+                floor.next_day();
+                progress::advance(day + 1, days);
+            }
+            floor.count_black()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flip_rules() {
+        let exhibit: FlipRules = "B2/S12".parse().unwrap();
+        assert_eq!(exhibit, FlipRules::EXHIBIT);
+        assert_eq!(exhibit.to_string(), "B2/S12");
+        assert!(exhibit.turns_black(2) && !exhibit.turns_black(3));
+        assert!(!exhibit.stays_black(0) && exhibit.stays_black(1) && !exhibit.stays_black(3));
+
+        let rules: FlipRules = "B24/S".parse().unwrap();
+        assert_eq!(rules.to_string(), "B24/S");
+        for invalid in &["B0/S12", "B7/S1", "B2", "S12/B2", "B2/S1/S2", "B2/Sx"] {
+            assert!(invalid.parse::<FlipRules>().is_err(), "{}", invalid);
+        }
+
+        // Under B2/S, the pair east and north-east of the reference tile both turn white, while
+        // the two tiles next to both of them turn black.
+        let pair: FastSet<Coord> = [(1, 0), (1, 1)].iter().copied().collect();
+        let next = simulate(pair, 1, "B2/S".parse().unwrap());
+        assert_eq!(next, [(0, 0), (2, 1)].iter().copied().collect());
+    }
+
+    #[test]
+    fn test_render_tiles() {
+        // The reference tile with black tiles to its east and north-east.
+        let tiles: FastSet<Coord> = [(0, 0), (1, 0), (1, 1)].iter().copied().collect();
+        let bounds = tile_bounds(tiles.iter());
+        assert_eq!(bounds, ((0, 0), (1, 1)));
+        assert_eq!(render_tiles(&tiles, bounds), ". # \n # #");
+        assert_eq!(tile_bounds([].iter()), ((0, 0), (0, 0)));
+    }
+
+    #[test]
+    fn test_dense_floor() {
+        let tiles: FastSet<Coord> = [(0, 0), (1, 0), (3, -2), (-1, 4), (2, 2)]
+            .iter()
+            .copied()
+            .collect();
+        for &rules in &[FlipRules::EXHIBIT, "B24/S0".parse().unwrap()] {
+            let mut sparse = tiles.clone();
+            let mut dense = DenseFloor::new(&tiles, 12, rules);
+            assert_eq!(dense.black_tiles(), tiles);
+            for _ in 0..12 {
+                sparse = next_day(&sparse, rules);
+                dense.next_day();
+                assert_eq!(dense.black_tiles(), sparse);
+            }
+            assert_eq!(dense.count_black(), sparse.len());
+            assert_eq!(run(tiles.clone(), 12, rules, Engine::Dense), sparse.len());
+        }
+    }
+}
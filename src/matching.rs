@@ -0,0 +1,175 @@
+/// Assigning each of a list of slots a different value out of the values it could be, as for the
+/// ticket fields of Day 16 and the allergens of Day 21.
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::io;
+
+/// The ways assigning every slot a different value can fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignError {
+    /// No assignment gives every slot a different value.
+    Unsatisfiable,
+    /// More than one assignment does, including one giving the slot at this index another value.
+    Ambiguous(usize),
+}
+
+impl fmt::Display for AssignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unsatisfiable => write!(f, "No assignment gives every slot a different value"),
+            Self::Ambiguous(slot) => write!(
+                f,
+                "Slot {} can be assigned more than one value, so the assignment is ambiguous",
+                slot
+            ),
+        }
+    }
+}
+
+impl Error for AssignError {}
+
+impl From<AssignError> for io::Error {
+    fn from(err: AssignError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Assign each slot a value by repeatedly finding a slot with a single possibility and removing
+/// that value from every other slot's possibilities. This is quick, but gives up if there comes a
+/// point where every slot left has several possibilities.
+///
+/// Every value it assigns was the only one left for its slot, so if it succeeds the assignment is
+/// the only one there is.
+pub fn assign_by_elimination<T, S>(candidates: &[HashSet<T, S>]) -> Option<Vec<T>>
+where
+    T: Clone + Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    let mut remaining = candidates.to_vec();
+    let mut assigned: Vec<Option<T>> = vec![None; candidates.len()];
+    while let Some(slot) =
+        (0..remaining.len()).find(|&slot| assigned[slot].is_none() && remaining[slot].len() == 1)
+    {
+        let value = remaining[slot].iter().next().cloned()?;
+        for possibles in remaining.iter_mut() {
+            possibles.remove(&value);
+        }
+        assigned[slot] = Some(value);
+    }
+    assigned.into_iter().collect()
+}
+
+/// Try to give a slot a value, taking one from another slot if that slot can be given a different
+/// value instead (an augmenting path in the bipartite graph of slots and values).
+fn augment<'a, T, S>(
+    slot: usize,
+    candidates: &'a [HashSet<T, S>],
+    owners: &mut HashMap<&'a T, usize>,
+    visited: &mut HashSet<&'a T>,
+) -> bool
+where
+    T: Eq + Hash,
+{
+    for value in &candidates[slot] {
+        if !visited.insert(value) {
+            continue;
+        }
+        let free = match owners.get(value) {
+            Some(&owner) => augment(owner, candidates, owners, visited),
+            None => true,
+        };
+        if free {
+            owners.insert(value, slot);
+            return true;
+        }
+    }
+    false
+}
+
+/// Assign each slot a different value by finding a maximum matching between slots and the values
+/// they could be, which succeeds whenever any such assignment exists (though if several do it is
+/// one of them).
+pub fn assign_by_matching<T, S>(candidates: &[HashSet<T, S>]) -> Option<Vec<T>>
+where
+    T: Clone + Eq + Hash,
+{
+    let mut owners: HashMap<&T, usize> = HashMap::new();
+    for slot in 0..candidates.len() {
+        if !augment(slot, candidates, &mut owners, &mut HashSet::new()) {
+            return None;
+        }
+    }
+    let mut assigned: Vec<Option<T>> = vec![None; candidates.len()];
+    for (value, slot) in owners {
+        assigned[slot] = Some(value.clone());
+    }
+    assigned.into_iter().collect()
+}
+
+/// Assign each slot a different value, erroring unless exactly one assignment does.
+///
+/// Elimination is tried first as it is quick and any assignment it finds is the only one. If it
+/// gets stuck, a matching finds some assignment if there is one, which is then the only one if no
+/// slot can be matched to any other value while the rest still get one each.
+pub fn assign_unique<T, S>(candidates: &[HashSet<T, S>]) -> Result<Vec<T>, AssignError>
+where
+    T: Clone + Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    if let Some(assigned) = assign_by_elimination(candidates) {
+        return Ok(assigned);
+    }
+    let assigned = assign_by_matching(candidates).ok_or(AssignError::Unsatisfiable)?;
+    for (slot, value) in assigned.iter().enumerate() {
+        let mut others = candidates.to_vec();
+        others[slot].remove(value);
+        if assign_by_matching(&others).is_some() {
+            return Err(AssignError::Ambiguous(slot));
+        }
+    }
+    Ok(assigned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates(slots: &[&[&'static str]]) -> Vec<HashSet<&'static str>> {
+        slots
+            .iter()
+            .map(|values| values.iter().copied().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_matching_when_elimination_stalls() {
+        let stalls = candidates(&[&["a", "b"], &["a", "b", "c"], &["c"]]);
+        // Once "c" is taken every slot left could be "a" or "b".
+        assert_eq!(assign_by_elimination(&stalls), None);
+        let assigned = assign_by_matching(&stalls).unwrap();
+        assert_eq!(assigned[2], "c");
+        assert_eq!(
+            assigned[..2].iter().collect::<HashSet<_>>(),
+            ["a", "b"].iter().collect()
+        );
+
+        // Two slots which could only be the same value can't both have it.
+        assert_eq!(assign_by_matching(&candidates(&[&["a"], &["a"]])), None);
+    }
+
+    #[test]
+    fn test_assign_unique() {
+        let given = candidates(&[&["a", "b"], &["b"], &["a", "b", "c"]]);
+        assert_eq!(assign_unique(&given), Ok(vec!["a", "b", "c"]));
+        // Once "d" is taken the rest can share out "a", "b" and "c" several ways.
+        let swaps = candidates(&[&["a", "b"], &["a", "b", "c"], &["b", "c", "d"], &["d"]]);
+        assert!(assign_by_matching(&swaps).is_some());
+        assert_eq!(assign_unique(&swaps), Err(AssignError::Ambiguous(0)));
+        assert_eq!(
+            assign_unique(&candidates(&[&["a", "b"], &["a"], &["b"]])),
+            Err(AssignError::Unsatisfiable)
+        );
+    }
+}
@@ -0,0 +1,152 @@
+/// Modular arithmetic, for solving systems of congruences with the Chinese remainder theorem.
+use crate::checked::{self, Overflow};
+use std::mem;
+
+/// The greatest common divisor of two numbers.
+pub fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let rem = a % b;
+        a = b;
+        b = rem;
+    }
+    a
+}
+
+/// Add two numbers already reduced modulo another without overflowing.
+fn add_mod(a: u128, b: u128, modulus: u128) -> u128 {
+    if a >= modulus - b {
+        a - (modulus - b)
+    } else {
+        a + b
+    }
+}
+
+/// Multiply two numbers modulo another without overflowing, by doubling and adding when the
+/// product itself doesn't fit.
+fn mul_mod(a: u128, b: u128, modulus: u128) -> u128 {
+    let (mut a, mut b) = (a % modulus, b % modulus);
+    if let Some(product) = a.checked_mul(b) {
+        return product % modulus;
+    }
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product = add_mod(product, a, modulus);
+        }
+        a = add_mod(a, a, modulus);
+        b >>= 1;
+    }
+    product
+}
+
+/// The inverse of `a` modulo `modulus`, if they are coprime.
+pub fn inverse(a: u128, modulus: u128) -> Option<u128> {
+    // Run the extended Euclidean algorithm, keeping the coefficient of `a` reduced modulo the
+    // modulus so it never needs a sign.
+    let (mut old_r, mut r) = (a % modulus, modulus);
+    let (mut old_s, mut s) = (1 % modulus, 0);
+    while r != 0 {
+        let quotient = old_r / r;
+        let next_r = old_r - quotient * r;
+        old_r = mem::replace(&mut r, next_r);
+        let next_s = add_mod(old_s, modulus - mul_mod(quotient, s, modulus), modulus);
+        old_s = mem::replace(&mut s, next_s % modulus);
+    }
+    match old_r {
+        1 => Some(old_s),
+        _ => None,
+    }
+}
+
+/// A congruence `x ≡ residue (mod modulus)`.
+pub type Congruence = (u128, u128);
+
+/// Solve a system of congruences, giving the smallest non-negative solution along with the
+/// modulus all solutions are congruent under (the LCM of the moduli).
+///
+/// The moduli need not be pairwise coprime: any which share a factor must then agree modulo it,
+/// and if they don't the system has no solution. Errors if the combined modulus overflows.
+pub fn crt(congruences: &[Congruence]) -> Result<Option<Congruence>, Overflow> {
+    let mut solution: Congruence = (0, 1);
+    for &(residue, modulus) in congruences {
+        let (x, n) = solution;
+        let residue = residue % modulus;
+        let g = gcd(n, modulus);
+        // Need x + n k ≡ residue (mod modulus), which only has solutions for k if the gap between
+        // the residues is a multiple of the factor the moduli share.
+        let gap = add_mod(residue, (modulus - x % modulus) % modulus, modulus);
+        if !gap.is_multiple_of(g) {
+            return Ok(None);
+        }
+        let reduced = modulus / g;
+        let k = match inverse(n / g % reduced, reduced) {
+            Some(inverse) => mul_mod(gap / g, inverse, reduced),
+            // Only when reduced is 1, where everything is congruent.
+            None => 0,
+        };
+        let lcm = checked::mul(n / g, modulus)?;
+        solution = (add_mod(x % lcm, mul_mod(n, k, lcm), lcm), lcm);
+    }
+    Ok(Some(solution))
+}
+
+/// Solve a system of congruences as `crt` does, but by sieving: step through the solutions of the
+/// congruences so far, a combined modulus at a time, until one also satisfies the next. This takes
+/// up to a modulus of steps for each congruence rather than the logarithm of one, so is only
+/// quick for small moduli, but needs no inverses.
+pub fn sieve(congruences: &[Congruence]) -> Result<Option<Congruence>, Overflow> {
+    let mut solution: Congruence = (0, 1);
+    for &(residue, modulus) in congruences {
+        let (mut x, n) = solution;
+        let residue = residue % modulus;
+        // The candidates repeat modulo the LCM of the moduli, so if none below it satisfy this
+        // congruence, none ever will.
+        let lcm = checked::mul(n / gcd(n, modulus), modulus)?;
+        while x % modulus != residue {
+            if lcm - x <= n {
+                return Ok(None);
+            }
+            x += n;
+        }
+        solution = (x, lcm);
+    }
+    Ok(Some(solution))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(inverse(3, 7), Some(5));
+        assert_eq!(inverse(4, 8), None);
+
+        // The classic x ≡ 2 (mod 3), x ≡ 3 (mod 5), x ≡ 2 (mod 7).
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Ok(Some((23, 105))));
+
+        // Moduli sharing a factor work as long as they agree modulo it.
+        assert_eq!(crt(&[(3, 4), (1, 6)]), Ok(Some((7, 12))));
+        assert_eq!(crt(&[(0, 4), (1, 6)]), Ok(None));
+
+        // Products far beyond 64 bits are still reduced exactly.
+        assert_eq!(mul_mod(u128::MAX - 1, u128::MAX - 1, u128::MAX), 1);
+        assert_eq!(inverse(u128::MAX - 1, u128::MAX), Some(u128::MAX - 1));
+        assert_eq!(crt(&[(0, u128::MAX), (1, 2)]), Err(Overflow));
+    }
+
+    #[test]
+    fn test_sieve() {
+        for congruences in [
+            vec![(2, 3), (3, 5), (2, 7)],
+            vec![(3, 4), (1, 6)],
+            vec![(0, 4), (1, 6)],
+            vec![(0, 7), (12, 13), (55, 59), (25, 31), (12, 19)],
+            vec![],
+        ] {
+            assert_eq!(sieve(&congruences), crt(&congruences));
+        }
+        assert_eq!(sieve(&[(0, u128::MAX), (1, 2)]), Err(Overflow));
+    }
+}
@@ -0,0 +1,225 @@
+/// Navigation instructions of Day 12, which steer the ship either directly or by a waypoint.
+use std::io;
+use std::str::FromStr;
+
+/// A vector in the plane as (east, north).
+pub type Vector = (i32, i32);
+
+/// A rotation anticlockwise by a multiple of 90°, as the cosine and sine of its angle.
+///
+/// Treating vectors as complex numbers, applying it is multiplying by `cos + i sin`, which for
+/// these angles is a power of `i` and so stays on the integer grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rotation {
+    cos: i32,
+    sin: i32,
+}
+
+impl Rotation {
+    /// The rotation anticlockwise by the given angle, which must be a multiple of 90°.
+    pub fn from_degrees(degrees: i32) -> io::Result<Self> {
+        let (cos, sin) = match degrees.rem_euclid(360) {
+            0 => (1, 0),
+            90 => (0, 1),
+            180 => (-1, 0),
+            270 => (0, -1),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Cannot turn by {}°: not a multiple of 90°", degrees),
+                ))
+            }
+        };
+        Ok(Self { cos, sin })
+    }
+
+    pub fn apply(&self, (x, y): Vector) -> Vector {
+        (x * self.cos - y * self.sin, x * self.sin + y * self.cos)
+    }
+}
+
+/// An instruction for navigating the ship.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// Move north, east, south or west by a vector.
+    Move(Vector),
+    /// Turn left (anticlockwise) or right (clockwise).
+    Turn(Rotation),
+    /// Move forward by some multiple of the heading.
+    Forward(i32),
+}
+
+impl FromStr for Instruction {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid instruction {:?}", s),
+            )
+        };
+        let mut chars = s.chars();
+        let action = chars.next().ok_or_else(invalid)?;
+        let num: i32 = chars.as_str().parse().map_err(|_| invalid())?;
+        Ok(match action {
+            'N' => Self::Move((0, num)),
+            'E' => Self::Move((num, 0)),
+            'S' => Self::Move((0, -num)),
+            'W' => Self::Move((-num, 0)),
+            'L' => Self::Turn(Rotation::from_degrees(num)?),
+            'R' => Self::Turn(Rotation::from_degrees(-num)?),
+            'F' => Self::Forward(num),
+            _ => return Err(invalid()),
+        })
+    }
+}
+
+/// The ship's position along with the vector it travels by when moving forward, which is either
+/// the direction it faces or the waypoint relative to it.
+struct Ship {
+    x: i32,
+    y: i32,
+    heading: Vector,
+}
+
+impl Ship {
+    fn new(heading: Vector) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            heading,
+        }
+    }
+
+    fn taxicab_distance(&self) -> u32 {
+        (self.x.abs() + self.y.abs()) as u32
+    }
+
+    fn forward(&mut self, num: i32) {
+        self.x += self.heading.0 * num;
+        self.y += self.heading.1 * num;
+    }
+
+    /// Follow an instruction where the heading is the direction the ship faces.
+    fn update_v1(&mut self, instruction: &Instruction) {
+        match *instruction {
+            Instruction::Move((dx, dy)) => {
+                self.x += dx;
+                self.y += dy;
+            }
+            Instruction::Turn(rotation) => self.heading = rotation.apply(self.heading),
+            Instruction::Forward(num) => self.forward(num),
+        }
+    }
+
+    /// Follow an instruction where the heading is the waypoint, which moves with the ship.
+    fn update_v2(&mut self, instruction: &Instruction) {
+        match *instruction {
+            Instruction::Move((dx, dy)) => {
+                self.heading.0 += dx;
+                self.heading.1 += dy;
+            }
+            Instruction::Turn(rotation) => self.heading = rotation.apply(self.heading),
+            Instruction::Forward(num) => self.forward(num),
+        }
+    }
+}
+
+/// The ship after an instruction: where it is, and its heading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackPoint {
+    pub ship: Vector,
+    pub heading: Vector,
+}
+
+/// Follow the instructions from the origin with the given starting heading and way of updating
+/// the ship, recording the ship before the first instruction and after every one.
+fn track<F>(data: &[Instruction], heading: Vector, update: F) -> Vec<TrackPoint>
+where
+    F: Fn(&mut Ship, &Instruction),
+{
+    let mut ship = Ship::new(heading);
+    let mut points = vec![TrackPoint {
+        ship: (0, 0),
+        heading,
+    }];
+    for instruction in data {
+        update(&mut ship, instruction);
+        points.push(TrackPoint {
+            ship: (ship.x, ship.y),
+            heading: ship.heading,
+        });
+    }
+    points
+}
+
+/// The tracks taken by the ship in each part.
+pub fn tracks(data: &[Instruction]) -> [Vec<TrackPoint>; 2] {
+    [
+        track(data, (1, 0), Ship::update_v1),
+        track(data, (10, 1), Ship::update_v2),
+    ]
+}
+
+/// How far the ship ends up from where it started when the instructions move the ship, for part
+/// one.
+pub fn distance_by_heading(data: &[Instruction]) -> Option<u32> {
+    // The ship starts facing east.
+    let mut ship = Ship::new((1, 0));
+    for instruction in data {
+        ship.update_v1(instruction);
+    }
+    Some(ship.taxicab_distance())
+}
+
+/// How far the ship ends up from where it started when the instructions move the waypoint, for
+/// part two.
+pub fn distance_by_waypoint(data: &[Instruction]) -> Option<u32> {
+    // The waypoint starts 10 units east and 1 unit north of the ship.
+    let mut ship = Ship::new((10, 1));
+    for instruction in data {
+        ship.update_v2(instruction);
+    }
+    Some(ship.taxicab_distance())
+}
+
+/// Parse the navigation instructions, one per line.
+pub fn parse_instructions(input: &str) -> io::Result<Vec<Instruction>> {
+    input.lines().map(|s| s.parse()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_example() {
+        let input = "F10
+N3
+F7
+R90
+F11";
+        let data = parse_instructions(input).expect("Couldn't convert test input");
+
+        // Assert get the right number.
+        assert_eq!(distance_by_heading(&data), Some(17 + 8));
+        assert_eq!(distance_by_waypoint(&data), Some(214 + 72));
+    }
+
+    #[test]
+    fn test_rotations() {
+        let left = Rotation::from_degrees(90).unwrap();
+        assert_eq!(left.apply((10, 4)), (-4, 10));
+        assert_eq!(Rotation::from_degrees(-270).unwrap(), left);
+        assert_eq!(
+            Rotation::from_degrees(180).unwrap().apply((10, 4)),
+            (-10, -4)
+        );
+
+        // Turning by anything but right angles is rejected rather than rounded.
+        assert!(parse_instructions("R45").is_err());
+        assert!(parse_instructions("L90\nR450\nF1").is_ok());
+        assert!(parse_instructions("X90").is_err());
+    }
+}
@@ -0,0 +1,527 @@
+/// Passport fields of Day 4, which are checked for being present and then for being valid.
+use crate::diagnostic::Diagnostic;
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// A passport field, which may be missing, present but with an invalid value, or parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field<T> {
+    Missing,
+    Invalid { value: String, reason: String },
+    Valid(T),
+}
+
+impl<T> Field<T> {
+    /// Parse the value of a field if it was given, where the parser rejects invalid values with
+    /// the reason why.
+    fn parse<F>(value: Option<&str>, parser: F) -> Self
+    where
+        F: Fn(&str) -> Result<T, String>,
+    {
+        match value {
+            None => Self::Missing,
+            Some(value) => match parser(value) {
+                Ok(parsed) => Self::Valid(parsed),
+                Err(reason) => Self::Invalid {
+                    value: value.to_string(),
+                    reason,
+                },
+            },
+        }
+    }
+
+    fn is_present(&self) -> bool {
+        !matches!(self, Self::Missing)
+    }
+
+    fn is_valid(&self) -> bool {
+        matches!(self, Self::Valid(_))
+    }
+}
+
+impl<T: fmt::Display> Field<T> {
+    /// Convert a parsed value back to text, so fields of different types can be treated alike.
+    fn erased(&self) -> Field<String> {
+        match self {
+            Self::Missing => Field::Missing,
+            Self::Invalid { value, reason } => Field::Invalid {
+                value: value.clone(),
+                reason: reason.clone(),
+            },
+            Self::Valid(value) => Field::Valid(value.to_string()),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Field<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing => Ok(()),
+            Self::Invalid { value, .. } => write!(f, "{}", value),
+            Self::Valid(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// A four digit year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Year(u16);
+
+impl Year {
+    /// A parser for years within an inclusive range.
+    fn between(min: u16, max: u16) -> impl Fn(&str) -> Result<Self, String> {
+        move |s| match s.parse()? {
+            Year(year) if (min..=max).contains(&year) => Ok(Year(year)),
+            _ => Err(format!("expected a year from {} to {}", min, max)),
+        }
+    }
+}
+
+impl FromStr for Year {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || "expected a four digit year".to_string();
+        if s.len() != 4 || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        s.parse().map(Self).map_err(|_| invalid())
+    }
+}
+
+impl fmt::Display for Year {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A height in either centimetres or inches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Height {
+    Cm(u16),
+    In(u16),
+}
+
+impl Height {
+    /// Check the height is within the range allowed for its unit.
+    fn plausible(self) -> Result<Self, String> {
+        match self {
+            Self::Cm(cm) if !(150..=193).contains(&cm) => Err("expected 150-193cm".to_string()),
+            Self::In(inches) if !(59..=76).contains(&inches) => Err("expected 59-76in".to_string()),
+            _ => Ok(self),
+        }
+    }
+}
+
+impl FromStr for Height {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |unit| format!("expected a whole number of {}", unit);
+        if let Some(cm) = s.strip_suffix("cm") {
+            cm.parse().map(Self::Cm).map_err(|_| invalid("cm"))
+        } else if let Some(inches) = s.strip_suffix("in") {
+            inches.parse().map(Self::In).map_err(|_| invalid("in"))
+        } else {
+            Err("expected a height in cm or in".to_string())
+        }
+    }
+}
+
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Cm(cm) => write!(f, "{}cm", cm),
+            Self::In(inches) => write!(f, "{}in", inches),
+        }
+    }
+}
+
+/// A hair colour given as a "#rrggbb" hex code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HairColor(u32);
+
+impl FromStr for HairColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || "expected a colour as #rrggbb in lowercase hex".to_string();
+        match s.strip_prefix('#') {
+            Some(hex)
+                if hex.len() == 6
+                    && hex
+                        .bytes()
+                        .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) =>
+            {
+                u32::from_str_radix(hex, 16)
+                    .map(Self)
+                    .map_err(|_| invalid())
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for HairColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:06x}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EyeColor {
+    Amber,
+    Blue,
+    Brown,
+    Grey,
+    Green,
+    Hazel,
+    Other,
+}
+
+impl FromStr for EyeColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "amb" => Ok(Self::Amber),
+            "blu" => Ok(Self::Blue),
+            "brn" => Ok(Self::Brown),
+            "gry" => Ok(Self::Grey),
+            "grn" => Ok(Self::Green),
+            "hzl" => Ok(Self::Hazel),
+            "oth" => Ok(Self::Other),
+            _ => Err("expected one of amb, blu, brn, gry, grn, hzl or oth".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for EyeColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            Self::Amber => "amb",
+            Self::Blue => "blu",
+            Self::Brown => "brn",
+            Self::Grey => "gry",
+            Self::Green => "grn",
+            Self::Hazel => "hzl",
+            Self::Other => "oth",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+/// A nine digit passport ID, kept as text to preserve leading zeros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pid(String);
+
+impl FromStr for Pid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 9 && s.bytes().all(|b| b.is_ascii_digit()) {
+            Ok(Self(s.to_string()))
+        } else {
+            Err("expected a nine digit number".to_string())
+        }
+    }
+}
+
+impl fmt::Display for Pid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Structure representing passport data, which may or may not be fully filled.
+#[derive(Debug)]
+pub struct PassportData {
+    byr: Field<Year>,
+    iyr: Field<Year>,
+    eyr: Field<Year>,
+    hgt: Field<Height>,
+    hcl: Field<HairColor>,
+    ecl: Field<EyeColor>,
+    pid: Field<Pid>,
+    /// The country ID is optional and can be anything.
+    cid: Option<String>,
+    /// Any fields which aren't part of a passport, in the order given.
+    unknown: Vec<(String, String)>,
+}
+
+impl FromStr for PassportData {
+    type Err = io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut map: HashMap<&str, &str> = HashMap::new();
+        let mut unknown = Vec::new();
+        for entry in s.split_whitespace() {
+            let (key, value) = entry.split_once(':').ok_or_else(|| {
+                Diagnostic::new(
+                    entry,
+                    format!("Passport entry {:?} is not \"key:value\"", entry),
+                )
+            })?;
+            match key {
+                "byr" | "iyr" | "eyr" | "hgt" | "hcl" | "ecl" | "pid" | "cid" => {
+                    map.insert(key, value);
+                }
+                _ => unknown.push((key.to_string(), value.to_string())),
+            }
+        }
+        Ok(PassportData {
+            byr: Field::parse(map.get("byr").copied(), Year::between(1920, 2002)),
+            iyr: Field::parse(map.get("iyr").copied(), Year::between(2010, 2020)),
+            eyr: Field::parse(map.get("eyr").copied(), Year::between(2020, 2030)),
+            hgt: Field::parse(map.get("hgt").copied(), |s| {
+                s.parse::<Height>()?.plausible()
+            }),
+            hcl: Field::parse(map.get("hcl").copied(), str::parse),
+            ecl: Field::parse(map.get("ecl").copied(), str::parse),
+            pid: Field::parse(map.get("pid").copied(), str::parse),
+            cid: map.get("cid").map(|s| s.to_string()),
+            unknown,
+        })
+    }
+}
+
+impl fmt::Display for PassportData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = self.required_fields();
+        let given = fields
+            .iter()
+            .filter(|(_, field)| field.is_present())
+            .map(|(key, field)| (*key, field.to_string()))
+            .chain(self.cid.iter().map(|cid| ("cid", cid.clone())))
+            .chain(self.unknown.iter().map(|(k, v)| (k.as_str(), v.clone())));
+        write!(
+            f,
+            "{}",
+            given
+                .map(|(key, value)| format!("{}:{}", key, value))
+                .join(" ")
+        )
+    }
+}
+
+impl PassportData {
+    /// The required fields along with their keys, in the order of the puzzle statement.
+    fn required_fields(&self) -> [(&'static str, Field<String>); 7] {
+        [
+            ("byr", self.byr.erased()),
+            ("iyr", self.iyr.erased()),
+            ("eyr", self.eyr.erased()),
+            ("hgt", self.hgt.erased()),
+            ("hcl", self.hcl.erased()),
+            ("ecl", self.ecl.erased()),
+            ("pid", self.pid.erased()),
+        ]
+    }
+
+    /// Report which required fields are missing and which fields are invalid, and why.
+    pub fn report(&self) -> Report {
+        let mut report = Report::default();
+        for (key, field) in self.required_fields().iter() {
+            match field {
+                Field::Missing => report.missing.push(key),
+                Field::Invalid { value, reason } => {
+                    report.invalid.push((key, value.clone(), reason.clone()))
+                }
+                Field::Valid(_) => (),
+            }
+        }
+        report
+    }
+
+    /// Whether all the required fields are present, regardless of their values.
+    fn is_valid1(&self) -> bool {
+        self.byr.is_present()
+            && self.iyr.is_present()
+            && self.eyr.is_present()
+            && self.hgt.is_present()
+            && self.hcl.is_present()
+            && self.ecl.is_present()
+            && self.pid.is_present()
+    }
+
+    /// Whether all the required fields are present and hold valid values.
+    fn is_valid2(&self) -> bool {
+        self.byr.is_valid()
+            && self.iyr.is_valid()
+            && self.eyr.is_valid()
+            && self.hgt.is_valid()
+            && self.hcl.is_valid()
+            && self.ecl.is_valid()
+            && self.pid.is_valid()
+    }
+}
+
+/// The problems found with a passport.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The required fields which were not given.
+    missing: Vec<&'static str>,
+    /// The fields given invalid values, as the field, its value and the reason it is invalid.
+    invalid: Vec<(&'static str, String, String)>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.missing.is_empty() && self.invalid.is_empty() {
+            return write!(f, "valid");
+        }
+        let missing = (!self.missing.is_empty())
+            .then(|| format!("missing {}", self.missing.iter().join(", ")));
+        let invalid = self
+            .invalid
+            .iter()
+            .map(|(key, value, reason)| format!("invalid {}:{} ({})", key, value, reason));
+        write!(f, "{}", missing.into_iter().chain(invalid).join("; "))
+    }
+}
+
+/// Count the passports with all the required fields present, for part one.
+pub fn count_complete(passports: &[PassportData]) -> Option<u32> {
+    Some(passports.iter().map(|p| p.is_valid1() as u32).sum())
+}
+
+/// Count the passports with all the required fields present and valid, for part two.
+pub fn count_valid(passports: &[PassportData]) -> Option<u32> {
+    Some(passports.iter().map(|p| p.is_valid2() as u32).sum())
+}
+
+/// Parse each passport, pointing any error at where it is in the input.
+pub fn parse_passports(input: &str) -> Result<Vec<PassportData>, Diagnostic> {
+    input
+        .split("\n\n")
+        .map(|s| {
+            s.parse()
+                .map_err(|err| Diagnostic::from_io(err, s).locate(input))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_example_part_one() {
+        let input: String = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn pid:760753108 byr:1931
+hgt:179cm
+
+hcl:#cfa07d eyr:2025 pid:166559648
+iyr:2011 ecl:brn hgt:59in"
+            .to_string();
+        let data = parse_passports(&input).expect("Couldn't convert test input");
+
+        // Check each gives the right answer.
+        assert_eq!(count_complete(&data), Some(2));
+    }
+
+    #[test]
+    fn test_given_example_part_two() {
+        let invalid_input: String = "eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+iyr:2019
+hcl:#602927 eyr:1967 hgt:170cm
+ecl:grn pid:012533040 byr:1946
+
+hcl:dab227 iyr:2012
+ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
+
+hgt:59cm ecl:zzz
+eyr:2038 hcl:74454a iyr:2023
+pid:3556412378 byr:2007"
+            .to_string();
+        let invalid_data = parse_passports(&invalid_input).expect("Couldn't convert test input");
+
+        // Check none of the passports are given as valid.
+        assert_eq!(count_valid(&invalid_data), Some(0));
+
+        let input: String = "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f
+
+eyr:2029 ecl:blu cid:129 byr:1989
+iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
+
+hcl:#888785
+hgt:164cm byr:2001 iyr:2015 cid:88
+pid:545766238 ecl:hzl
+eyr:2022"
+            .to_string();
+        let data = parse_passports(&input).expect("Couldn't convert test input");
+
+        // Check all of the passports are given as valid.
+        assert_eq!(count_valid(&data), Some(3));
+    }
+
+    #[test]
+    fn test_typed_fields() {
+        let passport: PassportData = "hgt:74in ecl:grn hcl:#623a2f byr:1980 foo:bar iyr:12"
+            .parse()
+            .expect("Couldn't convert test input");
+
+        assert_eq!(passport.hgt, Field::Valid(Height::In(74)));
+        assert_eq!(passport.ecl, Field::Valid(EyeColor::Green));
+        assert_eq!(passport.hcl, Field::Valid(HairColor(0x623a2f)));
+        assert_eq!(passport.byr, Field::Valid(Year(1980)));
+        assert_eq!(
+            passport.iyr,
+            Field::Invalid {
+                value: "12".to_string(),
+                reason: "expected a four digit year".to_string()
+            }
+        );
+        assert_eq!(passport.pid, Field::Missing);
+        assert_eq!(
+            passport.unknown,
+            vec![("foo".to_string(), "bar".to_string())]
+        );
+        assert_eq!(
+            passport.to_string(),
+            "byr:1980 iyr:12 hgt:74in hcl:#623a2f ecl:grn foo:bar"
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let passport: PassportData = "hgt:170 iyr:2019 hcl:#602927 eyr:1967 ecl:grn byr:1946"
+            .parse()
+            .expect("Couldn't convert test input");
+
+        let report = passport.report();
+        assert_eq!(report.missing, vec!["pid"]);
+        assert_eq!(
+            report.invalid,
+            vec![
+                (
+                    "eyr",
+                    "1967".to_string(),
+                    "expected a year from 2020 to 2030".to_string()
+                ),
+                (
+                    "hgt",
+                    "170".to_string(),
+                    "expected a height in cm or in".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "missing pid; invalid eyr:1967 (expected a year from 2020 to 2030); \
+             invalid hgt:170 (expected a height in cm or in)"
+        );
+    }
+}
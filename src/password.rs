@@ -0,0 +1,229 @@
+/// Password policies of Day 2 and the checks of passwords against them.
+use crate::{parse_lines, parse_range};
+use rayon::prelude::*;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Structure specifying a policy and the password to validate against it.
+#[derive(Debug)]
+pub struct PasswordPolicy {
+    range: RangeInclusive<usize>,
+    ch: char,
+    password: String,
+}
+
+impl FromStr for PasswordPolicy {
+    type Err = io::Error;
+
+    /// Parse a policy of the form "<min>-<max> <char>: <password>".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |expected: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid password policy {:?}: expected {}", s, expected),
+            )
+        };
+        let (range, rest) = s.split_once(' ').ok_or_else(|| invalid("a character"))?;
+        let (ch, password) = rest.split_once(' ').ok_or_else(|| invalid("a password"))?;
+
+        let range = parse_range(range)?;
+        let ch = ch.chars().next().ok_or_else(|| invalid("a character"))?;
+
+        Ok(PasswordPolicy {
+            ch,
+            range,
+            password: password.to_string(),
+        })
+    }
+}
+
+/// The reason a password fails a policy.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Failure {
+    /// The policy character appears a number of times outside the allowed range.
+    Count { found: usize },
+    /// The policy character is at neither or both of the checked positions: holds what was found
+    /// at each, which is `None` if the password is too short to reach it.
+    Positions { found: (Option<char>, Option<char>) },
+}
+
+impl PasswordPolicy {
+    /// Check the password against the sled shop policy.
+    pub fn check_sled_policy(&self) -> Result<(), Failure> {
+        let num_chars = self.password.matches(self.ch).count();
+        if self.range.contains(&num_chars) {
+            Ok(())
+        } else {
+            Err(Failure::Count { found: num_chars })
+        }
+    }
+
+    /// Check the password against the toboggan store policy.
+    pub fn check_toboggan_policy(&self) -> Result<(), Failure> {
+        // Positions are indexed from one, and zero is never a match.
+        let at = |pos: usize| {
+            pos.checked_sub(1)
+                .and_then(|idx| self.password.chars().nth(idx))
+        };
+        let found = (at(*self.range.start()), at(*self.range.end()));
+        if (found.0 == Some(self.ch)) ^ (found.1 == Some(self.ch)) {
+            Ok(())
+        } else {
+            Err(Failure::Positions { found })
+        }
+    }
+
+    /// The policy character as a byte, if it is ASCII and so can be compared against the password
+    /// bytes directly.
+    fn ascii_ch(&self) -> Option<u8> {
+        if self.ch.is_ascii() {
+            Some(self.ch as u8)
+        } else {
+            None
+        }
+    }
+
+    /// Check if the password is valid given the sled shop policy.
+    pub fn is_valid_sled_policy(&self) -> bool {
+        match self.ascii_ch() {
+            Some(ch) => {
+                let num_chars = self.password.bytes().filter(|&byte| byte == ch).count();
+                self.range.contains(&num_chars)
+            }
+            None => self.check_sled_policy().is_ok(),
+        }
+    }
+
+    /// Check if the password is valid given the toboggan store policy.
+    pub fn is_valid_toboggan_policy(&self) -> bool {
+        match self.ascii_ch() {
+            // Positions count characters, which are only bytes if the password is all ASCII.
+            Some(ch) if self.password.is_ascii() => {
+                let bytes = self.password.as_bytes();
+                let at =
+                    |pos: usize| pos.checked_sub(1).and_then(|idx| bytes.get(idx)) == Some(&ch);
+                at(*self.range.start()) ^ at(*self.range.end())
+            }
+            _ => self.check_toboggan_policy().is_ok(),
+        }
+    }
+
+    /// Describe the outcome of checking the password against a policy.
+    pub fn describe(&self, result: Result<(), Failure>) -> String {
+        let show = |found: Option<char>| match found {
+            Some(ch) => format!("{:?}", ch),
+            None => "nothing".to_string(),
+        };
+        match result {
+            Ok(()) => "valid".to_string(),
+            Err(Failure::Count { found }) => format!(
+                "expected {:?} {}-{} times, found {}",
+                self.ch,
+                self.range.start(),
+                self.range.end(),
+                found
+            ),
+            Err(Failure::Positions { found }) => format!(
+                "expected {:?} at exactly one of positions {} and {}, found {} and {}",
+                self.ch,
+                self.range.start(),
+                self.range.end(),
+                show(found.0),
+                show(found.1)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for PasswordPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{} {}: {}",
+            self.range.start(),
+            self.range.end(),
+            self.ch,
+            self.password
+        )
+    }
+}
+
+/// Count the policies valid under the sled and toboggan criteria, checking them in parallel.
+pub fn count_valid_parallel(policies: &[PasswordPolicy]) -> (u32, u32) {
+    policies
+        .par_iter()
+        .map(|policy| {
+            (
+                policy.is_valid_sled_policy() as u32,
+                policy.is_valid_toboggan_policy() as u32,
+            )
+        })
+        .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
+}
+
+/// Count the policies valid under the sled and toboggan criteria while streaming them from a
+/// reader, so the list of policies never needs to be held in memory.
+pub fn count_valid_streaming<R: BufRead>(reader: R) -> io::Result<(u32, u32)> {
+    let mut counts = (0, 0);
+    for policy in parse_lines::<_, PasswordPolicy>(reader) {
+        let policy = policy?;
+        counts.0 += policy.is_valid_sled_policy() as u32;
+        counts.1 += policy.is_valid_toboggan_policy() as u32;
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_policies() {
+        for line in &["", "1-3", "1-3 a:", "1-3  abcde", "1-x a: abcde"] {
+            let err = line.parse::<PasswordPolicy>().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", line);
+        }
+        assert!(count_valid_streaming("1-3 a: abcde\n1-3 a:\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_fast_path_agrees() {
+        let policies: Vec<PasswordPolicy> = [
+            "1-3 a: abcde",
+            "1-3 b: cdefg",
+            "2-9 c: ccccccccc",
+            "1-4 é: éaéa",
+            "0-2 x: xx",
+            "3-40 z: zz",
+            // Checked positions after a multi-byte character are counted in characters.
+            "1-2 a: éa",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+
+        for policy in &policies {
+            assert_eq!(
+                policy.is_valid_sled_policy(),
+                policy.check_sled_policy().is_ok()
+            );
+            assert_eq!(
+                policy.is_valid_toboggan_policy(),
+                policy.check_toboggan_policy().is_ok()
+            );
+        }
+        assert_eq!(count_valid_parallel(&policies), (5, 4));
+    }
+
+    #[test]
+    fn test_streaming_matches() {
+        let input = "1-3 a: abcde
+1-3 b: cdefg
+2-9 c: ccccccccc
+";
+        assert_eq!(count_valid_streaming(input.as_bytes()).unwrap(), (2, 1));
+        assert!(count_valid_streaming("1-x a: abcde".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+/// Progress of long solves as a stream of JSON lines, for dashboards to follow without reading
+/// the output meant for people.
+///
+/// Events are only written once a stream has been opened with `init`, which the days with long
+/// solves do when given "--progress-events <path>". Each event is an object on a line of its own
+/// giving the day, the phase being solved, how far through it is as a whole percentage and how
+/// long it has taken so far in microseconds, e.g.
+/// `{"day":"23","phase":"part two","percent":42,"elapsed_us":170214}`. An event is written when a
+/// phase starts, each time it gets another percent through, and when it finishes.
+use crate::get_option;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Whether a stream is open, checked before taking the lock so that reporting progress costs
+/// next to nothing when nobody is listening.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static EVENTS: Mutex<Option<Events>> = Mutex::new(None);
+
+/// Where events go, and what they are about.
+struct Events {
+    out: Box<dyn Write + Send>,
+    day: &'static str,
+    phase: String,
+    started: Instant,
+    percent: Option<usize>,
+}
+
+impl Events {
+    fn emit(&mut self, percent: usize) {
+        if self.percent.is_some_and(|last| percent <= last) {
+            return;
+        }
+        self.percent = Some(percent);
+        // Progress is only for watching, so a listener going away mustn't stop the solve.
+        let _ = writeln!(
+            self.out,
+            "{{\"day\":\"{}\",\"phase\":\"{}\",\"percent\":{},\"elapsed_us\":{}}}",
+            escape_json(self.day),
+            escape_json(&self.phase),
+            percent,
+            self.started.elapsed().as_micros()
+        );
+        let _ = self.out.flush();
+    }
+}
+
+/// Escape the characters a JSON string can't hold as they are.
+pub(crate) fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            ch if ch.is_control() => escaped.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Write progress events for a day to the file given by "--progress-events <path>", if any. A file
+/// descriptor already open can be given as its path under "/dev/fd".
+pub fn init(day: &'static str) -> io::Result<()> {
+    if let Some(path) = get_option::<String>("--progress-events")? {
+        *EVENTS.lock().unwrap() = Some(Events {
+            out: Box::new(File::create(path)?),
+            day,
+            phase: String::new(),
+            started: Instant::now(),
+            percent: None,
+        });
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// Start a phase of the solve, such as "part two".
+pub fn start(phase: &str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(events) = EVENTS.lock().unwrap().as_mut() {
+        events.phase = phase.to_string();
+        events.started = Instant::now();
+        events.percent = None;
+        events.emit(0);
+    }
+}
+
+/// Record that `done` out of `total` steps of the current phase have been taken.
+pub fn advance(done: usize, total: usize) {
+    if !ENABLED.load(Ordering::Relaxed) || total == 0 {
+        return;
+    }
+    if let Some(events) = EVENTS.lock().unwrap().as_mut() {
+        events.emit(done.min(total) * 100 / total);
+    }
+}
+
+/// Finish the current phase.
+pub fn finish() {
+    advance(1, 1);
+}
+
+/// Take `total` steps, a percent of them at a time, recording progress after each batch.
+pub fn in_steps<F: FnMut(usize)>(total: usize, mut take: F) {
+    let batch = (total / 100).max(1);
+    let mut done = 0;
+    while done < total {
+        let steps = batch.min(total - done);
+        take(steps);
+        done += steps;
+        advance(done, total);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// A stream which can still be read once it has been handed over.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        assert_eq!(escape_json("a \"b\"\n"), "a \\\"b\\\"\\u000a");
+
+        // Only events which get further through the phase are written.
+        let shared = Shared::default();
+        let mut events = Events {
+            out: Box::new(shared.clone()),
+            day: "23",
+            phase: "part \"two\"".to_string(),
+            started: Instant::now(),
+            percent: None,
+        };
+        for percent in [0, 0, 42, 41, 100, 100] {
+            events.emit(percent);
+        }
+        let out = String::from_utf8(shared.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(
+            lines[0].starts_with(r#"{"day":"23","phase":"part \"two\"","percent":0,"elapsed_us":"#)
+        );
+        assert!(lines[1].contains(r#""percent":42,"#));
+        assert!(lines[2].contains(r#""percent":100,"#));
+        assert!(lines.iter().all(|line| line.ends_with('}')));
+
+        // Every step is taken, in batches of a percent, whether or not anyone is listening.
+        let mut batches = Vec::new();
+        in_steps(250, |steps| batches.push(steps));
+        assert_eq!(batches.len(), 125);
+        assert_eq!(batches.iter().sum::<usize>(), 250);
+        in_steps(3, |steps| batches.push(steps));
+        assert_eq!(batches[125..], [1, 1, 1]);
+    }
+}
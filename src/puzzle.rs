@@ -0,0 +1,254 @@
+/// Puzzle descriptions, fetched from the Advent of Code site once and cached, and drawn as text
+/// for reading in a terminal.
+use crate::leaderboard::YEAR;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Where a day's puzzle page is cached.
+pub fn cache_path(day: u32) -> PathBuf {
+    PathBuf::from(format!("cache/puzzle-{}-{:02}.html", YEAR, day))
+}
+
+/// Fetch a day's puzzle page and cache it. Part two is only on the page once part one has been
+/// solved by whoever the session token belongs to, so it's left out without one.
+pub fn fetch(day: u32, session: Option<&str>) -> io::Result<String> {
+    let url = format!("https://adventofcode.com/{}/day/{}", YEAR, day);
+    let html = crate::fetch_page(&url, session)?;
+    let path = cache_path(day);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, &html)?;
+    Ok(html)
+}
+
+/// The descriptions of each part on a puzzle page.
+pub fn descriptions(html: &str) -> Vec<&str> {
+    let mut descriptions = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find("<article class=\"day-desc\">") {
+        rest = &rest[start..];
+        let end = rest.find("</article>").unwrap_or(rest.len());
+        descriptions.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    descriptions
+}
+
+/// Replace the character references the puzzle pages use with the characters they stand for.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) if end <= 8 => end,
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let ch = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            entity => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match ch {
+            Some(ch) => {
+                decoded.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Styles switched on and off with ANSI escape codes.
+const BOLD: (&str, &str) = ("\x1b[1m", "\x1b[22m");
+const CODE: (&str, &str) = ("\x1b[36m", "\x1b[39m");
+
+/// How many columns a piece of text takes up, not counting the escape codes in it.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut escaped = false;
+    for ch in text.chars() {
+        match (escaped, ch) {
+            (false, '\x1b') => escaped = true,
+            (false, _) => width += 1,
+            (true, 'm') => escaped = false,
+            (true, _) => {}
+        }
+    }
+    width
+}
+
+/// Wrap text to a width, starting the first line with `first` and the rest with `indent`.
+fn wrap(text: &str, width: usize, first: &str, indent: &str) -> String {
+    let mut out = String::from(first);
+    let mut column = visible_width(first);
+    let mut line_empty = true;
+    for word in text.split_whitespace() {
+        let word_width = visible_width(word);
+        if !line_empty && column + 1 + word_width > width {
+            out.push('\n');
+            out.push_str(indent);
+            column = visible_width(indent);
+            line_empty = true;
+        }
+        if !line_empty {
+            out.push(' ');
+            column += 1;
+        }
+        out.push_str(word);
+        column += word_width;
+        line_empty = false;
+    }
+    out
+}
+
+/// Draw a puzzle description as text for a terminal `width` columns wide: headings and emphasis
+/// in bold, code in colour, paragraphs and list items wrapped, and code blocks indented as given.
+pub fn render(description: &str, width: usize) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+    // The text of the block being read, and how to start it once wrapped.
+    let mut text = String::new();
+    let mut bullet = None;
+    let mut in_pre = false;
+    let finish = |text: &mut String, bullet: &mut Option<&str>, blocks: &mut Vec<String>| {
+        if !text.trim().is_empty() {
+            let block = match bullet.take() {
+                Some(bullet) => wrap(text, width, bullet, "    "),
+                None => wrap(text, width, "", ""),
+            };
+            blocks.push(block);
+        }
+        text.clear();
+    };
+    let mut rest = description;
+    while !rest.is_empty() {
+        let (chunk, tag) = match rest.find('<') {
+            Some(0) => {
+                let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+                let tag = &rest[..end];
+                rest = &rest[end..];
+                (None, Some(tag))
+            }
+            Some(start) => {
+                let chunk = &rest[..start];
+                rest = &rest[start..];
+                (Some(chunk), None)
+            }
+            None => {
+                let chunk = rest;
+                rest = "";
+                (Some(chunk), None)
+            }
+        };
+        if let Some(chunk) = chunk {
+            text.push_str(&decode_entities(chunk));
+            continue;
+        }
+        let tag = tag.unwrap_or_default();
+        let name = tag
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        match name {
+            "h2" => {
+                finish(&mut text, &mut bullet, &mut blocks);
+                text.push_str(BOLD.0);
+            }
+            "/h2" => {
+                text.push_str(BOLD.1);
+                finish(&mut text, &mut bullet, &mut blocks);
+            }
+            "p" | "/p" | "ul" | "/ul" | "/li" => finish(&mut text, &mut bullet, &mut blocks),
+            "li" => {
+                finish(&mut text, &mut bullet, &mut blocks);
+                bullet = Some("  - ");
+            }
+            "pre" => {
+                finish(&mut text, &mut bullet, &mut blocks);
+                in_pre = true;
+            }
+            "/pre" => {
+                // Code blocks keep their lines as they are, rather than being wrapped.
+                let block = text
+                    .trim_end_matches('\n')
+                    .lines()
+                    .map(|line| format!("    {}{}{}", CODE.0, line, CODE.1))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                blocks.push(block);
+                text.clear();
+                in_pre = false;
+            }
+            "em" => text.push_str(BOLD.0),
+            "/em" => text.push_str(BOLD.1),
+            "code" if !in_pre => text.push_str(CODE.0),
+            "/code" if !in_pre => text.push_str(CODE.1),
+            _ => {}
+        }
+    }
+    finish(&mut text, &mut bullet, &mut blocks);
+    let mut out = blocks.join("\n\n");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &amp; &#39;c&#x27; & d;"),
+            "a <b> & 'c' & d;"
+        );
+    }
+
+    #[test]
+    fn test_descriptions() {
+        let html = "<main><article class=\"day-desc\"><h2>One</h2></article>\
+                    <p>Answer</p><article class=\"day-desc\"><h2>Two</h2></article></main>";
+        let descriptions = descriptions(html);
+        assert_eq!(descriptions.len(), 2);
+        assert!(descriptions[1].ends_with("<h2>Two</h2>"));
+    }
+
+    #[test]
+    fn test_render() {
+        let description = "<article class=\"day-desc\"><h2>--- Day 1: Report Repair ---</h2>\
+            <p>Find the <em>two entries</em> that sum to <code>2020</code>; what do you get?</p>\
+            <pre><code>1721\n979\n</code></pre>\
+            <ul><li>One &amp; only item which goes on and on</li></ul></article>";
+        assert_eq!(
+            render(description, 30),
+            "\x1b[1m--- Day 1: Report Repair ---\x1b[22m\n\
+             \n\
+             Find the \x1b[1mtwo entries\x1b[22m that sum\n\
+             to \x1b[36m2020\x1b[39m; what do you get?\n\
+             \n    \x1b[36m1721\x1b[39m\n    \x1b[36m979\x1b[39m\n\
+             \n  - One & only item which goes\n    on and on\n"
+        );
+    }
+}
@@ -0,0 +1,441 @@
+/// Reports summarising every day's answers and how long they took, gathered from the results
+/// each day's binary prints.
+use crate::answer::DayResult;
+use crate::progress::escape_json;
+use std::fmt::Write;
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The format to write a report in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A Markdown table, for pasting into a results log.
+    Markdown,
+    /// A standalone HTML page of the table, followed by drawings of the days with something to
+    /// see.
+    Html,
+}
+
+impl FromStr for Format {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "md" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid report format {:?}: expected md or html", s),
+            )),
+        }
+    }
+}
+
+/// What a day's binary reported about its run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DayReport {
+    pub day: u32,
+    /// How long parsing the input took, for the days which time it separately.
+    pub parse: Option<Duration>,
+    /// The result of each part, for the parts which were answered.
+    pub parts: [Option<DayResult>; 2],
+    pub notes: String,
+    /// How the day's binary failed, if it did (e.g. "exit status: 101").
+    pub failure: Option<String>,
+}
+
+impl DayReport {
+    /// Format the answer to a part, "failed" if the day failed before answering it, or a dash if
+    /// it wasn't answered.
+    fn format_answer(&self, part: usize) -> String {
+        match (&self.parts[part], &self.failure) {
+            (Some(result), _) => result.answer.to_string(),
+            (None, Some(_)) => "failed".to_string(),
+            (None, None) => "-".to_string(),
+        }
+    }
+
+    /// The notes on the day, or how it failed if it did.
+    fn format_notes(&self) -> String {
+        match &self.failure {
+            Some(failure) => format!("Failed with {}", failure),
+            None => self.notes.clone(),
+        }
+    }
+}
+
+/// Parse a "Took 123µs." line into the time taken.
+fn parse_took(line: &str) -> Option<Duration> {
+    let micros = line.strip_prefix("Took ")?.strip_suffix("µs.")?;
+    micros.parse().ok().map(Duration::from_micros)
+}
+
+/// Read the answers and timings out of the output of a day's binary.
+///
+/// Each day prints the result of each part as a `DayResult`, e.g. "Day 7 part 1: 355 (375µs)",
+/// and may print more about it on the lines after. Days which announce "Getting data..." then
+/// print the time taken to parse their input.
+pub fn parse_output(day: u32, output: &str) -> DayReport {
+    let mut report = DayReport {
+        day,
+        ..Default::default()
+    };
+    let mut parsing = false;
+    for line in output.lines().map(str::trim) {
+        if line == "Getting data..." {
+            parsing = true;
+        } else if let Some(took) = parse_took(line) {
+            if parsing && report.parse.is_none() {
+                report.parse = Some(took);
+            }
+        } else if let Ok(result) = line.parse::<DayResult>() {
+            if result.day == day && (1..=2).contains(&result.part) {
+                let slot = &mut report.parts[result.part as usize - 1];
+                if slot.is_none() {
+                    *slot = Some(result);
+                }
+            }
+        }
+    }
+    report
+}
+
+/// The first paragraph of the doc comment at the top of a module's source, on one line.
+fn module_notes(source: &str) -> String {
+    source
+        .lines()
+        .map_while(|line| {
+            line.strip_prefix("///")
+                .or_else(|| line.strip_prefix("//!"))
+        })
+        .map(str::trim)
+        .take_while(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Notes on how a day is implemented, from the doc comments of the library modules it's built on.
+pub fn day_notes(day: u32) -> String {
+    let source = match day {
+        1 => include_str!("expenses.rs"),
+        2 => include_str!("password.rs"),
+        3 => include_str!("toboggan.rs"),
+        4 => include_str!("passports.rs"),
+        5 => include_str!("boarding.rs"),
+        6 => include_str!("customs.rs"),
+        7 => include_str!("bags.rs"),
+        8 => include_str!("vm.rs"),
+        9 => include_str!("xmas.rs"),
+        10 => include_str!("adapters.rs"),
+        11 => include_str!("seating.rs"),
+        12 => include_str!("navigation.rs"),
+        13 => include_str!("buses.rs"),
+        14 => include_str!("docking.rs"),
+        15 => include_str!("vaneck.rs"),
+        16 => include_str!("tickets.rs"),
+        17 => include_str!("conway.rs"),
+        18 => include_str!("homework.rs"),
+        19 => include_str!("grammar.rs"),
+        20 => include_str!("jigsaw.rs"),
+        21 => include_str!("allergens.rs"),
+        22 => include_str!("combat.rs"),
+        23 => include_str!("cups.rs"),
+        24 => include_str!("lobby.rs"),
+        25 => include_str!("handshake.rs"),
+        _ => return String::new(),
+    };
+    module_notes(source)
+}
+
+/// Format a time taken in microseconds, or a dash if it wasn't reported.
+fn format_took(took: Option<Duration>) -> String {
+    match took {
+        Some(took) => format!("{}µs", took.as_micros()),
+        None => "-".to_string(),
+    }
+}
+
+/// How long a part took to solve, if it was answered and timed.
+fn part_took(result: &Option<DayResult>) -> Option<Duration> {
+    result.as_ref().and_then(|result| result.duration)
+}
+
+/// Write a Markdown table of the answers, timings and notes of each day.
+pub fn to_markdown(reports: &[DayReport]) -> String {
+    let cell = |s: &str| s.replace('|', "\\|");
+    let mut table = String::new();
+    table.push_str(
+        "| Day | Part one | Part two | Parse | Part one time | Part two time | Notes |\n",
+    );
+    table.push_str(
+        "|----:|----------|----------|------:|--------------:|--------------:|-------|\n",
+    );
+    for report in reports {
+        let [one, two] = &report.parts;
+        writeln!(
+            table,
+            "| {} | {} | {} | {} | {} | {} | {} |",
+            report.day,
+            cell(&report.format_answer(0)),
+            cell(&report.format_answer(1)),
+            format_took(report.parse),
+            format_took(part_took(one)),
+            format_took(part_took(two)),
+            cell(&report.format_notes()),
+        )
+        .unwrap();
+    }
+    table
+}
+
+/// Write the answers and timings of each day as a JSON object, along with how long the whole run
+/// took, for sending on to other tools. Anything a day didn't report is `null`, as is the failure
+/// of a day which didn't fail.
+pub fn to_json(reports: &[DayReport], took: Duration) -> String {
+    let answer = |result: &Option<DayResult>| match result {
+        Some(result) => format!("\"{}\"", escape_json(&result.answer.to_string())),
+        None => "null".to_string(),
+    };
+    let micros = |took: Option<Duration>| match took {
+        Some(took) => took.as_micros().to_string(),
+        None => "null".to_string(),
+    };
+    let days = reports
+        .iter()
+        .map(|report| {
+            let [one, two] = &report.parts;
+            let failure = match &report.failure {
+                Some(failure) => format!("\"{}\"", escape_json(failure)),
+                None => "null".to_string(),
+            };
+            format!(
+                "{{\"day\":{},\"answers\":[{},{}],\"parse_us\":{},\"took_us\":[{},{}],\"failure\":{}}}",
+                report.day,
+                answer(one),
+                answer(two),
+                micros(report.parse),
+                micros(part_took(one)),
+                micros(part_took(two)),
+                failure
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{{\"took_ms\":{},\"days\":[{}]}}", took.as_millis(), days)
+}
+
+/// A drawing of the outcome of a day, as text a character per cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Visual {
+    pub day: u32,
+    pub caption: String,
+    pub drawing: String,
+}
+
+/// Escape the characters HTML gives a meaning to.
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Write a standalone HTML page with a table of the answers, timings and notes of each day, and
+/// each drawing below it in a figure of its own.
+pub fn to_html(reports: &[DayReport], visuals: &[Visual]) -> String {
+    let mut page = String::new();
+    page.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    page.push_str("<title>Advent of Code 2020</title>\n<style>\n");
+    page.push_str("table { border-collapse: collapse; }\n");
+    page.push_str("th, td { border: 1px solid #ccc; padding: 0.2em 0.5em; }\n");
+    page.push_str("pre { font-size: 6px; line-height: 6px; }\n");
+    page.push_str("</style>\n</head>\n<body>\n<h1>Advent of Code 2020</h1>\n<table>\n");
+    page.push_str("<tr><th>Day</th><th>Part one</th><th>Part two</th><th>Parse</th>");
+    page.push_str("<th>Part one time</th><th>Part two time</th><th>Notes</th></tr>\n");
+    for report in reports {
+        let [one, two] = &report.parts;
+        writeln!(
+            page,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            report.day,
+            escape_html(&report.format_answer(0)),
+            escape_html(&report.format_answer(1)),
+            format_took(report.parse),
+            format_took(part_took(one)),
+            format_took(part_took(two)),
+            escape_html(&report.format_notes()),
+        )
+        .unwrap();
+    }
+    page.push_str("</table>\n");
+    for visual in visuals {
+        writeln!(
+            page,
+            "<figure>\n<figcaption>Day {}: {}</figcaption>\n<pre>{}</pre>\n</figure>",
+            visual.day,
+            escape_html(&visual.caption),
+            escape_html(&visual.drawing),
+        )
+        .unwrap();
+    }
+    page.push_str("</body>\n</html>\n");
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer::Answer;
+
+    fn answered(day: u32, part: u32, answer: &str, micros: Option<u64>) -> Option<DayResult> {
+        Some(DayResult {
+            day,
+            part,
+            answer: answer.parse().unwrap(),
+            duration: micros.map(Duration::from_micros),
+        })
+    }
+
+    #[test]
+    fn test_parse_output() {
+        let output = "Day 07:
+==========
+Getting data...
+Took 564µs.
+==========
+Solving part one...
+Day 7 part 1: 355 (375µs)
+==========
+Solving part two...
+Day 7 part 2: 5312 (31µs)
+";
+        let report = parse_output(7, output);
+        assert_eq!(report.parse, Some(Duration::from_micros(564)));
+        assert_eq!(report.parts[0], answered(7, 1, "355", Some(375)));
+        assert_eq!(report.parts[1], answered(7, 2, "5312", Some(31)));
+
+        // Not every day times parsing its input, and some say more about their answers.
+        let output = "Day 01:
+==========
+Day 1 part 1: 539851 (1µs)
+Entries: 317, 1703
+Day 1 part 2: 212481360 (46µs)
+Entries: 315, 624, 1081
+";
+        let report = parse_output(1, output);
+        assert_eq!(report.parse, None);
+        assert_eq!(report.parts[0], answered(1, 1, "539851", Some(1)));
+        assert_eq!(
+            report.parts[1].as_ref().map(|result| &result.answer),
+            Some(&Answer::Number(212481360))
+        );
+
+        // A day which fails part way through has nothing for the rest.
+        let report = parse_output(25, "Day 25:\n==========\nGetting data...\n");
+        assert_eq!(report.parts[0], None);
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        assert_eq!(
+            day_notes(17),
+            "Conway cubes of Day 17: the Game of Life in any number of dimensions."
+        );
+        assert!(day_notes(19).starts_with("Message rules of Day 19"));
+        assert_eq!(
+            day_notes(1),
+            "Expense report entries of Day 1 and the sets of them which sum to a target."
+        );
+        // Every day's module opens with its notes.
+        for day in 1..=25 {
+            assert!(!day_notes(day).is_empty(), "No notes on day {}", day);
+        }
+        assert_eq!(day_notes(26), "");
+
+        let report = DayReport {
+            day: 21,
+            parts: [answered(21, 1, "2635", Some(49)), None],
+            notes: "a | b".to_string(),
+            ..Default::default()
+        };
+        let table = to_markdown(&[report]);
+        assert_eq!(
+            table.lines().nth(2),
+            Some("| 21 | 2635 | - | - | 49µs | - | a \\| b |")
+        );
+
+        // A day which failed says so rather than leaving its cells blank.
+        let report = DayReport {
+            day: 25,
+            parts: [answered(25, 1, "42", None), None],
+            failure: Some("exit status: 101".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            to_markdown(&[report]).lines().nth(2),
+            Some("| 25 | 42 | failed | - | - | - | Failed with exit status: 101 |")
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let report = DayReport {
+            day: 21,
+            parse: Some(Duration::from_micros(12)),
+            parts: [
+                answered(21, 1, "2635", Some(49)),
+                answered(21, 2, "x\"y", None),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            to_json(&[report], Duration::from_millis(1500)),
+            r#"{"took_ms":1500,"days":[{"day":21,"answers":["2635","x\"y"],"parse_us":12,"took_us":[49,null],"failure":null}]}"#
+        );
+        let report = DayReport {
+            day: 25,
+            failure: Some("exit status: 101".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            to_json(&[report], Duration::from_millis(7)),
+            r#"{"took_ms":7,"days":[{"day":25,"answers":[null,null],"parse_us":null,"took_us":[null,null],"failure":"exit status: 101"}]}"#
+        );
+        assert_eq!(
+            to_json(&[], Duration::from_millis(3)),
+            r#"{"took_ms":3,"days":[]}"#
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        let report = DayReport {
+            day: 21,
+            parts: [answered(21, 1, "2635", Some(49)), None],
+            notes: "Matching <slots> & values".to_string(),
+            ..Default::default()
+        };
+        let visual = Visual {
+            day: 11,
+            caption: "Final floorplan".to_string(),
+            drawing: "#.L\nL.#".to_string(),
+        };
+        let page = to_html(&[report], &[visual]);
+        assert!(page.contains(
+            "<tr><td>21</td><td>2635</td><td>-</td><td>-</td><td>49µs</td><td>-</td>\
+             <td>Matching &lt;slots&gt; &amp; values</td></tr>"
+        ));
+        assert!(
+            page.contains("<figcaption>Day 11: Final floorplan</figcaption>\n<pre>#.L\nL.#</pre>")
+        );
+        assert!(page.ends_with("</html>\n"));
+    }
+}
@@ -0,0 +1,391 @@
+/// Seating system simulation of Day 11.
+use crate::grid::{Grid, Neighborhood, Position, DIRECTIONS_8};
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::fmt;
+use std::io;
+use std::iter;
+use std::mem;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tile {
+    Floor,
+    Seat(bool),
+}
+
+impl Tile {
+    pub fn from_ch(ch: char) -> Option<Self> {
+        match ch {
+            '.' => Some(Self::Floor),
+            'L' => Some(Self::Seat(false)),
+            '#' => Some(Self::Seat(true)),
+            _ => None,
+        }
+    }
+
+    pub fn to_ch(&self) -> char {
+        match self {
+            Self::Floor => '.',
+            Self::Seat(false) => 'L',
+            Self::Seat(true) => '#',
+        }
+    }
+
+    pub fn occupied(&self) -> bool {
+        matches!(self, Self::Seat(true))
+    }
+
+    pub fn is_seat(&self) -> bool {
+        matches!(self, Self::Seat(_))
+    }
+}
+
+/// The first seat visible along each of the eight directions out from a seat, looking through
+/// floor at most `limit` places away (or as far as the floorplan goes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sightline {
+    pub limit: Option<usize>,
+}
+
+impl Neighborhood<Tile> for Sightline {
+    fn neighbours(&self, grid: &Grid<Tile>, pos: Position) -> Vec<Position> {
+        DIRECTIONS_8
+            .iter()
+            .filter_map(|&direction| {
+                iter::successors(grid.offset(pos, direction), |&seen| {
+                    grid.offset(seen, direction)
+                })
+                .take(self.limit.unwrap_or(usize::MAX))
+                .find(|&seen| grid[seen].is_seat())
+            })
+            .collect()
+    }
+}
+
+/// The rules seats change by: an empty seat is filled if none of the seats it can see are
+/// occupied, and an occupied seat is vacated if at least `tolerance` of them are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeatingRules {
+    pub tolerance: usize,
+    /// How far seats can see, or `None` to see as far as the floorplan goes.
+    pub sight: Option<usize>,
+}
+
+impl SeatingRules {
+    /// The rules of part one: seats see only those adjacent, and tolerate three occupied.
+    pub const PART_ONE: Self = Self {
+        tolerance: 4,
+        sight: Some(1),
+    };
+
+    /// The rules of part two: seats see along their sightlines, and tolerate four occupied.
+    pub const PART_TWO: Self = Self {
+        tolerance: 5,
+        sight: None,
+    };
+
+    /// The seats each seat considers under these rules.
+    pub fn neighbourhood(&self) -> Sightline {
+        Sightline { limit: self.sight }
+    }
+}
+
+impl FromStr for SeatingRules {
+    type Err = io::Error;
+
+    /// Parse "<tolerance>,<sight>", where the sight is a distance or "inf" for unlimited.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Invalid seating rules {:?}: expected <tolerance>,<sight> with both above 0",
+                    s
+                ),
+            )
+        };
+        let (tolerance, sight) = s.split(',').collect_tuple().ok_or_else(invalid)?;
+        let tolerance = tolerance.parse().map_err(|_| invalid())?;
+        let sight = match sight {
+            "inf" => None,
+            _ => Some(sight.parse().map_err(|_| invalid())?),
+        };
+        // Seats which can't see, or can't bear even themselves, would never settle.
+        if tolerance == 0 || sight == Some(0) {
+            return Err(invalid());
+        }
+        Ok(Self { tolerance, sight })
+    }
+}
+
+impl fmt::Display for SeatingRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.sight {
+            Some(sight) => write!(f, "{},{}", self.tolerance, sight),
+            None => write!(f, "{},inf", self.tolerance),
+        }
+    }
+}
+
+/// The floorplan as it evolves under a set of seating rules.
+///
+/// Each generation is written into a second buffer which is then swapped with the first, so no
+/// floorplans are allocated after the simulation is created.
+pub struct Simulation {
+    current: Grid<Tile>,
+    next: Grid<Tile>,
+    /// The index of each seat each cell can see, which never changes since seats don't move.
+    neighbours: Vec<Vec<usize>>,
+    tolerance: usize,
+    /// Whether the latest generation matches the one before the last, but not the last.
+    oscillating: bool,
+    parallel: bool,
+}
+
+impl Simulation {
+    pub fn new(floorplan: &Grid<Tile>, rules: SeatingRules) -> Self {
+        // Scanning the sightlines once up front saves repeating them every generation.
+        let neighbourhood = rules.neighbourhood();
+        let width = floorplan.width();
+        let neighbours = floorplan
+            .enumerate()
+            .map(|(pos, tile)| match tile {
+                Tile::Floor => Vec::new(),
+                Tile::Seat(_) => neighbourhood
+                    .neighbours(floorplan, pos)
+                    .into_iter()
+                    .map(|(row, col)| row * width + col)
+                    .collect(),
+            })
+            .collect();
+        Self {
+            current: floorplan.clone(),
+            next: floorplan.clone(),
+            neighbours,
+            tolerance: rules.tolerance,
+            oscillating: false,
+            parallel: false,
+        }
+    }
+
+    /// The floorplan as of the latest generation.
+    pub fn floorplan(&self) -> &Grid<Tile> {
+        &self.current
+    }
+
+    /// Whether the floorplan has fallen into flipping between two generations forever.
+    pub fn is_oscillating(&self) -> bool {
+        self.oscillating
+    }
+
+    /// Count the occupied seats in the latest generation.
+    pub fn occupied(&self) -> usize {
+        self.current.iter().filter(|tile| tile.occupied()).count()
+    }
+
+    /// Compute each generation's rows in parallel, which gives the same results as computing
+    /// them in turn.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Apply the seat change rules once, returning whether any seat changed.
+    pub fn step(&mut self) -> bool {
+        let cells = self.current.as_slice();
+        let tolerance = self.tolerance;
+        let (changed, reverted) = if self.parallel {
+            // Each row only reads the current generation, so they can all be written at once.
+            let width = self.current.width().max(1);
+            self.next
+                .as_mut_slice()
+                .par_chunks_mut(width)
+                .zip(self.neighbours.par_chunks(width))
+                .enumerate()
+                .map(|(row, (to, neighbours))| {
+                    let from = &cells[row * width..(row + 1) * width];
+                    update_seats(cells, from, neighbours, to, tolerance)
+                })
+                .reduce(
+                    || (false, true),
+                    |(changed, reverted), (row_changed, row_reverted)| {
+                        (changed || row_changed, reverted && row_reverted)
+                    },
+                )
+        } else {
+            update_seats(
+                cells,
+                cells,
+                &self.neighbours,
+                self.next.as_mut_slice(),
+                tolerance,
+            )
+        };
+        mem::swap(&mut self.current, &mut self.next);
+        self.oscillating = changed && reverted;
+        changed
+    }
+
+    /// Iterate the seat changes until the floorplan stabilises, and count the occupied seats.
+    /// Returns `None` if the floorplan oscillates instead.
+    pub fn run(&mut self) -> Option<usize> {
+        while self.step() {
+            if self.oscillating {
+                return None;
+            }
+        }
+        Some(self.occupied())
+    }
+}
+
+/// Apply the seat change rules to a run of seats, writing the next generation of `from` into
+/// `to`, where `cells` is the whole of the current generation for looking up neighbours and `to`
+/// still holds the generation before it. Returns whether any seat changed, and whether all of them
+/// reverted to the generation before.
+fn update_seats(
+    cells: &[Tile],
+    from: &[Tile],
+    neighbours: &[Vec<usize>],
+    to: &mut [Tile],
+    tolerance: usize,
+) -> (bool, bool) {
+    let mut changed = false;
+    let mut reverted = true;
+    for ((&tile, neighbours), to) in from.iter().zip(neighbours).zip(to) {
+        let occupied = || {
+            neighbours
+                .iter()
+                .filter(|&&neighbour| cells[neighbour].occupied())
+                .count()
+        };
+        let before = *to;
+        *to = match tile {
+            // If seat is empty and no neighbouring seats are occupied, it is filled.
+            Tile::Seat(false) if occupied() == 0 => Tile::Seat(true),
+            // If seat is occupied and enough neighbouring seats are too, it is vacated.
+            Tile::Seat(true) if occupied() >= tolerance => Tile::Seat(false),
+            _ => tile,
+        };
+        changed |= *to != tile;
+        reverted &= *to == before;
+    }
+    (changed, reverted)
+}
+
+/// Iterate the seat changes until the floorplan stabilises, as `Simulation::run` does, but finding
+/// the seats each seat considers afresh from a neighbourhood every generation rather than once up
+/// front. Kept to compare `Simulation` against.
+pub fn run_naive<N>(floorplan: &Grid<Tile>, neighbourhood: &N, tolerance: usize) -> Option<usize>
+where
+    N: Neighborhood<Tile>,
+{
+    let mut before: Option<Grid<Tile>> = None;
+    let mut current = floorplan.clone();
+    loop {
+        let mut next = current.clone();
+        for ((pos, &tile), to) in current.enumerate().zip(next.iter_mut()) {
+            let occupied = || neighbourhood.count(&current, pos, Tile::occupied);
+            *to = match tile {
+                Tile::Seat(false) if occupied() == 0 => Tile::Seat(true),
+                Tile::Seat(true) if occupied() >= tolerance => Tile::Seat(false),
+                _ => tile,
+            };
+        }
+        if next == current {
+            return Some(next.iter().filter(|tile| tile.occupied()).count());
+        }
+        // Back to the generation before the last, so it will flip between them forever.
+        if before.as_ref() == Some(&next) {
+            return None;
+        }
+        before = Some(mem::replace(&mut current, next));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{Adjacent8, FirstVisible};
+
+    #[test]
+    fn test_generations() {
+        let floorplan = Grid::from_chars("L.L\nLLL\n.L.", Tile::from_ch).unwrap();
+        let mut simulation = Simulation::new(&floorplan, SeatingRules::PART_ONE);
+
+        // Every seat fills, then the crowded middle seat empties and the rest stay put.
+        assert!(simulation.step());
+        assert_eq!(simulation.floorplan().render(Tile::to_ch), "#.#\n###\n.#.");
+        assert!(simulation.step());
+        assert_eq!(simulation.floorplan().render(Tile::to_ch), "#.#\n#L#\n.#.");
+        assert_eq!(simulation.run(), Some(5));
+        assert!(!simulation.step());
+    }
+
+    #[test]
+    fn test_rules() {
+        let rules: SeatingRules = "5,inf".parse().unwrap();
+        assert_eq!(rules, SeatingRules::PART_TWO);
+        assert_eq!(SeatingRules::PART_ONE.to_string(), "4,1");
+        assert!("0,1".parse::<SeatingRules>().is_err());
+        assert!("4,0".parse::<SeatingRules>().is_err());
+        assert!("4".parse::<SeatingRules>().is_err());
+
+        // Sight stops at the limit, but sees through floor up to it.
+        let floorplan = Grid::from_chars("L..L.L", Tile::from_ch).unwrap();
+        let seen = |sight| Sightline { limit: sight }.neighbours(&floorplan, (0, 0));
+        assert_eq!(seen(Some(2)), vec![]);
+        assert_eq!(seen(Some(3)), vec![(0, 3)]);
+        assert_eq!(seen(None), vec![(0, 3)]);
+
+        // Seats which can't bear a single neighbour flip between full and empty forever.
+        let floorplan = Grid::from_chars("LL", Tile::from_ch).unwrap();
+        let mut simulation = Simulation::new(&floorplan, "1,1".parse().unwrap());
+        assert_eq!(simulation.run(), None);
+        assert!(simulation.is_oscillating());
+    }
+
+    #[test]
+    fn test_naive_matches() {
+        let floorplan = Grid::from_chars(
+            "L.LL.LL.LL\nLLLLLLL.LL\nL.L.L..L..\nLLLL.LL.LL\nL.LL.LL.LL",
+            Tile::from_ch,
+        )
+        .unwrap();
+        let run = |rules| Simulation::new(&floorplan, rules).run();
+        assert_eq!(
+            run_naive(&floorplan, &Adjacent8, 4),
+            run(SeatingRules::PART_ONE)
+        );
+        let sightlines = FirstVisible {
+            is_visible: Tile::is_seat,
+        };
+        assert_eq!(
+            run_naive(&floorplan, &sightlines, 5),
+            run(SeatingRules::PART_TWO)
+        );
+        let floorplan = Grid::from_chars("LL", Tile::from_ch).unwrap();
+        assert_eq!(run_naive(&floorplan, &Adjacent8, 1), None);
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let floorplan = Grid::from_chars(
+            "L.LL.LL.LL\nLLLLLLL.LL\nL.L.L..L..\nLLLL.LL.LL\nL.LL.LL.LL",
+            Tile::from_ch,
+        )
+        .unwrap();
+        for &rules in &[SeatingRules::PART_ONE, SeatingRules::PART_TWO] {
+            let mut serial = Simulation::new(&floorplan, rules);
+            let mut parallel = Simulation::new(&floorplan, rules).parallel(true);
+            loop {
+                let changed = serial.step();
+                assert_eq!(parallel.step(), changed);
+                assert_eq!(parallel.floorplan(), serial.floorplan());
+                if !changed {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+/// Snapshots of the long simulations saved to disk part way through, so that a long solve can be
+/// stopped and picked up again later, and its state at some point looked over when debugging.
+///
+/// The days with long simulations (15, 23 and 24) save a snapshot of each phase to a folder given
+/// by "--checkpoint <dir>" as they go, and with "--resume" start each phase from its snapshot if
+/// there is one. A snapshot file starts with "AOC2020" and a tag for the kind of engine it holds,
+/// then a hash of the engine's state when the phase started, how many steps the phase takes and how
+/// many of them had been taken, then the engine's state as little-endian integers. A phase only
+/// resumes from a snapshot of the same start and length, so a snapshot left over from another input
+/// is refused rather than picked up. Each is written beside where it's going and renamed into
+/// place, so a snapshot interrupted part way through being written never replaces a good one.
+use crate::{get_option, has_flag, progress};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The start of every snapshot file.
+const MAGIC: [u8; 8] = *b"AOC2020\n";
+
+/// How many snapshots to save over a phase, unless overridden with "--checkpoint-every <steps>".
+const CHECKPOINTS: usize = 10;
+
+/// An engine which can be saved to and loaded from a snapshot.
+pub trait Snapshot: Sized {
+    /// Marks snapshots of this kind of engine, so that one isn't loaded as another.
+    const TAG: [u8; 8];
+
+    fn write_state(&self, out: &mut dyn Write) -> io::Result<()>;
+
+    fn read_state(input: &mut dyn Read) -> io::Result<Self>;
+
+    /// A summary of the state, for looking over a snapshot.
+    fn describe(&self) -> String;
+}
+
+/// How a snapshot's phase started and how far it had got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// A hash of the engine's state when the phase started, from `fingerprint`.
+    pub start: u64,
+    /// How many steps the phase takes.
+    pub total: usize,
+    /// How many steps had been taken.
+    pub steps: usize,
+}
+
+/// Feeds everything written to it into an FNV-1a hash.
+struct Fnv(u64);
+
+impl Write for Fnv {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A hash of an engine's state, to tell whether a snapshot started from it. The hash is of what
+/// would be saved, so is the same from run to run.
+pub fn fingerprint<T: Snapshot>(engine: &T) -> io::Result<u64> {
+    let mut hash = Fnv(0xcbf2_9ce4_8422_2325);
+    engine.write_state(&mut hash)?;
+    Ok(hash.0)
+}
+
+pub(crate) fn write_u64(out: &mut dyn Write, value: u64) -> io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn read_u64(input: &mut dyn Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Write a list of numbers, preceded by its length.
+pub(crate) fn write_u32s(out: &mut dyn Write, values: &[u32]) -> io::Result<()> {
+    write_u64(out, values.len() as u64)?;
+    values
+        .iter()
+        .try_for_each(|value| out.write_all(&value.to_le_bytes()))
+}
+
+pub(crate) fn read_u32s(input: &mut dyn Read) -> io::Result<Vec<u32>> {
+    let len = read_u64(input)? as usize;
+    let mut values = Vec::new();
+    let mut bytes = [0; 4];
+    for _ in 0..len {
+        input.read_exact(&mut bytes)?;
+        values.push(u32::from_le_bytes(bytes));
+    }
+    Ok(values)
+}
+
+/// Write a list of numbers, preceded by its length.
+pub(crate) fn write_u64s(out: &mut dyn Write, values: &[u64]) -> io::Result<()> {
+    write_u64(out, values.len() as u64)?;
+    values.iter().try_for_each(|&value| write_u64(out, value))
+}
+
+pub(crate) fn read_u64s(input: &mut dyn Read) -> io::Result<Vec<u64>> {
+    let len = read_u64(input)? as usize;
+    (0..len).map(|_| read_u64(input)).collect()
+}
+
+/// The error for a snapshot which doesn't hold what it should.
+pub(crate) fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Save an engine to a snapshot, along with how its phase started and how far it has got.
+pub fn save<T: Snapshot>(path: &Path, engine: &T, header: Header) -> io::Result<()> {
+    let partial = path.with_extension("partial");
+    let mut out = BufWriter::new(File::create(&partial)?);
+    out.write_all(&MAGIC)?;
+    out.write_all(&T::TAG)?;
+    write_u64(&mut out, header.start)?;
+    write_u64(&mut out, header.total as u64)?;
+    write_u64(&mut out, header.steps as u64)?;
+    engine.write_state(&mut out)?;
+    out.into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()?;
+    fs::rename(partial, path)
+}
+
+/// Read an engine from a snapshot, along with how its phase started and how far it had got.
+fn open<T: Snapshot>(path: &Path) -> io::Result<(T, Header)> {
+    let mut input = BufReader::new(File::open(path)?);
+    let mut tag = [0; 16];
+    input.read_exact(&mut tag)?;
+    if tag[..8] != MAGIC || tag[8..] != T::TAG {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} isn't a snapshot of this day", path.display()),
+        ));
+    }
+    let header = Header {
+        start: read_u64(&mut input)?,
+        total: read_u64(&mut input)? as usize,
+        steps: read_u64(&mut input)? as usize,
+    };
+    Ok((T::read_state(&mut input)?, header))
+}
+
+/// Load an engine from a snapshot of a phase which started from the state hashed as `start` and
+/// takes `total` steps, along with how many steps it had taken. A snapshot of any other phase is
+/// refused.
+pub fn load<T: Snapshot>(path: &Path, start: u64, total: usize) -> io::Result<(T, usize)> {
+    let (engine, header) = open(path)?;
+    let mismatch = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} was saved {}: delete it or run without --resume",
+                path.display(),
+                what
+            ),
+        )
+    };
+    if header.start != start {
+        return Err(mismatch("from a different starting state"));
+    }
+    if header.total != total {
+        return Err(mismatch(&format!(
+            "from a phase of {} steps, not {}",
+            header.total, total
+        )));
+    }
+    Ok((engine, header.steps.min(total)))
+}
+
+/// Print a summary of a snapshot, for the days given "--inspect <path>".
+pub fn inspect<T: Snapshot>(path: &str) -> io::Result<()> {
+    let (engine, header) = open::<T>(Path::new(path))?;
+    println!("After {} of {} steps:", header.steps, header.total);
+    println!("{}", engine.describe());
+    Ok(())
+}
+
+/// Take `total` steps of a phase of a day, a percent of them at a time as `progress::in_steps`
+/// does, saving a snapshot to the folder given by "--checkpoint <dir>" every tenth of the phase
+/// (or every "--checkpoint-every <steps>" steps) and at the end. With "--resume", the phase starts
+/// from its snapshot in that folder if there is one, so long as it's of this phase from the same
+/// start.
+pub fn run_phase<T, F>(
+    day: &str,
+    phase: &str,
+    engine: &mut T,
+    total: usize,
+    mut take: F,
+) -> io::Result<()>
+where
+    T: Snapshot,
+    F: FnMut(&mut T, usize),
+{
+    let path = get_option::<PathBuf>("--checkpoint")?
+        .map(|dir| dir.join(format!("{}-{}.snapshot", day, phase.replace(' ', "-"))));
+    let every = get_option("--checkpoint-every")?.unwrap_or((total / CHECKPOINTS).max(1));
+    let mut done = 0;
+    if path.is_none() && has_flag("--resume") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Nothing to resume from: expected --checkpoint <dir> too",
+        ));
+    }
+    // Only hash the start when it's to be saved, as it's as big as the engine.
+    let start = match &path {
+        Some(_) => fingerprint(engine)?,
+        None => 0,
+    };
+    if let Some(path) = &path {
+        fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+        if has_flag("--resume") && path.exists() {
+            let (resumed, steps) = load(path, start, total)?;
+            *engine = resumed;
+            done = steps;
+            eprintln!("Resuming {} from step {} of {}", phase, done, total);
+        }
+    }
+    let batch = (total / 100).max(1);
+    let mut since_saved = 0;
+    while done < total {
+        let steps = batch.min(total - done);
+        take(engine, steps);
+        done += steps;
+        since_saved += steps;
+        progress::advance(done, total);
+        if let Some(path) = &path {
+            if since_saved >= every || done == total {
+                let header = Header {
+                    start,
+                    total,
+                    steps: done,
+                };
+                save(path, engine, header)?;
+                since_saved = 0;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stand-in for an engine, holding some numbers.
+    #[derive(Debug, PartialEq)]
+    struct Counter(Vec<u32>);
+
+    impl Snapshot for Counter {
+        const TAG: [u8; 8] = *b"counter\0";
+
+        fn write_state(&self, out: &mut dyn Write) -> io::Result<()> {
+            write_u32s(out, &self.0)
+        }
+
+        fn read_state(input: &mut dyn Read) -> io::Result<Self> {
+            read_u32s(input).map(Self)
+        }
+
+        fn describe(&self) -> String {
+            format!("{:?}", self.0)
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("aoc2020-snapshot-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("counter.snapshot");
+        let start = fingerprint(&Counter(vec![3, 1])).unwrap();
+        let header = Header {
+            start,
+            total: 100,
+            steps: 42,
+        };
+        save(&path, &Counter(vec![3, 1, 4]), header).unwrap();
+        assert_eq!(
+            load::<Counter>(&path, start, 100).unwrap(),
+            (Counter(vec![3, 1, 4]), 42)
+        );
+        assert!(!path.with_extension("partial").exists());
+
+        // A phase which started from a different state, or takes a different number of steps,
+        // doesn't resume from it.
+        let other = fingerprint(&Counter(vec![1, 3])).unwrap();
+        assert_ne!(other, start);
+        let err = load::<Counter>(&path, other, 100).unwrap_err();
+        assert!(err.to_string().contains("different starting state"));
+        assert!(load::<Counter>(&path, start, 1000).is_err());
+
+        // Snapshots of other engines, or truncated ones, aren't loaded.
+        fs::write(&path, b"AOC2020\nother\0\0\0").unwrap();
+        assert!(load::<Counter>(&path, start, 100).is_err());
+        fs::write(&path, b"AOC2020\ncounter\0\x02").unwrap();
+        assert!(load::<Counter>(&path, start, 100).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
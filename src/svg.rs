@@ -0,0 +1,196 @@
+/// Drawings of grids as SVG, for snapshots at a higher resolution than drawing them as text.
+///
+/// Cells are filled with colours from a palette keyed by the character each cell is drawn as in
+/// text, so the same palette works for any day which can draw itself as text.
+use crate::grid::Grid;
+use crate::hex::Coord;
+use std::fmt::Write;
+use std::io;
+use std::str::FromStr;
+
+/// The colours to fill cells with, by the character they're drawn as in text. Cells drawn as a
+/// character without a colour are left out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette(Vec<(char, String)>);
+
+impl Palette {
+    /// The colour of a cell drawn as a character, if it has one.
+    pub fn colour(&self, ch: char) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| *key == ch)
+            .map(|(_, colour)| colour.as_str())
+    }
+}
+
+impl Default for Palette {
+    /// Dark for occupied seats, rough water and black tiles, green for empty seats and pale grey
+    /// for floor, calm water and white tiles.
+    fn default() -> Self {
+        Self(vec![
+            ('#', "#333333".to_string()),
+            ('L', "#4caf50".to_string()),
+            ('.', "#eeeeee".to_string()),
+        ])
+    }
+}
+
+impl FromStr for Palette {
+    type Err = io::Error;
+
+    /// Parse a palette from a comma-separated list of characters and the colour to fill their
+    /// cells with, each of which can be any colour SVG understands, e.g. "#=black,.=#ffcc00".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|entry| {
+                let mut chars = entry.chars();
+                match (chars.next(), chars.next(), chars.as_str()) {
+                    (Some(ch), Some('='), colour) if !colour.is_empty() => {
+                        Ok((ch, colour.to_string()))
+                    }
+                    _ => Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid palette entry {:?}: expected <char>=<colour>",
+                            entry
+                        ),
+                    )),
+                }
+            })
+            .collect::<io::Result<_>>()
+            .map(Self)
+    }
+}
+
+/// Start an SVG document of the given size in pixels.
+fn open(width: f64, height: f64) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0:.1}\" height=\"{1:.1}\" \
+         viewBox=\"0 0 {0:.1} {1:.1}\">\n",
+        width, height
+    )
+}
+
+/// Escape the characters which would end an attribute value early.
+fn escape_attribute(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+/// Draw a grid as squares `size` pixels across, coloured by the character each cell is drawn as.
+pub fn squares<T, F>(grid: &Grid<T>, size: f64, palette: &Palette, draw: F) -> String
+where
+    F: Fn(&T) -> char,
+{
+    let mut svg = open(grid.width() as f64 * size, grid.height() as f64 * size);
+    for ((row, col), cell) in grid.enumerate() {
+        if let Some(colour) = palette.colour(draw(cell)) {
+            writeln!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>",
+                col as f64 * size,
+                row as f64 * size,
+                size,
+                size,
+                escape_attribute(colour)
+            )
+            .unwrap();
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Draw every hexagonal tile between the given corners as a hexagon `size` pixels from its centre
+/// to each corner, coloured by the character each tile is drawn as.
+///
+/// The hexagons point up and down, so that moving east is a step to the right and moving
+/// north-east or north-west is a step up a row, half a tile to either side.
+pub fn hexagons<F>((min, max): (Coord, Coord), size: f64, palette: &Palette, draw: F) -> String
+where
+    F: Fn(Coord) -> char,
+{
+    let across = 3f64.sqrt() * size;
+    // The leftmost tile is in the top row, which is shifted furthest to the left.
+    let left = min.0 as f64 - max.1 as f64 / 2.0;
+    let columns = (max.0 - min.0) as f64 + (max.1 - min.1) as f64 / 2.0 + 1.0;
+    let rows = (max.1 - min.1) as f64;
+    let mut svg = open(columns * across, rows * 1.5 * size + 2.0 * size);
+    for x in min.0..=max.0 {
+        for y in min.1..=max.1 {
+            let colour = match palette.colour(draw((x, y))) {
+                Some(colour) => colour,
+                None => continue,
+            };
+            let centre_x = (x as f64 - y as f64 / 2.0 - left) * across + across / 2.0;
+            let centre_y = (max.1 - y) as f64 * 1.5 * size + size;
+            let points = (0..6)
+                .map(|corner| {
+                    let angle = (60.0 * corner as f64 + 30.0).to_radians();
+                    format!(
+                        "{:.1},{:.1}",
+                        centre_x + size * angle.cos(),
+                        centre_y + size * angle.sin()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                svg,
+                "<polygon points=\"{}\" fill=\"{}\"/>",
+                points,
+                escape_attribute(colour)
+            )
+            .unwrap();
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette() {
+        let palette: Palette = "#=black,.=#ffcc00".parse().unwrap();
+        assert_eq!(palette.colour('#'), Some("black"));
+        assert_eq!(palette.colour('.'), Some("#ffcc00"));
+        assert_eq!(palette.colour('L'), None);
+        assert!("#black".parse::<Palette>().is_err());
+        assert!("#=".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn test_squares() {
+        let grid = Grid::from_rows(vec![vec!['#', 'x'], vec!['.', '#']]).unwrap();
+        let svg = squares(&grid, 10.0, &Palette::default(), |&ch| ch);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20.0\""));
+        // The cell without a colour is left out.
+        assert_eq!(svg.matches("<rect").count(), 3);
+        assert!(svg.contains(
+            "<rect x=\"10.0\" y=\"10.0\" width=\"10.0\" height=\"10.0\" fill=\"#333333\"/>"
+        ));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn test_hexagons() {
+        // Two tiles side by side, with one up and to the left of them: the same as
+        // `lobby::render_tiles` draws as ". # \n # #".
+        let svg = hexagons(((0, 0), (1, 1)), 10.0, &Palette::default(), |tile| {
+            if tile == (0, 1) {
+                '.'
+            } else {
+                '#'
+            }
+        });
+        assert_eq!(svg.matches("<polygon").count(), 4);
+        // The top left tile is at the top left, and the bottom left tile half a tile to its right.
+        let across = 3f64.sqrt() * 10.0;
+        assert!(svg.contains(&format!("points=\"{:.1},15.0 ", across)));
+        assert!(svg.contains(&format!("points=\"{:.1},30.0 ", 1.5 * across)));
+    }
+}
@@ -0,0 +1,374 @@
+/// Notes on the train ticket fields of Day 16, and working out which column is which field.
+use crate::diagnostic::Diagnostic;
+use crate::matching::{assign_by_elimination, assign_by_matching};
+use crate::parse_range;
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead};
+use std::iter;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+type Number = u64;
+/// The values of a ticket, in the order of its columns.
+pub type Ticket = Vec<Number>;
+/// The two ranges of values a field can take.
+pub type Constraint = [RangeInclusive<Number>; 2];
+
+/// The notes taken on the ticket fields, your ticket and the nearby tickets.
+#[derive(Debug, Clone)]
+pub struct Notes {
+    pub constraints: HashMap<String, Constraint>,
+    pub your_ticket: Ticket,
+    pub tickets: Vec<Ticket>,
+}
+
+/// Parse a field's constraint, e.g. "row: 6-11 or 33-44". Errors carry a `Diagnostic` pointing
+/// at the constraint or range at fault.
+fn parse_constraint(line: &str) -> io::Result<(String, Constraint)> {
+    let invalid = || Diagnostic::new(line, format!("Invalid field constraint {:?}", line));
+    let (key, range_specifier) = line.split_once(": ").ok_or_else(invalid)?;
+    let (range1, range2) = range_specifier.split_once(" or ").ok_or_else(invalid)?;
+    let range = |range| parse_range(range).map_err(|err| Diagnostic::from_io(err, range));
+    Ok((key.to_string(), [range(range1)?, range(range2)?]))
+}
+
+/// Parse a ticket's comma-separated values. Errors carry a `Diagnostic` pointing at the value at
+/// fault.
+fn parse_ticket(line: &str) -> io::Result<Ticket> {
+    line.split(',')
+        .map(|s| {
+            s.parse().map_err(|_| {
+                Diagnostic::new(s, format!("Invalid ticket value {:?} in {:?}", s, line)).into()
+            })
+        })
+        .collect()
+}
+
+/// The error for a section of the input missing its header.
+fn missing_header(header: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Expected a {:?} section", header),
+    )
+}
+
+impl FromStr for Notes {
+    type Err = io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // A missing section is pointed at the end of the input, and a section missing its header
+        // at the start of the section.
+        let at = |section: &str, header: &str| Diagnostic::from_io(missing_header(header), section);
+        let (fields, yours, nearby) = s
+            .split("\n\n")
+            .next_tuple()
+            .ok_or_else(|| at(&s[s.len()..], "nearby tickets:"))?;
+
+        let constraints = fields
+            .lines()
+            .map(parse_constraint)
+            .collect::<Result<_, io::Error>>()?;
+        let your_ticket = yours
+            .strip_prefix("your ticket:\n")
+            .ok_or_else(|| at(yours, "your ticket:").into())
+            .and_then(parse_ticket)?;
+        let tickets = nearby
+            .strip_prefix("nearby tickets:\n")
+            .ok_or_else(|| at(nearby, "nearby tickets:"))?
+            .lines()
+            .map(parse_ticket)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            constraints,
+            your_ticket,
+            tickets,
+        })
+    }
+}
+
+/// Every number which fits any field's constraint, as sorted ranges with any overlapping or
+/// touching ranges merged, so checking a number is a binary search rather than a check against
+/// every constraint.
+#[derive(Debug, Clone, PartialEq)]
+struct ValidRanges(Vec<RangeInclusive<Number>>);
+
+impl ValidRanges {
+    fn new(constraints: &HashMap<String, Constraint>) -> Self {
+        let mut ranges: Vec<RangeInclusive<Number>> =
+            constraints.values().flatten().cloned().collect();
+        ranges.sort_unstable_by_key(|range| *range.start());
+        let mut merged: Vec<RangeInclusive<Number>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                    if range.end() > last.end() {
+                        *last = *last.start()..=*range.end();
+                    }
+                }
+                _ => merged.push(range),
+            }
+        }
+        Self(merged)
+    }
+
+    /// Whether the number fits at least one field constraint.
+    fn contains(&self, number: Number) -> bool {
+        let index = self.0.partition_point(|range| *range.end() < number);
+        self.0
+            .get(index)
+            .is_some_and(|range| range.contains(&number))
+    }
+}
+
+fn ticket_find_invalid(ticket: &Ticket, valid: &ValidRanges) -> Vec<Number> {
+    ticket
+        .iter()
+        .filter(|&&number| !valid.contains(number))
+        .cloned()
+        .collect()
+}
+
+/// A version of ticket_find_invalid which returns early on the first invalid number to be able to
+/// quickly dismiss invalid tickets.
+fn ticket_is_invalid(ticket: &Ticket, valid: &ValidRanges) -> bool {
+    ticket.iter().any(|&number| !valid.contains(number))
+}
+
+/// The ticket scanning error rate: the sum of the values on nearby tickets which fit no field,
+/// for part one.
+pub fn error_rate(data: &Notes) -> Option<Number> {
+    let valid = ValidRanges::new(&data.constraints);
+    let mut invalid = Vec::new();
+    for ticket in &data.tickets {
+        invalid.extend(ticket_find_invalid(ticket, &valid));
+    }
+    Some(invalid.iter().sum())
+}
+
+/// Whether a number fits either range of a field's constraint.
+fn fits(constraint: &Constraint, number: Number) -> bool {
+    constraint.iter().any(|range| range.contains(&number))
+}
+
+/// Narrow down the fields each column could be to those the ticket's value in that column fits.
+fn narrow_candidates(
+    candidates: &mut [HashSet<String>],
+    ticket: &Ticket,
+    constraints: &HashMap<String, Constraint>,
+) {
+    for (possibles, &number) in candidates.iter_mut().zip(ticket) {
+        possibles.retain(|field| fits(&constraints[field], number));
+    }
+}
+
+/// The fields each column could be: those whose constraint is fit by every ticket's value in it.
+fn column_candidates<'a, I>(
+    constraints: &HashMap<String, Constraint>,
+    columns: usize,
+    tickets: I,
+) -> Vec<HashSet<String>>
+where
+    I: IntoIterator<Item = &'a Ticket>,
+{
+    let mut candidates = vec![constraints.keys().cloned().collect(); columns];
+    for ticket in tickets {
+        narrow_candidates(&mut candidates, ticket, constraints);
+    }
+    candidates
+}
+
+/// Multiply together the values on your ticket in the fields starting with the given prefix,
+/// once each column has been assigned a field.
+fn product_of_fields(
+    candidates: &[HashSet<String>],
+    your_ticket: &Ticket,
+    startswith: &str,
+) -> Option<Number> {
+    // Eliminating fields column by column is usually enough, but fall back to a full matching
+    // if it gets stuck.
+    let fields = assign_by_elimination(candidates).or_else(|| assign_by_matching(candidates))?;
+    Some(
+        fields
+            .iter()
+            .zip(your_ticket)
+            .filter(|(field, _)| field.starts_with(startswith))
+            .map(|(_, &number)| number)
+            .product(),
+    )
+}
+
+/// Multiply together the values on your ticket in the fields starting with the given prefix, once
+/// the nearby tickets have placed every field, for part two.
+pub fn field_product(data: &Notes, startswith: &str) -> Option<Number> {
+    let valid = ValidRanges::new(&data.constraints);
+    let valid_tickets = data
+        .tickets
+        .iter()
+        .filter(|ticket| !ticket_is_invalid(ticket, &valid));
+    let candidates = column_candidates(&data.constraints, data.your_ticket.len(), valid_tickets);
+    product_of_fields(&candidates, &data.your_ticket, startswith)
+}
+
+/// Solve both parts while streaming the input from a reader, validating each nearby ticket as it
+/// is read and only keeping the running error rate and what each column could be, so any number
+/// of nearby tickets can be handled.
+pub fn solve_streaming<R: BufRead>(
+    reader: R,
+    startswith: &str,
+) -> io::Result<(Number, Option<Number>)> {
+    let mut lines = reader.lines();
+    let mut next_line = |header: &str| lines.next().unwrap_or_else(|| Err(missing_header(header)));
+
+    let mut constraints = HashMap::new();
+    loop {
+        let line = next_line("your ticket:")?;
+        if line.is_empty() {
+            break;
+        }
+        let (field, constraint) = parse_constraint(&line)?;
+        constraints.insert(field, constraint);
+    }
+    if next_line("your ticket:")? != "your ticket:" {
+        return Err(missing_header("your ticket:"));
+    }
+    let your_ticket = parse_ticket(&next_line("your ticket:")?)?;
+    if !next_line("nearby tickets:")?.is_empty()
+        || next_line("nearby tickets:")? != "nearby tickets:"
+    {
+        return Err(missing_header("nearby tickets:"));
+    }
+
+    let valid = ValidRanges::new(&constraints);
+    let mut error_rate = 0;
+    let mut candidates = column_candidates(&constraints, your_ticket.len(), iter::empty());
+    for line in lines {
+        let ticket = parse_ticket(&line?)?;
+        let invalid = ticket_find_invalid(&ticket, &valid);
+        if invalid.is_empty() {
+            narrow_candidates(&mut candidates, &ticket, &constraints);
+        } else {
+            error_rate += invalid.iter().sum::<Number>();
+        }
+    }
+    Ok((
+        error_rate,
+        product_of_fields(&candidates, &your_ticket, startswith),
+    ))
+}
+
+/// Parse the input, pointing any error at where it is in the input.
+pub fn parse_notes(input: &str) -> Result<Notes, Diagnostic> {
+    input
+        .parse()
+        .map_err(|err| Diagnostic::from_io(err, input).locate(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_example() {
+        let input = "class: 1-3 or 5-7
+row: 6-11 or 33-44
+seat: 13-40 or 45-50
+
+your ticket:
+7,1,14
+
+nearby tickets:
+7,3,47
+40,4,50
+55,2,20
+38,6,12";
+
+        let data = parse_notes(input).expect("Couldn't convert test input");
+
+        // Assert get the right number.
+        assert_eq!(error_rate(&data), Some(4 + 55 + 12));
+    }
+
+    #[test]
+    fn test_valid_ranges() {
+        let data = parse_notes(
+            "a: 5-7 or 1-3\nb: 4-4 or 20-30\nc: 22-25 or 10-12\n\nyour ticket:\n1\n\nnearby tickets:\n",
+        )
+        .unwrap();
+        let valid = ValidRanges::new(&data.constraints);
+        // Ranges which touch are merged as well as those which overlap.
+        assert_eq!(valid, ValidRanges(vec![1..=7, 10..=12, 20..=30]));
+        for number in 0..35 {
+            let fits_any = data
+                .constraints
+                .values()
+                .any(|constraint| fits(constraint, number));
+            assert_eq!(valid.contains(number), fits_any, "{}", number);
+        }
+    }
+
+    #[test]
+    fn test_given_example_part_two() {
+        let input = "class: 0-1 or 4-19
+row: 0-5 or 8-19
+seat: 0-13 or 16-19
+
+your ticket:
+11,12,13
+
+nearby tickets:
+3,9,18
+15,1,5
+5,14,9";
+
+        let data = parse_notes(input).expect("Couldn't convert test input");
+
+        // Assert get the right number (second arg gives different fields).
+        assert_eq!(field_product(&data, "class"), Some(12));
+        assert_eq!(field_product(&data, "row"), Some(11));
+        assert_eq!(field_product(&data, "seat"), Some(13));
+    }
+
+    #[test]
+    fn test_streaming_matches() {
+        let input = "class: 0-1 or 4-19
+row: 0-5 or 8-19
+seat: 0-13 or 16-19
+
+your ticket:
+11,12,13
+
+nearby tickets:
+3,9,18
+15,1,5
+5,14,9
+20,0,1";
+        let data = parse_notes(input).unwrap();
+        assert_eq!(
+            solve_streaming(input.as_bytes(), "row").unwrap(),
+            (error_rate(&data).unwrap(), field_product(&data, "row"))
+        );
+        assert_eq!(
+            solve_streaming(input.as_bytes(), "row").unwrap(),
+            (20, Some(11))
+        );
+        assert!(solve_streaming("class: 0-1 or 4-19\n\nnearby tickets:".as_bytes(), "").is_err());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let input = "class: 0-1 or 4-19\nrow: 0-5 or 8\n\nyour ticket:\n11\n\nnearby tickets:\n3\n";
+        let err = parse_notes(input).unwrap_err();
+        assert_eq!(err.message(), "Invalid range \"8\"");
+        assert_eq!(err.position(), Some((2, 13)));
+
+        let input = "class: 0-1 or 4-19\n\nyour ticket:\n11\n\nnearby tickets:\n3\n1,x\n";
+        let err = parse_notes(input).unwrap_err();
+        assert_eq!(err.position(), Some((8, 3)));
+
+        let input = "class: 0-1 or 4-19\n\nnearby tickets:\n3\n";
+        let err = parse_notes(input).unwrap_err();
+        // A missing section is pointed at the end of the input.
+        assert_eq!(err.position(), Some((5, 1)));
+    }
+}
@@ -0,0 +1,292 @@
+/// Toboggan runs down the repeating tree map of Day 3.
+use crate::bitset::BitSet;
+use crate::grid::{Grid, WrappedGrid};
+use crate::parse_lines;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+/// A slope as the number of columns moved right for each number of rows moved down.
+pub type Slope = (usize, usize);
+
+/// The slopes checked in part two.
+pub const SLOPES: [Slope; 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+
+/// Each tile is either a tree or open space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Open,
+    Tree,
+}
+
+impl Tile {
+    pub fn from_ch(ch: char) -> Option<Self> {
+        match ch {
+            '.' => Some(Self::Open),
+            '#' => Some(Self::Tree),
+            _ => None,
+        }
+    }
+
+    pub fn to_ch(self) -> char {
+        match self {
+            Self::Open => '.',
+            Self::Tree => '#',
+        }
+    }
+}
+
+/// Count the trees hit going down the map from the top-left at the given slope, where the map
+/// repeats infinitely to the right. The starting tile is not counted.
+///
+/// Panics if the slope does not move down.
+pub fn trees_hit(grid: &Grid<Tile>, (right, down): Slope) -> u64 {
+    let map = WrappedGrid::new(grid);
+    (0..map.height())
+        .step_by(down)
+        .enumerate()
+        .skip(1)
+        .filter(|&(step, row)| map.get((row, step * right)) == Some(&Tile::Tree))
+        .count() as u64
+}
+
+/// The map with the trees of each row as set bits, so each step down a path tests the one bit it
+/// lands on in its row's words (the whole row fits in a single word for maps up to 64 wide).
+#[derive(Clone, Debug)]
+pub struct TreeMap {
+    rows: Vec<BitSet>,
+    width: usize,
+}
+
+impl TreeMap {
+    pub fn new(grid: &Grid<Tile>) -> Self {
+        Self {
+            rows: grid
+                .rows()
+                .map(|row| row.iter().map(|&tile| tile == Tile::Tree).collect())
+                .collect(),
+            width: grid.width(),
+        }
+    }
+
+    /// Count the trees hit going down the map at the given slope, as `trees_hit` does.
+    ///
+    /// Panics if the slope does not move down.
+    pub fn trees_hit(&self, (right, down): Slope) -> u64 {
+        if self.width == 0 {
+            return 0;
+        }
+        self.rows
+            .iter()
+            .step_by(down)
+            .enumerate()
+            .skip(1)
+            .filter(|(step, row)| row.contains(step * right % self.width))
+            .count() as u64
+    }
+}
+
+/// How the map is stored to count the trees on a path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    /// The grid of tiles, tested a tile at a time.
+    Grid,
+    /// A `TreeMap` of bits, tested a bit at a time.
+    Bitset,
+}
+
+impl FromStr for Engine {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grid" => Ok(Self::Grid),
+            "bitset" => Ok(Self::Bitset),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid engine {:?}: expected grid or bitset", s),
+            )),
+        }
+    }
+}
+
+/// Render the path taken down the map at the given slope like the puzzle statement does: each
+/// tile landed on is drawn as `O` if open or `X` if a tree, and the map is repeated to the right as
+/// many times as the path needs.
+///
+/// Panics if the slope does not move down.
+pub fn render_path(grid: &Grid<Tile>, (right, down): Slope) -> String {
+    let map = WrappedGrid::new(grid);
+    if map.width() == 0 {
+        return String::new();
+    }
+    let steps = (0..map.height()).step_by(down).count();
+    let width = (steps.saturating_sub(1) * right / map.width() + 1) * map.width();
+
+    let rows = (0..map.height())
+        .map(|row| (0..width).map(|col| map[(row, col)].to_ch()).collect())
+        .collect();
+    let mut rendered = Grid::from_rows(rows).expect("Repeated rows are all the same length");
+    for (step, row) in (0..map.height()).step_by(down).enumerate().skip(1) {
+        let pos = (row, step * right);
+        rendered[pos] = match map[pos] {
+            Tile::Open => 'O',
+            Tile::Tree => 'X',
+        };
+    }
+    rendered.render(|&ch| ch)
+}
+
+/// Parse a list of slopes written as "right,down" pairs separated by semicolons (e.g.
+/// "1,1;3,1;1,2").
+pub fn parse_slopes(s: &str) -> io::Result<Vec<Slope>> {
+    let invalid = |slope: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Invalid slope {:?}: expected \"right,down\" with down > 0",
+                slope
+            ),
+        )
+    };
+    s.split(';')
+        .map(|slope| {
+            let (right, down) = slope.trim().split_once(',').ok_or_else(|| invalid(slope))?;
+            match (right.trim().parse(), down.trim().parse()) {
+                (Ok(right), Ok(down)) if down > 0 => Ok((right, down)),
+                _ => Err(invalid(slope)),
+            }
+        })
+        .collect()
+}
+
+/// A single row of the map, used when streaming the map a row at a time.
+struct TileRow {
+    row: Vec<Tile>,
+}
+
+impl FromStr for TileRow {
+    type Err = io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            row: s
+                .chars()
+                .map(|c| {
+                    Tile::from_ch(c)
+                        .ok_or_else(|| Self::Err::other("Invalid character hit reading input"))
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Count the trees hit on each of the slopes while streaming the rows from a reader, so only the
+/// current row is ever held in memory.
+pub fn trees_hit_streaming<R: BufRead>(reader: R, slopes: &[Slope]) -> io::Result<Vec<u64>> {
+    let mut tree_counts = vec![0; slopes.len()];
+
+    // The starting position is never counted, so skip the first row.
+    for (row_idx, row) in parse_lines::<_, TileRow>(reader).enumerate().skip(1) {
+        let row = row?;
+        if row.row.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Row {} of the map is empty", row_idx + 1),
+            ));
+        }
+        for (&(right, down), count) in slopes.iter().zip(tree_counts.iter_mut()) {
+            if row_idx % down == 0
+                && row.row[(row_idx / down * right) % row.row.len()] == Tile::Tree
+            {
+                *count += 1;
+            }
+        }
+    }
+
+    Ok(tree_counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_path() {
+        let input = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+        let grid = Grid::from_chars(input, Tile::from_ch).unwrap();
+        let rendered = render_path(&grid, (3, 1));
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        // The last step lands in column 30, so the map is drawn three times over.
+        assert_eq!(lines.len(), 11);
+        assert!(lines.iter().all(|line| line.len() == 33));
+        assert_eq!(lines[0], "..##.........##.........##.......");
+        assert_eq!(lines[1], "#..O#...#..#...#...#..#...#...#..");
+        assert_eq!(lines[2], ".#....X..#..#....#..#..#....#..#.");
+        assert_eq!(
+            rendered.matches('X').count() as u64,
+            trees_hit(&grid, (3, 1))
+        );
+    }
+
+    #[test]
+    fn test_tree_map() {
+        let input = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+        let grid = Grid::from_chars(input, Tile::from_ch).unwrap();
+        let map = TreeMap::new(&grid);
+        for &slope in &[(1, 1), (3, 1), (5, 1), (7, 1), (1, 2), (0, 3), (13, 1)] {
+            assert_eq!(map.trees_hit(slope), trees_hit(&grid, slope), "{:?}", slope);
+        }
+    }
+
+    #[test]
+    fn test_parse_slopes() {
+        assert_eq!(
+            parse_slopes("1,1;3,1; 1,2").unwrap(),
+            vec![(1, 1), (3, 1), (1, 2)]
+        );
+        assert!(parse_slopes("1,0").is_err());
+        assert!(parse_slopes("1;2").is_err());
+    }
+
+    #[test]
+    fn test_streaming_matches() {
+        let input = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+        assert_eq!(
+            trees_hit_streaming(input.as_bytes(), &SLOPES).unwrap(),
+            vec![2, 7, 3, 4, 2]
+        );
+
+        // A row with no squares can't be wrapped around.
+        assert!(trees_hit_streaming("..#\n\n#..".as_bytes(), &SLOPES).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+/// The elves' memory game of Day 15, a Van Eck sequence from a list of starting numbers.
+use crate::snapshot::{self, Snapshot};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+/// The numbers spoken in the memory game: first the starting numbers, then on each turn 0 if the
+/// last number spoken was new, or else how many turns apart it was last spoken.
+///
+/// The history of when each number was last spoken is a table indexed by number, which for long
+/// games is tens of millions of entries visited in no particular order, so the game is bound by
+/// memory rather than arithmetic. To keep the table small, turns are stored as `u32` (with no
+/// turn 0, so 0 means never spoken), and with a bitmap an extra bit per number records whether it
+/// has been spoken at all, so the first time a number is spoken only the bitmap (a thirty-second
+/// of the size) needs to be read.
+pub struct VanEck {
+    starting: Vec<usize>,
+    /// How many turns have been taken.
+    turn: u32,
+    /// The number spoken on the latest turn, whose turn isn't in the history yet.
+    last: usize,
+    last_seen: Vec<u32>,
+    seen: Option<Vec<u64>>,
+}
+
+impl VanEck {
+    pub fn new(starting: &[usize]) -> Self {
+        Self::with_capacity(starting, 0)
+    }
+
+    /// Start the game with room in the history for every number below `capacity`, which for a
+    /// game of `n` turns is every number spoken after the starting numbers.
+    ///
+    /// A zeroed allocation is left to the OS to zero page by page as it's first touched, so parts
+    /// of the history never reached cost nothing.
+    pub fn with_capacity(starting: &[usize], capacity: usize) -> Self {
+        let size = starting
+            .iter()
+            .map(|&number| number + 1)
+            .fold(capacity, usize::max);
+        Self {
+            starting: starting.to_vec(),
+            turn: 0,
+            last: 0,
+            last_seen: vec![0; size],
+            seen: None,
+        }
+    }
+
+    /// Also track which numbers have been spoken in a bitmap.
+    pub fn with_bitmap(mut self) -> Self {
+        self.seen = Some(vec![0; self.last_seen.len() / 64 + 1]);
+        self
+    }
+
+    /// The number spoken on the latest turn, if any have been taken.
+    pub fn last_spoken(&self) -> Option<usize> {
+        match self.turn {
+            0 => None,
+            _ => Some(self.last),
+        }
+    }
+
+    /// The number to speak after `last`: 0 if it was new, or else how many turns apart it was
+    /// last spoken.
+    fn recall(&self, last: usize) -> usize {
+        let previous = match &self.seen {
+            Some(seen) if seen[last / 64] & 1 << (last % 64) == 0 => 0,
+            _ => self.last_seen[last],
+        };
+        match previous {
+            // Never seen before as there is no turn 0.
+            0 => 0,
+            _ => (self.turn - previous) as usize,
+        }
+    }
+
+    /// Take a turn speaking a number, which puts the last one into the history now that the next
+    /// one has been worked out from it. Returns `None` if out of turns.
+    fn speak(&mut self, number: usize) -> Option<()> {
+        let turn = self.turn.checked_add(1)?;
+        if self.turn > 0 {
+            self.last_seen[self.last] = self.turn;
+            if let Some(seen) = &mut self.seen {
+                seen[self.last / 64] |= 1 << (self.last % 64);
+            }
+        }
+        grow(&mut self.last_seen, self.seen.as_mut(), number);
+        self.turn = turn;
+        self.last = number;
+        Some(())
+    }
+}
+
+/// Make room in the history (and bitmap) for a number, doubling its size if it needs to grow.
+#[inline]
+fn grow(last_seen: &mut Vec<u32>, seen: Option<&mut Vec<u64>>, number: usize) {
+    if number >= last_seen.len() {
+        let size = (number + 1).max(last_seen.len() * 2);
+        last_seen.resize(size, 0);
+        if let Some(seen) = seen {
+            seen.resize(size / 64 + 1, 0);
+        }
+    }
+}
+
+impl Iterator for VanEck {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let number = match self.starting.get(self.turn as usize) {
+            Some(&number) => number,
+            None => self.recall(self.last),
+        };
+        self.speak(number)?;
+        Some(number)
+    }
+
+    /// Skip ahead with the history borrowed once for the whole run rather than once per turn,
+    /// which keeps long games like part two's as quick as a plain loop.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let mut remaining = n;
+        while remaining > 0 && (self.turn as usize) < self.starting.len() {
+            self.next()?;
+            remaining -= 1;
+        }
+        if remaining == 0 {
+            return self.next();
+        }
+
+        let end = self.turn.checked_add(u32::try_from(remaining).ok()?)?;
+        let (mut turn, mut last) = (self.turn, self.last);
+        let last_seen = &mut self.last_seen;
+        match &mut self.seen {
+            Some(seen) => {
+                while turn < end {
+                    let (word, bit) = (last / 64, 1 << (last % 64));
+                    let number = match seen[word] & bit {
+                        0 => 0,
+                        _ => (turn - last_seen[last]) as usize,
+                    };
+                    seen[word] |= bit;
+                    last_seen[last] = turn;
+                    grow(last_seen, Some(seen), number);
+                    turn += 1;
+                    last = number;
+                }
+            }
+            None => {
+                while turn < end {
+                    let number = match last_seen[last] {
+                        0 => 0,
+                        previous => (turn - previous) as usize,
+                    };
+                    last_seen[last] = turn;
+                    grow(last_seen, None, number);
+                    turn += 1;
+                    last = number;
+                }
+            }
+        }
+        self.turn = turn;
+        self.last = last;
+        self.next()
+    }
+}
+
+impl Snapshot for VanEck {
+    const TAG: [u8; 8] = *b"vaneck\0\0";
+
+    fn write_state(&self, out: &mut dyn Write) -> io::Result<()> {
+        let starting: Vec<u64> = self.starting.iter().map(|&number| number as u64).collect();
+        snapshot::write_u64s(out, &starting)?;
+        snapshot::write_u64(out, self.turn as u64)?;
+        snapshot::write_u64(out, self.last as u64)?;
+        snapshot::write_u32s(out, &self.last_seen)?;
+        snapshot::write_u64(out, self.seen.is_some() as u64)?;
+        snapshot::write_u64s(out, self.seen.as_deref().unwrap_or_default())
+    }
+
+    fn read_state(input: &mut dyn Read) -> io::Result<Self> {
+        let starting = snapshot::read_u64s(input)?
+            .into_iter()
+            .map(|number| number as usize)
+            .collect();
+        let turn = u32::try_from(snapshot::read_u64(input)?)
+            .map_err(|_| snapshot::invalid("Turn out of range"))?;
+        let last = snapshot::read_u64(input)? as usize;
+        let last_seen = snapshot::read_u32s(input)?;
+        let bitmap = snapshot::read_u64(input)? != 0;
+        let seen = Some(snapshot::read_u64s(input)?).filter(|_| bitmap);
+        // The history must have room for every number spoken so far, and the bitmap for every
+        // number in the history.
+        if (turn > 0 && last >= last_seen.len())
+            || seen
+                .as_ref()
+                .is_some_and(|seen| seen.len() < last_seen.len() / 64 + 1)
+        {
+            return Err(snapshot::invalid(
+                "History too small for the numbers spoken",
+            ));
+        }
+        Ok(Self {
+            starting,
+            turn,
+            last,
+            last_seen,
+            seen,
+        })
+    }
+
+    fn describe(&self) -> String {
+        let spoken = self.last_seen.iter().filter(|&&turn| turn != 0).count();
+        format!(
+            "Turn {}, on which {} was spoken, after {} different numbers",
+            self.turn, self.last, spoken
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence() {
+        let spoken: Vec<usize> = VanEck::new(&[0, 3, 6]).take(10).collect();
+        assert_eq!(spoken, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+        for (n, &number) in spoken.iter().enumerate() {
+            assert_eq!(VanEck::new(&[0, 3, 6]).with_bitmap().nth(n), Some(number));
+        }
+        let mut game = VanEck::new(&[0, 3, 6]);
+        assert_eq!(game.nth(1), Some(3));
+        assert_eq!(game.nth(4), Some(1));
+        assert_eq!(game.next(), Some(0));
+
+        // The history grows as needed, and the bitmap doesn't change the game.
+        let mut grown = VanEck::new(&[3, 1, 2]);
+        let mut bitmapped = VanEck::with_capacity(&[3, 1, 2], 2020).with_bitmap();
+        assert_eq!(grown.nth(2019), Some(1836));
+        assert_eq!(bitmapped.nth(2019), Some(1836));
+        assert!(VanEck::new(&[3, 1, 2])
+            .with_bitmap()
+            .take(100000)
+            .eq(VanEck::new(&[3, 1, 2]).take(100000)));
+
+        // Repeated and large starting numbers are remembered like any other.
+        let spoken: Vec<usize> = VanEck::new(&[100, 100, 1]).take(6).collect();
+        assert_eq!(spoken, vec![100, 100, 1, 0, 0, 1]);
+    }
+}
@@ -0,0 +1,438 @@
+/// A small virtual machine growing out of the handheld game console of Day 8.
+///
+/// The machine has a handful of registers, the first of which is the accumulator the original
+/// console's `acc` instruction adds to. Programs can be parsed with only the original console's
+/// instruction set, or with the extended set which adds arithmetic on any register and
+/// conditional jumps.
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// The number of registers the machine has.
+pub const REGISTERS: usize = 4;
+
+/// A register of the machine, written as a letter from `a`: `a` is the accumulator.
+pub type Register = usize;
+
+pub type Code = Vec<Instruction>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Nop(isize),
+    Acc(i64),
+    Jmp(isize),
+    /// Set a register to a value.
+    Set(Register, i64),
+    /// Add a value to a register.
+    Add(Register, i64),
+    /// Multiply a register by a value.
+    Mul(Register, i64),
+    /// Bitwise and a register with a value.
+    And(Register, i64),
+    /// Jump by an offset if a register is zero.
+    Jz(Register, isize),
+    /// Jump by an offset if a register is not zero.
+    Jnz(Register, isize),
+}
+
+impl Instruction {
+    /// Whether the instruction is one of the original handheld console's.
+    pub fn is_handheld(&self) -> bool {
+        matches!(self, Self::Nop(_) | Self::Acc(_) | Self::Jmp(_))
+    }
+
+    /// Whether the instruction jumps depending on the value of a register.
+    pub fn is_conditional(&self) -> bool {
+        matches!(self, Self::Jz(..) | Self::Jnz(..))
+    }
+}
+
+fn register_name(register: Register) -> char {
+    (b'a' + register as u8) as char
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Nop(num) => write!(f, "nop {:+}", num),
+            Self::Acc(num) => write!(f, "acc {:+}", num),
+            Self::Jmp(num) => write!(f, "jmp {:+}", num),
+            Self::Set(reg, num) => write!(f, "set {} {:+}", register_name(reg), num),
+            Self::Add(reg, num) => write!(f, "add {} {:+}", register_name(reg), num),
+            Self::Mul(reg, num) => write!(f, "mul {} {:+}", register_name(reg), num),
+            Self::And(reg, num) => write!(f, "and {} {:+}", register_name(reg), num),
+            Self::Jz(reg, num) => write!(f, "jz {} {:+}", register_name(reg), num),
+            Self::Jnz(reg, num) => write!(f, "jnz {} {:+}", register_name(reg), num),
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = io::Error;
+
+    /// Parse an instruction of the form "<verb> [<register>] <amount>".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid instruction {:?}", s),
+            )
+        };
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let register = |field: &str| match field.as_bytes() {
+            [reg @ b'a'..=b'z'] if ((reg - b'a') as usize) < REGISTERS => {
+                Ok((reg - b'a') as Register)
+            }
+            _ => Err(invalid()),
+        };
+        Ok(match fields[..] {
+            ["nop", num] => Self::Nop(num.parse().map_err(|_| invalid())?),
+            ["acc", num] => Self::Acc(num.parse().map_err(|_| invalid())?),
+            ["jmp", num] => Self::Jmp(num.parse().map_err(|_| invalid())?),
+            ["set", reg, num] => Self::Set(register(reg)?, num.parse().map_err(|_| invalid())?),
+            ["add", reg, num] => Self::Add(register(reg)?, num.parse().map_err(|_| invalid())?),
+            ["mul", reg, num] => Self::Mul(register(reg)?, num.parse().map_err(|_| invalid())?),
+            ["and", reg, num] => Self::And(register(reg)?, num.parse().map_err(|_| invalid())?),
+            ["jz", reg, num] => Self::Jz(register(reg)?, num.parse().map_err(|_| invalid())?),
+            ["jnz", reg, num] => Self::Jnz(register(reg)?, num.parse().map_err(|_| invalid())?),
+            _ => return Err(invalid()),
+        })
+    }
+}
+
+/// Which instructions a program may use.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InstructionSet {
+    /// Only the original console's `nop`, `acc` and `jmp`.
+    Handheld,
+    /// Every instruction the machine supports.
+    Extended,
+}
+
+/// Parse a program with an instruction per line, rejecting instructions outside the given set.
+pub fn parse_code(input: &str, set: InstructionSet) -> io::Result<Code> {
+    input
+        .lines()
+        .map(|line| {
+            let instruction: Instruction = line.parse()?;
+            if set == InstructionSet::Handheld && !instruction.is_handheld() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{:?} is not a handheld instruction", line),
+                ));
+            }
+            Ok(instruction)
+        })
+        .collect()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExitCode {
+    /// The machine reached a state it had already been in, so would run forever.
+    LoopDetected,
+    /// The machine ran to the instruction just past the end of the program.
+    Success,
+    /// The machine jumped anywhere else outside the program.
+    Failure,
+}
+
+/// Callbacks run around each instruction the machine executes. Both do nothing by default.
+pub trait Hooks {
+    /// Called with the machine as it is before executing an instruction.
+    fn before(&mut self, _machine: &Machine, _instruction: Instruction) {}
+
+    /// Called with the machine as it is after executing an instruction.
+    fn after(&mut self, _machine: &Machine, _instruction: Instruction) {}
+}
+
+/// No hooks at all.
+impl Hooks for () {}
+
+#[derive(Debug, Clone)]
+pub struct Machine {
+    counter: usize,
+    registers: [i64; REGISTERS],
+    code: Code,
+}
+
+impl From<Code> for Machine {
+    fn from(code: Code) -> Self {
+        Machine {
+            counter: 0,
+            registers: [0; REGISTERS],
+            code,
+        }
+    }
+}
+
+impl Machine {
+    /// The index of the next instruction to execute.
+    pub fn counter(&self) -> usize {
+        self.counter
+    }
+
+    pub fn registers(&self) -> &[i64; REGISTERS] {
+        &self.registers
+    }
+
+    pub fn accumulator(&self) -> i64 {
+        self.registers[0]
+    }
+
+    pub fn code(&self) -> &[Instruction] {
+        &self.code
+    }
+
+    /// The instruction which will be executed next, if the counter is within the program.
+    pub fn next_instruction(&self) -> Option<Instruction> {
+        self.code.get(self.counter).copied()
+    }
+
+    /// Execute a single instruction, returning how the program exited if it has left the program.
+    ///
+    /// Arithmetic on the registers wraps around on overflow.
+    pub fn step(&mut self) -> Option<ExitCode> {
+        let instruction = match self.next_instruction() {
+            Some(instruction) => instruction,
+            None if self.counter == self.code.len() => return Some(ExitCode::Success),
+            None => return Some(ExitCode::Failure),
+        };
+
+        let mut offset = 1;
+        match instruction {
+            Instruction::Nop(_) => (),
+            Instruction::Acc(num) => self.registers[0] = self.registers[0].wrapping_add(num),
+            Instruction::Jmp(jump) => offset = jump,
+            Instruction::Set(reg, num) => self.registers[reg] = num,
+            Instruction::Add(reg, num) => {
+                self.registers[reg] = self.registers[reg].wrapping_add(num)
+            }
+            Instruction::Mul(reg, num) => {
+                self.registers[reg] = self.registers[reg].wrapping_mul(num)
+            }
+            Instruction::And(reg, num) => self.registers[reg] &= num,
+            Instruction::Jz(reg, jump) if self.registers[reg] == 0 => offset = jump,
+            Instruction::Jnz(reg, jump) if self.registers[reg] != 0 => offset = jump,
+            Instruction::Jz(..) | Instruction::Jnz(..) => (),
+        }
+
+        // Jumping to before the start of the program is as much a failure as jumping past the end.
+        match (self.counter as isize).checked_add(offset) {
+            Some(counter) if counter >= 0 => {
+                self.counter = counter as usize;
+                None
+            }
+            _ => Some(ExitCode::Failure),
+        }
+    }
+
+    /// Run the program until it leaves the program or is found to loop forever.
+    pub fn run(&mut self) -> ExitCode {
+        self.run_with_hooks(&mut ())
+    }
+
+    /// Run the program as with `run`, calling the hooks around each instruction.
+    pub fn run_with_hooks<H: Hooks>(&mut self, hooks: &mut H) -> ExitCode {
+        let mut detector = LoopDetector::new(&self.code);
+        loop {
+            if !detector.visit(self) {
+                return ExitCode::LoopDetected;
+            }
+
+            let instruction = self.next_instruction();
+            if let Some(instruction) = instruction {
+                hooks.before(self, instruction);
+            }
+            let exit = self.step();
+            if let Some(instruction) = instruction {
+                hooks.after(self, instruction);
+            }
+            if let Some(exit) = exit {
+                return exit;
+            }
+        }
+    }
+}
+
+/// Records the states a machine has been in, to tell when it has started looping forever.
+#[derive(Debug, Clone)]
+pub struct LoopDetector {
+    by_counter: bool,
+    visited: HashSet<(usize, [i64; REGISTERS])>,
+}
+
+impl LoopDetector {
+    pub fn new(code: &[Instruction]) -> Self {
+        // Without conditional jumps, the path through the program doesn't depend on the registers,
+        // so returning to any instruction means a loop. Otherwise the whole state must repeat.
+        Self {
+            by_counter: !code.iter().any(Instruction::is_conditional),
+            visited: HashSet::new(),
+        }
+    }
+
+    /// Record the machine's current state, returning false if it has been in it before.
+    pub fn visit(&mut self, machine: &Machine) -> bool {
+        let registers = if self.by_counter {
+            [0; REGISTERS]
+        } else {
+            machine.registers
+        };
+        self.visited.insert((machine.counter, registers))
+    }
+}
+
+/// Find the single `nop` or `jmp` in a looping handheld program which, when swapped for the
+/// other, lets the program exit successfully. Returns its index and the instruction to replace it
+/// with, or `None` if no such swap exists or the program uses more than the handheld instructions.
+///
+/// Works backwards from the end of the program to find every instruction from which it exits,
+/// then looks along the program's original path for an instruction whose swap leads to one of
+/// them, so each instruction is only considered a constant number of times.
+pub fn find_fix(code: &[Instruction]) -> Option<(usize, Instruction)> {
+    if !code.iter().all(Instruction::is_handheld) {
+        return None;
+    }
+
+    // Where execution goes after each instruction, if it stays within the program (or reaches the
+    // index just past the end, which is a successful exit).
+    let target = |index: usize, offset: isize| {
+        (index as isize)
+            .checked_add(offset)
+            .filter(|&next| next >= 0 && next as usize <= code.len())
+            .map(|next| next as usize)
+    };
+    let next = |index: usize, instruction: Instruction| match instruction {
+        Instruction::Jmp(offset) => target(index, offset),
+        _ => target(index, 1),
+    };
+
+    let mut came_from: Vec<Vec<usize>> = vec![Vec::new(); code.len() + 1];
+    for (index, &instruction) in code.iter().enumerate() {
+        if let Some(next) = next(index, instruction) {
+            came_from[next].push(index);
+        }
+    }
+    let mut exits = vec![false; code.len() + 1];
+    let mut to_visit = vec![code.len()];
+    exits[code.len()] = true;
+    while let Some(index) = to_visit.pop() {
+        for &previous in &came_from[index] {
+            if !exits[previous] {
+                exits[previous] = true;
+                to_visit.push(previous);
+            }
+        }
+    }
+
+    // A swapped instruction on the original path can't be passed through again after the swap:
+    // the original path never exits, so no instruction on it is one from which the program exits.
+    let mut visited = vec![false; code.len()];
+    let mut index = 0;
+    while index < code.len() && !visited[index] {
+        visited[index] = true;
+        let swapped = match code[index] {
+            Instruction::Nop(offset) => Instruction::Jmp(offset),
+            Instruction::Jmp(offset) => Instruction::Nop(offset),
+            _ => code[index],
+        };
+        if swapped != code[index] && next(index, swapped).is_some_and(|next| exits[next]) {
+            return Some((index, swapped));
+        }
+        index = next(index, code[index])?;
+    }
+    None
+}
+
+/// The accumulator just before a program first repeats an instruction, for part one. `None` if
+/// the program exits instead.
+pub fn accumulator_at_loop(code: &[Instruction]) -> Option<i64> {
+    let mut machine = Machine::from(code.to_vec());
+    match machine.run() {
+        ExitCode::LoopDetected => Some(machine.accumulator()),
+        _ => None,
+    }
+}
+
+/// The accumulator once a looping program has been fixed by `find_fix` and run to its end, for
+/// part two.
+pub fn accumulator_after_fix(code: &[Instruction]) -> Option<i64> {
+    let (index, fixed) = find_fix(code)?;
+    let mut code = code.to_vec();
+    code[index] = fixed;
+    let mut machine = Machine::from(code);
+    match machine.run() {
+        ExitCode::Success => Some(machine.accumulator()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended_instructions() {
+        // Compute 3 cubed in register b, counting down the multiplications in register c.
+        let program = "set b +1
+set c +3
+jz c +4
+mul b +3
+add c -1
+jmp -3
+nop +0";
+        assert!(parse_code(program, InstructionSet::Handheld).is_err());
+        let code = parse_code(program, InstructionSet::Extended).unwrap();
+        assert_eq!(code[3].to_string(), "mul b +3");
+
+        let mut machine = Machine::from(code);
+        assert_eq!(machine.run(), ExitCode::Success);
+        assert_eq!(machine.registers()[1], 27);
+        assert_eq!(machine.registers()[2], 0);
+    }
+
+    #[test]
+    fn test_find_fix() {
+        let program = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+        let code = parse_code(program, InstructionSet::Handheld).unwrap();
+        assert_eq!(find_fix(&code), Some((7, Instruction::Nop(-4))));
+        assert_eq!(accumulator_at_loop(&code), Some(5));
+        assert_eq!(accumulator_after_fix(&code), Some(8));
+
+        // Swapping the only jump just reaches another which loops back.
+        let code = parse_code("acc +1\nacc +1\njmp -2\njmp -3", InstructionSet::Handheld).unwrap();
+        assert_eq!(find_fix(&code), None);
+    }
+
+    #[test]
+    fn test_hooks() {
+        struct Counter(usize, usize);
+        impl Hooks for Counter {
+            fn before(&mut self, _: &Machine, _: Instruction) {
+                self.0 += 1;
+            }
+            fn after(&mut self, machine: &Machine, instruction: Instruction) {
+                if let Instruction::Acc(_) = instruction {
+                    self.1 = machine.accumulator() as usize;
+                }
+            }
+        }
+
+        let code = parse_code("acc +2\njmp +2\nacc +5\nacc +1", InstructionSet::Handheld).unwrap();
+        let mut counter = Counter(0, 0);
+        assert_eq!(
+            Machine::from(code).run_with_hooks(&mut counter),
+            ExitCode::Success
+        );
+        assert_eq!((counter.0, counter.1), (3, 3));
+    }
+}
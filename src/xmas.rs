@@ -0,0 +1,201 @@
+/// The XMAS encoding of Day 9, where each number should be the sum of two of the numbers just
+/// before it.
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::io;
+use std::ops::Range;
+
+/// How many numbers each number must be a sum of two of.
+pub const PREAMBLE: usize = 25;
+
+/// Find the first contiguous run of at least two numbers which sums to the target, as the range
+/// of their indices. Runs are found in order of where they end, then the longest first.
+pub fn find_contiguous_sum(data: &[u64], target: u64) -> Option<Range<usize>> {
+    let (start, run) = find_contiguous_run(data.iter().copied(), target)?;
+    Some(start..start + run.len())
+}
+
+/// Find the first contiguous run of at least two numbers in a stream which sums to the target, as
+/// `find_contiguous_sum` does, giving the index of its first number and the numbers in it. Only
+/// the current run is held in memory.
+///
+/// The run is tracked by two pointers with a running sum: the end moves on a number at a time and
+/// the start catches up while the sum is too high, so each number is added and taken away at most
+/// once. This relies on the numbers being unsigned, so that dropping a number never raises the sum.
+pub fn find_contiguous_run<I>(numbers: I, target: u64) -> Option<(usize, Vec<u64>)>
+where
+    I: IntoIterator<Item = u64>,
+{
+    let mut run = VecDeque::new();
+    let mut start = 0;
+    let mut sum: u64 = 0;
+    for number in numbers {
+        run.push_back(number);
+        sum += number;
+        while sum > target {
+            // We're too high: drop earlier numbers until we go low enough to continue.
+            sum -= run.pop_front().unwrap();
+            start += 1;
+        }
+        // May now reach the target with a lone number: but if we need more numbers, carry on.
+        if sum == target && run.len() > 1 {
+            return Some((start, run.into()));
+        }
+    }
+    None
+}
+
+/// Find the first number in the stream which is not the sum of two of the preceding `preamble`
+/// numbers. Only the preamble window is held in memory.
+///
+/// Fails if the stream doesn't even hold a whole preamble.
+pub fn first_invalid<I>(mut numbers: I, preamble: usize) -> io::Result<Option<u64>>
+where
+    I: Iterator<Item = u64>,
+{
+    let mut rolling_queue: VecDeque<u64> = numbers.by_ref().take(preamble).collect();
+    if preamble == 0 || rolling_queue.len() < preamble {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "A stream of {} numbers is too short for a preamble of {}",
+                rolling_queue.len(),
+                preamble
+            ),
+        ));
+    }
+    let mut rolling_set: HashSet<u64> = rolling_queue.iter().copied().collect();
+    for number in numbers {
+        let mut ans: Option<(u64, u64)> = None;
+
+        for x in &rolling_queue {
+            let x = *x;
+            if number > x {
+                let y: u64 = number - x;
+                if rolling_set.contains(&y) {
+                    ans = Some((x, y));
+                    break;
+                }
+            }
+        }
+
+        if ans.is_none() {
+            // This number is the first which does not respect the condition that it must contain
+            // a pair in the last preamble which sum to it set by XMAS.
+            return Ok(Some(number));
+        }
+
+        // Set the queue and tracking set to the new preamble given that this number is valid.
+        rolling_queue.push_back(number);
+        let old = rolling_queue.pop_front().unwrap();
+        rolling_set.insert(number);
+        rolling_set.remove(&old);
+    }
+    Ok(None)
+}
+
+/// A contiguous run of at least two numbers in the stream which sums to the invalid number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Weakness {
+    /// The index of the first number of the run in the stream.
+    pub start: usize,
+    pub values: Vec<u64>,
+}
+
+impl Weakness {
+    /// The index of the last number of the run in the stream.
+    pub fn end(&self) -> usize {
+        self.start + self.values.len() - 1
+    }
+
+    /// The encryption weakness: the sum of the smallest and largest numbers in the run.
+    pub fn encryption_weakness(&self) -> u64 {
+        match self.values.iter().minmax().into_option() {
+            Some((min, max)) => min + max,
+            None => 0,
+        }
+    }
+}
+
+impl fmt::Display for Weakness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "indices {}-{}: {}",
+            self.start,
+            self.end(),
+            self.values.iter().join(" + ")
+        )
+    }
+}
+
+/// Find every contiguous run of at least two numbers which sums to the target, ordered by where
+/// they end and then where they start.
+pub fn find_all_weaknesses(numbers: &[u64], target: u64) -> Vec<Weakness> {
+    // A run sums to the target when the sum of all numbers before its end, less the sum of all
+    // numbers before its start, is the target: so look up earlier prefix sums as we go.
+    let mut weaknesses = Vec::new();
+    let mut starts_by_prefix: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut prefix: u64 = 0;
+    for (end, &number) in numbers.iter().enumerate() {
+        starts_by_prefix.entry(prefix).or_default().push(end);
+        prefix += number;
+        if let Some(starts) = prefix
+            .checked_sub(target)
+            .and_then(|before| starts_by_prefix.get(&before))
+        {
+            for &start in starts.iter().filter(|&&start| start < end) {
+                weaknesses.push(Weakness {
+                    start,
+                    values: numbers[start..=end].to_vec(),
+                });
+            }
+        }
+    }
+    weaknesses
+}
+
+/// Find the first run which sums to the invalid number, by `find_contiguous_sum`, for part two.
+pub fn find_weakness(numbers: &[u64], target: u64) -> Option<Weakness> {
+    find_contiguous_sum(numbers, target).map(|range| Weakness {
+        start: range.start,
+        values: numbers[range].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_contiguous_sum() {
+        let data = vec![
+            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
+            576,
+        ];
+        assert_eq!(find_contiguous_sum(&data, 127), Some(2..6));
+        // A lone number isn't a run, even when it's the target.
+        assert_eq!(find_contiguous_sum(&data, 576), Some(11..15));
+        assert_eq!(find_contiguous_sum(&[1, 5, 1], 5), None);
+        assert_eq!(find_contiguous_sum(&[0, 0], 0), Some(0..2));
+        assert_eq!(find_contiguous_sum(&[], 0), None);
+
+        // A stream gives the numbers of the run along with where it starts.
+        assert_eq!(
+            find_contiguous_run(data.iter().copied(), 127),
+            Some((2, vec![15, 25, 47, 40]))
+        );
+    }
+
+    #[test]
+    fn test_find_all_weaknesses() {
+        let data = vec![1, 2, 3, 0, 3, 6];
+        let ranges: Vec<(usize, usize)> = find_all_weaknesses(&data, 6)
+            .iter()
+            .map(|weakness| (weakness.start, weakness.end()))
+            .collect();
+        // A lone 6 isn't a run, but the zero lets 1 + 2 + 3 be extended.
+        assert_eq!(ranges, vec![(0, 2), (0, 3), (2, 4)]);
+    }
+}
@@ -0,0 +1,67 @@
+/// Checks that each day still gives the answers recorded in `answers.toml` on its real input,
+/// running the day's binary from start to finish as the runner would, and solving it through the
+/// library's C API. Days without an input under `input/` are skipped.
+use aoc2020::ffi;
+use aoc2020::report::{parse_output, DayReport};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// What each day reported, so a day is only run once however many of its parts are checked.
+static REPORTS: [OnceLock<Option<DayReport>>; 25] = [const { OnceLock::new() }; 25];
+
+/// Run a day's binary on its input and read what it reported, or `None` if it has no input.
+fn report(day: u32, binary: &str) -> Option<&'static DayReport> {
+    REPORTS[day as usize - 1]
+        .get_or_init(|| {
+            if !Path::new(&format!("input/{:02}.txt", day)).exists() {
+                return None;
+            }
+            let output = Command::new(binary)
+                .output()
+                .unwrap_or_else(|err| panic!("Couldn't run day {}: {}", day, err));
+            assert!(output.status.success(), "Day {} failed", day);
+            Some(parse_output(day, &String::from_utf8_lossy(&output.stdout)))
+        })
+        .as_ref()
+}
+
+/// Check a day gives the recorded answer to a part, both from its binary and through `ffi::solve`.
+fn check(day: u32, part: u32, binary: &str, expected: &str) {
+    let report = match report(day, binary) {
+        Some(report) => report,
+        None => {
+            eprintln!("Skipping day {}, which has no input", day);
+            return;
+        }
+    };
+    match &report.parts[part as usize - 1] {
+        Some(result) => assert!(
+            *result == expected,
+            "{}, but {} is recorded",
+            result,
+            expected
+        ),
+        None => panic!("Day {} gave no answer to part {}", day, part),
+    }
+    let input = fs::read_to_string(format!("input/{:02}.txt", day)).unwrap();
+    match ffi::solve(day, part, &input) {
+        Ok(Some(answer)) => assert!(
+            answer == expected,
+            "{} through the C API, but {} is recorded",
+            answer,
+            expected
+        ),
+        Ok(None) => panic!(
+            "Day {} gave no answer to part {} through the C API",
+            day, part
+        ),
+        Err(err) => panic!(
+            "Couldn't solve day {} part {} through the C API: {}",
+            day, part, err
+        ),
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/answers.rs"));